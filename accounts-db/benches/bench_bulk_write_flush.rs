@@ -0,0 +1,100 @@
+#![allow(clippy::arithmetic_side_effects)]
+//! Simulates the distribution of epoch rewards, where every slot stores a
+//! partition of stake accounts, and compares the write cache flushes with and
+//! without bulk write hints. Without hints, the burst of writes pushes the
+//! write cache over its limit and unrooted slots get flushed aggressively.
+use {
+    criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion},
+    solana_accounts_db::{
+        accounts_db::{
+            AccountShrinkThreshold, AccountsDb, AccountsDbConfig, ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS,
+        },
+        accounts_index::AccountSecondaryIndexes,
+    },
+    solana_sdk::{
+        account::AccountSharedData, clock::Slot, genesis_config::ClusterType, pubkey::Pubkey,
+    },
+    std::sync::{atomic::Ordering, Arc},
+};
+
+/// the size of a stake account
+const STAKE_ACCOUNT_SIZE: usize = 200;
+/// stake accounts stored per slot, as in a reward partition
+const PARTITION_SIZE: usize = 4_096;
+/// slots of the distribution interval
+const NUM_SLOTS: Slot = 64;
+/// distance between the stored slot and the root
+const ROOT_LAG: Slot = 32;
+/// write cache limit, below the size of the unrooted slots
+const WRITE_CACHE_LIMIT_BYTES: u64 = 8 * (PARTITION_SIZE * STAKE_ACCOUNT_SIZE) as u64;
+
+fn new_accounts_db() -> AccountsDb {
+    AccountsDb::new_with_config(
+        Vec::new(),
+        &ClusterType::Development,
+        AccountSecondaryIndexes::default(),
+        AccountShrinkThreshold::default(),
+        Some(AccountsDbConfig {
+            write_cache_limit_bytes: Some(WRITE_CACHE_LIMIT_BYTES),
+            ..ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS
+        }),
+        None,
+        Arc::default(),
+    )
+}
+
+/// Store a partition per slot and root slots `ROOT_LAG` behind, like the
+/// validator does. Returns the number of aggressively flushed slots.
+fn distribute_rewards(accounts_db: &AccountsDb, stake_accounts: &[Pubkey], hint: bool) -> u64 {
+    let account = AccountSharedData::new(1, STAKE_ACCOUNT_SIZE, &Pubkey::default());
+    for (slot, partition) in (1..=NUM_SLOTS).zip(stake_accounts.chunks(PARTITION_SIZE)) {
+        if hint {
+            accounts_db.hint_bulk_write(slot, (partition.len() * STAKE_ACCOUNT_SIZE) as u64);
+        }
+        let accounts = partition
+            .iter()
+            .map(|pubkey| (pubkey, &account))
+            .collect::<Vec<_>>();
+        accounts_db.store_cached((slot, accounts.as_slice()), None);
+        accounts_db.mark_slot_frozen(slot);
+        if slot > ROOT_LAG {
+            let root = slot - ROOT_LAG;
+            accounts_db.add_root(root);
+            accounts_db.flush_accounts_cache(true, Some(root));
+        }
+    }
+    accounts_db
+        .stats
+        .aggressively_flushed_slots
+        .load(Ordering::Relaxed)
+}
+
+fn bench_bulk_write_flush(c: &mut Criterion) {
+    let stake_accounts = std::iter::repeat_with(Pubkey::new_unique)
+        .take(NUM_SLOTS as usize * PARTITION_SIZE)
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("reward_distribution_flush");
+    group.sample_size(10);
+    for hint in [false, true] {
+        // The flushes are deterministic, so they are reported as part of
+        // the benchmark id
+        let aggressively_flushed_slots =
+            distribute_rewards(&new_accounts_db(), &stake_accounts, hint);
+        let id = BenchmarkId::new(
+            format!("hint_{hint}"),
+            format!("{aggressively_flushed_slots}_aggressively_flushed_slots"),
+        );
+
+        group.bench_function(id, |b| {
+            b.iter_batched(
+                new_accounts_db,
+                |accounts_db| distribute_rewards(&accounts_db, &stake_accounts, hint),
+                BatchSize::PerIteration,
+            );
+        });
+    }
+}
+
+criterion_group!(benches, bench_bulk_write_flush);
+criterion_main!(benches);
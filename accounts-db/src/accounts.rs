@@ -833,7 +833,7 @@ mod tests {
         },
         solana_svm::{
             account_loader::LoadedTransaction,
-            transaction_results::{DurableNonceFee, TransactionExecutionDetails},
+            transaction_results::{AccountLifecycle, DurableNonceFee, TransactionExecutionDetails},
         },
         std::{
             borrow::Cow,
@@ -868,6 +868,14 @@ mod tests {
                 return_data: None,
                 executed_units: 0,
                 accounts_data_len_delta: 0,
+                accounts_data_growth: 0,
+                heap_allocation_stats: None,
+                error_location: None,
+                capability_manifest: None,
+                decoded_instructions: None,
+                compute_budget_report: None,
+                account_lifecycle: AccountLifecycle::default(),
+                signature_policy_decision: None,
             },
             programs_modified_by_tx: Box::<LoadedProgramsForTxBatch>::default(),
         }
@@ -1566,6 +1574,7 @@ mod tests {
                 program_indices: vec![],
                 rent: 0,
                 rent_debits: RentDebits::default(),
+                fee: 0,
             }),
             None,
         );
@@ -1576,6 +1585,7 @@ mod tests {
                 program_indices: vec![],
                 rent: 0,
                 rent_debits: RentDebits::default(),
+                fee: 0,
             }),
             None,
         );
@@ -1954,6 +1964,7 @@ mod tests {
                 program_indices: vec![],
                 rent: 0,
                 rent_debits: RentDebits::default(),
+                fee: 0,
             }),
             nonce.clone(),
         );
@@ -2060,6 +2071,7 @@ mod tests {
                 program_indices: vec![],
                 rent: 0,
                 rent_debits: RentDebits::default(),
+                fee: 0,
             }),
             nonce.clone(),
         );
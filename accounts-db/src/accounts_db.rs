@@ -95,7 +95,7 @@ use {
     std::{
         borrow::{Borrow, Cow},
         boxed::Box,
-        collections::{hash_map, BTreeSet, HashMap, HashSet},
+        collections::{hash_map, BTreeMap, BTreeSet, HashMap, HashSet},
         fs,
         hash::{Hash as StdHash, Hasher as StdHasher},
         io::Result as IoResult,
@@ -116,6 +116,8 @@ const PAGE_SIZE: u64 = 4 * 1024;
 // when the accounts write cache exceeds this many bytes, we will flush it
 // this can be specified on the command line, too (--accounts-db-cache-limit-mb)
 const WRITE_CACHE_LIMIT_BYTES_DEFAULT: u64 = 15_000_000_000;
+// bulk writes may grow the write cache by at most this percentage of its limit
+const BULK_WRITE_ALLOWANCE_MAX_PERCENT: u64 = 50;
 const SCAN_SLOT_PAR_ITER_THRESHOLD: usize = 4000;
 
 const UNREF_ACCOUNTS_BATCH_SIZE: usize = 10_000;
@@ -497,6 +499,7 @@ pub const ACCOUNTS_DB_CONFIG_FOR_TESTING: AccountsDbConfig = AccountsDbConfig {
     create_ancient_storage: CreateAncientStorage::Pack,
     test_partitioned_epoch_rewards: TestPartitionedEpochRewards::CompareResults,
     test_skip_rewrites_but_include_in_bank_hash: false,
+    enable_accounts_lt_hash: false,
 };
 pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig {
     index: Some(ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS),
@@ -510,6 +513,7 @@ pub const ACCOUNTS_DB_CONFIG_FOR_BENCHMARKS: AccountsDbConfig = AccountsDbConfig
     create_ancient_storage: CreateAncientStorage::Pack,
     test_partitioned_epoch_rewards: TestPartitionedEpochRewards::None,
     test_skip_rewrites_but_include_in_bank_hash: false,
+    enable_accounts_lt_hash: false,
 };
 
 pub type BinnedHashData = Vec<Vec<CalculateHashIntermediate>>;
@@ -555,6 +559,8 @@ pub struct AccountsDbConfig {
     /// how to create ancient storages
     pub create_ancient_storage: CreateAncientStorage,
     pub test_partitioned_epoch_rewards: TestPartitionedEpochRewards,
+    /// maintain the accounts lt hash in banks, which is experimental
+    pub enable_accounts_lt_hash: bool,
 }
 
 #[cfg(not(test))]
@@ -1315,10 +1321,18 @@ pub struct AccountsDb {
     /// true if this client should skip rewrites but still include those rewrites in the bank hash as if rewrites had occurred.
     pub test_skip_rewrites_but_include_in_bank_hash: bool,
 
+    /// true if banks maintain the accounts lt hash, from AccountsDbConfig
+    pub enable_accounts_lt_hash: bool,
+
     pub accounts_cache: AccountsCache,
 
     write_cache_limit_bytes: Option<u64>,
 
+    /// Bytes of bulk writes per slot, announced by `hint_bulk_write()`, which
+    /// the write cache may hold beyond its limit until their slots are rooted
+    /// and flushed
+    bulk_write_hints: Mutex<BTreeMap<Slot, u64>>,
+
     sender_bg_hasher: Option<Sender<CachedAccount>>,
     read_only_accounts_cache: ReadOnlyAccountsCache,
 
@@ -1457,6 +1471,8 @@ pub struct AccountsStats {
     handle_dead_keys_us: AtomicU64,
     purge_exact_us: AtomicU64,
     purge_exact_count: AtomicU64,
+    /// Unrooted slots flushed because the write cache exceeded its limit
+    pub aggressively_flushed_slots: AtomicU64,
 }
 
 #[derive(Debug, Default)]
@@ -2359,6 +2375,7 @@ impl AccountsDb {
             next_id: AtomicAccountsFileId::new(0),
             shrink_candidate_slots: Mutex::new(ShrinkCandidates::default()),
             write_cache_limit_bytes: None,
+            bulk_write_hints: Mutex::default(),
             write_version: AtomicU64::new(0),
             paths: vec![],
             base_working_path,
@@ -2400,6 +2417,7 @@ impl AccountsDb {
             partitioned_epoch_rewards_config: PartitionedEpochRewardsConfig::default(),
             epoch_accounts_hash_manager: EpochAccountsHashManager::new_invalid(),
             test_skip_rewrites_but_include_in_bank_hash: false,
+            enable_accounts_lt_hash: false,
         }
     }
 
@@ -2468,6 +2486,11 @@ impl AccountsDb {
             .map(|config| config.test_skip_rewrites_but_include_in_bank_hash)
             .unwrap_or_default();
 
+        let enable_accounts_lt_hash = accounts_db_config
+            .as_ref()
+            .map(|config| config.enable_accounts_lt_hash)
+            .unwrap_or_default();
+
         let partitioned_epoch_rewards_config: PartitionedEpochRewardsConfig =
             PartitionedEpochRewardsConfig::new(test_partitioned_epoch_rewards);
 
@@ -2487,6 +2510,7 @@ impl AccountsDb {
             partitioned_epoch_rewards_config,
             exhaustively_verify_refcounts,
             test_skip_rewrites_but_include_in_bank_hash,
+            enable_accounts_lt_hash,
             ..Self::default_with_accounts_index(
                 accounts_index,
                 base_working_path,
@@ -4948,6 +4972,30 @@ impl AccountsDb {
         account_accessor.account_matches_owners(owners)
     }
 
+    /// Call `inspect` with the account at `pubkey` as of `ancestors`, without
+    /// copying its data, e.g. to read its metadata. The read only accounts
+    /// cache is neither consulted nor populated. Like
+    /// `load_with_fixed_root()`, accounts with zero lamports are not found.
+    pub fn inspect_account_with_fixed_root<T>(
+        &self,
+        ancestors: &Ancestors,
+        pubkey: &Pubkey,
+        inspect: impl FnOnce(&LoadedAccount) -> T,
+    ) -> Option<T> {
+        let (slot, storage_location, _maybe_account_accessor) =
+            self.read_index_for_accessor_or_load_slow(ancestors, pubkey, None, false)?;
+        let (mut account_accessor, _slot) = self.retry_to_get_account_accessor(
+            slot,
+            storage_location,
+            ancestors,
+            pubkey,
+            None,
+            LoadHint::FixedMaxRoot,
+        )?;
+        let account = account_accessor.check_and_get_loaded_account();
+        (account.lamports() != 0).then(|| inspect(&account))
+    }
+
     /// load the account with `pubkey` into the read only accounts cache.
     /// The goal is to make subsequent loads (which caller expects to occur) to find the account quickly.
     pub fn load_account_into_read_cache(&self, ancestors: &Ancestors, pubkey: &Pubkey) {
@@ -6033,11 +6081,45 @@ impl AccountsDb {
 
     /// true if write cache is too big
     fn should_aggressively_flush_cache(&self) -> bool {
-        self.write_cache_limit_bytes
-            .unwrap_or(WRITE_CACHE_LIMIT_BYTES_DEFAULT)
+        let write_cache_limit_bytes = self
+            .write_cache_limit_bytes
+            .unwrap_or(WRITE_CACHE_LIMIT_BYTES_DEFAULT);
+        write_cache_limit_bytes.saturating_add(self.bulk_write_allowance(write_cache_limit_bytes))
             < self.accounts_cache.size()
     }
 
+    /// Hint that `slot` stores `bytes` of account data in a single batch, e.g.
+    /// a partition of epoch rewards, which is expected to be flushed once the
+    /// slot is rooted. Until then, the write cache may exceed its limit by
+    /// this amount before unrooted slots are flushed aggressively, so that a
+    /// burst of writes does not flush slots which may still become dead. All
+    /// hints together can not let the cache grow by more than
+    /// `BULK_WRITE_ALLOWANCE_MAX_PERCENT` of its limit.
+    pub fn hint_bulk_write(&self, slot: Slot, bytes: u64) {
+        let mut bulk_write_hints = self.bulk_write_hints.lock().unwrap();
+        let hinted_bytes = bulk_write_hints.entry(slot).or_default();
+        *hinted_bytes = hinted_bytes.saturating_add(bytes);
+    }
+
+    /// Bytes the write cache may hold beyond `write_cache_limit_bytes` due to
+    /// bulk writes
+    fn bulk_write_allowance(&self, write_cache_limit_bytes: u64) -> u64 {
+        let hinted_bytes = self
+            .bulk_write_hints
+            .lock()
+            .unwrap()
+            .values()
+            .fold(0u64, |allowance, bytes| allowance.saturating_add(*bytes));
+        hinted_bytes.min(write_cache_limit_bytes / 100 * BULK_WRITE_ALLOWANCE_MAX_PERCENT)
+    }
+
+    /// Drop the hints of slots which are flushed, or will never be because
+    /// they are older than the max flushed root
+    fn clear_bulk_write_hints(&self, max_flushed_root: Slot) {
+        let mut bulk_write_hints = self.bulk_write_hints.lock().unwrap();
+        *bulk_write_hints = bulk_write_hints.split_off(&max_flushed_root.saturating_add(1));
+    }
+
     // `force_flush` flushes all the cached roots `<= requested_flush_root`. It also then
     // flushes:
     // 1) excess remaining roots or unrooted slots while 'should_aggressively_flush_cache' is true
@@ -6066,6 +6148,7 @@ impl AccountsDb {
                 Some((&mut account_bytes_saved, &mut num_accounts_saved)),
             );
         flush_roots_elapsed.stop();
+        self.clear_bulk_write_hints(self.accounts_cache.fetch_max_flush_root());
 
         // Note we don't purge unrooted slots here because there may be ongoing scans/references
         // for those slot, let the Bank::drop() implementation do cleanup instead on dead
@@ -6098,6 +6181,9 @@ impl AccountsDb {
                     if self.should_aggressively_flush_cache() {
                         if let Some(stats) = self.flush_slot_cache(old_slot) {
                             flush_stats.accumulate(&stats);
+                            self.stats
+                                .aggressively_flushed_slots
+                                .fetch_add(1, Ordering::Relaxed);
                         }
                     }
                 } else {
@@ -13541,6 +13627,58 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_hint_bulk_write() {
+        let mut db = AccountsDb::new_single_for_tests();
+        db.write_cache_limit_bytes = Some(200);
+        let account = AccountSharedData::new(1, 100, &Pubkey::default());
+        // Three unrooted slots, together over the limit
+        for slot in 1..=3 {
+            db.store_cached((slot, &[(&Pubkey::new_unique(), &account)][..]), None);
+            db.mark_slot_frozen(slot);
+        }
+
+        db.hint_bulk_write(3, 100);
+        db.flush_accounts_cache(false, None);
+        assert_eq!(db.accounts_cache.num_slots(), 3);
+        assert_eq!(
+            db.stats.aggressively_flushed_slots.load(Ordering::Relaxed),
+            0
+        );
+
+        // Once the hinted slot is rooted and flushed, the limit applies again
+        db.clear_bulk_write_hints(3);
+        db.flush_accounts_cache(false, None);
+        assert_eq!(db.accounts_cache.num_slots(), 2);
+        assert_eq!(
+            db.stats.aggressively_flushed_slots.load(Ordering::Relaxed),
+            1
+        );
+    }
+
+    #[test]
+    fn test_hint_bulk_write_capped() {
+        let mut db = AccountsDb::new_single_for_tests();
+        db.write_cache_limit_bytes = Some(200);
+        let account = AccountSharedData::new(1, 100, &Pubkey::default());
+        // Four unrooted slots, twice the limit
+        for slot in 1..=4 {
+            db.store_cached((slot, &[(&Pubkey::new_unique(), &account)][..]), None);
+            db.mark_slot_frozen(slot);
+        }
+
+        // The hints exceed the allowance, which is half of the limit
+        db.hint_bulk_write(3, 200);
+        db.hint_bulk_write(4, u64::MAX);
+        assert_eq!(db.bulk_write_allowance(200), 100);
+        db.flush_accounts_cache(false, None);
+        assert_eq!(db.accounts_cache.num_slots(), 3);
+        assert_eq!(
+            db.stats.aggressively_flushed_slots.load(Ordering::Relaxed),
+            1
+        );
+    }
+
     #[test]
     fn test_read_only_accounts_cache() {
         let db = Arc::new(AccountsDb::new_single_for_tests());
@@ -13581,6 +13719,52 @@ pub mod tests {
         assert_eq!(db.read_only_accounts_cache.cache_len(), 1);
     }
 
+    #[test]
+    fn test_inspect_account_with_fixed_root() {
+        let db = AccountsDb::new_single_for_tests();
+        let owner = Pubkey::new_unique();
+
+        let stored_key = Pubkey::new_unique();
+        let stored_account = AccountSharedData::new(321, 10, &owner);
+        let cached_key = Pubkey::new_unique();
+        let cached_account = AccountSharedData::new(123, 20, &owner);
+        let zero_lamport_key = Pubkey::new_unique();
+        let zero_lamport_account = AccountSharedData::new(0, 1, &owner);
+
+        db.store_cached((0, &[(&stored_key, &stored_account)][..]), None);
+        db.store_cached((0, &[(&zero_lamport_key, &zero_lamport_account)][..]), None);
+        db.add_root(0);
+        // Flush the cache so that the first accounts are read from the storage
+        db.flush_accounts_cache(true, None);
+        db.store_cached((1, &[(&cached_key, &cached_account)][..]), None);
+
+        let ancestors: Ancestors = vec![(0, 0), (1, 0)].into_iter().collect();
+        fn inspect(account: &LoadedAccount) -> (u64, Pubkey, usize, bool) {
+            (
+                account.lamports(),
+                *account.owner(),
+                account.data().len(),
+                account.is_cached(),
+            )
+        }
+        assert_eq!(
+            db.inspect_account_with_fixed_root(&ancestors, &stored_key, inspect),
+            Some((321, owner, 10, false))
+        );
+        assert_eq!(
+            db.inspect_account_with_fixed_root(&ancestors, &cached_key, inspect),
+            Some((123, owner, 20, true))
+        );
+        assert_eq!(
+            db.inspect_account_with_fixed_root(&ancestors, &zero_lamport_key, inspect),
+            None
+        );
+        assert_eq!(
+            db.inspect_account_with_fixed_root(&ancestors, &Pubkey::new_unique(), inspect),
+            None
+        );
+    }
+
     #[test]
     fn test_account_matches_owners() {
         let db = Arc::new(AccountsDb::new_single_for_tests());
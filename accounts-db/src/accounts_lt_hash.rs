@@ -0,0 +1,127 @@
+//! Lattice based hash of the accounts state.
+//!
+//! The hash of a set of accounts is the element-wise wrapping sum of the
+//! hashes of the individual accounts. Adding or removing an account is thus
+//! a single addition or subtraction, which allows maintaining a commitment to
+//! the whole accounts state incrementally from the accounts written by each
+//! batch of transactions, instead of rehashing all accounts.
+
+use {
+    solana_sdk::{account::ReadableAccount, hash::Hash, pubkey::Pubkey},
+    std::fmt,
+};
+
+/// A lattice hash, see the module documentation
+#[derive(Clone, Eq, PartialEq)]
+pub struct LtHash(pub [u16; LtHash::NUM_ELEMENTS]);
+
+impl LtHash {
+    pub const NUM_ELEMENTS: usize = 1024;
+
+    /// The hash of the empty set
+    pub const fn identity() -> Self {
+        Self([0; Self::NUM_ELEMENTS])
+    }
+
+    /// Expand the output of `hasher` into a lattice hash
+    pub fn with(hasher: &blake3::Hasher) -> Self {
+        let mut new = Self::identity();
+        hasher
+            .finalize_xof()
+            .fill(bytemuck::cast_slice_mut(new.0.as_mut_slice()));
+        new
+    }
+
+    /// Add `other` to the set
+    pub fn mix_in(&mut self, other: &Self) {
+        for (lhs, rhs) in self.0.iter_mut().zip(other.0.iter()) {
+            *lhs = lhs.wrapping_add(*rhs);
+        }
+    }
+
+    /// Remove `other` from the set
+    pub fn mix_out(&mut self, other: &Self) {
+        for (lhs, rhs) in self.0.iter_mut().zip(other.0.iter()) {
+            *lhs = lhs.wrapping_sub(*rhs);
+        }
+    }
+
+    /// Compact 32 byte digest of the lattice hash
+    pub fn checksum(&self) -> Hash {
+        let hash = blake3::hash(bytemuck::cast_slice(self.0.as_slice()));
+        Hash::new_from_array(hash.into())
+    }
+}
+
+impl Default for LtHash {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl fmt::Debug for LtHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("LtHash").field(&self.checksum()).finish()
+    }
+}
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl solana_frozen_abi::abi_example::AbiExample for LtHash {
+    fn example() -> Self {
+        Self::identity()
+    }
+}
+
+/// Lattice hash of a single account. Accounts with zero lamports do not
+/// exist, so they hash to the identity.
+///
+/// Unlike `AccountsDb::hash_account()`, the rent epoch is not hashed, as it
+/// is not part of the state observable by transactions.
+pub fn lt_hash_account<T: ReadableAccount>(account: &T, pubkey: &Pubkey) -> LtHash {
+    if account.lamports() == 0 {
+        return LtHash::identity();
+    }
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&account.lamports().to_le_bytes());
+    hasher.update(account.data());
+    hasher.update(&[account.executable().into()]);
+    hasher.update(account.owner().as_ref());
+    hasher.update(pubkey.as_ref());
+    LtHash::with(&hasher)
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::account::AccountSharedData};
+
+    #[test]
+    fn test_lt_hash_mix_in_and_out() {
+        let pubkey = Pubkey::new_unique();
+        let account_a = AccountSharedData::new(1, 10, &Pubkey::new_unique());
+        let account_b = AccountSharedData::new(2, 0, &Pubkey::new_unique());
+        let lt_hash_a = lt_hash_account(&account_a, &pubkey);
+        let lt_hash_b = lt_hash_account(&account_b, &Pubkey::new_unique());
+        assert_ne!(lt_hash_a, LtHash::identity());
+        assert_ne!(lt_hash_a, lt_hash_b);
+
+        // Mixing in is commutative
+        let mut ab = LtHash::identity();
+        ab.mix_in(&lt_hash_a);
+        ab.mix_in(&lt_hash_b);
+        let mut ba = LtHash::identity();
+        ba.mix_in(&lt_hash_b);
+        ba.mix_in(&lt_hash_a);
+        assert_eq!(ab, ba);
+        assert_eq!(ab.checksum(), ba.checksum());
+
+        // Mixing out reverts mixing in
+        ab.mix_out(&lt_hash_a);
+        assert_eq!(ab, lt_hash_b);
+        ab.mix_out(&lt_hash_b);
+        assert_eq!(ab, LtHash::identity());
+
+        // Zero lamport accounts do not exist
+        let account = AccountSharedData::new(0, 10, &Pubkey::new_unique());
+        assert_eq!(lt_hash_account(&account, &pubkey), LtHash::identity());
+    }
+}
@@ -445,6 +445,7 @@ fn is_valid_snapshot_archive_entry(parts: &[&str], kind: tar::EntryType) -> bool
         (["snapshots", "status_cache"], Regular) => true,
         (["snapshots", dir, file], GNUSparse) if all_digits(dir) && all_digits(file) => true,
         (["snapshots", dir, file], Regular) if all_digits(dir) && all_digits(file) => true,
+        (["snapshots", dir, "builtins_summary"], Regular) if all_digits(dir) => true,
         (["snapshots", dir], Directory) if all_digits(dir) => true,
         _ => false,
     }
@@ -567,6 +568,10 @@ mod tests {
             &["snapshots", "3", "3"],
             tar::EntryType::Regular
         ));
+        assert!(is_valid_snapshot_archive_entry(
+            &["snapshots", "3", "builtins_summary"],
+            tar::EntryType::Regular
+        ));
         assert!(is_valid_snapshot_archive_entry(
             &["version"],
             tar::EntryType::Regular
@@ -600,6 +605,10 @@ mod tests {
             &["snapshots", "0", "aa"],
             tar::EntryType::Regular
         ));
+        assert!(!is_valid_snapshot_archive_entry(
+            &["snapshots", "builtins_summary"],
+            tar::EntryType::Regular
+        ));
         assert!(!is_valid_snapshot_archive_entry(
             &["aaaa"],
             tar::EntryType::Regular
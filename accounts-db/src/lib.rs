@@ -13,6 +13,7 @@ pub mod accounts_file;
 pub mod accounts_hash;
 pub mod accounts_index;
 pub mod accounts_index_storage;
+pub mod accounts_lt_hash;
 pub mod accounts_partition;
 pub mod accounts_update_notifier_interface;
 pub mod active_stats;
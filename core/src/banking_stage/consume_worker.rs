@@ -326,6 +326,8 @@ impl ConsumeWorkerMetrics {
             would_exceed_max_vote_cost_limit,
             would_exceed_account_data_block_limit,
             max_loaded_accounts_data_size_exceeded,
+            // Reported per slot by the leader slot metrics
+            max_loaded_accounts_data_size_exceeded_by_owner: _,
             program_execution_temporarily_restricted,
         }: &TransactionErrorMetrics,
     ) {
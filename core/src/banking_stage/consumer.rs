@@ -599,6 +599,7 @@ impl Consumer {
                 None, // account_overrides
                 self.log_messages_bytes_limit,
                 true,
+                false,
             ));
         execute_and_commit_timings.load_execute_us = load_execute_us;
 
@@ -2478,6 +2479,7 @@ mod tests {
                     count: n as u32,
                     errored_txs_compute_consumed: vec![],
                     total_errored_units: 0,
+                    ..ProgramTiming::default()
                 },
             );
             expected_us += n * 100;
@@ -121,6 +121,17 @@ impl ReplaySlotStats {
                     time.errored_txs_compute_consumed.len(),
                     i64
                 ),
+                ("invocation_count", time.invocation_count, i64),
+                (
+                    "avg_instruction_data_len",
+                    time.average_instruction_data_len(),
+                    i64
+                ),
+                (
+                    "avg_instruction_account_count",
+                    time.average_instruction_account_count(),
+                    i64
+                ),
             );
         }
         datapoint_info!(
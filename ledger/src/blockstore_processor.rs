@@ -3138,6 +3138,7 @@ pub mod tests {
                 InstructionError::MaxAccountsExceeded,
                 InstructionError::MaxInstructionTraceLengthExceeded,
                 InstructionError::BuiltinProgramsMustConsumeComputeUnits,
+                InstructionError::MaxAccountsDataGrowthExceeded,
             ]
         }
 
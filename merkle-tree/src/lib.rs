@@ -1,4 +1,4 @@
 #![allow(clippy::arithmetic_side_effects)]
 
 pub mod merkle_tree;
-pub use merkle_tree::MerkleTree;
+pub use merkle_tree::{MerkleTree, MultiProof};
@@ -1,4 +1,7 @@
-use solana_program::hash::{hashv, Hash};
+use {
+    serde_derive::{Deserialize, Serialize},
+    solana_program::hash::{hashv, Hash},
+};
 
 // We need to discern between leaf and intermediate nodes to prevent trivial second
 // pre-image attacks.
@@ -62,6 +65,100 @@ impl<'a> Proof<'a> {
     }
 }
 
+/// Inclusion proof of several leaves at once
+///
+/// The paths of the leaves to the root share their upper nodes, and the nodes
+/// which can be computed from the proven leaves themselves are left out, so
+/// the proof is smaller than the individual proofs of the leaves combined.
+///
+/// A proof received from elsewhere can be malformed, which `verify()` rejects.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultiProof {
+    leaf_count: usize,
+    /// Indices of the proven leaves, ascending
+    indices: Vec<usize>,
+    /// Siblings which can not be computed from the proven leaves, level by level
+    siblings: Vec<Hash>,
+    root: Hash,
+}
+
+impl MultiProof {
+    pub fn new(leaf_count: usize, indices: Vec<usize>, siblings: Vec<Hash>, root: Hash) -> Self {
+        Self {
+            leaf_count,
+            indices,
+            siblings,
+            root,
+        }
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.leaf_count
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn root(&self) -> &Hash {
+        &self.root
+    }
+
+    /// Verifies the leaf hashes of the proven leaves, in the order of `indices()`
+    pub fn verify(&self, candidates: &[Hash]) -> bool {
+        if candidates.len() != self.indices.len() || !self.has_valid_indices() {
+            return false;
+        }
+        let mut known: Vec<(usize, Hash)> = self
+            .indices
+            .iter()
+            .copied()
+            .zip(candidates.iter().copied())
+            .collect();
+        let mut siblings = self.siblings.iter();
+        let mut level_len = self.leaf_count;
+        while level_len > 1 {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let (node_index, hash) = known[i];
+                let (lsib, rsib) = if node_index % 2 == 1 {
+                    match siblings.next() {
+                        Some(sibling) => (*sibling, hash),
+                        None => return false,
+                    }
+                } else if node_index + 1 == level_len {
+                    // The last entry is duplicated if the level length is odd
+                    (hash, hash)
+                } else if i + 1 < known.len() && known[i + 1].0 == node_index + 1 {
+                    i += 1;
+                    (hash, known[i].1)
+                } else {
+                    match siblings.next() {
+                        Some(sibling) => (hash, *sibling),
+                        None => return false,
+                    }
+                };
+                parents.push((node_index / 2, hash_intermediate!(lsib, rsib)));
+                i += 1;
+            }
+            known = parents;
+            level_len = MerkleTree::next_level_len(level_len);
+        }
+        siblings.next().is_none() && known == [(0, self.root)]
+    }
+
+    /// Whether `indices` are ascending leaves of the tree, without duplicates
+    fn has_valid_indices(&self) -> bool {
+        !self.indices.is_empty()
+            && self.indices.windows(2).all(|pair| pair[0] < pair[1])
+            && self
+                .indices
+                .last()
+                .is_some_and(|last| *last < self.leaf_count)
+    }
+}
+
 impl MerkleTree {
     #[inline]
     fn next_level_len(level_len: usize) -> usize {
@@ -175,6 +272,51 @@ impl MerkleTree {
         }
         Some(path)
     }
+
+    /// Finds a single proof for the leaves at `indices`, which may be in any
+    /// order and contain duplicates
+    pub fn find_multi_path(&self, indices: &[usize]) -> Option<MultiProof> {
+        if indices.is_empty() || indices.iter().any(|index| *index >= self.leaf_count) {
+            return None;
+        }
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+
+        let mut level_len = self.leaf_count;
+        let mut level_start = 0;
+        let mut siblings = vec![];
+        let mut known = indices.clone();
+        while level_len > 1 {
+            let level = &self.nodes[level_start..(level_start + level_len)];
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let node_index = known[i];
+                if node_index % 2 == 1 {
+                    // The left sibling would have been visited first if it was known
+                    siblings.push(level[node_index - 1]);
+                } else if node_index + 1 == level_len {
+                    // The last entry is duplicated if the level length is odd
+                } else if i + 1 < known.len() && known[i + 1] == node_index + 1 {
+                    i += 1;
+                } else {
+                    siblings.push(level[node_index + 1]);
+                }
+                parents.push(node_index / 2);
+                i += 1;
+            }
+            known = parents;
+            level_start += level_len;
+            level_len = MerkleTree::next_level_len(level_len);
+        }
+        Some(MultiProof::new(
+            self.leaf_count,
+            indices,
+            siblings,
+            *self.get_root()?,
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -247,6 +389,111 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multi_path_verify_good() {
+        let mt = MerkleTree::new(TEST);
+        let hashes: Vec<Hash> = TEST.iter().map(|s| hash_leaf!(s)).collect();
+        for indices in [
+            vec![0],
+            vec![10],
+            vec![0, 1],
+            vec![3, 4],
+            vec![9, 10],
+            vec![1, 5, 8],
+            vec![10, 2, 2, 7],
+            (0..TEST.len()).collect(),
+        ] {
+            let proof = mt.find_multi_path(&indices).unwrap();
+            assert_eq!(proof.root(), mt.get_root().unwrap());
+            let candidates: Vec<Hash> = proof.indices().iter().map(|i| hashes[*i]).collect();
+            assert!(proof.verify(&candidates), "{indices:?}");
+        }
+
+        let input = b"test";
+        let mt = MerkleTree::new(&[input]);
+        let proof = mt.find_multi_path(&[0]).unwrap();
+        assert!(proof.verify(&[hash_leaf!(input)]));
+    }
+
+    #[test]
+    fn test_multi_path_verify_bad() {
+        let mt = MerkleTree::new(TEST);
+        let hashes: Vec<Hash> = TEST.iter().map(|s| hash_leaf!(s)).collect();
+        let indices = [1, 5, 8];
+        let proof = mt.find_multi_path(&indices).unwrap();
+        let candidates: Vec<Hash> = indices.iter().map(|i| hashes[*i]).collect();
+        assert!(proof.verify(&candidates));
+        assert!(!proof.verify(&candidates[..2]));
+        let mut swapped = candidates.clone();
+        swapped.swap(0, 1);
+        assert!(!proof.verify(&swapped));
+        for (i, s) in BAD.iter().enumerate() {
+            let mut candidates = candidates.clone();
+            candidates[i] = hash_leaf!(s);
+            assert!(!proof.verify(&candidates));
+        }
+    }
+
+    #[test]
+    fn test_multi_path_verify_decoded() {
+        let mt = MerkleTree::new(TEST);
+        let hashes: Vec<Hash> = TEST.iter().map(|s| hash_leaf!(s)).collect();
+        let proof = mt.find_multi_path(&[1, 5, 8]).unwrap();
+        let candidates: Vec<Hash> = proof.indices().iter().map(|i| hashes[*i]).collect();
+
+        let decoded: MultiProof =
+            bincode::deserialize(&bincode::serialize(&proof).unwrap()).unwrap();
+        assert_eq!(decoded, proof);
+        assert!(decoded.verify(&candidates));
+
+        // Malformed proofs are rejected rather than panicking
+        let malformed = |leaf_count, indices: Vec<usize>| {
+            MultiProof::new(leaf_count, indices, proof.siblings.clone(), *proof.root())
+        };
+        for malformed in [
+            // Out of bounds
+            malformed(proof.leaf_count(), vec![1, 5, TEST.len()]),
+            malformed(5, vec![1, 5, 8]),
+            malformed(0, vec![1, 5, 8]),
+            // Not ascending
+            malformed(proof.leaf_count(), vec![5, 1, 8]),
+            malformed(proof.leaf_count(), vec![1, 5, 5]),
+        ] {
+            assert!(!malformed.verify(&candidates), "{malformed:?}");
+        }
+        assert!(!malformed(0, vec![]).verify(&[]));
+        assert!(!MultiProof::default().verify(&[]));
+    }
+
+    #[test]
+    fn test_multi_path_shares_nodes() {
+        let mt = MerkleTree::new(TEST);
+        let indices = [0, 1, 2, 3];
+        let proof = mt.find_multi_path(&indices).unwrap();
+        let individual_len: usize = indices
+            .iter()
+            .map(|i| mt.find_path(*i).unwrap().0.len())
+            .sum();
+        // Only the sibling of the subtree of the four leaves, at each level
+        assert_eq!(proof.siblings.len(), 2);
+        assert!(proof.siblings.len() < individual_len);
+
+        // Every node is computable from the leaves
+        let proof = mt
+            .find_multi_path(&(0..TEST.len()).collect::<Vec<_>>())
+            .unwrap();
+        assert!(proof.siblings.is_empty());
+    }
+
+    #[test]
+    fn test_multi_path_creation_bad_indices() {
+        let mt = MerkleTree::new(TEST);
+        assert_eq!(mt.find_multi_path(&[]), None);
+        assert_eq!(mt.find_multi_path(&[0, TEST.len()]), None);
+        let mt = MerkleTree::new::<[u8; 0]>(&[]);
+        assert_eq!(mt.find_multi_path(&[0]), None);
+    }
+
     #[test]
     fn test_proof_entry_instantiation_lsib_set() {
         ProofEntry::new(&Hash::default(), Some(&Hash::default()), None);
@@ -0,0 +1,56 @@
+#![feature(test)]
+
+extern crate test;
+
+use {
+    solana_program_runtime::log_collector::{LogBufferPool, LogCollector},
+    std::{cell::RefCell, rc::Rc},
+    test::Bencher,
+};
+
+const TRANSACTIONS_PER_BATCH: usize = 1_000;
+const MESSAGES_PER_TRANSACTION: usize = 20;
+const BYTES_LIMIT: usize = 10 * 1000;
+
+/// Log the messages of a batch of transactions whose logs are not kept
+fn log_batch(buffer_pool: Option<&Rc<RefCell<LogBufferPool>>>) {
+    for _ in 0..TRANSACTIONS_PER_BATCH {
+        let log_collector = match buffer_pool {
+            Some(buffer_pool) => {
+                LogCollector::new_ref_with_pool(Some(BYTES_LIMIT), buffer_pool.clone())
+            }
+            None => LogCollector::new_ref_with_limit(Some(BYTES_LIMIT)),
+        };
+        for _ in 0..MESSAGES_PER_TRANSACTION {
+            log_collector
+                .borrow_mut()
+                .log("Program log: Instruction: Transfer");
+        }
+        let messages = log_collector.take().into_messages();
+        if let Some(buffer_pool) = buffer_pool {
+            buffer_pool.borrow_mut().recycle(messages);
+        }
+    }
+}
+
+#[bench]
+fn bench_log_collector_unpooled(bencher: &mut Bencher) {
+    bencher.iter(|| log_batch(None));
+}
+
+#[bench]
+fn bench_log_collector_pooled(bencher: &mut Bencher) {
+    let buffer_pool = LogBufferPool::new_ref(1024 * 1024);
+    log_batch(Some(&buffer_pool));
+    // Only the first transaction of the batch allocates its messages, instead
+    // of all of them without a pool
+    assert_eq!(
+        buffer_pool.borrow().allocations(),
+        MESSAGES_PER_TRANSACTION as u64
+    );
+    bencher.iter(|| {
+        let buffer_pool = LogBufferPool::new_ref(1024 * 1024);
+        log_batch(Some(&buffer_pool));
+        buffer_pool.borrow().allocations()
+    });
+}
@@ -60,6 +60,50 @@ impl From<ComputeBudgetLimits> for FeeBudgetLimits {
     }
 }
 
+/// How a limit of the compute budget of a transaction is determined
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComputeBudgetRequest<T> {
+    /// Index of the instruction requesting the limit, if any
+    pub instruction_index: Option<u8>,
+    /// The requested value, if any
+    pub requested: Option<T>,
+    /// The value in effect, the default if none was requested
+    pub effective: T,
+}
+
+impl<T: PartialEq> ComputeBudgetRequest<T> {
+    /// Whether the requested value was clamped to the allowed range
+    pub fn is_clamped(&self) -> bool {
+        self.requested
+            .as_ref()
+            .is_some_and(|requested| *requested != self.effective)
+    }
+}
+
+/// The compute budget instructions of a transaction in canonical form: the
+/// limit in effect for each request, and the instructions which were not
+/// applied.
+///
+/// The effective limits are determined by the instructions which were
+/// applied, as they would be if the others were removed from the
+/// transaction. Any instruction which is not applied fails the transaction,
+/// with the error of the first one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComputeBudgetReport {
+    pub heap_bytes: ComputeBudgetRequest<u32>,
+    pub compute_unit_limit: ComputeBudgetRequest<u32>,
+    pub compute_unit_price: ComputeBudgetRequest<u64>,
+    pub loaded_accounts_bytes: ComputeBudgetRequest<u32>,
+    /// Indexes of the instructions repeating a request of an earlier
+    /// instruction
+    pub duplicate_instructions: Vec<u8>,
+    /// Indexes of the instructions which can not be decoded or request an
+    /// invalid value
+    pub invalid_instructions: Vec<u8>,
+    /// The result of `process_compute_budget_instructions()`
+    pub result: Result<ComputeBudgetLimits, TransactionError>,
+}
+
 /// Processing compute_budget could be part of tx sanitizing, failed to process
 /// these instructions will drop the transaction eventually without execution,
 /// may as well fail it early.
@@ -68,50 +112,62 @@ impl From<ComputeBudgetLimits> for FeeBudgetLimits {
 pub fn process_compute_budget_instructions<'a>(
     instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
 ) -> Result<ComputeBudgetLimits, TransactionError> {
+    canonicalize_compute_budget_instructions(instructions).result
+}
+
+/// Canonicalize the compute budget instructions of a transaction, reporting
+/// how each limit is determined. Lets wallets and transaction builders
+/// predict the compute budget of a transaction exactly.
+pub fn canonicalize_compute_budget_instructions<'a>(
+    instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+) -> ComputeBudgetReport {
     let mut num_non_compute_budget_instructions: u32 = 0;
     let mut updated_compute_unit_limit = None;
     let mut updated_compute_unit_price = None;
     let mut requested_heap_size = None;
     let mut updated_loaded_accounts_data_size_limit = None;
+    let mut duplicate_instructions = vec![];
+    let mut invalid_instructions = vec![];
+    let mut first_error = None;
 
     for (i, (program_id, instruction)) in instructions.enumerate() {
         if compute_budget::check_id(program_id) {
-            let invalid_instruction_data_error = TransactionError::InstructionError(
-                i as u8,
-                InstructionError::InvalidInstructionData,
-            );
-            let duplicate_instruction_error = TransactionError::DuplicateInstruction(i as u8);
-
-            match try_from_slice_unchecked(&instruction.data) {
+            let index = i as u8;
+            let error = match try_from_slice_unchecked(&instruction.data) {
                 Ok(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
                     if requested_heap_size.is_some() {
-                        return Err(duplicate_instruction_error);
-                    }
-                    if sanitize_requested_heap_size(bytes) {
-                        requested_heap_size = Some(bytes);
+                        Some(TransactionError::DuplicateInstruction(index))
+                    } else if sanitize_requested_heap_size(bytes) {
+                        requested_heap_size = Some((index, bytes));
+                        None
                     } else {
-                        return Err(invalid_instruction_data_error);
+                        Some(TransactionError::InstructionError(
+                            index,
+                            InstructionError::InvalidInstructionData,
+                        ))
                     }
                 }
                 Ok(ComputeBudgetInstruction::SetComputeUnitLimit(compute_unit_limit)) => {
-                    if updated_compute_unit_limit.is_some() {
-                        return Err(duplicate_instruction_error);
-                    }
-                    updated_compute_unit_limit = Some(compute_unit_limit);
+                    set_request(&mut updated_compute_unit_limit, index, compute_unit_limit)
                 }
                 Ok(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
-                    if updated_compute_unit_price.is_some() {
-                        return Err(duplicate_instruction_error);
-                    }
-                    updated_compute_unit_price = Some(micro_lamports);
+                    set_request(&mut updated_compute_unit_price, index, micro_lamports)
                 }
                 Ok(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
-                    if updated_loaded_accounts_data_size_limit.is_some() {
-                        return Err(duplicate_instruction_error);
-                    }
-                    updated_loaded_accounts_data_size_limit = Some(bytes);
+                    set_request(&mut updated_loaded_accounts_data_size_limit, index, bytes)
                 }
-                _ => return Err(invalid_instruction_data_error),
+                _ => Some(TransactionError::InstructionError(
+                    index,
+                    InstructionError::InvalidInstructionData,
+                )),
+            };
+            if let Some(error) = error {
+                if matches!(error, TransactionError::DuplicateInstruction(_)) {
+                    duplicate_instructions.push(index);
+                } else {
+                    invalid_instructions.push(index);
+                }
+                first_error.get_or_insert(error);
             }
         } else {
             // only include non-request instructions in default max calc
@@ -121,29 +177,75 @@ pub fn process_compute_budget_instructions<'a>(
     }
 
     // sanitize limits
-    let updated_heap_bytes = requested_heap_size
-        .unwrap_or(u32::try_from(MIN_HEAP_FRAME_BYTES).unwrap()) // loader's default heap_size
-        .min(MAX_HEAP_FRAME_BYTES);
-
-    let compute_unit_limit = updated_compute_unit_limit
-        .unwrap_or_else(|| {
-            num_non_compute_budget_instructions
-                .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
-        })
-        .min(MAX_COMPUTE_UNIT_LIMIT);
-
-    let compute_unit_price = updated_compute_unit_price.unwrap_or(0);
-
-    let loaded_accounts_bytes = updated_loaded_accounts_data_size_limit
-        .unwrap_or(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
-        .min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES);
+    let heap_bytes = new_request(requested_heap_size, |requested_heap_size| {
+        requested_heap_size
+            .unwrap_or(u32::try_from(MIN_HEAP_FRAME_BYTES).unwrap()) // loader's default heap_size
+            .min(MAX_HEAP_FRAME_BYTES)
+    });
+
+    let compute_unit_limit = new_request(updated_compute_unit_limit, |compute_unit_limit| {
+        compute_unit_limit
+            .unwrap_or_else(|| {
+                num_non_compute_budget_instructions
+                    .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+            })
+            .min(MAX_COMPUTE_UNIT_LIMIT)
+    });
+
+    let compute_unit_price = new_request(updated_compute_unit_price, |compute_unit_price| {
+        compute_unit_price.unwrap_or(0)
+    });
+
+    let loaded_accounts_bytes = new_request(
+        updated_loaded_accounts_data_size_limit,
+        |loaded_accounts_bytes| {
+            loaded_accounts_bytes
+                .unwrap_or(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
+                .min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
+        },
+    );
+
+    let result = match first_error {
+        Some(error) => Err(error),
+        None => Ok(ComputeBudgetLimits {
+            updated_heap_bytes: heap_bytes.effective,
+            compute_unit_limit: compute_unit_limit.effective,
+            compute_unit_price: compute_unit_price.effective,
+            loaded_accounts_bytes: loaded_accounts_bytes.effective,
+        }),
+    };
 
-    Ok(ComputeBudgetLimits {
-        updated_heap_bytes,
+    ComputeBudgetReport {
+        heap_bytes,
         compute_unit_limit,
         compute_unit_price,
         loaded_accounts_bytes,
-    })
+        duplicate_instructions,
+        invalid_instructions,
+        result,
+    }
+}
+
+/// Record the request of the instruction at `index`, failing if an earlier
+/// instruction requested the same limit
+fn set_request<T>(request: &mut Option<(u8, T)>, index: u8, value: T) -> Option<TransactionError> {
+    if request.is_some() {
+        return Some(TransactionError::DuplicateInstruction(index));
+    }
+    *request = Some((index, value));
+    None
+}
+
+fn new_request<T: Copy>(
+    request: Option<(u8, T)>,
+    effective: impl FnOnce(Option<T>) -> T,
+) -> ComputeBudgetRequest<T> {
+    let requested = request.map(|(_, value)| value);
+    ComputeBudgetRequest {
+        instruction_index: request.map(|(index, _)| index),
+        requested,
+        effective: effective(requested),
+    }
 }
 
 fn sanitize_requested_heap_size(bytes: u32) -> bool {
@@ -497,4 +599,84 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_canonicalize_compute_budget_instructions() {
+        let payer_keypair = Keypair::new();
+        let canonicalize = |instructions: &[Instruction]| {
+            let transaction = SanitizedTransaction::from_transaction_for_tests(Transaction::new(
+                &[&payer_keypair],
+                Message::new(instructions, Some(&payer_keypair.pubkey())),
+                Hash::default(),
+            ));
+            canonicalize_compute_budget_instructions(
+                transaction.message().program_instructions_iter(),
+            )
+        };
+
+        // Requested limits are clamped, the others are defaults
+        let report = canonicalize(&[
+            ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT + 1),
+            Instruction::new_with_bincode(Pubkey::new_unique(), &0_u8, vec![]),
+            ComputeBudgetInstruction::set_compute_unit_price(5),
+        ]);
+        assert_eq!(
+            report.compute_unit_limit,
+            ComputeBudgetRequest {
+                instruction_index: Some(0),
+                requested: Some(MAX_COMPUTE_UNIT_LIMIT + 1),
+                effective: MAX_COMPUTE_UNIT_LIMIT,
+            }
+        );
+        assert!(report.compute_unit_limit.is_clamped());
+        assert_eq!(
+            report.compute_unit_price,
+            ComputeBudgetRequest {
+                instruction_index: Some(2),
+                requested: Some(5),
+                effective: 5,
+            }
+        );
+        assert!(!report.compute_unit_price.is_clamped());
+        assert_eq!(
+            report.heap_bytes,
+            ComputeBudgetRequest {
+                instruction_index: None,
+                requested: None,
+                effective: u32::try_from(MIN_HEAP_FRAME_BYTES).unwrap(),
+            }
+        );
+        assert!(report.duplicate_instructions.is_empty());
+        assert!(report.invalid_instructions.is_empty());
+        assert_eq!(
+            report.result,
+            Ok(ComputeBudgetLimits {
+                compute_unit_limit: MAX_COMPUTE_UNIT_LIMIT,
+                compute_unit_price: 5,
+                ..ComputeBudgetLimits::default()
+            })
+        );
+
+        // Every instruction which is not applied is reported, and the
+        // transaction fails with the error of the first one
+        let report = canonicalize(&[
+            ComputeBudgetInstruction::set_compute_unit_price(5),
+            Instruction::new_with_bincode(compute_budget::id(), &0_u8, vec![]),
+            ComputeBudgetInstruction::set_compute_unit_price(6),
+            ComputeBudgetInstruction::request_heap_frame(1),
+            ComputeBudgetInstruction::set_compute_unit_price(7),
+        ]);
+        assert_eq!(report.compute_unit_price.requested, Some(5));
+        assert_eq!(report.compute_unit_price.effective, 5);
+        assert_eq!(report.heap_bytes.requested, None);
+        assert_eq!(report.duplicate_instructions, vec![2, 4]);
+        assert_eq!(report.invalid_instructions, vec![1, 3]);
+        assert_eq!(
+            report.result,
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+    }
 }
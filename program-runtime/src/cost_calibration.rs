@@ -0,0 +1,192 @@
+//! Calibration of compute unit costs against wall-clock time.
+//!
+//! Every BPF instruction is charged one compute unit, so the time measured per
+//! executed instruction defines what a compute unit is worth on the machine
+//! running the calibration. A syscall is then expected to take as long as the
+//! compute units it is charged; the ratio between the measured and the
+//! expected time is the drift of its cost constants. A drift above one means
+//! the syscall is underpriced on that machine.
+//!
+//! The `cost_calibration` bench of the BPF loader measures the instruction
+//! classes and syscalls and prints the report as CSV, so that reports of
+//! different machines and commits can be compared by tools.
+
+use std::{
+    fmt,
+    hint::black_box,
+    io::{self, Write},
+    time::Instant,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostClass {
+    /// A class of BPF instructions, charged one compute unit each
+    Instruction,
+    /// A syscall, charged according to the `ComputeBudget`
+    Syscall,
+}
+
+impl fmt::Display for CostClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instruction => write!(f, "instruction"),
+            Self::Syscall => write!(f, "syscall"),
+        }
+    }
+}
+
+/// Wall-clock time and compute units of repeated runs of one operation
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostSample {
+    pub class: CostClass,
+    pub name: String,
+    pub iterations: u64,
+    /// Compute units charged per iteration
+    pub compute_units: u64,
+    pub elapsed_ns: u64,
+}
+
+impl CostSample {
+    /// Run `operation` `iterations` times and measure the total time.
+    /// `operation` returns the compute units charged for one run.
+    pub fn measure(
+        class: CostClass,
+        name: &str,
+        iterations: u64,
+        mut operation: impl FnMut() -> u64,
+    ) -> Self {
+        let mut compute_units = 0;
+        let start = Instant::now();
+        for _ in 0..iterations {
+            compute_units = black_box(operation());
+        }
+        let elapsed_ns = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        Self {
+            class,
+            name: name.to_string(),
+            iterations,
+            compute_units,
+            elapsed_ns,
+        }
+    }
+
+    pub fn ns_per_iteration(&self) -> f64 {
+        self.elapsed_ns as f64 / self.iterations.max(1) as f64
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CalibrationReport {
+    pub samples: Vec<CostSample>,
+}
+
+impl CalibrationReport {
+    pub fn add(&mut self, sample: CostSample) {
+        self.samples.push(sample);
+    }
+
+    /// Wall-clock value of a compute unit, derived from the instruction
+    /// samples. `None` if there are none.
+    pub fn ns_per_compute_unit(&self) -> Option<f64> {
+        let (elapsed_ns, compute_units) = self
+            .samples
+            .iter()
+            .filter(|sample| sample.class == CostClass::Instruction)
+            .fold((0f64, 0f64), |(elapsed_ns, compute_units), sample| {
+                (
+                    elapsed_ns + sample.elapsed_ns as f64,
+                    compute_units + sample.compute_units as f64 * sample.iterations as f64,
+                )
+            });
+        (compute_units > 0.0).then(|| elapsed_ns / compute_units)
+    }
+
+    /// Ratio between the measured time of `sample` and the time its compute
+    /// units are worth
+    pub fn drift(&self, sample: &CostSample) -> Option<f64> {
+        let expected_ns = self.ns_per_compute_unit()? * sample.compute_units as f64;
+        (expected_ns > 0.0).then(|| sample.ns_per_iteration() / expected_ns)
+    }
+
+    /// Write the report as CSV, one line per sample
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "class,name,iterations,compute_units,ns_per_iteration,ns_per_compute_unit,drift"
+        )?;
+        let ns_per_compute_unit = self.ns_per_compute_unit();
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{:.2},{},{}",
+                sample.class,
+                sample.name,
+                sample.iterations,
+                sample.compute_units,
+                sample.ns_per_iteration(),
+                ns_per_compute_unit
+                    .map(|ns| format!("{ns:.4}"))
+                    .unwrap_or_default(),
+                self.drift(sample)
+                    .map(|drift| format!("{drift:.4}"))
+                    .unwrap_or_default(),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_sample(class: CostClass, name: &str, compute_units: u64, elapsed_ns: u64) -> CostSample {
+        CostSample {
+            class,
+            name: name.to_string(),
+            iterations: 10,
+            compute_units,
+            elapsed_ns,
+        }
+    }
+
+    #[test]
+    fn test_calibration_report() {
+        let mut report = CalibrationReport::default();
+        assert_eq!(report.ns_per_compute_unit(), None);
+
+        // 1000 instructions in 20us and 3000 instructions in 20us
+        report.add(new_sample(CostClass::Instruction, "alu64", 100, 20_000));
+        report.add(new_sample(CostClass::Instruction, "branch", 300, 20_000));
+        assert_eq!(report.ns_per_compute_unit(), Some(10.0));
+
+        // 100 CUs are worth 1us, the syscall takes 2us
+        let syscall = new_sample(CostClass::Syscall, "sol_sha256", 100, 20_000);
+        assert_eq!(syscall.ns_per_iteration(), 2_000.0);
+        assert_eq!(report.drift(&syscall), Some(2.0));
+        report.add(syscall);
+
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines.last(),
+            Some(&"syscall,sol_sha256,10,100,2000.00,10.0000,2.0000")
+        );
+    }
+
+    #[test]
+    fn test_cost_sample_measure() {
+        let mut runs = 0u64;
+        let sample = CostSample::measure(CostClass::Syscall, "noop", 5, || {
+            runs = runs.saturating_add(1);
+            7
+        });
+        assert_eq!(runs, 5);
+        assert_eq!(sample.iterations, 5);
+        assert_eq!(sample.compute_units, 7);
+        assert_eq!(sample.name, "noop");
+    }
+}
@@ -0,0 +1,252 @@
+//! Detection of floating-point math in consensus sources, for tests.
+//!
+//! Results of float math are not guaranteed to be bit-identical across
+//! compilers, targets and optimization levels, so they may differ between
+//! validators. The modules computing compute budgets, fees and the
+//! partitioning of rewards are float-free, and tests over their sources with
+//! `find_float_usage` keep them that way: a change which introduces a float
+//! into them fails the test, and has to be reviewed for determinism
+//! explicitly. The inflation rates from which rewards are calculated remain
+//! floats.
+//!
+//! The scan is lexical: it finds `f32` and `f64` tokens and float literals
+//! outside of comments and string literals. Floats reached through other
+//! crates without being named, e.g. a method call whose result type is
+//! inferred, are not found.
+
+use std::{iter::Peekable, str::Chars};
+
+/// A float type or literal in a source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FloatUsage {
+    /// One-based
+    pub line: usize,
+    pub token: String,
+}
+
+/// The float types and literals in `source`, in order
+pub fn find_float_usage(source: &str) -> Vec<FloatUsage> {
+    strip_comments_and_literals(source)
+        .lines()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            line.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '.'))
+                .filter(|token| is_float_token(token))
+                .map(move |token| FloatUsage {
+                    line: index.saturating_add(1),
+                    token: token.to_string(),
+                })
+        })
+        .collect()
+}
+
+fn is_float_token(token: &str) -> bool {
+    if token.split('.').any(|part| part == "f32" || part == "f64") {
+        return true;
+    }
+    if !token.starts_with(|c: char| c.is_ascii_digit())
+        || token.starts_with("0x")
+        || token.starts_with("0b")
+        || token.starts_with("0o")
+    {
+        return false;
+    }
+    // Typed literals, e.g. `1f64` or `1_f32`
+    if token.ends_with("f32") || token.ends_with("f64") {
+        return true;
+    }
+    let mut rest = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '_');
+    let mut has_fraction = false;
+    // Fractional part, e.g. `1.5`, but not ranges or method calls like `1..2`
+    // or `1.max(2)`
+    if let Some(fraction) = rest.strip_prefix('.') {
+        if !fraction.starts_with(|c: char| c.is_ascii_digit()) {
+            return false;
+        }
+        rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit() || c == '_');
+        has_fraction = true;
+    }
+    // Exponent, e.g. `1e9`, or `1e` of `1e-9` which is split at the sign
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        return exponent.is_empty() || exponent.starts_with(|c: char| c.is_ascii_digit());
+    }
+    has_fraction
+}
+
+/// `source` with comments and the contents of string and character literals
+/// removed, keeping line breaks
+fn strip_comments_and_literals(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut previous = ' ';
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        code.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut depth = 1usize;
+                let mut last = ' ';
+                while depth > 0 {
+                    let Some(c) = chars.next() else { break };
+                    match (last, c) {
+                        ('/', '*') => {
+                            depth = depth.saturating_add(1);
+                            last = ' ';
+                        }
+                        ('*', '/') => {
+                            depth = depth.saturating_sub(1);
+                            last = ' ';
+                        }
+                        _ => {
+                            push_line_break(&mut code, c);
+                            last = c;
+                        }
+                    }
+                }
+                code.push(' ');
+            }
+            '"' => {
+                skip_string(&mut chars, &mut code, 0);
+                code.push_str("\"\"");
+            }
+            'r' if !is_identifier_char(previous) && starts_raw_string(&chars) => {
+                let mut hashes = 0usize;
+                while chars.next_if_eq(&'#').is_some() {
+                    hashes = hashes.saturating_add(1);
+                }
+                chars.next();
+                skip_string(&mut chars, &mut code, hashes);
+                code.push_str("\"\"");
+            }
+            '\'' => {
+                // Character literals, as opposed to lifetimes and labels
+                let mut ahead = chars.clone();
+                match (ahead.next(), ahead.next()) {
+                    (Some('\\'), _) => {
+                        chars.next();
+                        chars.next();
+                        for c in chars.by_ref() {
+                            if c == '\'' {
+                                break;
+                            }
+                        }
+                        code.push_str("' '");
+                    }
+                    (Some(_), Some('\'')) => {
+                        chars.next();
+                        chars.next();
+                        code.push_str("' '");
+                    }
+                    _ => code.push(c),
+                }
+            }
+            c => code.push(c),
+        }
+        previous = code.chars().next_back().unwrap_or(' ');
+    }
+    code
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Whether the `r` just consumed starts a raw string, i.e. is followed by
+/// any number of `#` and a `"`
+fn starts_raw_string(chars: &Peekable<Chars>) -> bool {
+    chars.clone().find(|c| *c != '#').is_some_and(|c| c == '"')
+}
+
+/// Skip to the end of a string literal whose opening quote was consumed,
+/// followed by `hashes` `#` if it is a raw string
+fn skip_string(chars: &mut Peekable<Chars>, code: &mut String, hashes: usize) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if hashes == 0 => {
+                if let Some(escaped) = chars.next() {
+                    push_line_break(code, escaped);
+                }
+            }
+            '"' => {
+                let mut ahead = chars.clone();
+                if (0..hashes).all(|_| ahead.next() == Some('#')) {
+                    for _ in 0..hashes {
+                        chars.next();
+                    }
+                    return;
+                }
+            }
+            c => push_line_break(code, c),
+        }
+    }
+}
+
+fn push_line_break(code: &mut String, c: char) {
+    if c == '\n' {
+        code.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_float_usage() {
+        let source = r##"
+            // A comment with 0.5 and f64
+            /* A block /* nested */ comment
+               with 1.5 */
+            fn rate<'a>(value: &'a u64) -> f64 {
+                let quote = '"';
+                let escaped = '\'';
+                let message = "a \" string with 2.5
+                    over two lines";
+                let raw = r#"a raw "string" with 3.5"#;
+                for index in 0..10 {
+                    let _ = index.max(1).min(2) + tuple.0 + 1.max(2) + 0xe5 + 5usize;
+                }
+                *value as f64 * 0.25 + 1e9 + 2E-3 + 3_f32 + 4f64 + std::f64::consts::PI
+            }
+        "##;
+        let expected = [
+            (5, "f64"),
+            (14, "f64"),
+            (14, "0.25"),
+            (14, "1e9"),
+            (14, "2E"),
+            (14, "3_f32"),
+            (14, "4f64"),
+            (14, "f64"),
+        ]
+        .map(|(line, token)| FloatUsage {
+            line,
+            token: token.to_string(),
+        });
+        assert_eq!(find_float_usage(source), expected);
+    }
+
+    #[test]
+    fn test_consensus_sources_are_float_free() {
+        for (name, source) in [
+            ("compute_budget.rs", include_str!("compute_budget.rs")),
+            (
+                "compute_budget_processor.rs",
+                include_str!("compute_budget_processor.rs"),
+            ),
+            (
+                "prioritization_fee.rs",
+                include_str!("prioritization_fee.rs"),
+            ),
+        ] {
+            assert_eq!(find_float_usage(source), vec![], "floats in {name}");
+        }
+    }
+}
@@ -38,9 +38,10 @@ use {
     std::{
         alloc::Layout,
         cell::RefCell,
+        collections::BTreeSet,
         fmt::{self, Debug},
         rc::Rc,
-        sync::{atomic::Ordering, Arc},
+        sync::Arc,
     },
 };
 
@@ -118,11 +119,27 @@ impl fmt::Display for AllocErr {
 pub struct BpfAllocator {
     len: u64,
     pos: u64,
+    allocation_count: u64,
 }
 
 impl BpfAllocator {
     pub fn new(len: u64) -> Self {
-        Self { len, pos: 0 }
+        Self {
+            len,
+            pos: 0,
+            allocation_count: 0,
+        }
+    }
+
+    /// Highest number of heap bytes in use, including alignment padding.
+    /// Since this is a bump allocator, that is the current position.
+    pub fn peak_bytes(&self) -> u64 {
+        self.pos
+    }
+
+    /// Number of successful allocations
+    pub fn allocation_count(&self) -> u64 {
+        self.allocation_count
     }
 
     pub fn alloc(&mut self, layout: Layout) -> Result<u64, AllocErr> {
@@ -136,6 +153,7 @@ impl BpfAllocator {
             self.pos = self.pos.saturating_add(bytes_to_align);
             let addr = MM_HEAP_START.saturating_add(self.pos);
             self.pos = self.pos.saturating_add(layout.size() as u64);
+            self.allocation_count = self.allocation_count.saturating_add(1);
             Ok(addr)
         } else {
             Err(AllocErr)
@@ -143,6 +161,56 @@ impl BpfAllocator {
     }
 }
 
+/// Heap usage of the programs executed by a transaction
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct HeapAllocationStats {
+    /// Largest number of bytes allocated in a single heap frame
+    pub peak_bytes: u64,
+    /// Number of allocations across all heap frames
+    pub allocation_count: u64,
+}
+
+impl HeapAllocationStats {
+    fn accumulate(&mut self, allocator: &BpfAllocator) {
+        self.peak_bytes = self.peak_bytes.max(allocator.peak_bytes());
+        self.allocation_count = self
+            .allocation_count
+            .saturating_add(allocator.allocation_count());
+    }
+}
+
+/// Location in a program at which its execution aborted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramErrorLocation {
+    pub program_id: Pubkey,
+    /// Instruction offset of the failing instruction in the text section
+    pub pc: u64,
+    /// Function containing `pc`, if the program was loaded with symbols
+    pub symbol: Option<String>,
+}
+
+/// A capability of the runtime used by a program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Capability {
+    /// Invoking another program
+    Cpi,
+    /// Changing the data length of an account
+    DataRealloc,
+    /// Setting return data
+    ReturnData,
+    /// Reading a sysvar through a syscall
+    SysvarRead,
+}
+
+/// Syscalls invoked and capabilities used by the programs executed by a
+/// transaction, for auditing their behavior
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilityManifest {
+    /// Names of the invoked syscalls
+    pub syscalls: BTreeSet<String>,
+    pub capabilities: BTreeSet<Capability>,
+}
+
 pub struct SyscallContext {
     pub allocator: BpfAllocator,
     pub accounts_metadata: Vec<SerializedAccountMetadata>,
@@ -173,6 +241,11 @@ pub struct InvokeContext<'a> {
     pub lamports_per_signature: u64,
     pub syscall_context: Vec<Option<SyscallContext>>,
     traces: Vec<Vec<[u64; 12]>>,
+    strict_determinism: bool,
+    heap_allocation_stats: Option<HeapAllocationStats>,
+    record_error_location: bool,
+    error_location: Option<ProgramErrorLocation>,
+    capability_manifest: RefCell<Option<CapabilityManifest>>,
 }
 
 impl<'a> InvokeContext<'a> {
@@ -203,6 +276,11 @@ impl<'a> InvokeContext<'a> {
             lamports_per_signature,
             syscall_context: Vec::new(),
             traces: Vec::new(),
+            strict_determinism: false,
+            heap_allocation_stats: None,
+            record_error_location: false,
+            error_location: None,
+            capability_manifest: RefCell::new(None),
         }
     }
 
@@ -276,6 +354,9 @@ impl<'a> InvokeContext<'a> {
     /// Pop a stack frame from the invocation stack
     pub fn pop(&mut self) -> Result<(), InstructionError> {
         if let Some(Some(syscall_context)) = self.syscall_context.pop() {
+            if let Some(stats) = self.heap_allocation_stats.as_mut() {
+                stats.accumulate(&syscall_context.allocator);
+            }
             self.traces.push(syscall_context.trace_log);
         }
         self.transaction_context.pop()
@@ -485,9 +566,15 @@ impl<'a> InvokeContext<'a> {
             _ => None,
         }
         .ok_or(InstructionError::UnsupportedProgramId)?;
-        entry.ix_usage_counter.fetch_add(1, Ordering::Relaxed);
+        self.programs_loaded_for_tx_batch
+            .record_instruction_usage(&entry);
 
         let program_id = *instruction_context.get_last_program_key(self.transaction_context)?;
+        self.timings.accumulate_program_invocation(
+            &program_id,
+            instruction_context.get_instruction_data().len(),
+            instruction_context.get_number_of_instruction_accounts() as usize,
+        );
         self.transaction_context
             .set_return_data(program_id, Vec::new())?;
         let logger = self.get_log_collector();
@@ -582,6 +669,87 @@ impl<'a> InvokeContext<'a> {
         self.sysvar_cache
     }
 
+    /// Enable or disable strict determinism for this invocation.
+    ///
+    /// In strict determinism mode, syscalls which would otherwise depend on
+    /// inputs that were not provided to the runtime fail with a typed error.
+    pub fn set_strict_determinism(&mut self, strict_determinism: bool) {
+        self.strict_determinism = strict_determinism;
+    }
+
+    /// Whether syscalls must reject non-deterministic inputs
+    pub fn is_strict_determinism(&self) -> bool {
+        self.strict_determinism
+    }
+
+    /// Start recording heap allocation statistics for the programs invoked
+    /// from now on
+    pub fn enable_heap_allocation_stats(&mut self) {
+        self.heap_allocation_stats
+            .get_or_insert_with(HeapAllocationStats::default);
+    }
+
+    /// Heap allocation statistics, if recording is enabled
+    pub fn get_heap_allocation_stats(&self) -> Option<HeapAllocationStats> {
+        self.heap_allocation_stats
+    }
+
+    /// Start recording the location at which a program aborts
+    pub fn enable_error_location_recording(&mut self) {
+        self.record_error_location = true;
+    }
+
+    pub fn is_error_location_recording_enabled(&self) -> bool {
+        self.record_error_location
+    }
+
+    /// Record the location at which a program aborted. Only the first one is
+    /// kept, which is the innermost one if the error propagates through CPI.
+    pub fn record_error_location(&mut self, error_location: ProgramErrorLocation) {
+        if self.record_error_location && self.error_location.is_none() {
+            self.error_location = Some(error_location);
+        }
+    }
+
+    /// Location at which a program aborted, if recording is enabled
+    pub fn get_error_location(&self) -> Option<&ProgramErrorLocation> {
+        self.error_location.as_ref()
+    }
+
+    /// Start recording the syscalls invoked and the capabilities used by
+    /// the programs invoked from now on
+    pub fn enable_capability_recording(&mut self) {
+        self.capability_manifest
+            .get_mut()
+            .get_or_insert_with(CapabilityManifest::default);
+    }
+
+    pub fn is_capability_recording_enabled(&self) -> bool {
+        self.capability_manifest.borrow().is_some()
+    }
+
+    /// Record that a program invoked the syscall `name`
+    pub fn record_syscall(&self, name: &[u8]) {
+        if let Some(manifest) = self.capability_manifest.borrow_mut().as_mut() {
+            let name = String::from_utf8_lossy(name);
+            if !manifest.syscalls.contains(name.as_ref()) {
+                manifest.syscalls.insert(name.into_owned());
+            }
+        }
+    }
+
+    /// Record that a program used `capability`
+    pub fn record_capability(&self, capability: Capability) {
+        if let Some(manifest) = self.capability_manifest.borrow_mut().as_mut() {
+            manifest.capabilities.insert(capability);
+        }
+    }
+
+    /// Syscalls invoked and capabilities used, if recording is enabled
+    pub fn get_capability_manifest(&self) -> Option<CapabilityManifest> {
+        self.capability_manifest.borrow().clone()
+    }
+
     // Should alignment be enforced during user pointer translation
     pub fn get_check_aligned(&self) -> bool {
         self.transaction_context
@@ -1116,6 +1284,99 @@ mod tests {
         invoke_context.pop().unwrap();
     }
 
+    #[test]
+    fn test_heap_allocation_stats() {
+        fn invoke_frame(invoke_context: &mut InvokeContext, allocations: &[usize]) {
+            invoke_context
+                .transaction_context
+                .get_next_instruction_context()
+                .unwrap()
+                .configure(&[0], &[], &[]);
+            invoke_context.push().unwrap();
+            let mut allocator = BpfAllocator::new(1024);
+            for size in allocations {
+                allocator
+                    .alloc(Layout::from_size_align(*size, 8).unwrap())
+                    .unwrap();
+            }
+            invoke_context
+                .set_syscall_context(SyscallContext {
+                    allocator,
+                    accounts_metadata: vec![],
+                    trace_log: vec![],
+                })
+                .unwrap();
+            invoke_context.pop().unwrap();
+        }
+
+        let transaction_accounts =
+            vec![(solana_sdk::pubkey::new_rand(), AccountSharedData::default())];
+        with_mock_invoke_context!(invoke_context, transaction_context, transaction_accounts);
+
+        // Disabled by default
+        invoke_frame(&mut invoke_context, &[8]);
+        assert_eq!(invoke_context.get_heap_allocation_stats(), None);
+
+        invoke_context.enable_heap_allocation_stats();
+        invoke_frame(&mut invoke_context, &[3, 8]);
+        invoke_frame(&mut invoke_context, &[100]);
+        assert_eq!(
+            invoke_context.get_heap_allocation_stats(),
+            Some(HeapAllocationStats {
+                peak_bytes: 100,
+                allocation_count: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_location() {
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+        let location = |pc| ProgramErrorLocation {
+            program_id: Pubkey::new_unique(),
+            pc,
+            symbol: None,
+        };
+
+        // Nothing is recorded unless enabled
+        invoke_context.record_error_location(location(1));
+        assert_eq!(invoke_context.get_error_location(), None);
+
+        // The innermost location is kept
+        invoke_context.enable_error_location_recording();
+        let inner = location(2);
+        invoke_context.record_error_location(inner.clone());
+        invoke_context.record_error_location(location(3));
+        assert_eq!(invoke_context.get_error_location(), Some(&inner));
+    }
+
+    #[test]
+    fn test_capability_manifest() {
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+
+        // Nothing is recorded unless enabled
+        invoke_context.record_syscall(b"sol_log_");
+        invoke_context.record_capability(Capability::Cpi);
+        assert_eq!(invoke_context.get_capability_manifest(), None);
+
+        invoke_context.enable_capability_recording();
+        invoke_context.record_syscall(b"sol_log_");
+        invoke_context.record_syscall(b"sol_invoke_signed_rust");
+        invoke_context.record_syscall(b"sol_log_");
+        invoke_context.record_capability(Capability::Cpi);
+        invoke_context.record_capability(Capability::Cpi);
+        assert_eq!(
+            invoke_context.get_capability_manifest(),
+            Some(CapabilityManifest {
+                syscalls: ["sol_invoke_signed_rust", "sol_log_"]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+                capabilities: BTreeSet::from([Capability::Cpi]),
+            })
+        );
+    }
+
     #[test]
     fn test_process_instruction_accounts_resize_delta() {
         let program_key = Pubkey::new_unique();
@@ -1229,4 +1490,85 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_process_instruction_accounts_data_growth_limit() {
+        let program_key = Pubkey::new_unique();
+        let user_account_data_len = 123u64;
+        let user_account =
+            AccountSharedData::new(100, user_account_data_len as usize, &program_key);
+        let dummy_account = AccountSharedData::new(10, 0, &program_key);
+        let mut program_account = AccountSharedData::new(500, 500, &native_loader::id());
+        program_account.set_executable(true);
+        let transaction_accounts = vec![
+            (Pubkey::new_unique(), user_account),
+            (Pubkey::new_unique(), dummy_account),
+            (program_key, program_account),
+        ];
+        let instruction_accounts = [
+            InstructionAccount {
+                index_in_transaction: 0,
+                index_in_caller: 0,
+                index_in_callee: 0,
+                is_signer: false,
+                is_writable: true,
+            },
+            InstructionAccount {
+                index_in_transaction: 1,
+                index_in_caller: 1,
+                index_in_callee: 1,
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        with_mock_invoke_context!(invoke_context, transaction_context, transaction_accounts);
+        let mut programs_loaded_for_tx_batch = LoadedProgramsForTxBatch::default();
+        programs_loaded_for_tx_batch.replenish(
+            program_key,
+            Arc::new(LoadedProgram::new_builtin(0, 0, MockBuiltin::vm)),
+        );
+        invoke_context.programs_loaded_for_tx_batch = &programs_loaded_for_tx_batch;
+        invoke_context
+            .transaction_context
+            .set_max_accounts_data_growth(Some(2));
+
+        // Growing, shrinking and growing again: shrinking does not give back
+        // growth
+        for (new_len, expected_result, expected_growth, expected_resize_delta) in [
+            (user_account_data_len.saturating_add(1), Ok(()), 1, 1),
+            (user_account_data_len, Ok(()), 1, 0),
+            (
+                user_account_data_len.saturating_add(2),
+                Err(InstructionError::MaxAccountsDataGrowthExceeded),
+                1,
+                0,
+            ),
+            (user_account_data_len.saturating_add(1), Ok(()), 2, 1),
+        ] {
+            let instruction_data =
+                bincode::serialize(&MockInstruction::Resize { new_len }).unwrap();
+            let result = invoke_context.process_instruction(
+                &instruction_data,
+                &instruction_accounts,
+                &[2],
+                &mut 0,
+                &mut ExecuteTimings::default(),
+            );
+            assert_eq!(result, expected_result);
+            assert_eq!(
+                invoke_context
+                    .transaction_context
+                    .accounts_data_growth()
+                    .unwrap(),
+                expected_growth
+            );
+            assert_eq!(
+                invoke_context
+                    .transaction_context
+                    .accounts_resize_delta()
+                    .unwrap(),
+                expected_resize_delta
+            );
+        }
+    }
 }
@@ -12,6 +12,9 @@ extern crate solana_metrics;
 pub use solana_rbpf;
 pub mod compute_budget;
 pub mod compute_budget_processor;
+pub mod cost_calibration;
+#[cfg(feature = "dev-context-only-utils")]
+pub mod float_audit;
 pub mod invoke_context;
 pub mod loaded_programs;
 pub mod log_collector;
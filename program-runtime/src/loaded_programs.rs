@@ -1,6 +1,7 @@
 use {
     crate::{
         invoke_context::{BuiltinFunctionWithContext, InvokeContext},
+        runtime_config::RuntimeConfig,
         timings::ExecuteDetailsTimings,
     },
     log::{debug, error, log_enabled, trace},
@@ -11,11 +12,12 @@ use {
         elf::Executable,
         program::{BuiltinProgram, FunctionRegistry},
         verifier::RequisiteVerifier,
-        vm::Config,
+        vm::{Config, ContextObject},
     },
     solana_sdk::{
         bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable,
         clock::{Epoch, Slot},
+        hash::{hashv, Hash, Hasher},
         loader_v4,
         pubkey::Pubkey,
         saturating_add_assign,
@@ -154,6 +156,8 @@ pub struct Stats {
     pub misses: AtomicU64,
     /// a compiled executable was unloaded
     pub evictions: HashMap<Pubkey, u64>,
+    /// bytes of memory reclaimed by unloading compiled executables
+    pub evicted_bytes: AtomicU64,
     /// an unloaded program was loaded again (opposite of eviction)
     pub reloads: AtomicU64,
     /// a program was loaded or un/re/deployed
@@ -168,8 +172,21 @@ pub struct Stats {
     pub prunes_orphan: AtomicU64,
     /// a program got pruned because it was not recompiled for the next epoch
     pub prunes_environment: AtomicU64,
+    /// a program was removed because its account was mutated outside of the loaders
+    pub invalidations: AtomicU64,
     /// the [SecondLevel] was empty because all slot versions got pruned
     pub empty_entries: AtomicU64,
+    /// transactions which could not use a cached program, by slot of their batch
+    pub visibility_conflicts: HashMap<Slot, VisibilityConflicts>,
+}
+
+/// Transactions of a slot which could not use a cached program.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VisibilityConflicts {
+    /// the program was deployed too recently to be visible, so the transactions failed
+    pub delay_visibility: u64,
+    /// the program was only cached for another environment, so the transactions waited for it to be reloaded
+    pub environment_mismatches: u64,
 }
 
 impl Stats {
@@ -178,6 +195,7 @@ impl Stats {
         let hits = self.hits.load(Ordering::Relaxed);
         let misses = self.misses.load(Ordering::Relaxed);
         let evictions: u64 = self.evictions.values().sum();
+        let evicted_bytes = self.evicted_bytes.load(Ordering::Relaxed);
         let reloads = self.reloads.load(Ordering::Relaxed);
         let insertions = self.insertions.load(Ordering::Relaxed);
         let lost_insertions = self.lost_insertions.load(Ordering::Relaxed);
@@ -185,13 +203,16 @@ impl Stats {
         let one_hit_wonders = self.one_hit_wonders.load(Ordering::Relaxed);
         let prunes_orphan = self.prunes_orphan.load(Ordering::Relaxed);
         let prunes_environment = self.prunes_environment.load(Ordering::Relaxed);
+        let invalidations = self.invalidations.load(Ordering::Relaxed);
         let empty_entries = self.empty_entries.load(Ordering::Relaxed);
+        let visibility_conflicts = self.visibility_conflicts(slot);
         datapoint_info!(
             "loaded-programs-cache-stats",
             ("slot", slot, i64),
             ("hits", hits, i64),
             ("misses", misses, i64),
             ("evictions", evictions, i64),
+            ("evicted_bytes", evicted_bytes, i64),
             ("reloads", reloads, i64),
             ("insertions", insertions, i64),
             ("lost_insertions", lost_insertions, i64),
@@ -199,11 +220,22 @@ impl Stats {
             ("one_hit_wonders", one_hit_wonders, i64),
             ("prunes_orphan", prunes_orphan, i64),
             ("prunes_environment", prunes_environment, i64),
+            ("invalidations", invalidations, i64),
             ("empty_entries", empty_entries, i64),
+            (
+                "delay_visibility_conflicts",
+                visibility_conflicts.delay_visibility,
+                i64
+            ),
+            (
+                "environment_mismatches",
+                visibility_conflicts.environment_mismatches,
+                i64
+            ),
         );
         debug!(
-            "Loaded Programs Cache Stats -- Hits: {}, Misses: {}, Evictions: {}, Reloads: {}, Insertions: {} Lost-Insertions: {}, Replacements: {}, One-Hit-Wonders: {}, Prunes-Orphan: {}, Prunes-Environment: {}, Empty: {}",
-            hits, misses, evictions, reloads, insertions, lost_insertions, replacements, one_hit_wonders, prunes_orphan, prunes_environment, empty_entries
+            "Loaded Programs Cache Stats -- Hits: {}, Misses: {}, Evictions: {}, Evicted-Bytes: {}, Reloads: {}, Insertions: {} Lost-Insertions: {}, Replacements: {}, One-Hit-Wonders: {}, Prunes-Orphan: {}, Prunes-Environment: {}, Invalidations: {}, Empty: {}, Delay-Visibility-Conflicts: {}, Environment-Mismatches: {}",
+            hits, misses, evictions, evicted_bytes, reloads, insertions, lost_insertions, replacements, one_hit_wonders, prunes_orphan, prunes_environment, invalidations, empty_entries, visibility_conflicts.delay_visibility, visibility_conflicts.environment_mismatches
         );
         if log_enabled!(log::Level::Trace) && !self.evictions.is_empty() {
             let mut evictions = self.evictions.iter().collect::<Vec<_>>();
@@ -228,6 +260,26 @@ impl Stats {
     pub fn reset(&mut self) {
         *self = Stats::default();
     }
+
+    /// Transactions of batches at `slot` which could not use a cached program
+    pub fn visibility_conflicts(&self, slot: Slot) -> VisibilityConflicts {
+        self.visibility_conflicts
+            .get(&slot)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn record_visibility_conflicts(&mut self, slot: Slot, conflicts: VisibilityConflicts) {
+        if conflicts == VisibilityConflicts::default() {
+            return;
+        }
+        let recorded = self.visibility_conflicts.entry(slot).or_default();
+        saturating_add_assign!(recorded.delay_visibility, conflicts.delay_visibility);
+        saturating_add_assign!(
+            recorded.environment_mismatches,
+            conflicts.environment_mismatches
+        );
+    }
 }
 
 /// Time measurements for loading a single [LoadedProgram].
@@ -402,6 +454,27 @@ impl LoadedProgram {
         })
     }
 
+    /// Bytes of memory occupied by this entry
+    ///
+    /// This is the entry itself, plus the ELF, read-only section and JIT compiled code of an executable,
+    /// or the function registry of a built-in program. Environments are shared across entries and not included.
+    pub fn mem_size(&self) -> usize {
+        let payload_size = match &self.program {
+            LoadedProgramType::LegacyV0(executable)
+            | LoadedProgramType::LegacyV1(executable)
+            | LoadedProgramType::Typed(executable) => executable.mem_size(),
+            LoadedProgramType::Builtin(program) => program.mem_size(),
+            // Stands in for an executable of the size of its account
+            #[cfg(test)]
+            LoadedProgramType::TestLoaded(_) => self.account_size,
+            LoadedProgramType::FailedVerification(_)
+            | LoadedProgramType::Closed
+            | LoadedProgramType::DelayVisibility
+            | LoadedProgramType::Unloaded(_) => 0,
+        };
+        std::mem::size_of::<Self>().saturating_add(payload_size)
+    }
+
     /// Creates a new built-in program
     pub fn new_builtin(
         deployment_slot: Slot,
@@ -477,6 +550,18 @@ pub struct ProgramRuntimeEnvironments {
     pub program_runtime_v2: ProgramRuntimeEnvironment,
 }
 
+impl ProgramRuntimeEnvironments {
+    /// Identity of the environments, combining the `environment_hash()` of
+    /// both of them. Two sets of environments with the same hash can not
+    /// diverge because one of them offers a syscall the other one lacks.
+    pub fn hash(&self) -> Hash {
+        hashv(&[
+            environment_hash(&self.program_runtime_v1).as_ref(),
+            environment_hash(&self.program_runtime_v2).as_ref(),
+        ])
+    }
+}
+
 impl Default for ProgramRuntimeEnvironments {
     fn default() -> Self {
         let empty_loader = Arc::new(BuiltinProgram::new_loader(
@@ -490,6 +575,87 @@ impl Default for ProgramRuntimeEnvironments {
     }
 }
 
+/// Digest of the syscalls registered in a program runtime environment.
+///
+/// The registry is built from the feature set and may be extended by the
+/// embedder, so the digest changes whenever a feature gated syscall is
+/// activated or an additional syscall is registered.
+pub fn syscall_registry_digest<C: ContextObject>(environment: &BuiltinProgram<C>) -> Hash {
+    let mut hasher = Hasher::default();
+    for (key, (name, _function)) in environment.get_function_registry().iter() {
+        hasher.hash(&key.to_le_bytes());
+        hasher.hash(&(name.len() as u64).to_le_bytes());
+        hasher.hash(name);
+    }
+    hasher.result()
+}
+
+/// Digest of the config of a program runtime environment
+pub fn config_digest(config: &Config) -> Hash {
+    // Destructured so that new fields of the config can not be forgotten
+    let Config {
+        max_call_depth,
+        stack_frame_size,
+        enable_address_translation,
+        enable_stack_frame_gaps,
+        instruction_meter_checkpoint_distance,
+        enable_instruction_meter,
+        enable_instruction_tracing,
+        enable_symbol_and_section_labels,
+        reject_broken_elfs,
+        noop_instruction_rate,
+        sanitize_user_provided_values,
+        external_internal_function_hash_collision,
+        reject_callx_r10,
+        optimize_rodata,
+        new_elf_parser,
+        aligned_memory_mapping,
+        enable_sbpf_v1,
+        enable_sbpf_v2,
+    } = *config;
+    let mut hasher = Hasher::default();
+    for value in [
+        max_call_depth as u64,
+        stack_frame_size as u64,
+        instruction_meter_checkpoint_distance as u64,
+        u64::from(noop_instruction_rate),
+    ] {
+        hasher.hash(&value.to_le_bytes());
+    }
+    hasher.hash(&[
+        u8::from(enable_address_translation),
+        u8::from(enable_stack_frame_gaps),
+        u8::from(enable_instruction_meter),
+        u8::from(enable_instruction_tracing),
+        u8::from(enable_symbol_and_section_labels),
+        u8::from(reject_broken_elfs),
+        u8::from(sanitize_user_provided_values),
+        u8::from(external_internal_function_hash_collision),
+        u8::from(reject_callx_r10),
+        u8::from(optimize_rodata),
+        u8::from(new_elf_parser),
+        u8::from(aligned_memory_mapping),
+        u8::from(enable_sbpf_v1),
+        u8::from(enable_sbpf_v2),
+    ]);
+    hasher.result()
+}
+
+/// Identity of a program runtime environment, made of the `config_digest()`
+/// of its config and the `syscall_registry_digest()` of its syscalls.
+///
+/// Unlike comparing the environments, the identity can be compared across
+/// nodes, e.g. to detect that two of them registered different embedder
+/// syscalls. It does not cover the implementations of the syscalls, which
+/// are only known to the node itself, so whether programs must be
+/// recompiled is decided by comparing the environments.
+pub fn environment_hash<C: ContextObject>(environment: &BuiltinProgram<C>) -> Hash {
+    hashv(&[
+        config_digest(environment.get_config()).as_ref(),
+        syscall_registry_digest(environment).as_ref(),
+    ])
+}
+
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct LoadingTaskCookie(u64);
 
@@ -550,6 +716,21 @@ struct SecondLevel {
     cooperative_loading_lock: Option<(Slot, std::thread::ThreadId)>,
 }
 
+/// Outcome of looking up the version of a program for a TX batch
+enum TxBatchLookup {
+    /// The version the batch can use
+    Found(Arc<LoadedProgram>),
+    /// Tombstone of a version on the fork of the batch which is not
+    /// effective yet
+    DelayVisibility(Arc<LoadedProgram>),
+    /// The program has to be loaded
+    Missing {
+        /// Whether a version effective for the batch was compiled for
+        /// another environment
+        environment_mismatch: bool,
+    },
+}
+
 /// This structure is the global cache of loaded, verified and compiled programs.
 ///
 /// It ...
@@ -586,6 +767,13 @@ pub struct ProgramCache<FG: ForkGraph> {
     pub programs_to_recompile: Vec<(Pubkey, Arc<LoadedProgram>)>,
     /// Statistics counters
     pub stats: Stats,
+    /// Bytes of memory occupied by all entries, maintained as they are inserted, replaced and removed
+    loaded_bytes: usize,
+    /// Limit of [ProgramCache::loaded_bytes], enforced by the evictions in addition to [MAX_LOADED_ENTRY_COUNT]
+    ///
+    /// The entry count alone does not bound the memory usage, as the size of programs varies by orders of magnitude.
+    /// It is configured by [RuntimeConfig::program_cache_max_loaded_bytes].
+    max_loaded_bytes: Option<usize>,
     /// Reference to the block store
     pub fork_graph: Option<Arc<RwLock<FG>>>,
     /// Coordinates TX batches waiting for others to complete their task during cooperative loading
@@ -626,6 +814,9 @@ pub struct LoadedProgramsForTxBatch {
     /// The epoch of the last rerooting
     pub latest_root_epoch: Epoch,
     pub hit_max_limit: bool,
+    /// Whether the batch is a dry run, whose use of the programs is not
+    /// counted in the entries shared with the global [ProgramCache]
+    pub dry_run: bool,
 }
 
 impl LoadedProgramsForTxBatch {
@@ -642,6 +833,7 @@ impl LoadedProgramsForTxBatch {
             upcoming_environments,
             latest_root_epoch,
             hit_max_limit: false,
+            dry_run: false,
         }
     }
 
@@ -657,6 +849,7 @@ impl LoadedProgramsForTxBatch {
             upcoming_environments: cache.get_upcoming_environments_for_epoch(epoch),
             latest_root_epoch: cache.latest_root_epoch,
             hit_max_limit: false,
+            dry_run: false,
         }
     }
 
@@ -703,6 +896,14 @@ impl LoadedProgramsForTxBatch {
         self.slot
     }
 
+    /// Count an instruction executed by `entry`, unless the batch is a dry
+    /// run
+    pub fn record_instruction_usage(&self, entry: &LoadedProgram) {
+        if !self.dry_run {
+            entry.ix_usage_counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
     pub fn set_slot_for_tests(&mut self, slot: Slot) {
         self.slot = slot;
     }
@@ -722,6 +923,15 @@ pub enum LoadedProgramMatchCriteria {
 
 impl<FG: ForkGraph> ProgramCache<FG> {
     pub fn new(root_slot: Slot, root_epoch: Epoch) -> Self {
+        Self::new_with_config(root_slot, root_epoch, &RuntimeConfig::default())
+    }
+
+    /// Creates a cache with the limits configured in `runtime_config`
+    pub fn new_with_config(
+        root_slot: Slot,
+        root_epoch: Epoch,
+        runtime_config: &RuntimeConfig,
+    ) -> Self {
         Self {
             entries: HashMap::new(),
             latest_root_slot: root_slot,
@@ -730,6 +940,8 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             upcoming_environments: None,
             programs_to_recompile: Vec::default(),
             stats: Stats::default(),
+            loaded_bytes: 0,
+            max_loaded_bytes: runtime_config.program_cache_max_loaded_bytes,
             fork_graph: None,
             loading_task_waiter: Arc::new(LoadingTaskWaiter::default()),
         }
@@ -799,11 +1011,16 @@ impl<FG: ForkGraph> ProgramCache<FG> {
                     existing.ix_usage_counter.load(Ordering::Relaxed),
                     Ordering::Relaxed,
                 );
+                self.loaded_bytes = self
+                    .loaded_bytes
+                    .saturating_sub(existing.mem_size())
+                    .saturating_add(entry.mem_size());
                 *existing = Arc::clone(&entry);
                 self.stats.reloads.fetch_add(1, Ordering::Relaxed);
             }
             Err(index) => {
                 self.stats.insertions.fetch_add(1, Ordering::Relaxed);
+                self.loaded_bytes = self.loaded_bytes.saturating_add(entry.mem_size());
                 slot_versions.insert(index, Arc::clone(&entry));
             }
         }
@@ -811,14 +1028,98 @@ impl<FG: ForkGraph> ProgramCache<FG> {
     }
 
     pub fn prune_by_deployment_slot(&mut self, slot: Slot) {
+        let mut removed_bytes = 0usize;
         for second_level in self.entries.values_mut() {
-            second_level
-                .slot_versions
-                .retain(|entry| entry.deployment_slot != slot);
+            second_level.slot_versions.retain(|entry| {
+                let retain = entry.deployment_slot != slot;
+                if !retain {
+                    removed_bytes = removed_bytes.saturating_add(entry.mem_size());
+                }
+                retain
+            });
         }
+        self.loaded_bytes = self.loaded_bytes.saturating_sub(removed_bytes);
         self.remove_programs_with_no_entries();
     }
 
+    /// Invalidates the cached versions of the program `key`, for when its
+    /// account was mutated at `slot` outside of the loaders, e.g. by a
+    /// governance driven hot fix.
+    ///
+    /// The mutation does not change the deployment slot of the program
+    /// account, so the cached versions stay valid for the other forks and for
+    /// the slots before `slot`. Instead, this inserts an unloaded version
+    /// deployed at `slot`, which makes the batches of `slot` and its
+    /// descendants load the program again. The reloaded program takes the
+    /// place of the unloaded version, see `finish_cooperative_loading_task()`.
+    /// Builtins are not loaded from accounts and thus not affected.
+    pub fn invalidate_program(&mut self, key: Pubkey, slot: Slot) {
+        let Some(second_level) = self.entries.get_mut(&key) else {
+            return;
+        };
+        let Some(latest) = second_level.slot_versions.last() else {
+            return;
+        };
+        if matches!(latest.program, LoadedProgramType::Builtin(_)) {
+            return;
+        }
+        let environment = latest
+            .program
+            .get_environment()
+            .unwrap_or(&self.environments.program_runtime_v1)
+            .clone();
+        let invalidation = Arc::new(LoadedProgram {
+            program: LoadedProgramType::Unloaded(environment),
+            account_size: 0,
+            deployment_slot: slot,
+            effective_slot: slot,
+            tx_usage_counter: AtomicU64::default(),
+            ix_usage_counter: AtomicU64::default(),
+            latest_access_slot: AtomicU64::new(slot),
+        });
+        self.loaded_bytes = self.loaded_bytes.saturating_add(invalidation.mem_size());
+        match second_level.slot_versions.binary_search_by(|at| {
+            at.effective_slot
+                .cmp(&slot)
+                .then(at.deployment_slot.cmp(&slot))
+        }) {
+            // Mutated again in the same slot
+            Ok(index) => {
+                let replaced = std::mem::replace(
+                    second_level.slot_versions.get_mut(index).unwrap(),
+                    invalidation,
+                );
+                self.loaded_bytes = self.loaded_bytes.saturating_sub(replaced.mem_size());
+            }
+            Err(index) => second_level.slot_versions.insert(index, invalidation),
+        }
+        self.stats.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The unloaded version of `second_level` which is the latest version
+    /// effective in `slot` on its fork, if any, see `invalidate_program()`
+    fn find_invalidation(
+        second_level: &SecondLevel,
+        latest_root_slot: Slot,
+        fork_graph: &FG,
+        slot: Slot,
+    ) -> Option<(Slot, Slot)> {
+        second_level
+            .slot_versions
+            .iter()
+            .rev()
+            .find(|entry| {
+                entry.effective_slot <= slot
+                    && (entry.deployment_slot <= latest_root_slot
+                        || matches!(
+                            fork_graph.relationship(entry.deployment_slot, slot),
+                            BlockRelation::Equal | BlockRelation::Ancestor
+                        ))
+            })
+            .filter(|entry| matches!(entry.program, LoadedProgramType::Unloaded(_)))
+            .map(|entry| (entry.deployment_slot, entry.effective_slot))
+    }
+
     /// Before rerooting the blockstore this removes all superfluous entries
     pub fn prune(&mut self, new_root_slot: Slot, new_root_epoch: Epoch) {
         let Some(fork_graph) = self.fork_graph.clone() else {
@@ -838,7 +1139,9 @@ impl<FG: ForkGraph> ProgramCache<FG> {
                 self.programs_to_recompile.clear();
             }
         }
+        let mut removed_bytes = 0usize;
         for second_level in self.entries.values_mut() {
+            let previous_bytes = Self::mem_size_of(second_level.slot_versions.iter());
             // Remove entries un/re/deployed on orphan forks
             let mut first_ancestor_found = false;
             let mut first_ancestor_env = None;
@@ -893,12 +1196,49 @@ impl<FG: ForkGraph> ProgramCache<FG> {
                 .cloned()
                 .collect();
             second_level.slot_versions.reverse();
+            removed_bytes = removed_bytes.saturating_add(
+                previous_bytes.saturating_sub(Self::mem_size_of(second_level.slot_versions.iter())),
+            );
         }
+        self.loaded_bytes = self.loaded_bytes.saturating_sub(removed_bytes);
         self.remove_programs_with_no_entries();
         debug_assert!(self.latest_root_slot <= new_root_slot);
         self.latest_root_slot = new_root_slot;
     }
 
+    /// Removes the entries verified for environments other than the ones of
+    /// `current_epoch` and the upcoming ones, and releases the memory held
+    /// by the index. Unlike `prune()` it needs no fork graph, so that
+    /// embedders which never reroot can call it too. Returns the removed
+    /// entries.
+    pub fn prune_outdated_environments(&mut self, current_epoch: Epoch) -> Vec<Arc<LoadedProgram>> {
+        let environments = self.get_environments_for_epoch(current_epoch).clone();
+        let upcoming_environments = self.upcoming_environments.clone();
+        let mut removed = vec![];
+        for second_level in self.entries.values_mut() {
+            second_level.slot_versions.retain(|entry| {
+                let outdated = !Self::matches_environment(entry, &environments)
+                    && !upcoming_environments
+                        .as_ref()
+                        .is_some_and(|upcoming| Self::matches_environment(entry, upcoming));
+                if outdated {
+                    removed.push(entry.clone());
+                }
+                !outdated
+            });
+            second_level.slot_versions.shrink_to_fit();
+        }
+        self.stats
+            .prunes_environment
+            .fetch_add(removed.len() as u64, Ordering::Relaxed);
+        self.loaded_bytes = self
+            .loaded_bytes
+            .saturating_sub(Self::mem_size_of(removed.iter()));
+        self.remove_programs_with_no_entries();
+        self.entries.shrink_to_fit();
+        removed
+    }
+
     fn matches_environment(
         entry: &Arc<LoadedProgram>,
         environments: &ProgramRuntimeEnvironments,
@@ -923,6 +1263,56 @@ impl<FG: ForkGraph> ProgramCache<FG> {
         }
     }
 
+    /// Finds the version of a program in `second_level` which the
+    /// transactions of `loaded_programs_for_tx_batch` can use
+    fn find_for_tx_batch(
+        second_level: &SecondLevel,
+        latest_root_slot: Slot,
+        fork_graph: &FG,
+        match_criteria: &LoadedProgramMatchCriteria,
+        loaded_programs_for_tx_batch: &LoadedProgramsForTxBatch,
+    ) -> TxBatchLookup {
+        let mut environment_mismatch = false;
+        for entry in second_level.slot_versions.iter().rev() {
+            if entry.deployment_slot <= latest_root_slot
+                || matches!(
+                    fork_graph
+                        .relationship(entry.deployment_slot, loaded_programs_for_tx_batch.slot),
+                    BlockRelation::Equal | BlockRelation::Ancestor
+                )
+            {
+                if loaded_programs_for_tx_batch.slot >= entry.effective_slot
+                    && Self::matches_environment(entry, &loaded_programs_for_tx_batch.environments)
+                {
+                    if !Self::matches_loaded_program_criteria(entry, match_criteria) {
+                        break;
+                    }
+                    if let LoadedProgramType::Unloaded(_environment) = &entry.program {
+                        break;
+                    }
+                    return TxBatchLookup::Found(entry.clone());
+                } else if entry
+                    .is_implicit_delay_visibility_tombstone(loaded_programs_for_tx_batch.slot)
+                {
+                    // Found a program entry on the current fork, but it's not effective
+                    // yet. It indicates that the program has delayed visibility. Return
+                    // the tombstone to reflect that.
+                    return TxBatchLookup::DelayVisibility(Arc::new(LoadedProgram::new_tombstone(
+                        entry.deployment_slot,
+                        LoadedProgramType::DelayVisibility,
+                    )));
+                } else {
+                    // Effective, but compiled for another environment
+                    environment_mismatch |=
+                        loaded_programs_for_tx_batch.slot >= entry.effective_slot;
+                }
+            }
+        }
+        TxBatchLookup::Missing {
+            environment_mismatch,
+        }
+    }
+
     /// Extracts a subset of the programs relevant to a transaction batch
     /// and returns which program accounts the accounts DB needs to load.
     pub fn extract(
@@ -934,53 +1324,42 @@ impl<FG: ForkGraph> ProgramCache<FG> {
         debug_assert!(self.fork_graph.is_some());
         let locked_fork_graph = self.fork_graph.as_ref().unwrap().read().unwrap();
         let mut cooperative_loading_task = None;
+        let mut visibility_conflicts = VisibilityConflicts::default();
         search_for.retain(|(key, (match_criteria, usage_count))| {
-            if let Some(second_level) = self.entries.get_mut(key) {
-                for entry in second_level.slot_versions.iter().rev() {
-                    if entry.deployment_slot <= self.latest_root_slot
-                        || matches!(
-                            locked_fork_graph.relationship(
-                                entry.deployment_slot,
-                                loaded_programs_for_tx_batch.slot
-                            ),
-                            BlockRelation::Equal | BlockRelation::Ancestor
-                        )
-                    {
-                        let entry_to_return = if loaded_programs_for_tx_batch.slot
-                            >= entry.effective_slot
-                            && Self::matches_environment(
-                                entry,
-                                &loaded_programs_for_tx_batch.environments,
-                            ) {
-                            if !Self::matches_loaded_program_criteria(entry, match_criteria) {
-                                break;
-                            }
-                            if let LoadedProgramType::Unloaded(_environment) = &entry.program {
-                                break;
-                            }
-                            entry.clone()
-                        } else if entry.is_implicit_delay_visibility_tombstone(
-                            loaded_programs_for_tx_batch.slot,
-                        ) {
-                            // Found a program entry on the current fork, but it's not effective
-                            // yet. It indicates that the program has delayed visibility. Return
-                            // the tombstone to reflect that.
-                            Arc::new(LoadedProgram::new_tombstone(
-                                entry.deployment_slot,
-                                LoadedProgramType::DelayVisibility,
-                            ))
-                        } else {
-                            continue;
-                        };
-                        entry_to_return.update_access_slot(loaded_programs_for_tx_batch.slot);
-                        entry_to_return
-                            .tx_usage_counter
-                            .fetch_add(*usage_count, Ordering::Relaxed);
-                        loaded_programs_for_tx_batch
-                            .entries
-                            .insert(*key, entry_to_return);
-                        return false;
+            if let Some(second_level) = self.entries.get(key) {
+                let entry_to_return = match Self::find_for_tx_batch(
+                    second_level,
+                    self.latest_root_slot,
+                    &*locked_fork_graph,
+                    match_criteria,
+                    loaded_programs_for_tx_batch,
+                ) {
+                    TxBatchLookup::Found(entry) => Some(entry),
+                    TxBatchLookup::DelayVisibility(tombstone) => {
+                        saturating_add_assign!(visibility_conflicts.delay_visibility, *usage_count);
+                        Some(tombstone)
                     }
+                    TxBatchLookup::Missing {
+                        environment_mismatch,
+                    } => {
+                        if environment_mismatch && is_first_round {
+                            saturating_add_assign!(
+                                visibility_conflicts.environment_mismatches,
+                                *usage_count
+                            );
+                        }
+                        None
+                    }
+                };
+                if let Some(entry_to_return) = entry_to_return {
+                    entry_to_return.update_access_slot(loaded_programs_for_tx_batch.slot);
+                    entry_to_return
+                        .tx_usage_counter
+                        .fetch_add(*usage_count, Ordering::Relaxed);
+                    loaded_programs_for_tx_batch
+                        .entries
+                        .insert(*key, entry_to_return);
+                    return false;
                 }
             }
             if cooperative_loading_task.is_none() {
@@ -998,6 +1377,8 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             true
         });
         drop(locked_fork_graph);
+        self.stats
+            .record_visibility_conflicts(loaded_programs_for_tx_batch.slot, visibility_conflicts);
         if is_first_round {
             self.stats
                 .misses
@@ -1010,6 +1391,39 @@ impl<FG: ForkGraph> ProgramCache<FG> {
         cooperative_loading_task
     }
 
+    /// Like [ProgramCache::extract], but leaves the cache as it is: the usage
+    /// of the extracted programs is not counted, no statistics are recorded
+    /// and no cooperative loading task is selected. The batch becomes a dry
+    /// run. The programs left in `search_for` are loaded by the caller, who
+    /// must not store them in the cache.
+    pub fn extract_for_dry_run(
+        &self,
+        search_for: &mut Vec<(Pubkey, (LoadedProgramMatchCriteria, u64))>,
+        loaded_programs_for_tx_batch: &mut LoadedProgramsForTxBatch,
+    ) {
+        debug_assert!(self.fork_graph.is_some());
+        let locked_fork_graph = self.fork_graph.as_ref().unwrap().read().unwrap();
+        loaded_programs_for_tx_batch.dry_run = true;
+        search_for.retain(|(key, (match_criteria, _usage_count))| {
+            let Some(second_level) = self.entries.get(key) else {
+                return true;
+            };
+            match Self::find_for_tx_batch(
+                second_level,
+                self.latest_root_slot,
+                &*locked_fork_graph,
+                match_criteria,
+                loaded_programs_for_tx_batch,
+            ) {
+                TxBatchLookup::Found(entry) | TxBatchLookup::DelayVisibility(entry) => {
+                    loaded_programs_for_tx_batch.entries.insert(*key, entry);
+                    false
+                }
+                TxBatchLookup::Missing { .. } => true,
+            }
+        });
+    }
+
     /// Called by Bank::replenish_program_cache() for each program that is done loading.
     pub fn finish_cooperative_loading_task(
         &mut self,
@@ -1023,6 +1437,42 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             Some((slot, std::thread::current().id()))
         );
         second_level.cooperative_loading_lock = None;
+        // A program invalidated on this fork is reloaded as the version of
+        // the invalidation, since its account still reports the deployment
+        // slot from before the mutation. It takes the place of the unloaded
+        // version whatever the result of the reload is, so that a hot fix
+        // which fails verification is not loaded again in every batch.
+        let invalidation = Self::find_invalidation(
+            second_level,
+            self.latest_root_slot,
+            &*self.fork_graph.as_ref().unwrap().read().unwrap(),
+            slot,
+        )
+        .filter(|(deployment_slot, _)| *deployment_slot > loaded_program.deployment_slot);
+        if let Some((deployment_slot, effective_slot)) = invalidation {
+            let index = second_level.slot_versions.binary_search_by(|at| {
+                at.effective_slot
+                    .cmp(&effective_slot)
+                    .then(at.deployment_slot.cmp(&deployment_slot))
+            });
+            // The loaded program is not shared before it is inserted
+            let was_occupied = if let (Ok(index), Ok(mut program)) =
+                (index, Arc::try_unwrap(loaded_program))
+            {
+                program.deployment_slot = deployment_slot;
+                program.effective_slot = effective_slot;
+                let program = Arc::new(program);
+                self.loaded_bytes = self.loaded_bytes.saturating_add(program.mem_size());
+                let replaced =
+                    std::mem::replace(second_level.slot_versions.get_mut(index).unwrap(), program);
+                self.loaded_bytes = self.loaded_bytes.saturating_sub(replaced.mem_size());
+                true
+            } else {
+                false
+            };
+            self.loading_task_waiter.notify();
+            return was_occupied;
+        }
         // Check that it will be visible to our own fork once inserted
         if loaded_program.deployment_slot > self.latest_root_slot
             && !matches!(
@@ -1077,6 +1527,30 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             .collect()
     }
 
+    /// Returns the bytes of memory occupied by all entries
+    pub fn loaded_bytes(&self) -> usize {
+        self.loaded_bytes
+    }
+
+    /// Returns the limit of [ProgramCache::loaded_bytes], if any
+    pub fn max_loaded_bytes(&self) -> Option<usize> {
+        self.max_loaded_bytes
+    }
+
+    fn mem_size_of<'a>(programs: impl Iterator<Item = &'a Arc<LoadedProgram>>) -> usize {
+        programs
+            .map(|program| program.mem_size())
+            .fold(0, usize::saturating_add)
+    }
+
+    /// Returns the bytes to reclaim to reduce [ProgramCache::loaded_bytes] to the given percentage of the budget
+    fn bytes_over_budget(&self, shrink_to: PercentageInteger) -> usize {
+        self.max_loaded_bytes.map_or(0, |max_loaded_bytes| {
+            self.loaded_bytes()
+                .saturating_sub(shrink_to.apply_to(max_loaded_bytes))
+        })
+    }
+
     /// Unloads programs which were used infrequently
     pub fn sort_and_unload(&mut self, shrink_to: PercentageInteger) {
         let mut sorted_candidates = self.get_flattened_entries(true, true);
@@ -1085,16 +1559,25 @@ impl<FG: ForkGraph> ProgramCache<FG> {
         let num_to_unload = sorted_candidates
             .len()
             .saturating_sub(shrink_to.apply_to(MAX_LOADED_ENTRY_COUNT));
-        self.unload_program_entries(sorted_candidates.iter().take(num_to_unload));
+        let mut bytes_to_reclaim = self.bytes_over_budget(shrink_to);
+        for (index, (program, entry)) in sorted_candidates.iter().enumerate() {
+            if index >= num_to_unload && bytes_to_reclaim == 0 {
+                break;
+            }
+            let reclaimed_bytes = self.unload_program_entry(program, entry);
+            bytes_to_reclaim = bytes_to_reclaim.saturating_sub(reclaimed_bytes);
+        }
     }
 
     /// Evicts programs using 2's random selection, choosing the least used program out of the two entries.
-    /// The eviction is performed enough number of times to reduce the cache usage to the given percentage.
+    /// The eviction is performed enough number of times to reduce the cache usage to the given percentage,
+    /// both in entries and in bytes if there is a [ProgramCache::max_loaded_bytes].
     pub fn evict_using_2s_random_selection(&mut self, shrink_to: PercentageInteger, now: Slot) {
         let mut candidates = self.get_flattened_entries(true, true);
         let num_to_unload = candidates
             .len()
             .saturating_sub(shrink_to.apply_to(MAX_LOADED_ENTRY_COUNT));
+        let mut bytes_to_reclaim = self.bytes_over_budget(shrink_to);
         fn random_index_and_usage_counter(
             candidates: &[(Pubkey, Arc<LoadedProgram>)],
             now: Slot,
@@ -1109,7 +1592,8 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             (index, usage_counter)
         }
 
-        for _ in 0..num_to_unload {
+        let mut num_unloaded = 0usize;
+        while !candidates.is_empty() && (num_unloaded < num_to_unload || bytes_to_reclaim > 0) {
             let (index1, usage_counter1) = random_index_and_usage_counter(&candidates, now);
             let (index2, usage_counter2) = random_index_and_usage_counter(&candidates, now);
 
@@ -1118,14 +1602,20 @@ impl<FG: ForkGraph> ProgramCache<FG> {
             } else {
                 candidates.swap_remove(index2)
             };
-            self.unload_program_entry(&program, &entry);
+            let reclaimed_bytes = self.unload_program_entry(&program, &entry);
+            bytes_to_reclaim = bytes_to_reclaim.saturating_sub(reclaimed_bytes);
+            num_unloaded = num_unloaded.saturating_add(1);
         }
     }
 
     /// Removes all the entries at the given keys, if they exist
     pub fn remove_programs(&mut self, keys: impl Iterator<Item = Pubkey>) {
         for k in keys {
-            self.entries.remove(&k);
+            if let Some(second_level) = self.entries.remove(&k) {
+                self.loaded_bytes = self
+                    .loaded_bytes
+                    .saturating_sub(Self::mem_size_of(second_level.slot_versions.iter()));
+            }
         }
     }
 
@@ -1139,7 +1629,12 @@ impl<FG: ForkGraph> ProgramCache<FG> {
 
     /// This function removes the given entry for the given program from the cache.
     /// The function expects that the program and entry exists in the cache. Otherwise it'll panic.
-    fn unload_program_entry(&mut self, program: &Pubkey, remove_entry: &Arc<LoadedProgram>) {
+    /// Returns the bytes of memory reclaimed.
+    fn unload_program_entry(
+        &mut self,
+        program: &Pubkey,
+        remove_entry: &Arc<LoadedProgram>,
+    ) -> usize {
         let second_level = self.entries.get_mut(program).expect("Cache lookup failed");
         let candidate = second_level
             .slot_versions
@@ -1159,16 +1654,15 @@ impl<FG: ForkGraph> ProgramCache<FG> {
                 .entry(*program)
                 .and_modify(|c| saturating_add_assign!(*c, 1))
                 .or_insert(1);
+            let reclaimed_bytes = candidate.mem_size().saturating_sub(unloaded.mem_size());
+            self.stats
+                .evicted_bytes
+                .fetch_add(reclaimed_bytes as u64, Ordering::Relaxed);
+            self.loaded_bytes = self.loaded_bytes.saturating_sub(reclaimed_bytes);
             *candidate = Arc::new(unloaded);
-        }
-    }
-
-    fn unload_program_entries<'a>(
-        &mut self,
-        remove: impl Iterator<Item = &'a (Pubkey, Arc<LoadedProgram>)>,
-    ) {
-        for (program, entry) in remove {
-            self.unload_program_entry(program, entry);
+            reclaimed_bytes
+        } else {
+            0
         }
     }
 
@@ -1206,14 +1700,18 @@ impl<FG: ForkGraph> solana_frozen_abi::abi_example::AbiExample for ProgramCache<
 #[cfg(test)]
 mod tests {
     use {
-        crate::loaded_programs::{
-            BlockRelation, ForkGraph, LoadedProgram, LoadedProgramMatchCriteria, LoadedProgramType,
-            LoadedProgramsForTxBatch, ProgramCache, ProgramRuntimeEnvironment,
-            ProgramRuntimeEnvironments, DELAY_VISIBILITY_SLOT_OFFSET,
+        crate::{
+            loaded_programs::{
+                config_digest, BlockRelation, ForkGraph, LoadedProgram, LoadedProgramMatchCriteria,
+                LoadedProgramType, LoadedProgramsForTxBatch, ProgramCache,
+                ProgramRuntimeEnvironment, ProgramRuntimeEnvironments, VisibilityConflicts,
+                DELAY_VISIBILITY_SLOT_OFFSET,
+            },
+            runtime_config::RuntimeConfig,
         },
         assert_matches::assert_matches,
         percentage::Percentage,
-        solana_rbpf::program::BuiltinProgram,
+        solana_rbpf::{program::BuiltinProgram, vm::Config},
         solana_sdk::{clock::Slot, pubkey::Pubkey},
         std::{
             ops::ControlFlow,
@@ -1237,6 +1735,16 @@ mod tests {
         cache
     }
 
+    /// The bytes occupied by the entries of `cache`, counted from scratch
+    fn count_loaded_bytes<FG: ForkGraph>(cache: &ProgramCache<FG>) -> usize {
+        ProgramCache::<FG>::mem_size_of(
+            cache
+                .entries
+                .values()
+                .flat_map(|second_level| second_level.slot_versions.iter()),
+        )
+    }
+
     fn new_test_loaded_program(deployment_slot: Slot, effective_slot: Slot) -> Arc<LoadedProgram> {
         new_test_loaded_program_with_usage(deployment_slot, effective_slot, AtomicU64::default())
     }
@@ -1595,6 +2103,85 @@ mod tests {
         assert_eq!(num_tombstones, num_tombstones_expected);
     }
 
+    #[test]
+    fn test_eviction_by_bytes() {
+        let cache = ProgramCache::<TestForkGraph>::new_with_config(
+            0,
+            0,
+            &RuntimeConfig {
+                program_cache_max_loaded_bytes: Some(1_000_000),
+                ..RuntimeConfig::default()
+            },
+        );
+        assert_eq!(cache.max_loaded_bytes(), Some(1_000_000));
+
+        let mut cache = new_mock_cache::<TestForkGraph>();
+        assert_eq!(cache.max_loaded_bytes(), None);
+        assert_eq!(cache.loaded_bytes(), 0);
+
+        // (account size, usage counter) of each program
+        let [largest, smallest, medium, most_used] = [(8000, 1), (1000, 2), (2000, 3), (4000, 4)]
+            .map(|(account_size, usage)| {
+                let program_id = Pubkey::new_unique();
+                let program = Arc::new(LoadedProgram {
+                    account_size,
+                    ..Arc::into_inner(new_test_loaded_program_with_usage(
+                        0,
+                        1,
+                        AtomicU64::new(usage),
+                    ))
+                    .unwrap()
+                });
+                cache.assign_program(program_id, program);
+                program_id
+            });
+        let is_loaded = |cache: &ProgramCache<TestForkGraph>, program_id: &Pubkey| {
+            cache
+                .get_slot_versions_for_tests(program_id)
+                .iter()
+                .all(|program| matches!(program.program, LoadedProgramType::TestLoaded(_)))
+        };
+        // The entries themselves, which remain after unloading
+        let entries_size = std::mem::size_of::<LoadedProgram>().saturating_mul(4);
+        assert_eq!(cache.loaded_bytes(), entries_size.saturating_add(15000));
+
+        // Without a budget, the entry count is below the limit
+        cache.sort_and_unload(Percentage::from(100));
+        assert!(is_loaded(&cache, &largest));
+
+        // The least used program is the largest, unloading it is enough
+        cache.max_loaded_bytes = Some(cache.loaded_bytes().saturating_sub(5000));
+        cache.sort_and_unload(Percentage::from(100));
+        assert!(!is_loaded(&cache, &largest));
+        assert!(is_loaded(&cache, &smallest));
+        assert!(is_loaded(&cache, &medium));
+        assert!(is_loaded(&cache, &most_used));
+        assert_eq!(cache.loaded_bytes(), entries_size.saturating_add(7000));
+        assert_eq!(cache.stats.evicted_bytes.load(Ordering::Relaxed), 8000);
+
+        // The two next least used programs are needed
+        cache.max_loaded_bytes = Some(cache.loaded_bytes().saturating_sub(2000));
+        cache.sort_and_unload(Percentage::from(100));
+        assert!(!is_loaded(&cache, &smallest));
+        assert!(!is_loaded(&cache, &medium));
+        assert!(is_loaded(&cache, &most_used));
+        assert_eq!(cache.stats.evicted_bytes.load(Ordering::Relaxed), 11000);
+        assert_eq!(cache.loaded_bytes(), count_loaded_bytes(&cache));
+
+        // The budget can not be met by unloading, which stops once there is nothing left to unload
+        cache.max_loaded_bytes = Some(entries_size);
+        cache.evict_using_2s_random_selection(Percentage::from(90), 0);
+        assert!(!is_loaded(&cache, &most_used));
+        assert_eq!(cache.loaded_bytes(), entries_size);
+        assert_eq!(cache.stats.evicted_bytes.load(Ordering::Relaxed), 15000);
+
+        // Removed entries are no longer accounted for
+        cache.remove_programs([largest, smallest].into_iter());
+        assert_eq!(cache.loaded_bytes(), entries_size / 2);
+        cache.prune_by_deployment_slot(0);
+        assert_eq!(cache.loaded_bytes(), 0);
+    }
+
     #[test]
     fn test_usage_count_of_unloaded_program() {
         let mut cache = new_mock_cache::<TestForkGraph>();
@@ -2353,6 +2940,49 @@ mod tests {
         assert!(match_missing(&missing, &program3, false));
     }
 
+    #[test]
+    fn test_extract_for_dry_run() {
+        let mut cache = new_mock_cache::<TestForkGraphSpecific>();
+        let mut fork_graph = TestForkGraphSpecific::default();
+        fork_graph.insert_fork(&[0, 10, 20]);
+        cache.set_fork_graph(Arc::new(RwLock::new(fork_graph)));
+
+        let program1 = Pubkey::new_unique();
+        cache.assign_program(program1, new_test_loaded_program(0, 1));
+        let program2 = Pubkey::new_unique();
+
+        let mut missing = vec![
+            (program1, (LoadedProgramMatchCriteria::NoCriteria, 1)),
+            (program2, (LoadedProgramMatchCriteria::NoCriteria, 1)),
+        ];
+        let mut extracted = LoadedProgramsForTxBatch::new(20, cache.environments.clone(), None, 0);
+        cache.extract_for_dry_run(&mut missing, &mut extracted);
+
+        assert!(extracted.dry_run);
+        assert!(match_slot(&extracted, &program1, 0, 20));
+        assert!(match_missing(&missing, &program2, false));
+        assert_eq!(missing.len(), 1);
+
+        // Neither the usage of the programs nor the lookups are recorded
+        let entry = extracted.find(&program1).unwrap();
+        extracted.record_instruction_usage(&entry);
+        assert_eq!(entry.tx_usage_counter.load(Ordering::Relaxed), 0);
+        assert_eq!(entry.ix_usage_counter.load(Ordering::Relaxed), 0);
+        assert_eq!(entry.latest_access_slot.load(Ordering::Relaxed), 0);
+        assert_eq!(cache.stats.hits.load(Ordering::Relaxed), 0);
+        assert_eq!(cache.stats.misses.load(Ordering::Relaxed), 0);
+        // No loading task was selected for the missing program
+        assert!(!cache.entries.contains_key(&program2));
+
+        let mut extracted = LoadedProgramsForTxBatch::new(20, cache.environments.clone(), None, 0);
+        let mut missing = vec![(program1, (LoadedProgramMatchCriteria::NoCriteria, 1))];
+        cache.extract(&mut missing, &mut extracted, true);
+        let entry = extracted.find(&program1).unwrap();
+        extracted.record_instruction_usage(&entry);
+        assert_eq!(entry.tx_usage_counter.load(Ordering::Relaxed), 1);
+        assert_eq!(entry.ix_usage_counter.load(Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_extract_unloaded() {
         let mut cache = new_mock_cache::<TestForkGraphSpecific>();
@@ -2447,6 +3077,73 @@ mod tests {
         assert!(match_missing(&missing, &program3, true));
     }
 
+    #[test]
+    fn test_extract_visibility_conflicts() {
+        let mut cache = new_mock_cache::<TestForkGraphSpecific>();
+        let mut fork_graph = TestForkGraphSpecific::default();
+        fork_graph.insert_fork(&[0, 5, 10, 11]);
+        cache.set_fork_graph(Arc::new(RwLock::new(fork_graph)));
+
+        // Deployed in the slot of the batch
+        let program1 = Pubkey::new_unique();
+        cache.assign_program(
+            program1,
+            new_test_loaded_program(10, 10 + DELAY_VISIBILITY_SLOT_OFFSET),
+        );
+        let program2 = Pubkey::new_unique();
+        cache.assign_program(program2, new_test_loaded_program(0, 1));
+
+        let mut missing = vec![
+            (program1, (LoadedProgramMatchCriteria::NoCriteria, 3)),
+            (program2, (LoadedProgramMatchCriteria::NoCriteria, 2)),
+        ];
+        let mut extracted = LoadedProgramsForTxBatch::new(10, cache.environments.clone(), None, 0);
+        cache.extract(&mut missing, &mut extracted, true);
+        assert_eq!(
+            cache.stats.visibility_conflicts(10),
+            VisibilityConflicts {
+                delay_visibility: 3,
+                environment_mismatches: 0,
+            }
+        );
+
+        // Batch of the same slot with other environments, e.g. after a feature activation
+        let environments = ProgramRuntimeEnvironments {
+            program_runtime_v1: Arc::new(BuiltinProgram::new_mock()),
+            program_runtime_v2: Arc::new(BuiltinProgram::new_mock()),
+        };
+        let mut missing = vec![
+            (program1, (LoadedProgramMatchCriteria::NoCriteria, 1)),
+            (program2, (LoadedProgramMatchCriteria::NoCriteria, 2)),
+        ];
+        let mut extracted = LoadedProgramsForTxBatch::new(10, environments, None, 0);
+        cache.extract(&mut missing, &mut extracted, true);
+        assert!(match_missing(&missing, &program2, true));
+        assert_eq!(
+            cache.stats.visibility_conflicts(10),
+            VisibilityConflicts {
+                delay_visibility: 4,
+                environment_mismatches: 2,
+            }
+        );
+
+        // Visible in the next slot
+        let mut missing = vec![(program1, (LoadedProgramMatchCriteria::NoCriteria, 1))];
+        let mut extracted = LoadedProgramsForTxBatch::new(11, cache.environments.clone(), None, 0);
+        cache.extract(&mut missing, &mut extracted, true);
+        assert!(match_slot(&extracted, &program1, 10, 11));
+        assert_eq!(
+            cache.stats.visibility_conflicts(11),
+            VisibilityConflicts::default()
+        );
+
+        cache.stats.reset();
+        assert_eq!(
+            cache.stats.visibility_conflicts(10),
+            VisibilityConflicts::default()
+        );
+    }
+
     #[test]
     fn test_unloaded() {
         let mut cache = new_mock_cache::<TestForkGraph>();
@@ -2618,6 +3315,112 @@ mod tests {
         assert!(match_missing(&missing, &program2, false));
     }
 
+    #[test]
+    fn test_invalidate_program() {
+        let mut cache = new_mock_cache::<TestForkGraphSpecific>();
+
+        // Fork graph created for the test
+        //                   0
+        //                   |
+        //                   5
+        //                 /   \
+        //                7    15
+        //                |     |
+        //                10   25
+        //                |
+        //                20
+        let mut fork_graph = TestForkGraphSpecific::default();
+        fork_graph.insert_fork(&[0, 5, 7, 10, 20]);
+        fork_graph.insert_fork(&[0, 5, 15, 25]);
+        cache.set_fork_graph(Arc::new(RwLock::new(fork_graph)));
+
+        let program1 = Pubkey::new_unique();
+        cache.assign_program(program1, new_test_loaded_program(0, 1));
+        cache.assign_program(program1, new_test_loaded_program(5, 6));
+
+        let builtin = Pubkey::new_unique();
+        cache.assign_program(builtin, new_test_builtin_program(0, 0));
+
+        // The account of program1 is mutated in slot 10
+        cache.invalidate_program(program1, 10);
+        assert_eq!(cache.stats.invalidations.load(Ordering::Relaxed), 1);
+        assert_eq!(cache.loaded_bytes(), count_loaded_bytes(&cache));
+        let extract = |cache: &mut ProgramCache<TestForkGraphSpecific>, slot| {
+            let mut missing = vec![(program1, (LoadedProgramMatchCriteria::NoCriteria, 1))];
+            let mut extracted =
+                LoadedProgramsForTxBatch::new(slot, cache.environments.clone(), None, 0);
+            let task = cache.extract(&mut missing, &mut extracted, true);
+            (missing, extracted, task)
+        };
+
+        // The sibling fork and the slots before the mutation keep the cached version
+        for slot in [7, 25] {
+            let (_missing, extracted, _task) = extract(&mut cache, slot);
+            assert!(match_slot(&extracted, &program1, 5, slot));
+        }
+
+        // The slots of the mutation and after it load the program again
+        let (missing, _extracted, task) = extract(&mut cache, 20);
+        assert!(match_missing(&missing, &program1, false));
+        assert_eq!(task, Some((program1, 1)));
+
+        // The reloaded program keeps the deployment slot of its account, but
+        // replaces the invalidated version on the fork of the mutation only
+        cache.finish_cooperative_loading_task(20, program1, new_test_loaded_program(5, 6));
+        let (_missing, extracted, _task) = extract(&mut cache, 20);
+        assert!(match_slot(&extracted, &program1, 10, 20));
+        let (_missing, extracted, _task) = extract(&mut cache, 25);
+        assert!(match_slot(&extracted, &program1, 5, 25));
+        assert_eq!(
+            cache
+                .get_slot_versions_for_tests(&program1)
+                .iter()
+                .map(|entry| (entry.deployment_slot, entry.effective_slot))
+                .collect::<Vec<_>>(),
+            vec![(0, 1), (5, 6), (10, 10)]
+        );
+        assert_eq!(cache.loaded_bytes(), count_loaded_bytes(&cache));
+
+        // Builtins are not loaded from accounts, so they stay
+        cache.invalidate_program(builtin, 10);
+        assert_eq!(cache.get_slot_versions_for_tests(&builtin).len(), 1);
+
+        // Unknown programs are ignored
+        cache.invalidate_program(Pubkey::new_unique(), 10);
+        assert_eq!(cache.stats.invalidations.load(Ordering::Relaxed), 1);
+
+        // Mutated again in slot 10, the reloaded program is replaced
+        cache.invalidate_program(program1, 10);
+        assert_eq!(cache.get_slot_versions_for_tests(&program1).len(), 3);
+        assert_eq!(cache.loaded_bytes(), count_loaded_bytes(&cache));
+    }
+
+    #[test]
+    fn test_config_digest() {
+        let config = Config::default();
+        assert_eq!(config_digest(&config), config_digest(&Config::default()));
+        for other in [
+            Config {
+                max_call_depth: config.max_call_depth.saturating_add(1),
+                ..config
+            },
+            Config {
+                noop_instruction_rate: config.noop_instruction_rate.saturating_add(1),
+                ..config
+            },
+            Config {
+                reject_broken_elfs: !config.reject_broken_elfs,
+                ..config
+            },
+            Config {
+                enable_sbpf_v2: !config.enable_sbpf_v2,
+                ..config
+            },
+        ] {
+            assert_ne!(config_digest(&config), config_digest(&other));
+        }
+    }
+
     #[test]
     fn test_usable_entries_for_slot() {
         new_mock_cache::<TestForkGraph>();
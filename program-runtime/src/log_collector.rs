@@ -1,13 +1,105 @@
 pub use log;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, mem::size_of, rc::Rc};
 
-const LOG_MESSAGES_BYTES_LIMIT: usize = 10 * 1000;
+pub const LOG_MESSAGES_BYTES_LIMIT: usize = 10 * 1000;
+
+/// Log buffers shared by the log collectors of the transactions of a batch.
+///
+/// Every logged message is a separate allocation, which adds up for large
+/// batches with logging enabled. Log collectors created with a pool take
+/// their buffers from it, and the messages of transactions whose logs are
+/// not kept are returned to it for the next transactions. The pool holds at
+/// most `max_pooled_bytes` of buffers, so that the logs of an unusual
+/// transaction are not retained for the rest of the batch. The recorded
+/// messages and the per-transaction limits are the same with and without a
+/// pool.
+#[derive(Debug, Default)]
+pub struct LogBufferPool {
+    message_lists: Vec<Vec<String>>,
+    messages: Vec<String>,
+    pooled_bytes: usize,
+    max_pooled_bytes: usize,
+    allocations: u64,
+}
+
+impl LogBufferPool {
+    pub fn new(max_pooled_bytes: usize) -> Self {
+        Self {
+            max_pooled_bytes,
+            ..Self::default()
+        }
+    }
+
+    pub fn new_ref(max_pooled_bytes: usize) -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self::new(max_pooled_bytes)))
+    }
+
+    /// Number of buffers allocated because the pool had none to reuse
+    pub fn allocations(&self) -> u64 {
+        self.allocations
+    }
+
+    fn take_message_list(&mut self) -> Vec<String> {
+        match self.message_lists.pop() {
+            Some(message_list) => {
+                self.pooled_bytes = self
+                    .pooled_bytes
+                    .saturating_sub(Self::message_list_size(message_list.capacity()));
+                message_list
+            }
+            None => Vec::new(),
+        }
+    }
+
+    fn take_message(&mut self, message: &str) -> String {
+        match self.messages.pop() {
+            Some(mut buffer) => {
+                self.pooled_bytes = self.pooled_bytes.saturating_sub(buffer.capacity());
+                if buffer.capacity() < message.len() {
+                    self.allocations = self.allocations.saturating_add(1);
+                }
+                buffer.push_str(message);
+                buffer
+            }
+            None => {
+                self.allocations = self.allocations.saturating_add(1);
+                message.to_string()
+            }
+        }
+    }
+
+    /// Return the messages of a transaction whose logs are not kept, as far
+    /// as the pool has room for them
+    pub fn recycle(&mut self, mut message_list: Vec<String>) {
+        for mut message in message_list.drain(..) {
+            let pooled_bytes = self.pooled_bytes.saturating_add(message.capacity());
+            if pooled_bytes > self.max_pooled_bytes {
+                break;
+            }
+            message.clear();
+            self.messages.push(message);
+            self.pooled_bytes = pooled_bytes;
+        }
+        let pooled_bytes = self
+            .pooled_bytes
+            .saturating_add(Self::message_list_size(message_list.capacity()));
+        if pooled_bytes <= self.max_pooled_bytes {
+            self.message_lists.push(message_list);
+            self.pooled_bytes = pooled_bytes;
+        }
+    }
+
+    fn message_list_size(capacity: usize) -> usize {
+        capacity.saturating_mul(size_of::<String>())
+    }
+}
 
 pub struct LogCollector {
     messages: Vec<String>,
     bytes_written: usize,
     bytes_limit: Option<usize>,
     limit_warning: bool,
+    buffer_pool: Option<Rc<RefCell<LogBufferPool>>>,
 }
 
 impl Default for LogCollector {
@@ -17,6 +109,7 @@ impl Default for LogCollector {
             bytes_written: 0,
             bytes_limit: Some(LOG_MESSAGES_BYTES_LIMIT),
             limit_warning: false,
+            buffer_pool: None,
         }
     }
 }
@@ -24,7 +117,7 @@ impl Default for LogCollector {
 impl LogCollector {
     pub fn log(&mut self, message: &str) {
         let Some(limit) = self.bytes_limit else {
-            self.messages.push(message.to_string());
+            self.push_message(message);
             return;
         };
 
@@ -32,14 +125,22 @@ impl LogCollector {
         if bytes_written >= limit {
             if !self.limit_warning {
                 self.limit_warning = true;
-                self.messages.push(String::from("Log truncated"));
+                self.push_message("Log truncated");
             }
         } else {
             self.bytes_written = bytes_written;
-            self.messages.push(message.to_string());
+            self.push_message(message);
         }
     }
 
+    fn push_message(&mut self, message: &str) {
+        let message = match &self.buffer_pool {
+            Some(buffer_pool) => buffer_pool.borrow_mut().take_message(message),
+            None => message.to_string(),
+        };
+        self.messages.push(message);
+    }
+
     pub fn get_recorded_content(&self) -> &[String] {
         self.messages.as_slice()
     }
@@ -55,6 +156,20 @@ impl LogCollector {
         }))
     }
 
+    /// A log collector taking its buffers from `buffer_pool`
+    pub fn new_ref_with_pool(
+        bytes_limit: Option<usize>,
+        buffer_pool: Rc<RefCell<LogBufferPool>>,
+    ) -> Rc<RefCell<Self>> {
+        let messages = buffer_pool.borrow_mut().take_message_list();
+        Rc::new(RefCell::new(Self {
+            messages,
+            bytes_limit,
+            buffer_pool: Some(buffer_pool),
+            ..Self::default()
+        }))
+    }
+
     pub fn into_messages(self) -> Vec<String> {
         self.messages
     }
@@ -119,4 +234,44 @@ pub(crate) mod tests {
         }
         assert_eq!(logs.last(), Some(&"Log truncated".to_string()));
     }
+
+    #[test]
+    fn test_log_buffer_pool() {
+        let log_transaction = |log_collector: Rc<RefCell<LogCollector>>| {
+            for i in 0..10 {
+                log_collector.borrow_mut().log(&format!("message {i}"));
+            }
+            log_collector.take().into_messages()
+        };
+
+        let buffer_pool = LogBufferPool::new_ref(1_000_000);
+        let mut all_messages = vec![];
+        for _ in 0..3 {
+            let messages = log_transaction(LogCollector::new_ref_with_pool(
+                Some(LOG_MESSAGES_BYTES_LIMIT),
+                buffer_pool.clone(),
+            ));
+            all_messages.push(messages.clone());
+            buffer_pool.borrow_mut().recycle(messages);
+        }
+        // Only the first transaction allocated its messages
+        assert_eq!(buffer_pool.borrow().allocations(), 10);
+        // The messages are the same as without the pool
+        let expected_messages = log_transaction(LogCollector::new_ref());
+        assert!(all_messages
+            .iter()
+            .all(|messages| *messages == expected_messages));
+
+        // The pool does not hold more than its limit
+        let buffer_pool = LogBufferPool::new_ref(0);
+        let messages = log_transaction(LogCollector::new_ref_with_pool(
+            Some(LOG_MESSAGES_BYTES_LIMIT),
+            buffer_pool.clone(),
+        ));
+        buffer_pool.borrow_mut().recycle(messages);
+        let buffer_pool = buffer_pool.borrow();
+        assert!(buffer_pool.messages.is_empty());
+        assert!(buffer_pool.message_lists.is_empty());
+        assert_eq!(buffer_pool.pooled_bytes, 0);
+    }
 }
@@ -14,4 +14,17 @@ pub struct RuntimeConfig {
     pub compute_budget: Option<ComputeBudget>,
     pub log_messages_bytes_limit: Option<usize>,
     pub transaction_account_lock_limit: Option<usize>,
+    /// Reject syscalls whose results are not fully determined by the
+    /// inputs provided to the runtime, such as reads of sysvars that were
+    /// never supplied, instead of falling back to their default behavior.
+    pub strict_determinism: bool,
+    /// Limit of the sum of the increases of account data lengths per
+    /// transaction, failing transactions which exceed it with
+    /// `InstructionError::MaxAccountsDataGrowthExceeded`. `None` only applies
+    /// the protocol limits.
+    pub max_accounts_data_growth_per_transaction: Option<u64>,
+    /// Limit of the memory, in bytes, occupied by the programs of the program
+    /// cache, enforced by its evictions in addition to the limit of entries.
+    /// `None` only limits the number of entries.
+    pub program_cache_max_loaded_bytes: Option<usize>,
 }
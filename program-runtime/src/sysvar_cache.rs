@@ -5,6 +5,8 @@ use solana_sdk::sysvar::{
 use {
     crate::invoke_context::InvokeContext,
     solana_sdk::{
+        clock::Slot,
+        hash::Hash,
         instruction::InstructionError,
         pubkey::Pubkey,
         sysvar::{
@@ -13,7 +15,7 @@ use {
         },
         transaction_context::{IndexOfAccount, InstructionContext, TransactionContext},
     },
-    std::sync::Arc,
+    std::{collections::HashMap, sync::Arc},
 };
 
 #[cfg(RUSTC_WITH_SPECIALIZATION)]
@@ -212,6 +214,64 @@ impl SysvarCache {
     }
 }
 
+/// Snapshots of the sysvar cache of several forks, keyed by slot and bank
+/// hash, for embedders which process the forks with a single sysvar cache.
+///
+/// A snapshot shares the sysvars with the cache it was taken from, and
+/// setting a sysvar replaces it instead of mutating it, so taking a snapshot
+/// is cheap and later updates of either cache don't affect the other.
+///
+/// At most `MAX_SYSVAR_CACHE_SNAPSHOTS` snapshots are kept: storing another
+/// one drops the snapshot of the oldest slot, which is the first to be rooted
+/// or abandoned.
+#[derive(Default, Clone, Debug)]
+pub struct SysvarCacheSnapshots {
+    snapshots: HashMap<(Slot, Hash), SysvarCache>,
+}
+
+/// Upper bound on the number of forks with a sysvar cache snapshot
+pub const MAX_SYSVAR_CACHE_SNAPSHOTS: usize = 64;
+
+impl SysvarCacheSnapshots {
+    /// Store a snapshot of `sysvar_cache` for the fork at `slot` with
+    /// `bank_hash`, replacing a previous snapshot of the fork
+    pub fn insert(&mut self, slot: Slot, bank_hash: Hash, sysvar_cache: &SysvarCache) {
+        if self.snapshots.len() >= MAX_SYSVAR_CACHE_SNAPSHOTS
+            && !self.snapshots.contains_key(&(slot, bank_hash))
+        {
+            if let Some(oldest) = self.snapshots.keys().min().copied() {
+                self.snapshots.remove(&oldest);
+            }
+        }
+        self.snapshots
+            .insert((slot, bank_hash), sysvar_cache.clone());
+    }
+
+    pub fn get(&self, slot: Slot, bank_hash: &Hash) -> Option<&SysvarCache> {
+        self.snapshots.get(&(slot, *bank_hash))
+    }
+
+    pub fn remove(&mut self, slot: Slot, bank_hash: &Hash) -> Option<SysvarCache> {
+        self.snapshots.remove(&(slot, *bank_hash))
+    }
+
+    /// Drop the snapshots of the slots before `root`, whose forks are either
+    /// rooted or abandoned. Returns the number of dropped snapshots.
+    pub fn purge_older_than(&mut self, root: Slot) -> usize {
+        let len = self.snapshots.len();
+        self.snapshots.retain(|(slot, _), _| *slot >= root);
+        len.saturating_sub(self.snapshots.len())
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+}
+
 /// These methods facilitate a transition from fetching sysvars from keyed
 /// accounts to fetching from the sysvar cache without breaking consensus. In
 /// order to keep consistent behavior, they continue to enforce the same checks
@@ -16,6 +16,12 @@ pub struct ProgramTiming {
     pub errored_txs_compute_consumed: Vec<u64>,
     // Sum of all units in `errored_txs_compute_consumed`
     pub total_errored_units: u64,
+    // Number of times the program was invoked, including CPIs
+    pub invocation_count: u64,
+    // Sum of the instruction data lengths over all invocations
+    pub accumulated_instruction_data_len: u64,
+    // Sum of the instruction account counts over all invocations
+    pub accumulated_instruction_account_count: u64,
 }
 
 impl ProgramTiming {
@@ -36,6 +42,29 @@ impl ProgramTiming {
         self.errored_txs_compute_consumed
             .extend(other.errored_txs_compute_consumed.clone());
         saturating_add_assign!(self.total_errored_units, other.total_errored_units);
+        saturating_add_assign!(self.invocation_count, other.invocation_count);
+        saturating_add_assign!(
+            self.accumulated_instruction_data_len,
+            other.accumulated_instruction_data_len
+        );
+        saturating_add_assign!(
+            self.accumulated_instruction_account_count,
+            other.accumulated_instruction_account_count
+        );
+    }
+
+    /// Average instruction data length per invocation
+    pub fn average_instruction_data_len(&self) -> u64 {
+        self.accumulated_instruction_data_len
+            .checked_div(self.invocation_count)
+            .unwrap_or(0)
+    }
+
+    /// Average number of instruction accounts per invocation
+    pub fn average_instruction_account_count(&self) -> u64 {
+        self.accumulated_instruction_account_count
+            .checked_div(self.invocation_count)
+            .unwrap_or(0)
     }
 }
 
@@ -461,6 +490,24 @@ impl ExecuteDetailsTimings {
             program_timing.count = program_timing.count.saturating_add(1);
         };
     }
+
+    pub fn accumulate_program_invocation(
+        &mut self,
+        program_id: &Pubkey,
+        instruction_data_len: usize,
+        instruction_account_count: usize,
+    ) {
+        let program_timing = self.per_program_timings.entry(*program_id).or_default();
+        saturating_add_assign!(program_timing.invocation_count, 1);
+        saturating_add_assign!(
+            program_timing.accumulated_instruction_data_len,
+            instruction_data_len as u64
+        );
+        saturating_add_assign!(
+            program_timing.accumulated_instruction_account_count,
+            instruction_account_count as u64
+        );
+    }
 }
 
 #[cfg(test)]
@@ -545,6 +592,33 @@ mod tests {
         assert_eq!(execute_details_timings, other_execute_details_timings);
     }
 
+    #[test]
+    fn test_execute_details_timing_accumulate_program_invocation() {
+        let program_id = Pubkey::new_unique();
+        let mut execute_details_timings = ExecuteDetailsTimings::default();
+        execute_details_timings.accumulate_program_invocation(&program_id, 10, 2);
+        execute_details_timings.accumulate_program_invocation(&program_id, 30, 4);
+
+        let program_timings = execute_details_timings
+            .per_program_timings
+            .get(&program_id)
+            .unwrap();
+        assert_eq!(program_timings.invocation_count, 2);
+        assert_eq!(program_timings.accumulated_instruction_data_len, 40);
+        assert_eq!(program_timings.accumulated_instruction_account_count, 6);
+        assert_eq!(program_timings.average_instruction_data_len(), 20);
+        assert_eq!(program_timings.average_instruction_account_count(), 3);
+
+        // Accumulating into another instance carries the invocation metrics over
+        let mut other_execute_details_timings = ExecuteDetailsTimings::default();
+        other_execute_details_timings.accumulate(&execute_details_timings);
+        assert_eq!(other_execute_details_timings, execute_details_timings);
+
+        // No invocations yields zero averages
+        assert_eq!(ProgramTiming::default().average_instruction_data_len(), 0);
+        assert_eq!(ProgramTiming::default().average_instruction_account_count(), 0);
+    }
+
     #[test]
     fn execute_timings_saturating_add_in_place() {
         let mut timings = ExecuteTimings::default();
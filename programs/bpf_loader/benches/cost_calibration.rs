@@ -0,0 +1,284 @@
+//! Measures the wall-clock cost of BPF instruction classes and syscalls and
+//! prints a `CalibrationReport` as CSV, see
+//! `solana_program_runtime::cost_calibration`.
+//!
+//! cargo bench -p solana-bpf-loader-program --bench cost_calibration > report.csv
+
+use {
+    solana_bpf_loader_program::syscalls::{
+        Blake3Hasher, Keccak256Hasher, Sha256Hasher, SyscallCreateProgramAddress, SyscallHash,
+        SyscallLogU64, SyscallMemcpy, SyscallMemset, SyscallRemainingComputeUnits,
+    },
+    solana_program_runtime::{
+        cost_calibration::{CalibrationReport, CostClass, CostSample},
+        invoke_context::InvokeContext,
+        solana_rbpf::{
+            aligned_memory::AlignedMemory,
+            assembler::assemble,
+            ebpf::{self, HOST_ALIGN},
+            memory_region::{MemoryMapping, MemoryRegion},
+            program::{BuiltinProgram, FunctionRegistry, SBPFVersion},
+            verifier::RequisiteVerifier,
+            vm::{Config, ContextObject, EbpfVm, TestContextObject},
+        },
+        with_mock_invoke_context,
+    },
+    solana_sdk::{account::AccountSharedData, bpf_loader, native_loader, pubkey::Pubkey},
+    std::{error::Error, io, sync::Arc},
+};
+
+/// Programs are JIT compiled in production, where available
+const INTERPRETED: bool = !cfg!(all(not(target_os = "windows"), target_arch = "x86_64"));
+/// Runs of each instruction class program
+const PROGRAM_RUNS: u64 = 100;
+/// Loop iterations of each instruction class program
+const LOOP_ITERATIONS: u64 = 10_000;
+/// Copies of the instruction class per loop iteration, to make the loop
+/// control instructions negligible
+const UNROLL: usize = 16;
+const SYSCALL_ITERATIONS: u64 = 100_000;
+
+/// Instruction classes, as instruction sequences operating on r2 and r3
+const INSTRUCTION_CLASSES: &[(&str, &[&str])] = &[
+    (
+        "alu64",
+        &["add64 r2, 3", "xor64 r2, r3", "lsh64 r2, 1", "or64 r2, r3"],
+    ),
+    ("mul64", &["mul64 r2, 3"]),
+    ("div64", &["div64 r2, 3"]),
+    ("load_store", &["stxdw [r10-8], r2", "ldxdw r2, [r10-8]"]),
+    ("branch", &["jeq r2, 1, +0"]),
+];
+
+fn measure_instruction_class(name: &str, instructions: &[&str]) -> CostSample {
+    let mut source = format!("mov64 r1, {LOOP_ITERATIONS}\nmov64 r3, 5\nloop:\n");
+    for _ in 0..UNROLL {
+        for instruction in instructions {
+            source.push_str(instruction);
+            source.push('\n');
+        }
+    }
+    source.push_str("sub64 r1, 1\njne r1, 0, loop\nexit");
+
+    let loader = Arc::new(BuiltinProgram::new_loader(
+        Config {
+            enable_sbpf_v2: false,
+            ..Config::default()
+        },
+        FunctionRegistry::default(),
+    ));
+    #[allow(unused_mut)]
+    let mut executable = assemble::<TestContextObject>(&source, loader).unwrap();
+    executable.verify::<RequisiteVerifier>().unwrap();
+    #[cfg(all(not(target_os = "windows"), target_arch = "x86_64"))]
+    executable.jit_compile().unwrap();
+
+    let config = executable.get_config();
+    let mut stack = AlignedMemory::<HOST_ALIGN>::zero_filled(config.stack_size());
+    let stack_len = stack.len();
+    let memory_mapping = MemoryMapping::new(
+        vec![
+            executable.get_ro_region(),
+            MemoryRegion::new_writable_gapped(
+                stack.as_slice_mut(),
+                ebpf::MM_STACK_START,
+                config.stack_frame_size as u64,
+            ),
+        ],
+        config,
+        executable.get_sbpf_version(),
+    )
+    .unwrap();
+    let mut context_object = TestContextObject::default();
+    let mut vm = EbpfVm::new(
+        executable.get_loader().clone(),
+        executable.get_sbpf_version(),
+        &mut context_object,
+        memory_mapping,
+        stack_len,
+    );
+    CostSample::measure(CostClass::Instruction, name, PROGRAM_RUNS, || {
+        vm.context_object_pointer.remaining = u64::MAX;
+        let (instruction_count, result) = vm.execute_program(&executable, INTERPRETED);
+        assert!(result.is_ok(), "{name}: {result:?}");
+        instruction_count
+    })
+}
+
+fn measure_syscall(
+    name: &str,
+    invoke_context: &mut InvokeContext,
+    memory_mapping: &mut MemoryMapping,
+    mut syscall: impl FnMut(&mut InvokeContext, &mut MemoryMapping) -> Result<u64, Box<dyn Error>>,
+) -> CostSample {
+    CostSample::measure(CostClass::Syscall, name, SYSCALL_ITERATIONS, || {
+        invoke_context.mock_set_remaining(u64::MAX);
+        syscall(invoke_context, memory_mapping).unwrap_or_else(|err| panic!("{name}: {err}"));
+        u64::MAX - invoke_context.get_remaining()
+    })
+}
+
+fn main() {
+    let mut report = CalibrationReport::default();
+    for (name, instructions) in INSTRUCTION_CLASSES {
+        report.add(measure_instruction_class(name, instructions));
+    }
+
+    let program_id = Pubkey::new_unique();
+    let transaction_accounts = vec![
+        (
+            bpf_loader::id(),
+            AccountSharedData::new(0, 0, &native_loader::id()),
+        ),
+        (program_id, AccountSharedData::new(0, 0, &bpf_loader::id())),
+    ];
+    with_mock_invoke_context!(invoke_context, transaction_context, transaction_accounts);
+    invoke_context
+        .transaction_context
+        .get_next_instruction_context()
+        .unwrap()
+        .configure(&[0, 1], &[], &[]);
+    invoke_context.push().unwrap();
+
+    const INPUT_ADDR: u64 = 0x1_0000_0000;
+    const SLICES_ADDR: u64 = 0x2_0000_0000;
+    const OUTPUT_ADDR: u64 = 0x3_0000_0000;
+    const PROGRAM_ID_ADDR: u64 = 0x4_0000_0000;
+    const HASHED_LEN: u64 = 256;
+    const MEM_OP_LEN: u64 = 1024;
+    let input = (0..MEM_OP_LEN).map(|i| i as u8).collect::<Vec<_>>();
+    // Slices as laid out by programs: the hashed input and a seed
+    let slices = AlignedMemory::<HOST_ALIGN>::from_slice(
+        &[INPUT_ADDR, HASHED_LEN, INPUT_ADDR, 32]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>(),
+    );
+    let mut output = AlignedMemory::<HOST_ALIGN>::zero_filled(MEM_OP_LEN as usize);
+    let mut memory_mapping = MemoryMapping::new(
+        vec![
+            MemoryRegion::new_readonly(&input, INPUT_ADDR),
+            MemoryRegion::new_readonly(slices.as_slice(), SLICES_ADDR),
+            MemoryRegion::new_writable(output.as_slice_mut(), OUTPUT_ADDR),
+            MemoryRegion::new_readonly(program_id.as_ref(), PROGRAM_ID_ADDR),
+        ],
+        &Config::default(),
+        &SBPFVersion::V1,
+    )
+    .unwrap();
+
+    report.add(measure_syscall(
+        "sol_log_64_",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallLogU64::rust(invoke_context, 1, 2, 3, 4, 5, memory_mapping)
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_sha256",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallHash::rust::<Sha256Hasher>(
+                invoke_context,
+                SLICES_ADDR,
+                1,
+                OUTPUT_ADDR,
+                0,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_keccak256",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallHash::rust::<Keccak256Hasher>(
+                invoke_context,
+                SLICES_ADDR,
+                1,
+                OUTPUT_ADDR,
+                0,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_blake3",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallHash::rust::<Blake3Hasher>(
+                invoke_context,
+                SLICES_ADDR,
+                1,
+                OUTPUT_ADDR,
+                0,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_memcpy_",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallMemcpy::rust(
+                invoke_context,
+                OUTPUT_ADDR,
+                INPUT_ADDR,
+                MEM_OP_LEN,
+                0,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_memset_",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallMemset::rust(
+                invoke_context,
+                OUTPUT_ADDR,
+                0xff,
+                MEM_OP_LEN,
+                0,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_create_program_address",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallCreateProgramAddress::rust(
+                invoke_context,
+                SLICES_ADDR + 16,
+                1,
+                PROGRAM_ID_ADDR,
+                OUTPUT_ADDR,
+                0,
+                memory_mapping,
+            )
+        },
+    ));
+    report.add(measure_syscall(
+        "sol_remaining_compute_units",
+        &mut invoke_context,
+        &mut memory_mapping,
+        |invoke_context, memory_mapping| {
+            SyscallRemainingComputeUnits::rust(invoke_context, 0, 0, 0, 0, 0, memory_mapping)
+        },
+    ));
+
+    report.write_csv(&mut io::stdout().lock()).unwrap();
+}
@@ -8,7 +8,10 @@ use {
     solana_measure::measure::Measure,
     solana_program_runtime::{
         ic_logger_msg, ic_msg,
-        invoke_context::{BpfAllocator, InvokeContext, SerializedAccountMetadata, SyscallContext},
+        invoke_context::{
+            BpfAllocator, Capability, InvokeContext, ProgramErrorLocation,
+            SerializedAccountMetadata, SyscallContext,
+        },
         loaded_programs::{
             LoadProgramMetrics, LoadedProgram, LoadedProgramType, DELAY_VISIBILITY_SLOT_OFFSET,
         },
@@ -448,7 +451,9 @@ pub fn process_instruction_inner(
         get_or_create_executor_time.as_us()
     );
 
-    executor.ix_usage_counter.fetch_add(1, Ordering::Relaxed);
+    invoke_context
+        .programs_loaded_for_tx_batch
+        .record_instruction_usage(&executor);
     match &executor.program {
         LoadedProgramType::FailedVerification(_)
         | LoadedProgramType::Closed
@@ -1328,6 +1333,23 @@ fn common_close_account(
     Ok(())
 }
 
+/// Name of the function containing the instruction at `pc`. Function names
+/// are only retained if the program was loaded with symbol labels enabled.
+fn resolve_function_symbol<C: ContextObject>(
+    executable: &Executable<C>,
+    pc: u64,
+) -> Option<String> {
+    if !executable.get_config().enable_symbol_and_section_labels {
+        return None;
+    }
+    executable
+        .get_function_registry()
+        .iter()
+        .filter(|(_key, (name, function_pc))| !name.is_empty() && *function_pc as u64 <= pc)
+        .max_by_key(|(_key, (_name, function_pc))| *function_pc)
+        .map(|(_key, (name, _function_pc))| String::from_utf8_lossy(name).into_owned())
+}
+
 fn execute<'a, 'b: 'a>(
     executable: &'a Executable<InvokeContext<'static>>,
     invoke_context: &'a mut InvokeContext<'b>,
@@ -1395,6 +1417,8 @@ fn execute<'a, 'b: 'a>(
 
         execute_time = Measure::start("execute");
         let (compute_units_consumed, result) = vm.execute_program(executable, !use_jit);
+        // The interpreter and the JIT leave the pc of the failing instruction in r11
+        let pc = vm.registers[11];
         drop(vm);
         ic_logger_msg!(
             log_collector,
@@ -1414,6 +1438,13 @@ fn execute<'a, 'b: 'a>(
                 Err(Box::new(error) as Box<dyn std::error::Error>)
             }
             ProgramResult::Err(mut error) => {
+                if invoke_context.is_error_location_recording_enabled() {
+                    invoke_context.record_error_location(ProgramErrorLocation {
+                        program_id,
+                        pc,
+                        symbol: resolve_function_symbol(executable, pc),
+                    });
+                }
                 if direct_mapping {
                     if let EbpfError::AccessViolation(
                         AccessType::Store,
@@ -1473,7 +1504,25 @@ fn execute<'a, 'b: 'a>(
             copy_account_data,
             parameter_bytes,
             &invoke_context.get_syscall_context()?.accounts_metadata,
-        )
+        )?;
+
+        if invoke_context.is_capability_recording_enabled() {
+            let transaction_context = &invoke_context.transaction_context;
+            let instruction_context = transaction_context.get_current_instruction_context()?;
+            let resized = (0..instruction_context.get_number_of_instruction_accounts())
+                .zip(&invoke_context.get_syscall_context()?.accounts_metadata)
+                .any(|(index_in_instruction, account_metadata)| {
+                    instruction_context
+                        .try_borrow_instruction_account(transaction_context, index_in_instruction)
+                        .is_ok_and(|account| {
+                            account.get_data().len() != account_metadata.original_data_len
+                        })
+                });
+            if resized {
+                invoke_context.record_capability(Capability::DataRealloc);
+            }
+        }
+        Ok(())
     }
 
     let mut deserialize_time = Measure::start("deserialize");
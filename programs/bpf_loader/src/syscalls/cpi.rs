@@ -1074,6 +1074,7 @@ fn cpi_common<S: SyscallInvokeSigned>(
         invoke_context,
         invoke_context.get_compute_budget().invoke_units,
     )?;
+    invoke_context.record_capability(Capability::Cpi);
 
     let instruction = S::translate_instruction(instruction_addr, memory_mapping, invoke_context)?;
     let transaction_context = &invoke_context.transaction_context;
@@ -1181,6 +1182,7 @@ fn update_callee_account(
     if callee_account.get_lamports() != *caller_account.lamports {
         callee_account.set_lamports(*caller_account.lamports)?;
     }
+    let pre_data_len = callee_account.get_data().len();
 
     if direct_mapping {
         let prev_len = callee_account.get_data().len();
@@ -1231,6 +1233,10 @@ fn update_callee_account(
         }
     }
 
+    if callee_account.get_data().len() != pre_data_len {
+        invoke_context.record_capability(Capability::DataRealloc);
+    }
+
     // Change the owner at the end so that we are allowed to change the lamports and data before
     if callee_account.get_owner() != caller_account.owner {
         callee_account.set_owner(caller_account.owner.as_ref())?;
@@ -12,14 +12,17 @@ pub use self::{
 #[allow(deprecated)]
 use {
     solana_program_runtime::{
-        compute_budget::ComputeBudget, ic_logger_msg, ic_msg, invoke_context::InvokeContext,
-        stable_log, timings::ExecuteTimings,
+        compute_budget::ComputeBudget,
+        ic_logger_msg, ic_msg,
+        invoke_context::{Capability, InvokeContext},
+        stable_log,
+        timings::ExecuteTimings,
     },
     solana_rbpf::{
         declare_builtin_function,
         memory_region::{AccessType, MemoryMapping},
         program::{BuiltinFunction, BuiltinProgram, FunctionRegistry},
-        vm::Config,
+        vm::{get_runtime_environment_key, Config, EbpfVm},
     },
     solana_sdk::{
         account_info::AccountInfo,
@@ -123,6 +126,10 @@ pub enum SyscallError {
     InvalidPointer,
     #[error("Arithmetic overflow")]
     ArithmeticOverflow,
+    #[error("Sysvar {0} was not provided and strict determinism is enabled")]
+    NonDeterministicSysvar(Pubkey),
+    #[error("Embedder syscall {0} does not use the reserved name prefix")]
+    InvalidEmbedderSyscallName(String),
 }
 
 type Error = Box<dyn std::error::Error>;
@@ -229,10 +236,40 @@ fn consume_compute_meter(invoke_context: &InvokeContext, amount: u64) -> Result<
     Ok(())
 }
 
+/// Record the syscall `name` in the capability manifest of the transaction
+/// executed by the VM `vm`, if capability recording is enabled
+fn record_syscall(vm: *mut EbpfVm<InvokeContext>, name: &[u8]) {
+    // The VM passes an obfuscated pointer to its syscalls, which the VM
+    // interface generated by `declare_builtin_function!` translates back the
+    // same way
+    let offset = (get_runtime_environment_key() as isize).wrapping_neg();
+    // SAFETY: `vm` is the pointer the VM passes to its syscalls, which is
+    // valid for the duration of the syscall
+    let invoke_context: &InvokeContext = unsafe {
+        &*(*(vm as *mut u64)
+            .offset(offset)
+            .cast::<EbpfVm<InvokeContext>>())
+        .context_object_pointer
+    };
+    invoke_context.record_syscall(name);
+}
+
+/// Register the VM interface `$call` of a syscall behind a shim which
+/// records the syscall in the capability manifest of the transaction, if
+/// capability recording is enabled
+macro_rules! register_function {
+    ($result:expr, $name:expr, $call:expr $(,)?) => {
+        $result.register_function_hashed($name, |vm, arg_a, arg_b, arg_c, arg_d, arg_e| {
+            record_syscall(vm, &$name);
+            $call(vm, arg_a, arg_b, arg_c, arg_d, arg_e)
+        })
+    };
+}
+
 macro_rules! register_feature_gated_function {
     ($result:expr, $is_feature_active:expr, $name:expr, $call:expr $(,)?) => {
         if $is_feature_active {
-            $result.register_function_hashed($name, $call)
+            register_function!($result, $name, $call)
         } else {
             Ok(0)
         }
@@ -257,11 +294,35 @@ pub fn morph_into_deployment_environment_v1(
     Ok(BuiltinProgram::new_loader(config, result))
 }
 
+/// Name prefix reserved for syscalls registered by embedders of the program
+/// runtime. Syscalls of the L1 runtime never use it, so embedder syscalls can
+/// not collide with syscalls activated by future features.
+pub const EMBEDDER_SYSCALL_PREFIX: &[u8] = b"sol_embedder_";
+
 pub fn create_program_runtime_environment_v1<'a>(
     feature_set: &FeatureSet,
     compute_budget: &ComputeBudget,
     reject_deployment_of_broken_elfs: bool,
     debugging_features: bool,
+) -> Result<BuiltinProgram<InvokeContext<'a>>, Error> {
+    create_program_runtime_environment_v1_with_embedder_syscalls(
+        feature_set,
+        compute_budget,
+        reject_deployment_of_broken_elfs,
+        debugging_features,
+        &[],
+    )
+}
+
+/// Same as `create_program_runtime_environment_v1()`, additionally
+/// registering `embedder_syscalls` on top of the syscalls enabled by the
+/// feature set. Their names must start with `EMBEDDER_SYSCALL_PREFIX`.
+pub fn create_program_runtime_environment_v1_with_embedder_syscalls<'a>(
+    feature_set: &FeatureSet,
+    compute_budget: &ComputeBudget,
+    reject_deployment_of_broken_elfs: bool,
+    debugging_features: bool,
+    embedder_syscalls: &[(&[u8], BuiltinFunction<InvokeContext<'a>>)],
 ) -> Result<BuiltinProgram<InvokeContext<'a>>, Error> {
     let enable_alt_bn128_syscall = feature_set.is_active(&enable_alt_bn128_syscall::id());
     let enable_alt_bn128_compression_syscall =
@@ -307,35 +368,49 @@ pub fn create_program_runtime_environment_v1<'a>(
     let mut result = FunctionRegistry::<BuiltinFunction<InvokeContext>>::default();
 
     // Abort
-    result.register_function_hashed(*b"abort", SyscallAbort::vm)?;
+    register_function!(result, *b"abort", SyscallAbort::vm)?;
 
     // Panic
-    result.register_function_hashed(*b"sol_panic_", SyscallPanic::vm)?;
+    register_function!(result, *b"sol_panic_", SyscallPanic::vm)?;
 
     // Logging
-    result.register_function_hashed(*b"sol_log_", SyscallLog::vm)?;
-    result.register_function_hashed(*b"sol_log_64_", SyscallLogU64::vm)?;
-    result.register_function_hashed(*b"sol_log_compute_units_", SyscallLogBpfComputeUnits::vm)?;
-    result.register_function_hashed(*b"sol_log_pubkey", SyscallLogPubkey::vm)?;
+    register_function!(result, *b"sol_log_", SyscallLog::vm)?;
+    register_function!(result, *b"sol_log_64_", SyscallLogU64::vm)?;
+    register_function!(
+        result,
+        *b"sol_log_compute_units_",
+        SyscallLogBpfComputeUnits::vm
+    )?;
+    register_function!(result, *b"sol_log_pubkey", SyscallLogPubkey::vm)?;
 
     // Program defined addresses (PDA)
-    result.register_function_hashed(
+    register_function!(
+        result,
         *b"sol_create_program_address",
         SyscallCreateProgramAddress::vm,
     )?;
-    result.register_function_hashed(
+    register_function!(
+        result,
         *b"sol_try_find_program_address",
         SyscallTryFindProgramAddress::vm,
     )?;
 
     // Sha256
-    result.register_function_hashed(*b"sol_sha256", SyscallHash::vm::<Sha256Hasher>)?;
+    register_function!(result, *b"sol_sha256", SyscallHash::vm::<Sha256Hasher>)?;
 
     // Keccak256
-    result.register_function_hashed(*b"sol_keccak256", SyscallHash::vm::<Keccak256Hasher>)?;
+    register_function!(
+        result,
+        *b"sol_keccak256",
+        SyscallHash::vm::<Keccak256Hasher>
+    )?;
 
     // Secp256k1 Recover
-    result.register_function_hashed(*b"sol_secp256k1_recover", SyscallSecp256k1Recover::vm)?;
+    register_function!(
+        result,
+        *b"sol_secp256k1_recover",
+        SyscallSecp256k1Recover::vm
+    )?;
 
     // Blake3
     register_feature_gated_function!(
@@ -366,8 +441,9 @@ pub fn create_program_runtime_environment_v1<'a>(
     )?;
 
     // Sysvars
-    result.register_function_hashed(*b"sol_get_clock_sysvar", SyscallGetClockSysvar::vm)?;
-    result.register_function_hashed(
+    register_function!(result, *b"sol_get_clock_sysvar", SyscallGetClockSysvar::vm)?;
+    register_function!(
+        result,
         *b"sol_get_epoch_schedule_sysvar",
         SyscallGetEpochScheduleSysvar::vm,
     )?;
@@ -377,7 +453,7 @@ pub fn create_program_runtime_environment_v1<'a>(
         *b"sol_get_fees_sysvar",
         SyscallGetFeesSysvar::vm,
     )?;
-    result.register_function_hashed(*b"sol_get_rent_sysvar", SyscallGetRentSysvar::vm)?;
+    register_function!(result, *b"sol_get_rent_sysvar", SyscallGetRentSysvar::vm)?;
 
     register_feature_gated_function!(
         result,
@@ -394,27 +470,32 @@ pub fn create_program_runtime_environment_v1<'a>(
     )?;
 
     // Memory ops
-    result.register_function_hashed(*b"sol_memcpy_", SyscallMemcpy::vm)?;
-    result.register_function_hashed(*b"sol_memmove_", SyscallMemmove::vm)?;
-    result.register_function_hashed(*b"sol_memcmp_", SyscallMemcmp::vm)?;
-    result.register_function_hashed(*b"sol_memset_", SyscallMemset::vm)?;
+    register_function!(result, *b"sol_memcpy_", SyscallMemcpy::vm)?;
+    register_function!(result, *b"sol_memmove_", SyscallMemmove::vm)?;
+    register_function!(result, *b"sol_memcmp_", SyscallMemcmp::vm)?;
+    register_function!(result, *b"sol_memset_", SyscallMemset::vm)?;
 
     // Processed sibling instructions
-    result.register_function_hashed(
+    register_function!(
+        result,
         *b"sol_get_processed_sibling_instruction",
         SyscallGetProcessedSiblingInstruction::vm,
     )?;
 
     // Stack height
-    result.register_function_hashed(*b"sol_get_stack_height", SyscallGetStackHeight::vm)?;
+    register_function!(result, *b"sol_get_stack_height", SyscallGetStackHeight::vm)?;
 
     // Return data
-    result.register_function_hashed(*b"sol_set_return_data", SyscallSetReturnData::vm)?;
-    result.register_function_hashed(*b"sol_get_return_data", SyscallGetReturnData::vm)?;
+    register_function!(result, *b"sol_set_return_data", SyscallSetReturnData::vm)?;
+    register_function!(result, *b"sol_get_return_data", SyscallGetReturnData::vm)?;
 
     // Cross-program invocation
-    result.register_function_hashed(*b"sol_invoke_signed_c", SyscallInvokeSignedC::vm)?;
-    result.register_function_hashed(*b"sol_invoke_signed_rust", SyscallInvokeSignedRust::vm)?;
+    register_function!(result, *b"sol_invoke_signed_c", SyscallInvokeSignedC::vm)?;
+    register_function!(
+        result,
+        *b"sol_invoke_signed_rust",
+        SyscallInvokeSignedRust::vm
+    )?;
 
     // Memory allocator
     register_feature_gated_function!(
@@ -465,7 +546,19 @@ pub fn create_program_runtime_environment_v1<'a>(
     )?;
 
     // Log data
-    result.register_function_hashed(*b"sol_log_data", SyscallLogData::vm)?;
+    register_function!(result, *b"sol_log_data", SyscallLogData::vm)?;
+
+    // Embedder syscalls
+    for (name, function) in embedder_syscalls {
+        if name.len() <= EMBEDDER_SYSCALL_PREFIX.len() || !name.starts_with(EMBEDDER_SYSCALL_PREFIX)
+        {
+            return Err(SyscallError::InvalidEmbedderSyscallName(
+                String::from_utf8_lossy(name).into_owned(),
+            )
+            .into());
+        }
+        result.register_function_hashed(*name, *function)?;
+    }
 
     Ok(BuiltinProgram::new_loader(config, result))
 }
@@ -1277,6 +1370,7 @@ declare_builtin_function!(
             })?;
 
         transaction_context.set_return_data(program_id, return_data)?;
+        invoke_context.record_capability(Capability::ReturnData);
 
         Ok(0)
     }
@@ -1969,7 +2063,13 @@ mod tests {
         crate::mock_create_vm,
         assert_matches::assert_matches,
         core::slice,
-        solana_program_runtime::{invoke_context::InvokeContext, with_mock_invoke_context},
+        solana_program_runtime::{
+            invoke_context::InvokeContext,
+            loaded_programs::{
+                environment_hash, syscall_registry_digest, ProgramRuntimeEnvironments,
+            },
+            with_mock_invoke_context,
+        },
         solana_rbpf::{
             error::EbpfError, memory_region::MemoryRegion, program::SBPFVersion, vm::Config,
         },
@@ -3584,6 +3684,59 @@ mod tests {
         &mut MemoryMapping,
     ) -> Result<u64, Box<dyn std::error::Error>>;
 
+    #[test]
+    fn test_syscall_get_sysvar_strict_determinism() {
+        use solana_rbpf::vm::ContextObject;
+
+        let config = Config::default();
+        with_mock_invoke_context!(invoke_context, transaction_context, vec![]);
+
+        let mut got_clock = Clock::default();
+        let got_clock_va = 0x100000000;
+        let mut memory_mapping = MemoryMapping::new(
+            vec![MemoryRegion::new_writable(
+                bytes_of_mut(&mut got_clock),
+                got_clock_va,
+            )],
+            &config,
+            &SBPFVersion::V2,
+        )
+        .unwrap();
+
+        // A missing sysvar is reported as unsupported by default
+        let result = SyscallGetClockSysvar::rust(
+            &mut invoke_context,
+            got_clock_va,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_matches!(
+            result,
+            Result::Err(error) if error.downcast_ref::<InstructionError>().unwrap() == &InstructionError::UnsupportedSysvar
+        );
+
+        // In strict determinism mode it is rejected before consuming any units
+        invoke_context.set_strict_determinism(true);
+        let remaining = invoke_context.get_remaining();
+        let result = SyscallGetClockSysvar::rust(
+            &mut invoke_context,
+            got_clock_va,
+            0,
+            0,
+            0,
+            0,
+            &mut memory_mapping,
+        );
+        assert_matches!(
+            result,
+            Result::Err(error) if error.downcast_ref::<SyscallError>().unwrap() == &SyscallError::NonDeterministicSysvar(sysvar::clock::id())
+        );
+        assert_eq!(invoke_context.get_remaining(), remaining);
+    }
+
     fn call_program_address_common<'a, 'b: 'a>(
         invoke_context: &'a mut InvokeContext<'b>,
         seeds: &[&[u8]],
@@ -3689,6 +3842,7 @@ mod tests {
         .unwrap();
 
         prepare_mockup!(invoke_context, program_id, bpf_loader::id());
+        invoke_context.enable_capability_recording();
 
         let result = SyscallSetReturnData::rust(
             &mut invoke_context,
@@ -3700,6 +3854,13 @@ mod tests {
             &mut memory_mapping,
         );
         assert_eq!(result.unwrap(), 0);
+        assert_eq!(
+            invoke_context
+                .get_capability_manifest()
+                .unwrap()
+                .capabilities,
+            std::collections::BTreeSet::from([Capability::ReturnData])
+        );
 
         let result = SyscallGetReturnData::rust(
             &mut invoke_context,
@@ -4189,4 +4350,75 @@ mod tests {
             assert_eq!(address_is_aligned::<u64>(address as u64), address == 0);
         }
     }
+
+    #[test]
+    fn test_embedder_syscalls() {
+        fn create_environment<'a>(
+            feature_set: &FeatureSet,
+            embedder_syscalls: &[(&[u8], BuiltinFunction<InvokeContext<'a>>)],
+        ) -> Result<BuiltinProgram<InvokeContext<'a>>, Error> {
+            create_program_runtime_environment_v1_with_embedder_syscalls(
+                feature_set,
+                &ComputeBudget::default(),
+                false,
+                false,
+                embedder_syscalls,
+            )
+        }
+        let abort: BuiltinFunction<InvokeContext> = SyscallAbort::vm;
+
+        let base = create_environment(&FeatureSet::default(), &[]).unwrap();
+        let all_features = create_environment(&FeatureSet::all_enabled(), &[]).unwrap();
+        let extended = create_environment(
+            &FeatureSet::default(),
+            &[(b"sol_embedder_abort".as_slice(), abort)],
+        )
+        .unwrap();
+        assert!(extended
+            .get_function_registry()
+            .lookup_by_name(b"sol_embedder_abort")
+            .is_some());
+
+        // Any difference in the registered syscalls changes the digest
+        let base_digest = syscall_registry_digest(&base);
+        assert_eq!(
+            base_digest,
+            syscall_registry_digest(&create_environment(&FeatureSet::default(), &[]).unwrap())
+        );
+        assert_ne!(base_digest, syscall_registry_digest(&all_features));
+        assert_ne!(base_digest, syscall_registry_digest(&extended));
+
+        // The syscalls are part of the identity of the environments
+        let environments_hash = |program_runtime_v1| {
+            ProgramRuntimeEnvironments {
+                program_runtime_v1: Arc::new(program_runtime_v1),
+                ..ProgramRuntimeEnvironments::default()
+            }
+            .hash()
+        };
+        let base_hash = environment_hash(&base);
+        assert_ne!(environments_hash(base), environments_hash(extended));
+
+        // So is the config
+        let rejecting_broken_elfs = create_program_runtime_environment_v1(
+            &FeatureSet::default(),
+            &ComputeBudget::default(),
+            true,
+            false,
+        )
+        .unwrap();
+        assert_eq!(base_digest, syscall_registry_digest(&rejecting_broken_elfs));
+        assert_ne!(base_hash, environment_hash(&rejecting_broken_elfs));
+
+        // Embedder syscalls must use the reserved prefix
+        for name in [b"sol_log_".as_slice(), b"abort", EMBEDDER_SYSCALL_PREFIX] {
+            assert_matches!(
+                create_environment(&FeatureSet::default(), &[(name, abort)])
+                    .unwrap_err()
+                    .downcast_ref::<SyscallError>()
+                    .unwrap(),
+                SyscallError::InvalidEmbedderSyscallName(_)
+            );
+        }
+    }
 }
@@ -7,6 +7,9 @@ fn get_sysvar<T: std::fmt::Debug + Sysvar + SysvarId + Clone>(
     memory_mapping: &mut MemoryMapping,
     invoke_context: &mut InvokeContext,
 ) -> Result<u64, Error> {
+    if invoke_context.is_strict_determinism() && sysvar.is_err() {
+        return Err(SyscallError::NonDeterministicSysvar(T::id()).into());
+    }
     consume_compute_meter(
         invoke_context,
         invoke_context
@@ -18,6 +21,7 @@ fn get_sysvar<T: std::fmt::Debug + Sysvar + SysvarId + Clone>(
 
     let sysvar: Arc<T> = sysvar?;
     *var = T::clone(sysvar.as_ref());
+    invoke_context.record_capability(Capability::SysvarRead);
 
     Ok(SUCCESS)
 }
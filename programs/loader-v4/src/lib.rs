@@ -598,9 +598,9 @@ pub fn process_instruction_inner(
             get_or_create_executor_time.as_us()
         );
         drop(program);
-        loaded_program
-            .ix_usage_counter
-            .fetch_add(1, Ordering::Relaxed);
+        invoke_context
+            .programs_loaded_for_tx_batch
+            .record_instruction_usage(&loaded_program);
         match &loaded_program.program {
             LoadedProgramType::FailedVerification(_)
             | LoadedProgramType::Closed
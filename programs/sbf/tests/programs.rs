@@ -109,6 +109,11 @@ fn process_transaction_and_record_inner(
                 enable_cpi_recording: true,
                 enable_log_recording: true,
                 enable_return_data_recording: false,
+                enable_heap_stats_recording: false,
+                enable_error_location_recording: false,
+                enable_capability_recording: false,
+                enable_instruction_decoding: false,
+                enable_compute_budget_report: false,
             },
             &mut ExecuteTimings::default(),
             None,
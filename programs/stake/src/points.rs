@@ -30,7 +30,7 @@ pub(crate) struct CalculatedStakePoints {
     pub(crate) force_credits_update_with_skipped_reward: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum InflationPointCalculationEvent {
     CalculatedPoints(u64, u128, u128, u128),
     SplitRewards(u64, u64, u64, PointValue),
@@ -46,7 +46,7 @@ pub(crate) fn null_tracer() -> Option<impl Fn(&InflationPointCalculationEvent)>
     None::<fn(&_)>
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum SkippedReason {
     DisabledInflation,
     JustActivated,
@@ -232,6 +232,7 @@ pub(crate) mod tests {
                 VersionedTransaction,
             },
         },
+        solana_svm::transaction_results::AccountLifecycle,
         solana_transaction_status::{
             token_balances::TransactionTokenBalancesSet, TransactionStatusMeta,
             TransactionTokenBalance,
@@ -373,6 +374,14 @@ pub(crate) mod tests {
             return_data: None,
             executed_units: 0,
             accounts_data_len_delta: 0,
+            accounts_data_growth: 0,
+            heap_allocation_stats: None,
+            error_location: None,
+            capability_manifest: None,
+            decoded_instructions: None,
+            compute_budget_report: None,
+            account_lifecycle: AccountLifecycle::default(),
+            signature_policy_decision: None,
         });
 
         let balances = TransactionBalancesSet {
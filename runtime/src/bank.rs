@@ -33,17 +33,22 @@
 //! It offers a high-level API that signs transactions
 //! on behalf of the caller, and a low-level API for when they have
 //! already been signed and verified.
+pub use partitioned_epoch_rewards::{
+    EpochRewardsPreview, InflationCurve, RewardDistributionMetadata,
+};
 #[allow(deprecated)]
 use solana_sdk::recent_blockhashes_account;
 pub use solana_sdk::reward_type::RewardType;
 use {
     crate::{
         bank::{
-            builtins::{BuiltinPrototype, BUILTINS},
+            accounts_data_size_audit::{AccountsDataSizeAudit, AccountsDataSizeSource},
+            builtins::{BuiltinPrototype, StatelessBuiltinPrototype, BUILTINS, STATELESS_BUILTINS},
+            epoch_boundary_schedule::{EpochBoundarySchedule, EpochBoundaryTask},
             metrics::*,
             partitioned_epoch_rewards::{
-                EpochRewardCalculateParamInfo, EpochRewardStatus, RewardInterval, StakeRewards,
-                VoteRewardsAccounts,
+                BackgroundRewardsCalculation, EpochRewardCalculateParamInfo, EpochRewardStatus,
+                RewardInterval, StakeRewards, VoteRewardsAccounts,
             },
         },
         bank_forks::BankForks,
@@ -81,6 +86,7 @@ use {
             AccountHash, AccountsHash, CalcAccountsHashConfig, HashStats, IncrementalAccountsHash,
         },
         accounts_index::{AccountSecondaryIndexes, IndexKey, ScanConfig, ScanResult, ZeroLamport},
+        accounts_lt_hash::{lt_hash_account, LtHash},
         accounts_partition::{self, Partition, PartitionIndex},
         accounts_update_notifier_interface::AccountsUpdateNotifier,
         ancestors::{Ancestors, AncestorsForSerialization},
@@ -134,8 +140,7 @@ use {
         message::{AccountKeys, SanitizedMessage},
         native_loader,
         native_token::LAMPORTS_PER_SOL,
-        nonce::{self, state::DurableNonce, NONCED_TX_MARKER_IX_INDEX},
-        nonce_account,
+        nonce::{self, state::DurableNonce},
         nonce_info::{NonceInfo, NoncePartial},
         packet::PACKET_DATA_SIZE,
         precompiles::get_precompiles,
@@ -163,10 +168,16 @@ use {
         stake_state::StakeStateV2,
     },
     solana_svm::{
+        account_contention::AccountContentionReport,
         account_loader::{TransactionCheckResult, TransactionLoadResult},
         account_overrides::AccountOverrides,
+        program_usage::ProgramUsageReport,
+        transaction_checks::{
+            self, durable_nonce_account, AlreadyProcessedCheck, BlockhashCheck, NonceCheck,
+            TransactionCheck,
+        },
         transaction_error_metrics::TransactionErrorMetrics,
-        transaction_processing_callback::TransactionProcessingCallback,
+        transaction_processing_callback::{AccountMeta, TransactionProcessingCallback},
         transaction_processor::{
             ExecutionRecordingConfig, TransactionBatchProcessor, TransactionLogMessages,
         },
@@ -215,15 +226,25 @@ struct VerifyAccountsHashConfig {
     store_hash_raw_data_for_debug: bool,
 }
 
+pub mod accounts_data_size_audit;
 mod address_lookup_table;
 pub mod bank_hash_details;
 mod builtin_programs;
 pub mod builtins;
+pub mod builtins_summary;
 pub mod epoch_accounts_hash_utils;
+pub mod epoch_boundary_replay;
+pub mod epoch_boundary_schedule;
+#[cfg(test)]
+pub(crate) mod fault_injection;
 mod fee_distribution;
 mod metrics;
 pub(crate) mod partitioned_epoch_rewards;
+pub mod pending_feature_activations;
 mod serde_snapshot;
+mod staged_writes;
+#[cfg(feature = "dev-context-only-utils")]
+pub mod standalone_processor;
 mod sysvar_cache;
 #[cfg(test)]
 pub(crate) mod tests;
@@ -340,6 +361,12 @@ pub struct LoadAndExecuteTransactionsOutput {
     pub executed_with_successful_result_count: usize,
     pub signature_count: u64,
     pub error_counters: TransactionErrorMetrics,
+    // Invocations and consumed compute units of each program invoked by the
+    // executed transactions
+    pub program_usage_report: ProgramUsageReport,
+    // Accounts locked by several of the executed transactions, at least one
+    // of which writes them
+    pub account_contention_report: AccountContentionReport,
 }
 
 pub struct TransactionSimulationResult {
@@ -556,6 +583,8 @@ impl PartialEq for Bank {
             is_delta,
             // TODO: Confirm if all these fields are intentionally ignored!
             builtin_program_ids: _,
+            builtin_entrypoint_overrides: _,
+            inflation_curve: _,
             runtime_config: _,
             rewards: _,
             cluster_type: _,
@@ -572,12 +601,17 @@ impl PartialEq for Bank {
             accounts_data_size_initial: _,
             accounts_data_size_delta_on_chain: _,
             accounts_data_size_delta_off_chain: _,
+            accounts_data_size_audit: _,
             fee_structure: _,
             incremental_snapshot_persistence: _,
             epoch_reward_status: _,
+            reward_distribution_metadata: _,
             transaction_processor: _,
             check_program_modification_slot: _,
+            epoch_boundary_schedule: _,
+            background_rewards_calculation: _,
             collector_fee_details: _,
+            accounts_lt_hash: _,
             // Ignore new fields explicitly if they do not impact PartialEq.
             // Adding ".." will remove compile-time checks that if a new field
             // is added to the struct, this PartialEq is accordingly updated.
@@ -645,6 +679,16 @@ impl AbiExample for OptionalDropCallback {
     }
 }
 
+#[derive(Debug, Default, Clone)]
+struct OptionalInflationCurve(Option<Arc<dyn InflationCurve>>);
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl AbiExample for OptionalInflationCurve {
+    fn example() -> Self {
+        Self(None)
+    }
+}
+
 /// Manager for the state of all accounts and programs after processing its entries.
 /// AbiExample is needed even without Serialize/Deserialize; actual (de-)serialization
 /// are implemented elsewhere for versioning
@@ -763,6 +807,13 @@ pub struct Bank {
 
     builtin_program_ids: HashSet<Pubkey>,
 
+    /// Builtin programs whose entrypoint was overridden outside of feature
+    /// activation, see `Bank::override_builtin_entrypoint`
+    builtin_entrypoint_overrides: HashSet<Pubkey>,
+
+    /// Emission schedule overriding `inflation`, see `Bank::set_inflation_curve`
+    inflation_curve: OptionalInflationCurve,
+
     /// Optional config parameters that can override runtime behavior
     pub(crate) runtime_config: Arc<RuntimeConfig>,
 
@@ -802,6 +853,9 @@ pub struct Bank {
     accounts_data_size_delta_on_chain: AtomicI64,
     /// The change to accounts data size in this Bank, due to off-chain events (i.e. rent collection)
     accounts_data_size_delta_off_chain: AtomicI64,
+    /// The off-chain accounts data size delta of the epoch boundary tasks of
+    /// this Bank, by task
+    accounts_data_size_audit: AccountsDataSizeAudit,
 
     /// until the skipped rewrites feature is activated, it is possible to skip rewrites and still include
     /// the account hash of the accounts that would have been rewritten as bank hash expects.
@@ -814,12 +868,25 @@ pub struct Bank {
 
     epoch_reward_status: EpochRewardStatus,
 
+    /// Rewards distributed in this block, if it is in the reward interval
+    reward_distribution_metadata: Option<RewardDistributionMetadata>,
+
     transaction_processor: TransactionBatchProcessor<BankForks>,
 
     check_program_modification_slot: bool,
 
+    /// When the deferrable epoch boundary work runs
+    epoch_boundary_schedule: EpochBoundarySchedule,
+
+    /// Rewards calculation of this epoch running in the background, until
+    /// its result is committed
+    background_rewards_calculation: Option<Arc<BackgroundRewardsCalculation>>,
+
     /// Collected fee details
     collector_fee_details: RwLock<CollectorFeeDetails>,
+
+    /// Lattice hash of all accounts, see `Bank::accounts_lt_hash()`
+    accounts_lt_hash: Mutex<LtHash>,
 }
 
 struct VoteWithStakeDelegations {
@@ -924,6 +991,8 @@ impl Bank {
             epoch_stakes: HashMap::<Epoch, EpochStakes>::default(),
             is_delta: AtomicBool::default(),
             builtin_program_ids: HashSet::<Pubkey>::default(),
+            builtin_entrypoint_overrides: HashSet::<Pubkey>::default(),
+            inflation_curve: OptionalInflationCurve::default(),
             runtime_config: Arc::<RuntimeConfig>::default(),
             rewards: RwLock::<Vec<(Pubkey, RewardInfo)>>::default(),
             cluster_type: Option::<ClusterType>::default(),
@@ -941,11 +1010,16 @@ impl Bank {
             accounts_data_size_initial: 0,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
+            accounts_data_size_audit: AccountsDataSizeAudit::default(),
             fee_structure: FeeStructure::default(),
             epoch_reward_status: EpochRewardStatus::default(),
+            reward_distribution_metadata: None,
             transaction_processor: TransactionBatchProcessor::default(),
             check_program_modification_slot: false,
+            epoch_boundary_schedule: EpochBoundarySchedule::default(),
+            background_rewards_calculation: None,
             collector_fee_details: RwLock::new(CollectorFeeDetails::default()),
+            accounts_lt_hash: Mutex::new(LtHash::identity()),
         };
 
         bank.transaction_processor = TransactionBatchProcessor::new(
@@ -954,9 +1028,10 @@ impl Bank {
             bank.epoch_schedule.clone(),
             bank.fee_structure.clone(),
             bank.runtime_config.clone(),
-            Arc::new(RwLock::new(ProgramCache::new(
+            Arc::new(RwLock::new(ProgramCache::new_with_config(
                 Slot::default(),
                 Epoch::default(),
+                &bank.runtime_config,
             ))),
         );
 
@@ -1168,6 +1243,8 @@ impl Bank {
             hash: RwLock::new(Hash::default()),
             is_delta: AtomicBool::new(false),
             builtin_program_ids,
+            builtin_entrypoint_overrides: parent.builtin_entrypoint_overrides.clone(),
+            inflation_curve: parent.inflation_curve.clone(),
             tick_height: AtomicU64::new(parent.tick_height.load(Relaxed)),
             signature_count: AtomicU64::new(0),
             runtime_config: parent.runtime_config.clone(),
@@ -1194,11 +1271,20 @@ impl Bank {
             accounts_data_size_initial,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
+            accounts_data_size_audit: AccountsDataSizeAudit::default(),
             fee_structure: parent.fee_structure.clone(),
             epoch_reward_status: parent.epoch_reward_status.clone(),
+            reward_distribution_metadata: None,
             transaction_processor: TransactionBatchProcessor::default(),
             check_program_modification_slot: false,
+            epoch_boundary_schedule: parent.epoch_boundary_schedule.clone(),
+            background_rewards_calculation: if parent.epoch() == epoch {
+                parent.background_rewards_calculation.clone()
+            } else {
+                None
+            },
             collector_fee_details: RwLock::new(CollectorFeeDetails::default()),
+            accounts_lt_hash: Mutex::new(parent.accounts_lt_hash.lock().unwrap().clone()),
         };
 
         new.transaction_processor = TransactionBatchProcessor::new(
@@ -1232,6 +1318,8 @@ impl Bank {
                 // Save a snapshot of stakes for use in consensus and stake weighted networking
                 let leader_schedule_epoch = new.epoch_schedule().get_leader_schedule_epoch(slot);
                 new.update_epoch_stakes(leader_schedule_epoch);
+                new.start_background_rewards_calculation(&parent, reward_calc_tracer.is_some());
+                new.run_deferred_epoch_boundary_tasks(&parent, reward_calc_tracer);
             }
             if new.is_partitioned_rewards_code_enabled() {
                 new.distribute_partitioned_epoch_rewards();
@@ -1284,6 +1372,8 @@ impl Bank {
                     false, /* debugging_features */
                 );
                 let mut upcoming_environments = program_cache.environments.clone();
+                // Programs are recompiled whenever their environment changes,
+                // including the implementations of its syscalls
                 let changed_program_runtime_v1 =
                     *upcoming_environments.program_runtime_v1 != program_runtime_environment_v1;
                 let changed_program_runtime_v2 =
@@ -1439,14 +1529,30 @@ impl Bank {
         let (_, update_rewards_with_thread_pool_time) = measure!(
             {
                 if self.is_partitioned_rewards_code_enabled() {
-                    self.begin_partitioned_rewards(
-                        reward_calc_tracer,
-                        &thread_pool,
-                        parent_epoch,
-                        parent_slot,
-                        parent_height,
-                        &mut rewards_metrics,
-                    );
+                    // The calculation may be deferred to a later block of the epoch
+                    let task = EpochBoundaryTask::RewardsCalculation;
+                    if self.is_epoch_boundary_task_due(task, None) {
+                        self.begin_partitioned_rewards(
+                            reward_calc_tracer,
+                            &thread_pool,
+                            parent_epoch,
+                            parent_slot,
+                            parent_height,
+                            &mut rewards_metrics,
+                        );
+                    } else if self.epoch_boundary_schedule.is_in_background(task)
+                        // Vote accounts change after the calculation, so the
+                        // vote rewards must be credited to the current ones
+                        && self.is_partitioned_vote_rewards_enabled()
+                    {
+                        let partition_seed = self
+                            .parent()
+                            .expect("Partitioned rewards calculation must still have access to parent Bank.")
+                            .last_blockhash();
+                        self.background_rewards_calculation = Some(Arc::new(
+                            BackgroundRewardsCalculation::new(parent_epoch, slot, partition_seed),
+                        ));
+                    }
                 } else {
                     self.update_rewards_with_thread_pool(
                         parent_epoch,
@@ -1627,6 +1733,10 @@ impl Bank {
             epoch_stakes: fields.epoch_stakes,
             is_delta: AtomicBool::new(fields.is_delta),
             builtin_program_ids: HashSet::<Pubkey>::default(),
+            builtin_entrypoint_overrides: HashSet::<Pubkey>::default(),
+            // every node calculates rewards with the inflation from genesis,
+            // see set_inflation_curve()
+            inflation_curve: OptionalInflationCurve::default(),
             runtime_config,
             rewards: RwLock::new(vec![]),
             cluster_type: Some(genesis_config.cluster_type),
@@ -1644,12 +1754,22 @@ impl Bank {
             accounts_data_size_initial,
             accounts_data_size_delta_on_chain: AtomicI64::new(0),
             accounts_data_size_delta_off_chain: AtomicI64::new(0),
+            accounts_data_size_audit: AccountsDataSizeAudit::default(),
             fee_structure: FeeStructure::default(),
             epoch_reward_status: fields.epoch_reward_status,
+            // reward_distribution_metadata is not serialized to snapshot
+            reward_distribution_metadata: None,
             transaction_processor: TransactionBatchProcessor::default(),
             check_program_modification_slot: false,
+            // every node runs the default epoch_boundary_schedule, see
+            // set_epoch_boundary_schedule()
+            epoch_boundary_schedule: EpochBoundarySchedule::default(),
+            // background_rewards_calculation is not serialized to snapshot
+            background_rewards_calculation: None,
             // collector_fee_details is not serialized to snapshot
             collector_fee_details: RwLock::new(CollectorFeeDetails::default()),
+            // accounts_lt_hash is not serialized to snapshot, it is calculated below
+            accounts_lt_hash: Mutex::new(LtHash::identity()),
         };
 
         bank.transaction_processor = TransactionBatchProcessor::new(
@@ -1658,9 +1778,20 @@ impl Bank {
             bank.epoch_schedule.clone(),
             bank.fee_structure.clone(),
             bank.runtime_config.clone(),
-            Arc::new(RwLock::new(ProgramCache::new(fields.slot, fields.epoch))),
+            Arc::new(RwLock::new(ProgramCache::new_with_config(
+                fields.slot,
+                fields.epoch,
+                &bank.runtime_config,
+            ))),
         );
 
+        let (_, calculate_accounts_lt_hash_us) = measure_us!({
+            if bank.is_accounts_lt_hash_enabled() {
+                let accounts_lt_hash = bank.calculate_accounts_lt_hash();
+                *bank.accounts_lt_hash.get_mut().unwrap() = accounts_lt_hash;
+            }
+        });
+
         bank.finish_init(
             genesis_config,
             additional_builtins,
@@ -1714,6 +1845,11 @@ impl Bank {
                 stakes_accounts_load_duration.as_micros(),
                 i64
             ),
+            (
+                "calculate_accounts_lt_hash_us",
+                calculate_accounts_lt_hash_us,
+                i64
+            ),
         );
         bank
     }
@@ -2132,24 +2268,35 @@ impl Bank {
         num_slots as f64 / self.slots_per_year
     }
 
+    /// Run `f` with the emission schedule in effect for this bank: the
+    /// installed `InflationCurve` if any, the L1 `Inflation` otherwise
+    fn with_inflation_curve<R>(&self, f: impl FnOnce(&dyn InflationCurve) -> R) -> R {
+        match &self.inflation_curve.0 {
+            Some(curve) => f(curve.as_ref()),
+            None => f(&*self.inflation.read().unwrap()),
+        }
+    }
+
     fn calculate_previous_epoch_inflation_rewards(
         &self,
         prev_epoch_capitalization: u64,
         prev_epoch: Epoch,
     ) -> PrevEpochInflationRewards {
         let slot_in_year = self.slot_in_year_for_inflation();
-        let (validator_rate, foundation_rate) = {
-            let inflation = self.inflation.read().unwrap();
-            (
-                (*inflation).validator(slot_in_year),
-                (*inflation).foundation(slot_in_year),
-            )
-        };
-
         let prev_epoch_duration_in_years = self.epoch_duration_in_years(prev_epoch);
-        let validator_rewards = (validator_rate
-            * prev_epoch_capitalization as f64
-            * prev_epoch_duration_in_years) as u64;
+        let (validator_rate, foundation_rate, validator_rewards) =
+            self.with_inflation_curve(|curve| {
+                let validator_rate = curve.validator_rate(slot_in_year);
+                (
+                    validator_rate,
+                    curve.foundation_rate(slot_in_year),
+                    curve.validator_rewards(
+                        validator_rate,
+                        prev_epoch_capitalization,
+                        prev_epoch_duration_in_years,
+                    ),
+                )
+            });
 
         PrevEpochInflationRewards {
             validator_rewards,
@@ -2999,6 +3146,9 @@ impl Bank {
 
             // freeze is a one-way trip, idempotent
             self.freeze_started.store(true, Relaxed);
+            if self.is_accounts_lt_hash_enabled() {
+                self.update_accounts_lt_hash();
+            }
             *hash = self.hash_internal_state();
             self.rc.accounts.accounts_db.mark_slot_frozen(self.slot());
         }
@@ -3615,11 +3765,18 @@ impl Bank {
                 enable_cpi_recording,
                 enable_log_recording: true,
                 enable_return_data_recording: true,
+                enable_heap_stats_recording: false,
+                enable_error_location_recording: false,
+                enable_capability_recording: false,
+                enable_instruction_decoding: false,
+                enable_compute_budget_report: false,
             },
             &mut timings,
             Some(&account_overrides),
             None,
             true,
+            // Simulation must not affect the caches of the bank
+            true,
         );
 
         let post_simulation_accounts = loaded_transactions
@@ -3706,96 +3863,6 @@ impl Bank {
         self.rc.accounts.accounts_db.remove_unrooted_slots(slots)
     }
 
-    fn check_age(
-        &self,
-        sanitized_txs: &[impl core::borrow::Borrow<SanitizedTransaction>],
-        lock_results: &[Result<()>],
-        max_age: usize,
-        error_counters: &mut TransactionErrorMetrics,
-    ) -> Vec<TransactionCheckResult> {
-        let hash_queue = self.blockhash_queue.read().unwrap();
-        let last_blockhash = hash_queue.last_hash();
-        let next_durable_nonce = DurableNonce::from_blockhash(&last_blockhash);
-
-        sanitized_txs
-            .iter()
-            .zip(lock_results)
-            .map(|(tx, lock_res)| match lock_res {
-                Ok(()) => self.check_transaction_age(
-                    tx.borrow(),
-                    max_age,
-                    &next_durable_nonce,
-                    &hash_queue,
-                    error_counters,
-                ),
-                Err(e) => (Err(e.clone()), None, None),
-            })
-            .collect()
-    }
-
-    fn check_transaction_age(
-        &self,
-        tx: &SanitizedTransaction,
-        max_age: usize,
-        next_durable_nonce: &DurableNonce,
-        hash_queue: &BlockhashQueue,
-        error_counters: &mut TransactionErrorMetrics,
-    ) -> TransactionCheckResult {
-        let recent_blockhash = tx.message().recent_blockhash();
-        if hash_queue.is_hash_valid_for_age(recent_blockhash, max_age) {
-            (
-                Ok(()),
-                None,
-                hash_queue.get_lamports_per_signature(tx.message().recent_blockhash()),
-            )
-        } else if let Some((address, account)) =
-            self.check_transaction_for_nonce(tx, next_durable_nonce)
-        {
-            let nonce = NoncePartial::new(address, account);
-            let lamports_per_signature = nonce.lamports_per_signature();
-            (Ok(()), Some(nonce), lamports_per_signature)
-        } else {
-            error_counters.blockhash_not_found += 1;
-            (Err(TransactionError::BlockhashNotFound), None, None)
-        }
-    }
-
-    fn is_transaction_already_processed(
-        &self,
-        sanitized_tx: &SanitizedTransaction,
-        status_cache: &BankStatusCache,
-    ) -> bool {
-        let key = sanitized_tx.message_hash();
-        let transaction_blockhash = sanitized_tx.message().recent_blockhash();
-        status_cache
-            .get_status(key, transaction_blockhash, &self.ancestors)
-            .is_some()
-    }
-
-    fn check_status_cache(
-        &self,
-        sanitized_txs: &[impl core::borrow::Borrow<SanitizedTransaction>],
-        lock_results: Vec<TransactionCheckResult>,
-        error_counters: &mut TransactionErrorMetrics,
-    ) -> Vec<TransactionCheckResult> {
-        let rcache = self.status_cache.read().unwrap();
-        sanitized_txs
-            .iter()
-            .zip(lock_results)
-            .map(|(sanitized_tx, (lock_result, nonce, lamports))| {
-                let sanitized_tx = sanitized_tx.borrow();
-                if lock_result.is_ok()
-                    && self.is_transaction_already_processed(sanitized_tx, &rcache)
-                {
-                    error_counters.already_processed += 1;
-                    return (Err(TransactionError::AlreadyProcessed), None, None);
-                }
-
-                (lock_result, nonce, lamports)
-            })
-            .collect()
-    }
-
     pub fn get_hash_age(&self, hash: &Hash) -> Option<u64> {
         self.blockhash_queue.read().unwrap().get_hash_age(hash)
     }
@@ -3808,32 +3875,19 @@ impl Bank {
     }
 
     fn check_message_for_nonce(&self, message: &SanitizedMessage) -> Option<TransactionAccount> {
-        let nonce_address = message.get_durable_nonce()?;
-        let nonce_account = self.get_account_with_fixed_root(nonce_address)?;
-        let nonce_data =
-            nonce_account::verify_nonce_account(&nonce_account, message.recent_blockhash())?;
-
-        let nonce_is_authorized = message
-            .get_ix_signers(NONCED_TX_MARKER_IX_INDEX as usize)
-            .any(|signer| signer == &nonce_data.authority);
-        if !nonce_is_authorized {
-            return None;
-        }
-
-        Some((*nonce_address, nonce_account))
+        durable_nonce_account(message, |address| self.get_account_with_fixed_root(address))
     }
 
+    #[cfg(test)]
     fn check_transaction_for_nonce(
         &self,
         tx: &SanitizedTransaction,
         next_durable_nonce: &DurableNonce,
     ) -> Option<TransactionAccount> {
-        let nonce_is_advanceable = tx.message().recent_blockhash() != next_durable_nonce.as_hash();
-        if nonce_is_advanceable {
-            self.check_message_for_nonce(tx.message())
-        } else {
-            None
-        }
+        NonceCheck::new(*next_durable_nonce, |address: &Pubkey| {
+            self.get_account_with_fixed_root(address)
+        })
+        .nonce_account(tx)
     }
 
     pub fn check_transactions(
@@ -3843,8 +3897,29 @@ impl Bank {
         max_age: usize,
         error_counters: &mut TransactionErrorMetrics,
     ) -> Vec<TransactionCheckResult> {
-        let lock_results = self.check_age(sanitized_txs, lock_results, max_age, error_counters);
-        self.check_status_cache(sanitized_txs, lock_results, error_counters)
+        let hash_queue = self.blockhash_queue.read().unwrap();
+        let next_durable_nonce = DurableNonce::from_blockhash(&hash_queue.last_hash());
+        let status_cache = self.status_cache.read().unwrap();
+
+        let check = BlockhashCheck::new(|recent_blockhash: &Hash| {
+            hash_queue
+                .is_hash_valid_for_age(recent_blockhash, max_age)
+                .then(|| hash_queue.get_lamports_per_signature(recent_blockhash))
+                .flatten()
+        })
+        .or(NonceCheck::new(next_durable_nonce, |address: &Pubkey| {
+            self.get_account_with_fixed_root(address)
+        }))
+        .and(AlreadyProcessedCheck::new(|tx: &SanitizedTransaction| {
+            status_cache
+                .get_status(
+                    tx.message_hash(),
+                    tx.message().recent_blockhash(),
+                    &self.ancestors,
+                )
+                .is_some()
+        }));
+        transaction_checks::check_transactions(&check, sanitized_txs, lock_results, error_counters)
     }
 
     pub fn collect_balances(&self, batch: &TransactionBatch) -> TransactionBalances {
@@ -3869,6 +3944,7 @@ impl Bank {
         account_overrides: Option<&AccountOverrides>,
         log_messages_bytes_limit: Option<usize>,
         limit_to_load_programs: bool,
+        dry_run: bool,
     ) -> LoadAndExecuteTransactionsOutput {
         let sanitized_txs = batch.sanitized_transactions();
         debug!("processing transactions: {}", sanitized_txs.len());
@@ -3921,6 +3997,10 @@ impl Bank {
         debug!("check: {}us", check_time.as_us());
         timings.saturating_add_in_place(ExecuteTimingType::CheckUs, check_time.as_us());
 
+        // The fee distribution of the output is SVM-only and left empty: the
+        // bank keeps the `NoopFeeDistributor` of its processor and distributes
+        // the collected fees itself when frozen, see
+        // `distribute_transaction_fees()`
         let sanitized_output = self
             .transaction_processor
             .load_and_execute_sanitized_transactions(
@@ -3934,6 +4014,7 @@ impl Bank {
                 self.builtin_program_ids.iter(),
                 log_messages_bytes_limit,
                 limit_to_load_programs,
+                dry_run,
             );
 
         let mut signature_count = 0;
@@ -3960,6 +4041,7 @@ impl Bank {
             let is_vote = tx.is_simple_vote_transaction();
 
             if execution_result.was_executed() // Skip log collection for unprocessed transactions
+                && !dry_run
                 && transaction_log_collector_config.filter != TransactionLogCollectorFilter::None
             {
                 let mut filtered_mentioned_addresses = Vec::new();
@@ -4060,6 +4142,8 @@ impl Bank {
             executed_with_successful_result_count,
             signature_count,
             error_counters,
+            program_usage_report: sanitized_output.program_usage_report,
+            account_contention_report: sanitized_output.account_contention_report,
         }
     }
 
@@ -5054,6 +5138,7 @@ impl Bank {
             None,
             log_messages_bytes_limit,
             false,
+            false,
         );
 
         let (last_blockhash, lamports_per_signature) =
@@ -5121,6 +5206,11 @@ impl Bank {
                 enable_cpi_recording: false,
                 enable_log_recording: true,
                 enable_return_data_recording: true,
+                enable_heap_stats_recording: false,
+                enable_error_location_recording: false,
+                enable_capability_recording: false,
+                enable_instruction_decoding: false,
+                enable_compute_budget_report: false,
             },
             &mut ExecuteTimings::default(),
             Some(1000 * 1000),
@@ -5349,7 +5439,10 @@ impl Bank {
                 .iter()
                 .chain(additional_builtins.unwrap_or(&[]).iter())
             {
-                if builtin.enable_feature_id.is_none() {
+                let is_retired = builtin
+                    .disable_feature_id
+                    .is_some_and(|feature_id| self.feature_set.is_active(&feature_id));
+                if builtin.enable_feature_id.is_none() && !is_retired {
                     self.add_builtin(
                         builtin.program_id,
                         builtin.name,
@@ -5521,6 +5614,73 @@ impl Bank {
         self.rc.accounts.load_all(&self.ancestors, self.bank_id)
     }
 
+    /// Whether the bank maintains the accounts lt hash, see
+    /// `AccountsDbConfig::enable_accounts_lt_hash`
+    fn is_accounts_lt_hash_enabled(&self) -> bool {
+        self.rc.accounts.accounts_db.enable_accounts_lt_hash
+    }
+
+    /// Lattice hash of all accounts as of this bank's slot, if enabled by
+    /// `AccountsDbConfig::enable_accounts_lt_hash`.
+    ///
+    /// It is maintained incrementally: freezing the bank mixes the accounts
+    /// written in its slot into the hash of its parent, without scanning all
+    /// accounts. It is final once the bank is frozen.
+    pub fn accounts_lt_hash(&self) -> Option<LtHash> {
+        self.is_accounts_lt_hash_enabled()
+            .then(|| self.accounts_lt_hash.lock().unwrap().clone())
+    }
+
+    /// Compact commitment to the accounts state as of this bank's slot, see
+    /// `Bank::accounts_lt_hash()`
+    pub fn accounts_lt_hash_checksum(&self) -> Option<Hash> {
+        self.is_accounts_lt_hash_enabled()
+            .then(|| self.accounts_lt_hash.lock().unwrap().checksum())
+    }
+
+    /// Replace the versions of the accounts written in this bank's slot which
+    /// are visible to its parent by the latest ones in the accounts lt hash.
+    /// Must be called when freezing the bank, once no account is stored
+    /// anymore.
+    fn update_accounts_lt_hash(&self) {
+        let Some(slot_cache) = self
+            .rc
+            .accounts
+            .accounts_db
+            .accounts_cache
+            .slot_cache(self.slot())
+        else {
+            return;
+        };
+        let mut parent_ancestors = self.ancestors.clone();
+        parent_ancestors.remove(&self.slot());
+        let mut delta = LtHash::identity();
+        for cached_account in slot_cache.iter() {
+            let pubkey = cached_account.key();
+            if let Some((account, _slot)) = self
+                .rc
+                .accounts
+                .load_with_fixed_root_do_not_populate_read_cache(&parent_ancestors, pubkey)
+            {
+                delta.mix_out(&lt_hash_account(&account, pubkey));
+            }
+            delta.mix_in(&lt_hash_account(&cached_account.value().account, pubkey));
+        }
+        self.accounts_lt_hash.lock().unwrap().mix_in(&delta);
+    }
+
+    /// Calculate the accounts lt hash from scratch, by scanning all accounts
+    fn calculate_accounts_lt_hash(&self) -> LtHash {
+        let mut accounts_lt_hash = LtHash::identity();
+        self.scan_all_accounts(|maybe_account| {
+            if let Some((pubkey, account, _slot)) = maybe_account {
+                accounts_lt_hash.mix_in(&lt_hash_account(&account, pubkey));
+            }
+        })
+        .expect("scan all accounts");
+        accounts_lt_hash
+    }
+
     // Scans all the accounts this bank can load, applying `scan_func`
     pub fn scan_all_accounts<F>(&self, scan_func: F) -> ScanResult<()>
     where
@@ -6265,6 +6425,19 @@ impl Bank {
         self.tick_height.load(Relaxed)
     }
 
+    /// Install a custom emission schedule used by the epoch rewards
+    /// calculation in place of the `Inflation` parameters. Child banks
+    /// inherit the curve.
+    ///
+    /// The rewards are part of consensus, so the emission schedule of a
+    /// cluster is its `Inflation`, which comes from genesis and is part of
+    /// snapshots. Other curves are only available to tests: a bank restored
+    /// from a snapshot calculates rewards with `Inflation` again.
+    #[cfg(feature = "dev-context-only-utils")]
+    pub fn set_inflation_curve(&mut self, inflation_curve: Arc<dyn InflationCurve>) {
+        self.inflation_curve = OptionalInflationCurve(Some(inflation_curve));
+    }
+
     /// Return the inflation parameters of the Bank
     pub fn inflation(&self) -> Inflation {
         *self.inflation.read().unwrap()
@@ -6470,6 +6643,33 @@ impl Bank {
         debug!("Removed program {}", program_id);
     }
 
+    /// Retire a built-in instruction processor without replacing it. Unlike
+    /// `remove_builtin()`, the program is no longer one of the bank's
+    /// built-in programs afterwards. The program cache gets a tombstone, so
+    /// that the builtin is only gone from this fork on. Retiring a builtin
+    /// which is not registered, e.g. again after loading a snapshot, does
+    /// nothing.
+    fn retire_builtin(&mut self, program_id: Pubkey, name: &str) {
+        // Don't remove the account since the bank expects the account state to
+        // be idempotent
+        if !self.builtin_program_ids.remove(&program_id) {
+            return;
+        }
+        debug!("Retiring program {} under {:?}", name, program_id);
+        self.transaction_processor
+            .program_cache
+            .write()
+            .unwrap()
+            .assign_program(
+                program_id,
+                Arc::new(LoadedProgram::new_tombstone(
+                    self.slot,
+                    LoadedProgramType::Closed,
+                )),
+            );
+        debug!("Retired program {}", program_id);
+    }
+
     pub fn add_precompile(&mut self, program_id: &Pubkey) {
         debug!("Adding precompiled program {}", program_id);
         self.add_precompiled_account(program_id);
@@ -6699,27 +6899,17 @@ impl Bank {
         only_apply_transitions_for_new_features: bool,
         new_feature_activations: &HashSet<Pubkey>,
     ) {
-        for builtin in BUILTINS.iter() {
-            if let Some(feature_id) = builtin.enable_feature_id {
-                let should_apply_action_for_feature_transition =
-                    if only_apply_transitions_for_new_features {
-                        new_feature_activations.contains(&feature_id)
-                    } else {
-                        self.feature_set.is_active(&feature_id)
-                    };
-                if should_apply_action_for_feature_transition {
-                    self.add_builtin(
-                        builtin.program_id,
-                        builtin.name,
-                        LoadedProgram::new_builtin(
-                            self.feature_set.activated_slot(&feature_id).unwrap_or(0),
-                            builtin.name.len(),
-                            builtin.entrypoint,
-                        ),
-                    );
-                }
-            }
-        }
+        let delta_off_chain_before = self.load_accounts_data_size_delta_off_chain();
+        self.apply_builtin_feature_transitions(
+            BUILTINS,
+            only_apply_transitions_for_new_features,
+            new_feature_activations,
+        );
+        self.apply_stateless_builtin_feature_transitions(
+            STATELESS_BUILTINS,
+            only_apply_transitions_for_new_features,
+            new_feature_activations,
+        );
         for precompile in get_precompiles() {
             let should_add_precompile = precompile
                 .feature
@@ -6730,6 +6920,97 @@ impl Bank {
                 self.add_precompile(&precompile.program_id);
             }
         }
+        self.audit_accounts_data_size_delta(
+            AccountsDataSizeSource::BuiltinAdditions,
+            delta_off_chain_before,
+        );
+    }
+
+    /// Add the builtins whose `enable_feature_id` is active, and retire those
+    /// whose `disable_feature_id` is active.
+    fn apply_builtin_feature_transitions(
+        &mut self,
+        builtins: &[BuiltinPrototype],
+        only_apply_transitions_for_new_features: bool,
+        new_feature_activations: &HashSet<Pubkey>,
+    ) {
+        for builtin in builtins {
+            let disable_feature_id = builtin
+                .disable_feature_id
+                .filter(|feature_id| self.feature_set.is_active(feature_id));
+            let Some(feature_id) = disable_feature_id.or(builtin.enable_feature_id) else {
+                continue;
+            };
+            let should_apply_action_for_feature_transition =
+                if only_apply_transitions_for_new_features {
+                    new_feature_activations.contains(&feature_id)
+                } else {
+                    self.feature_set.is_active(&feature_id)
+                };
+            if !should_apply_action_for_feature_transition {
+                continue;
+            }
+            if disable_feature_id.is_some() {
+                self.retire_builtin(builtin.program_id, builtin.name);
+            } else {
+                self.add_builtin(
+                    builtin.program_id,
+                    builtin.name,
+                    LoadedProgram::new_builtin(
+                        self.feature_set.activated_slot(&feature_id).unwrap_or(0),
+                        builtin.name.len(),
+                        builtin.entrypoint,
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Register the stateless builtins whose `enable_feature_id` is active
+    /// with the bank's built-in programs, and unregister those whose
+    /// `disable_feature_id` is active. Stateless builtins have neither an
+    /// account nor an entrypoint, so only the built-in program IDs change.
+    fn apply_stateless_builtin_feature_transitions(
+        &mut self,
+        stateless_builtins: &[StatelessBuiltinPrototype],
+        only_apply_transitions_for_new_features: bool,
+        new_feature_activations: &HashSet<Pubkey>,
+    ) {
+        let should_apply_action_for_feature_transition = |feature_id: &Pubkey| {
+            if only_apply_transitions_for_new_features {
+                new_feature_activations.contains(feature_id)
+            } else {
+                self.feature_set.is_active(feature_id)
+            }
+        };
+        let mut to_register = vec![];
+        let mut to_unregister = vec![];
+        for builtin in stateless_builtins {
+            let disable_feature_id = builtin
+                .disable_feature_id
+                .filter(|feature_id| self.feature_set.is_active(feature_id));
+            if let Some(feature_id) = disable_feature_id {
+                if should_apply_action_for_feature_transition(&feature_id) {
+                    to_unregister.push(builtin.program_id);
+                }
+            } else if let Some(feature_id) = builtin.enable_feature_id {
+                if should_apply_action_for_feature_transition(&feature_id) {
+                    to_register.push(builtin.program_id);
+                }
+            }
+        }
+        #[cfg(test)]
+        if let Err(err) = fault_injection::trigger(fault_injection::FaultPoint::BuiltinTransition) {
+            panic!("failed to apply the stateless builtin transitions: {err}");
+        }
+        for program_id in to_unregister {
+            debug!("Unregistering stateless builtin {}", program_id);
+            self.builtin_program_ids.remove(&program_id);
+        }
+        for program_id in to_register {
+            debug!("Registering stateless builtin {}", program_id);
+            self.builtin_program_ids.insert(program_id);
+        }
     }
 
     /// Use to replace programs by feature activation
@@ -6909,6 +7190,15 @@ impl TransactionProcessingCallback for Bank {
             .map(|(acc, _)| acc)
     }
 
+    fn peek_account_meta(&self, pubkey: &Pubkey) -> Option<AccountMeta> {
+        self.rc
+            .accounts
+            .accounts_db
+            .inspect_account_with_fixed_root(&self.ancestors, pubkey, |account| {
+                AccountMeta::from(account)
+            })
+    }
+
     fn get_last_blockhash_and_lamports_per_signature(&self) -> (Hash, u64) {
         self.last_blockhash_and_lamports_per_signature()
     }
@@ -6979,6 +7269,43 @@ impl Bank {
         Self::new_for_tests_with_config(genesis_config, BankTestConfig::default())
     }
 
+    /// Override the entrypoint of an already registered builtin program,
+    /// bypassing feature activation, e.g. to run an instrumented system
+    /// program. The override is recorded and inherited by child banks, but is
+    /// replaced whenever a feature transition re-adds the builtin.
+    pub fn override_builtin_entrypoint(
+        &mut self,
+        program_id: Pubkey,
+        entrypoint: BuiltinFunctionWithContext,
+    ) {
+        assert!(
+            self.builtin_program_ids.contains(&program_id),
+            "Cannot override the entrypoint of {program_id}, which is not a builtin program"
+        );
+        let account_size = self
+            .get_account_with_fixed_root(&program_id)
+            .map(|account| account.data().len())
+            .unwrap_or_default();
+        self.transaction_processor
+            .program_cache
+            .write()
+            .unwrap()
+            .assign_program(
+                program_id,
+                Arc::new(LoadedProgram::new_builtin(
+                    self.slot,
+                    account_size,
+                    entrypoint,
+                )),
+            );
+        self.builtin_entrypoint_overrides.insert(program_id);
+    }
+
+    /// Builtin programs whose entrypoint has been overridden
+    pub fn builtin_entrypoint_overrides(&self) -> &HashSet<Pubkey> {
+        &self.builtin_entrypoint_overrides
+    }
+
     pub fn new_with_mockup_builtin_for_tests(
         genesis_config: &GenesisConfig,
         program_id: Pubkey,
@@ -0,0 +1,130 @@
+//! Attribution of the off-chain accounts data size delta of a bank to the
+//! epoch boundary tasks which contributed to it.
+//!
+//! Several epoch boundary tasks change the size of account data outside of
+//! transactions, and the bank only keeps the sum of their changes. When the
+//! accounts data size of two nodes diverges, the deltas itemized by task and
+//! written to the bank hash details tell which subsystem is responsible.
+
+use {
+    super::Bank,
+    std::{collections::BTreeMap, sync::Mutex},
+};
+
+/// Epoch boundary task changing the size of account data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccountsDataSizeSource {
+    /// Builtins and precompiles added by feature activations
+    BuiltinAdditions,
+    /// Migrations of builtins to Core BPF programs
+    CoreBpfMigrations,
+    /// Creation, updates and removal of the epoch rewards sysvar
+    EpochRewardsSysvar,
+}
+
+impl AccountsDataSizeSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::BuiltinAdditions => "builtin_additions",
+            Self::CoreBpfMigrations => "core_bpf_migrations",
+            Self::EpochRewardsSysvar => "epoch_rewards_sysvar",
+        }
+    }
+}
+
+/// Accounts data size deltas of a bank, by epoch boundary task
+#[derive(Debug, Default)]
+pub struct AccountsDataSizeAudit {
+    deltas: Mutex<BTreeMap<AccountsDataSizeSource, i64>>,
+}
+
+impl AccountsDataSizeAudit {
+    /// Add `delta` to the delta of `source`. The arithmetic saturates.
+    pub fn record(&self, source: AccountsDataSizeSource, delta: i64) {
+        if delta == 0 {
+            return;
+        }
+        let mut deltas = self.deltas.lock().unwrap();
+        let total = deltas.entry(source).or_default();
+        *total = total.saturating_add(delta);
+    }
+
+    pub fn deltas(&self) -> BTreeMap<AccountsDataSizeSource, i64> {
+        self.deltas.lock().unwrap().clone()
+    }
+}
+
+impl Bank {
+    /// Attribute the change of the off-chain accounts data size delta since
+    /// it was `delta_off_chain_before` to `source`
+    pub(in crate::bank) fn audit_accounts_data_size_delta(
+        &self,
+        source: AccountsDataSizeSource,
+        delta_off_chain_before: i64,
+    ) {
+        let delta = self
+            .load_accounts_data_size_delta_off_chain()
+            .saturating_sub(delta_off_chain_before);
+        self.accounts_data_size_audit.record(source, delta);
+    }
+
+    /// The off-chain accounts data size deltas of this bank, by epoch
+    /// boundary task
+    pub fn accounts_data_size_deltas_by_source(&self) -> BTreeMap<AccountsDataSizeSource, i64> {
+        self.accounts_data_size_audit.deltas()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            feature_set::{self, FeatureSet},
+            genesis_config::create_genesis_config,
+        },
+        std::{collections::HashSet, sync::Arc},
+    };
+
+    #[test]
+    fn test_accounts_data_size_audit() {
+        let audit = AccountsDataSizeAudit::default();
+        audit.record(AccountsDataSizeSource::EpochRewardsSysvar, 0);
+        assert!(audit.deltas().is_empty());
+
+        audit.record(AccountsDataSizeSource::EpochRewardsSysvar, 10);
+        audit.record(AccountsDataSizeSource::BuiltinAdditions, 3);
+        audit.record(AccountsDataSizeSource::EpochRewardsSysvar, -4);
+        assert_eq!(
+            audit.deltas(),
+            BTreeMap::from([
+                (AccountsDataSizeSource::BuiltinAdditions, 3),
+                (AccountsDataSizeSource::EpochRewardsSysvar, 6),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_audit_builtin_additions() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(100_000);
+        let mut bank = Bank::new_for_tests(&genesis_config);
+        let delta_off_chain_before = bank.load_accounts_data_size_delta_off_chain();
+
+        let feature_id = feature_set::zk_token_sdk_enabled::id();
+        let mut feature_set = FeatureSet::clone(&bank.feature_set);
+        feature_set.activate(&feature_id, 0);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_builtin_program_feature_transitions(true, &HashSet::from([feature_id]));
+
+        // The account of the builtin holds its name
+        let delta = "zk_token_proof_program".len() as i64;
+        assert_eq!(
+            bank.load_accounts_data_size_delta_off_chain() - delta_off_chain_before,
+            delta
+        );
+        assert_eq!(
+            bank.accounts_data_size_deltas_by_source(),
+            BTreeMap::from([(AccountsDataSizeSource::BuiltinAdditions, delta)])
+        );
+    }
+}
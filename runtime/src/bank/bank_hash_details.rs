@@ -18,7 +18,7 @@ use {
         hash::Hash,
         pubkey::Pubkey,
     },
-    std::str::FromStr,
+    std::{collections::BTreeMap, str::FromStr},
 };
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -85,6 +85,11 @@ pub struct BankHashSlotDetails {
     #[serde(skip_serializing_if = "bankhashaccounts_is_empty")]
     #[serde(default)]
     pub accounts: BankHashAccounts,
+    /// Off-chain accounts data size deltas of the epoch boundary tasks of
+    /// the bank, by task
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[serde(default)]
+    pub accounts_data_size_deltas: BTreeMap<String, i64>,
 }
 
 fn u64_is_zero(val: &u64) -> bool {
@@ -113,6 +118,7 @@ impl BankHashSlotDetails {
             signature_count,
             last_blockhash: last_blockhash.to_string(),
             accounts,
+            accounts_data_size_deltas: BTreeMap::new(),
         }
     }
 }
@@ -145,15 +151,24 @@ impl TryFrom<&Bank> for BankHashSlotDetails {
         // sort by pubkey to match the ordering used for accounts delta hash
         accounts.sort_by_key(|account| account.pubkey);
 
-        Ok(Self::new(
-            slot,
-            bank.hash(),
-            bank.parent_hash(),
-            accounts_delta_hash,
-            bank.signature_count(),
-            bank.last_blockhash(),
-            BankHashAccounts { accounts },
-        ))
+        let accounts_data_size_deltas = bank
+            .accounts_data_size_deltas_by_source()
+            .into_iter()
+            .map(|(source, delta)| (source.name().to_string(), delta))
+            .collect();
+
+        Ok(Self {
+            accounts_data_size_deltas,
+            ..Self::new(
+                slot,
+                bank.hash(),
+                bank.parent_hash(),
+                accounts_delta_hash,
+                bank.signature_count(),
+                bank.last_blockhash(),
+                BankHashAccounts { accounts },
+            )
+        })
     }
 }
 
@@ -319,15 +334,21 @@ pub mod tests {
                 let accounts_delta_hash = hash("accounts_delta".as_bytes());
                 let last_blockhash = hash("last_blockhash".as_bytes());
 
-                BankHashSlotDetails::new(
-                    slot as Slot,
-                    bank_hash,
-                    parent_bank_hash,
-                    accounts_delta_hash,
-                    signature_count,
-                    last_blockhash,
-                    accounts,
-                )
+                BankHashSlotDetails {
+                    accounts_data_size_deltas: BTreeMap::from([(
+                        "builtin_additions".to_string(),
+                        slot as i64 - 1,
+                    )]),
+                    ..BankHashSlotDetails::new(
+                        slot as Slot,
+                        bank_hash,
+                        parent_bank_hash,
+                        accounts_delta_hash,
+                        signature_count,
+                        last_blockhash,
+                        accounts,
+                    )
+                }
             })
             .collect();
 
@@ -42,6 +42,145 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_apply_builtin_feature_transitions_retire() {
+        solana_program_runtime::declare_process_instruction!(MockBuiltin, 0, |_invoke_context| {
+            Ok(())
+        });
+        let (genesis_config, _mint_keypair) = create_genesis_config(100_000);
+        let mut bank = Bank::new_for_tests(&genesis_config);
+
+        let enable_feature_id = Pubkey::new_unique();
+        let disable_feature_id = Pubkey::new_unique();
+        let builtins = [BuiltinPrototype {
+            core_bpf_migration_config: None,
+            core_bpf_to_builtin_migration_config: None,
+            enable_feature_id: Some(enable_feature_id),
+            disable_feature_id: Some(disable_feature_id),
+            program_id: Pubkey::new_unique(),
+            name: "retired_builtin",
+            entrypoint: MockBuiltin::vm,
+        }];
+        let program_id = builtins[0].program_id;
+
+        let mut feature_set = FeatureSet::default();
+        feature_set.activate(&enable_feature_id, 0);
+        feature_set.inactive.insert(disable_feature_id);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_builtin_feature_transitions(
+            &builtins,
+            true,
+            &HashSet::from([enable_feature_id]),
+        );
+        assert!(bank.get_builtin_program_ids().contains(&program_id));
+
+        // Activating the disable feature in a later slot retires the builtin
+        // without a replacement
+        let mut bank = Bank::new_from_parent(Arc::new(bank), &Pubkey::default(), 1);
+        let builtin_account = bank.get_account(&program_id).unwrap();
+        let mut feature_set = FeatureSet::clone(&bank.feature_set);
+        feature_set.activate(&disable_feature_id, 1);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_builtin_feature_transitions(
+            &builtins,
+            true,
+            &HashSet::from([disable_feature_id]),
+        );
+        assert!(!bank.get_builtin_program_ids().contains(&program_id));
+        {
+            let program_cache = bank.transaction_processor.program_cache.read().unwrap();
+            let slot_versions = program_cache.get_slot_versions_for_tests(&program_id);
+            assert_eq!(slot_versions.len(), 2);
+            assert!(matches!(
+                slot_versions[0].program,
+                LoadedProgramType::Builtin(_)
+            ));
+            assert!(matches!(
+                slot_versions[1].program,
+                LoadedProgramType::Closed
+            ));
+            assert_eq!(slot_versions[1].deployment_slot, 1);
+        }
+        // The account is kept
+        assert_eq!(bank.get_account(&program_id), Some(builtin_account));
+
+        // The disable feature takes precedence over the enable feature when
+        // the transitions are applied from scratch, e.g. from a snapshot
+        bank.apply_builtin_feature_transitions(&builtins, false, &HashSet::new());
+        assert!(!bank.get_builtin_program_ids().contains(&program_id));
+    }
+
+    #[test]
+    fn test_apply_stateless_builtin_feature_transitions() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(100_000);
+        let mut bank = Bank::new_for_tests(&genesis_config);
+
+        let enable_feature_id = Pubkey::new_unique();
+        let disable_feature_id = Pubkey::new_unique();
+        let stateless_builtins = [
+            StatelessBuiltinPrototype {
+                core_bpf_migration_config: None,
+                enable_feature_id: Some(enable_feature_id),
+                disable_feature_id: Some(disable_feature_id),
+                program_id: Pubkey::new_unique(),
+                name: "gated_stateless_builtin",
+            },
+            StatelessBuiltinPrototype {
+                core_bpf_migration_config: None,
+                enable_feature_id: None,
+                disable_feature_id: None,
+                program_id: Pubkey::new_unique(),
+                name: "reserved_stateless_builtin",
+            },
+        ];
+        let gated_id = stateless_builtins[0].program_id;
+        let reserved_id = stateless_builtins[1].program_id;
+
+        let mut feature_set = FeatureSet::default();
+        feature_set.inactive.insert(enable_feature_id);
+        feature_set.inactive.insert(disable_feature_id);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_stateless_builtin_feature_transitions(
+            &stateless_builtins,
+            false,
+            &HashSet::new(),
+        );
+        assert!(!bank.get_builtin_program_ids().contains(&gated_id));
+        assert!(!bank.get_builtin_program_ids().contains(&reserved_id));
+
+        // Activating the enable feature registers the builtin
+        let mut feature_set = FeatureSet::clone(&bank.feature_set);
+        feature_set.activate(&enable_feature_id, 0);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_stateless_builtin_feature_transitions(
+            &stateless_builtins,
+            true,
+            &HashSet::from([enable_feature_id]),
+        );
+        assert!(bank.get_builtin_program_ids().contains(&gated_id));
+        assert!(!bank.get_builtin_program_ids().contains(&reserved_id));
+        // No account is created for it
+        assert!(bank.get_account(&gated_id).is_none());
+
+        // Activating the disable feature unregisters it again, also when
+        // the transitions are applied from scratch, e.g. from a snapshot
+        let mut feature_set = FeatureSet::clone(&bank.feature_set);
+        feature_set.activate(&disable_feature_id, 0);
+        bank.feature_set = Arc::new(feature_set);
+        bank.apply_stateless_builtin_feature_transitions(
+            &stateless_builtins,
+            true,
+            &HashSet::from([disable_feature_id]),
+        );
+        assert!(!bank.get_builtin_program_ids().contains(&gated_id));
+        bank.apply_stateless_builtin_feature_transitions(
+            &stateless_builtins,
+            false,
+            &HashSet::new(),
+        );
+        assert!(!bank.get_builtin_program_ids().contains(&gated_id));
+    }
+
     #[test]
     fn test_startup_from_snapshot_after_precompile_transition() {
         let (genesis_config, _mint_keypair) = create_genesis_config(100_000);
@@ -1,4 +1,5 @@
 use {
+    crate::bank::staged_writes::StagedWritesError,
     solana_sdk::{instruction::InstructionError, pubkey::Pubkey},
     thiserror::Error,
 };
@@ -36,4 +37,13 @@ pub enum CoreBpfMigrationError {
     /// Arithmetic overflow
     #[error("Arithmetic overflow")]
     ArithmeticOverflow,
+    /// Target program ID is an active reserved account key
+    #[error("Target program is an active reserved account key: {0:?}")]
+    TargetIsReservedAccountKey(Pubkey),
+    /// Target program ID is an enabled precompile
+    #[error("Target program is an enabled precompile: {0:?}")]
+    TargetIsPrecompile(Pubkey),
+    /// The migration's account writes failed validation
+    #[error("Invalid account writes: {0}")]
+    StagedWrites(#[from] StagedWritesError),
 }
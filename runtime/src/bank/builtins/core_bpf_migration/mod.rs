@@ -3,11 +3,19 @@ pub(crate) mod error;
 mod source_upgradeable_bpf;
 mod target_builtin;
 
+#[cfg(test)]
+use crate::bank::fault_injection::{self, FaultPoint};
 use {
-    crate::bank::Bank,
+    crate::bank::{
+        accounts_data_size_audit::AccountsDataSizeSource,
+        builtins::{BUILTINS, STATELESS_BUILTINS},
+        staged_writes::StagedWrites,
+        Bank,
+    },
     error::CoreBpfMigrationError,
     solana_program_runtime::{
-        invoke_context::InvokeContext, loaded_programs::LoadedProgramsForTxBatch,
+        invoke_context::{BuiltinFunctionWithContext, InvokeContext},
+        loaded_programs::{LoadedProgram, LoadedProgramsForTxBatch},
         sysvar_cache::SysvarCache,
     },
     solana_sdk::{
@@ -16,7 +24,10 @@ use {
         clock::Slot,
         hash::Hash,
         instruction::InstructionError,
+        native_loader,
+        precompiles::is_precompile,
         pubkey::Pubkey,
+        reserved_account_keys::ReservedAccountKeys,
         transaction_context::TransactionContext,
     },
     source_upgradeable_bpf::SourceUpgradeableBpf,
@@ -54,6 +65,24 @@ pub(crate) struct CoreBpfMigrationConfig {
     pub datapoint_name: &'static str,
 }
 
+/// Configuration for migrating a Core BPF program back to its built-in
+/// implementation, ie. rolling back a migration to Core BPF if the migrated
+/// program is found to be broken.
+#[derive(Debug, PartialEq)]
+pub(crate) struct CoreBpfToBuiltinMigrationConfig {
+    /// The feature gate to trigger the migration back to the builtin.
+    /// Note: This feature gate should never be the same as the `feature_id`
+    /// of the builtin's `CoreBpfMigrationConfig`. It should always be a
+    /// feature gate that will be activated after the builtin was migrated to
+    /// Core BPF.
+    pub feature_id: Pubkey,
+    /// Static message used to emit datapoint logging.
+    /// This is used to identify the migration in the logs.
+    /// Should be unique to the migration, ie:
+    /// "migrate_core_bpf_to_builtin_{program_name}".
+    pub datapoint_name: &'static str,
+}
+
 fn checked_add(a: usize, b: usize) -> Result<usize, CoreBpfMigrationError> {
     a.checked_add(b)
         .ok_or(CoreBpfMigrationError::ArithmeticOverflow)
@@ -141,12 +170,14 @@ impl Bank {
     /// Invoking the loader's `direct_deploy_program` function will update the
     /// program cache in the currently executing context (ie. `programs_loaded`
     /// and `programs_modified`), but the runtime must also propagate those
-    /// updates to the currently active cache.
+    /// updates to the currently active cache. The returned `programs_modified`
+    /// are merged into it by the caller once the migrated accounts are
+    /// stored, so that a failed migration leaves the cache as is.
     fn directly_invoke_loader_v3_deploy(
         &self,
         builtin_program_id: &Pubkey,
         program_data_account: &AccountSharedData,
-    ) -> Result<(), InstructionError> {
+    ) -> Result<LoadedProgramsForTxBatch, InstructionError> {
         let programdata_data_offset = UpgradeableLoaderState::size_of_programdata_metadata();
         let data_len = program_data_account.data().len();
         let elf = program_data_account
@@ -209,14 +240,39 @@ impl Bank {
             )?
         }
 
-        // Update the program cache by merging with `programs_modified`, which
-        // should have been updated by the deploy function.
-        self.transaction_processor
-            .program_cache
-            .write()
-            .unwrap()
-            .merge(&programs_modified);
+        Ok(programs_modified)
+    }
 
+    /// Refuse to migrate a program which the runtime still treats specially
+    /// outside of the builtins list: replacing it would silently change
+    /// transaction locking or precompile verification.
+    ///
+    /// Builtins are reserved account keys themselves, which is no reason to
+    /// refuse them. Only keys reserved for something else, e.g. sysvars and
+    /// the native loader, are refused.
+    fn check_core_bpf_migration_target(
+        &self,
+        builtin_program_id: &Pubkey,
+    ) -> Result<(), CoreBpfMigrationError> {
+        if is_precompile(builtin_program_id, |feature_id| {
+            self.feature_set.is_active(feature_id)
+        }) {
+            return Err(CoreBpfMigrationError::TargetIsPrecompile(
+                *builtin_program_id,
+            ));
+        }
+        let is_builtin = BUILTINS
+            .iter()
+            .map(|builtin| &builtin.program_id)
+            .chain(STATELESS_BUILTINS.iter().map(|builtin| &builtin.program_id))
+            .any(|program_id| program_id == builtin_program_id);
+        let mut reserved_account_keys = ReservedAccountKeys::default();
+        reserved_account_keys.update_active_set(&self.feature_set);
+        if !is_builtin && reserved_account_keys.is_reserved(builtin_program_id) {
+            return Err(CoreBpfMigrationError::TargetIsReservedAccountKey(
+                *builtin_program_id,
+            ));
+        }
         Ok(())
     }
 
@@ -227,6 +283,15 @@ impl Bank {
     ) -> Result<(), CoreBpfMigrationError> {
         datapoint_info!(config.datapoint_name, ("slot", self.slot, i64));
 
+        if let Err(err) = self.check_core_bpf_migration_target(builtin_program_id) {
+            datapoint_error!(
+                config.datapoint_name,
+                ("slot", self.slot, i64),
+                ("error", err.to_string(), String),
+            );
+            return Err(err);
+        }
+
         let target =
             TargetBuiltin::new_checked(self, builtin_program_id, &config.migration_target)?;
         let source = SourceUpgradeableBpf::new_checked(self, &config.source_program_id)?;
@@ -252,37 +317,131 @@ impl Bank {
         )?;
         let new_data_size = checked_add(source_program_len, source_program_data_len)?;
 
+        // Stage the account writes: replace the target builtin account with
+        // the `new_target_program_account` and clear the source program
+        // account, then copy the source program data account into the account
+        // at the target builtin program's data address, which was verified to
+        // be empty by `TargetBuiltin::new_checked`, and clear the source
+        // program data account.
+        // The lamports of the target program account are burned, since it is
+        // replaced. Validate the writes before modifying the bank, so that a
+        // failed migration leaves no partial state behind.
+        let mut staged_writes = StagedWrites::with_capacity(4);
+        staged_writes.stage(target.program_address, new_target_program_account);
+        staged_writes.stage(source.program_address, AccountSharedData::default());
+        staged_writes.stage(target.program_data_address, new_target_program_data_account);
+        staged_writes.stage(source.program_data_address, AccountSharedData::default());
+        let burned_lamports = target.program_account.lamports();
+        let expected_lamports_delta = -i128::from(burned_lamports);
+        self.check_staged_lamports_delta(&staged_writes, expected_lamports_delta)?;
+
         // Deploy the new target Core BPF program.
         // This step will validate the program ELF against the current runtime
-        // environment, as well as update the program cache.
-        self.directly_invoke_loader_v3_deploy(
+        // environment.
+        let programs_modified = self.directly_invoke_loader_v3_deploy(
             &target.program_address,
             &source.program_data_account,
         )?;
 
-        // Burn lamports from the target program account, since it will be
-        // replaced.
-        self.capitalization
-            .fetch_sub(target.program_account.lamports(), Relaxed);
-
-        // Replace the target builtin account with the
-        // `new_target_program_account` and clear the source program account.
-        self.store_account(&target.program_address, &new_target_program_account);
-        self.store_account(&source.program_address, &AccountSharedData::default());
+        // Store all staged writes at once and burn the lamports of the target
+        // program account.
+        self.commit_staged_writes(staged_writes, |staged_writes| {
+            #[cfg(test)]
+            fault_injection::trigger(FaultPoint::CoreBpfMigrationCommit)?;
+            self.check_staged_lamports_delta(staged_writes, expected_lamports_delta)
+        })?;
+        self.capitalization.fetch_sub(burned_lamports, Relaxed);
 
-        // Copy the source program data account into the account at the target
-        // builtin program's data address, which was verified to be empty by
-        // `TargetBuiltin::new_checked`, then clear the source program data
-        // account.
-        self.store_account(
-            &target.program_data_address,
-            &new_target_program_data_account,
-        );
-        self.store_account(&source.program_data_address, &AccountSharedData::default());
+        // Update the program cache by merging with `programs_modified`, which
+        // should have been updated by the deploy function.
+        self.transaction_processor
+            .program_cache
+            .write()
+            .unwrap()
+            .merge(&programs_modified);
 
         // Remove the built-in program from the bank's list of built-ins.
         self.builtin_program_ids.remove(&target.program_address);
 
+        // Update the account data size delta.
+        let delta_off_chain_before = self.load_accounts_data_size_delta_off_chain();
+        self.calculate_and_update_accounts_data_size_delta_off_chain(old_data_size, new_data_size);
+        self.audit_accounts_data_size_delta(
+            AccountsDataSizeSource::CoreBpfMigrations,
+            delta_off_chain_before,
+        );
+
+        Ok(())
+    }
+
+    /// Migrate a Core BPF program back to its built-in implementation.
+    /// This is the reverse of `migrate_builtin_to_core_bpf` for a stateful
+    /// builtin, used to roll back a migration if the Core BPF program is
+    /// found to be broken.
+    pub(crate) fn migrate_core_bpf_to_builtin(
+        &mut self,
+        builtin_program_id: &Pubkey,
+        name: &'static str,
+        entrypoint: BuiltinFunctionWithContext,
+        config: &CoreBpfToBuiltinMigrationConfig,
+    ) -> Result<(), CoreBpfMigrationError> {
+        datapoint_info!(config.datapoint_name, ("slot", self.slot, i64));
+
+        // The Core BPF program lives at the builtin's address, with its
+        // program data account at the derived address.
+        let source = SourceUpgradeableBpf::new_checked(self, builtin_program_id)?;
+
+        // The restored builtin account is created as it is by
+        // `Bank::add_builtin_account`, with the rent-unadjusted initial
+        // balance.
+        let new_builtin_account = native_loader::create_loadable_account_with_fields(
+            name,
+            self.inherit_specially_retained_account_fields(&None),
+        );
+
+        // The old data size is the size of both Core BPF program accounts,
+        // the new data size is the size of the restored builtin account.
+        let old_data_size = checked_add(
+            source.program_account.data().len(),
+            source.program_data_account.data().len(),
+        )?;
+        let new_data_size = new_builtin_account.data().len();
+
+        // Stage the account writes: replace the program account with the
+        // builtin account and clear the program data account.
+        // The lamports of both Core BPF program accounts are burned and the
+        // lamports of the builtin account are minted. Validate the writes
+        // before modifying the bank, so that a failed migration leaves no
+        // partial state behind.
+        let burned_lamports = source
+            .program_account
+            .lamports()
+            .checked_add(source.program_data_account.lamports())
+            .ok_or(CoreBpfMigrationError::ArithmeticOverflow)?;
+        let minted_lamports = new_builtin_account.lamports();
+        let expected_lamports_delta = i128::from(minted_lamports) - i128::from(burned_lamports);
+        let mut staged_writes = StagedWrites::with_capacity(2);
+        staged_writes.stage(source.program_address, new_builtin_account);
+        staged_writes.stage(source.program_data_address, AccountSharedData::default());
+        self.check_staged_lamports_delta(&staged_writes, expected_lamports_delta)?;
+
+        self.commit_staged_writes(staged_writes, |staged_writes| {
+            #[cfg(test)]
+            fault_injection::trigger(FaultPoint::CoreBpfMigrationCommit)?;
+            self.check_staged_lamports_delta(staged_writes, expected_lamports_delta)
+        })?;
+        self.capitalization.fetch_sub(burned_lamports, Relaxed);
+        self.capitalization.fetch_add(minted_lamports, Relaxed);
+
+        // Re-register the entrypoint, which adds the program back to the
+        // bank's list of built-ins and to the program cache. The builtin
+        // account stored above is left as is.
+        self.add_builtin(
+            *builtin_program_id,
+            name,
+            LoadedProgram::new_builtin(self.slot, name.len(), entrypoint),
+        );
+
         // Update the account data size delta.
         self.calculate_and_update_accounts_data_size_delta_off_chain(old_data_size, new_data_size);
 
@@ -302,6 +461,7 @@ mod tests {
             bpf_loader_upgradeable::{self, get_program_data_address},
             native_loader,
         },
+        std::sync::Arc,
     };
 
     const TEST_ELF: &[u8] =
@@ -319,12 +479,19 @@ mod tests {
         // Initialize some test values and set up the source BPF upgradeable
         // program in the bank.
         fn new(bank: &Bank) -> Self {
-            let builtin_id = Pubkey::new_unique();
-            let source_program_id = Pubkey::new_unique();
-            let upgrade_authority_address = Some(Pubkey::new_unique());
-            let elf = TEST_ELF.to_vec();
+            let test_context = Self {
+                builtin_id: Pubkey::new_unique(),
+                source_program_id: Pubkey::new_unique(),
+                upgrade_authority_address: Some(Pubkey::new_unique()),
+                elf: TEST_ELF.to_vec(),
+            };
+            test_context.store_source_program(bank);
+            test_context
+        }
 
-            let source_program_data_address = get_program_data_address(&source_program_id);
+        // Set up the source BPF upgradeable program in the bank.
+        fn store_source_program(&self, bank: &Bank) {
+            let source_program_data_address = get_program_data_address(&self.source_program_id);
 
             let source_program_account = {
                 let data = bincode::serialize(&UpgradeableLoaderState::Program {
@@ -344,10 +511,10 @@ mod tests {
             let source_program_data_account = {
                 let mut data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
                     slot: 99, // Arbitrary slot for testing.
-                    upgrade_authority_address,
+                    upgrade_authority_address: self.upgrade_authority_address,
                 })
                 .unwrap();
-                data.extend_from_slice(&elf);
+                data.extend_from_slice(&self.elf);
 
                 let data_len = data.len();
                 let lamports = bank.get_minimum_balance_for_rent_exemption(data_len);
@@ -359,20 +526,13 @@ mod tests {
             };
 
             bank.store_account_and_update_capitalization(
-                &source_program_id,
+                &self.source_program_id,
                 &source_program_account,
             );
             bank.store_account_and_update_capitalization(
                 &source_program_data_address,
                 &source_program_data_account,
             );
-
-            Self {
-                builtin_id,
-                source_program_id,
-                upgrade_authority_address,
-                elf,
-            }
         }
 
         // Evaluate the account state of the builtin and source post-migration.
@@ -508,6 +668,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_migrate_reserved_builtin() {
+        let mut bank = create_simple_test_bank(0);
+
+        // The config program is a reserved account key, but only for being a
+        // builtin.
+        let mut test_context = TestContext::new(&bank);
+        test_context.builtin_id = solana_sdk::config::program::id();
+        let mut reserved_account_keys = ReservedAccountKeys::default();
+        reserved_account_keys.update_active_set(&bank.feature_set);
+        assert!(reserved_account_keys.is_reserved(&test_context.builtin_id));
+        assert!(bank
+            .get_builtin_program_ids()
+            .contains(&test_context.builtin_id));
+
+        let core_bpf_migration_config = CoreBpfMigrationConfig {
+            source_program_id: test_context.source_program_id,
+            feature_id: Pubkey::new_unique(),
+            migration_target: CoreBpfMigrationTargetType::Builtin,
+            datapoint_name: "test_migrate_reserved_builtin",
+        };
+
+        bank.migrate_builtin_to_core_bpf(&test_context.builtin_id, &core_bpf_migration_config)
+            .unwrap();
+
+        test_context.run_program_checks_post_migration(&bank);
+    }
+
+    #[test]
+    fn test_migrate_reserved_account_key_fails() {
+        let mut bank = create_simple_test_bank(0);
+        let test_context = TestContext::new(&bank);
+
+        let core_bpf_migration_config = CoreBpfMigrationConfig {
+            source_program_id: test_context.source_program_id,
+            feature_id: Pubkey::new_unique(),
+            migration_target: CoreBpfMigrationTargetType::Builtin,
+            datapoint_name: "test_migrate_reserved_account_key",
+        };
+
+        // Sysvars are reserved, but not builtins.
+        let clock_id = solana_sdk::sysvar::clock::id();
+        assert_matches!(
+            bank.migrate_builtin_to_core_bpf(&clock_id, &core_bpf_migration_config)
+                .unwrap_err(),
+            CoreBpfMigrationError::TargetIsReservedAccountKey(program_id)
+                if program_id == clock_id
+        );
+
+        // Nothing was migrated.
+        assert!(bank.get_account(&test_context.source_program_id).is_some());
+    }
+
+    #[test]
+    fn test_migrate_precompile_fails() {
+        let mut bank = create_simple_test_bank(0);
+        let test_context = TestContext::new(&bank);
+
+        let core_bpf_migration_config = CoreBpfMigrationConfig {
+            source_program_id: test_context.source_program_id,
+            feature_id: Pubkey::new_unique(),
+            migration_target: CoreBpfMigrationTargetType::Stateless,
+            datapoint_name: "test_migrate_precompile",
+        };
+
+        let ed25519_program_id = solana_sdk::ed25519_program::id();
+        assert_matches!(
+            bank.migrate_builtin_to_core_bpf(&ed25519_program_id, &core_bpf_migration_config)
+                .unwrap_err(),
+            CoreBpfMigrationError::TargetIsPrecompile(program_id)
+                if program_id == ed25519_program_id
+        );
+
+        // Nothing was migrated.
+        assert!(bank.get_account(&test_context.source_program_id).is_some());
+    }
+
     #[test]
     fn test_migrate_stateless_builtin() {
         let mut bank = create_simple_test_bank(0);
@@ -553,4 +790,218 @@ mod tests {
             bank_pre_migration_accounts_data_size_delta_off_chain,
         );
     }
+
+    #[test]
+    fn test_migrate_core_bpf_to_builtin() {
+        solana_program_runtime::declare_process_instruction!(MockBuiltin, 0, |_invoke_context| {
+            // Do nothing
+            Ok(())
+        });
+
+        let mut bank = create_simple_test_bank(0);
+
+        let test_context = TestContext::new(&bank);
+
+        let TestContext {
+            builtin_id,
+            source_program_id,
+            ..
+        } = test_context;
+
+        let builtin_name = "test_builtin";
+        let builtin_account =
+            AccountSharedData::new_data(1, &builtin_name.to_string(), &native_loader::id())
+                .unwrap();
+        bank.store_account_and_update_capitalization(&builtin_id, &builtin_account);
+        bank.add_builtin(builtin_id, builtin_name, LoadedProgram::default());
+
+        // First migrate the builtin to Core BPF.
+        let core_bpf_migration_config = CoreBpfMigrationConfig {
+            source_program_id,
+            feature_id: Pubkey::new_unique(),
+            migration_target: CoreBpfMigrationTargetType::Builtin,
+            datapoint_name: "test_migrate_builtin",
+        };
+        bank.migrate_builtin_to_core_bpf(&builtin_id, &core_bpf_migration_config)
+            .unwrap();
+        test_context.run_program_checks_post_migration(&bank);
+
+        // Then migrate it back in a later slot, once the Core BPF program is
+        // effective.
+        let mut bank = Bank::new_from_parent(Arc::new(bank), &Pubkey::default(), 2);
+        let program_data_address = get_program_data_address(&builtin_id);
+        let core_bpf_lamports = bank.get_account(&builtin_id).unwrap().lamports()
+            + bank.get_account(&program_data_address).unwrap().lamports();
+        let core_bpf_data_len = bank.get_account(&builtin_id).unwrap().data().len()
+            + bank
+                .get_account(&program_data_address)
+                .unwrap()
+                .data()
+                .len();
+
+        let core_bpf_to_builtin_migration_config = CoreBpfToBuiltinMigrationConfig {
+            feature_id: Pubkey::new_unique(),
+            datapoint_name: "test_migrate_core_bpf_to_builtin",
+        };
+
+        // Gather bank information to check later.
+        let bank_pre_migration_capitalization = bank.capitalization();
+        let bank_pre_migration_accounts_data_size_delta_off_chain =
+            bank.accounts_data_size_delta_off_chain.load(Relaxed);
+
+        // Perform the migration.
+        bank.migrate_core_bpf_to_builtin(
+            &builtin_id,
+            builtin_name,
+            MockBuiltin::vm,
+            &core_bpf_to_builtin_migration_config,
+        )
+        .unwrap();
+
+        // The program account is a builtin account again, and the program
+        // data account has been cleared.
+        let program_account = bank.get_account(&builtin_id).unwrap();
+        assert_eq!(program_account.owner(), &native_loader::id());
+        assert!(program_account.executable());
+        assert_eq!(program_account.data(), builtin_name.as_bytes());
+        assert!(bank.get_account(&program_data_address).is_none());
+
+        // The bank's builtins should contain the builtin program ID again.
+        assert!(bank.builtin_program_ids.contains(&builtin_id));
+
+        // The cache should contain the builtin, effective in this slot.
+        let program_cache = bank.transaction_processor.program_cache.read().unwrap();
+        let builtin_entry = program_cache
+            .get_slot_versions_for_tests(&builtin_id)
+            .iter()
+            .max_by_key(|entry| entry.effective_slot)
+            .unwrap();
+        assert_eq!(builtin_entry.deployment_slot, bank.slot());
+        assert_eq!(builtin_entry.effective_slot, bank.slot());
+        assert_matches!(builtin_entry.program, LoadedProgramType::Builtin(..));
+        drop(program_cache);
+
+        // The bank's capitalization should reflect the burned lamports of
+        // the Core BPF program accounts and the lamports of the builtin
+        // account.
+        assert_eq!(
+            bank.capitalization(),
+            bank_pre_migration_capitalization - core_bpf_lamports + program_account.lamports()
+        );
+
+        // The bank's accounts data size delta off-chain should reflect the
+        // replaced Core BPF program accounts.
+        assert_eq!(
+            bank.accounts_data_size_delta_off_chain.load(Relaxed),
+            bank_pre_migration_accounts_data_size_delta_off_chain - core_bpf_data_len as i64
+                + builtin_name.len() as i64,
+        );
+    }
+
+    #[test]
+    fn test_migrate_core_bpf_to_builtin_fails_for_builtin() {
+        solana_program_runtime::declare_process_instruction!(MockBuiltin, 0, |_invoke_context| {
+            // Do nothing
+            Ok(())
+        });
+
+        let mut bank = create_simple_test_bank(0);
+        let builtin_id = Pubkey::new_unique();
+        let builtin_name = "test_builtin";
+        let builtin_account =
+            AccountSharedData::new_data(1, &builtin_name.to_string(), &native_loader::id())
+                .unwrap();
+        bank.store_account_and_update_capitalization(&builtin_id, &builtin_account);
+        bank.add_builtin(builtin_id, builtin_name, LoadedProgram::default());
+
+        let core_bpf_to_builtin_migration_config = CoreBpfToBuiltinMigrationConfig {
+            feature_id: Pubkey::new_unique(),
+            datapoint_name: "test_migrate_core_bpf_to_builtin_fails_for_builtin",
+        };
+
+        // The program was never migrated to Core BPF.
+        assert_matches!(
+            bank.migrate_core_bpf_to_builtin(
+                &builtin_id,
+                builtin_name,
+                MockBuiltin::vm,
+                &core_bpf_to_builtin_migration_config,
+            )
+            .unwrap_err(),
+            CoreBpfMigrationError::ProgramHasNoDataAccount(program_id) if program_id == builtin_id
+        );
+
+        // Nothing was migrated.
+        assert_eq!(bank.get_account(&builtin_id).unwrap(), builtin_account);
+    }
+
+    #[test]
+    fn test_migration_failure_isolation() {
+        solana_program_runtime::declare_process_instruction!(MockBuiltin, 0, |_invoke_context| {
+            // Do nothing
+            Ok(())
+        });
+
+        let test_context = TestContext::new(&create_simple_test_bank(0));
+        let TestContext {
+            builtin_id,
+            source_program_id,
+            ..
+        } = test_context;
+        let builtin_name = "test_builtin";
+        let pubkeys = [
+            builtin_id,
+            get_program_data_address(&builtin_id),
+            source_program_id,
+            get_program_data_address(&source_program_id),
+        ];
+
+        let core_bpf_migration_config = CoreBpfMigrationConfig {
+            source_program_id,
+            feature_id: Pubkey::new_unique(),
+            migration_target: CoreBpfMigrationTargetType::Builtin,
+            datapoint_name: "test_migrate_builtin",
+        };
+        let setup_builtin = || {
+            let mut bank = create_simple_test_bank(0);
+            test_context.store_source_program(&bank);
+            let builtin_account =
+                AccountSharedData::new_data(1, &builtin_name.to_string(), &native_loader::id())
+                    .unwrap();
+            bank.store_account_and_update_capitalization(&builtin_id, &builtin_account);
+            bank.add_builtin(builtin_id, builtin_name, LoadedProgram::default());
+            bank
+        };
+        // The deployed program is not added to the program cache either.
+        fault_injection::check_failure_isolation(
+            FaultPoint::CoreBpfMigrationCommit,
+            &pubkeys,
+            setup_builtin,
+            |bank| bank.migrate_builtin_to_core_bpf(&builtin_id, &core_bpf_migration_config),
+        );
+
+        let core_bpf_to_builtin_migration_config = CoreBpfToBuiltinMigrationConfig {
+            feature_id: Pubkey::new_unique(),
+            datapoint_name: "test_migrate_core_bpf_to_builtin",
+        };
+        let setup_core_bpf = || {
+            let mut bank = setup_builtin();
+            bank.migrate_builtin_to_core_bpf(&builtin_id, &core_bpf_migration_config)
+                .unwrap();
+            Bank::new_from_parent(Arc::new(bank), &Pubkey::default(), 2)
+        };
+        fault_injection::check_failure_isolation(
+            FaultPoint::CoreBpfMigrationCommit,
+            &pubkeys,
+            setup_core_bpf,
+            |bank| {
+                bank.migrate_core_bpf_to_builtin(
+                    &builtin_id,
+                    builtin_name,
+                    MockBuiltin::vm,
+                    &core_bpf_to_builtin_migration_config,
+                )
+            },
+        );
+    }
 }
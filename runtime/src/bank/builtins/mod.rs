@@ -32,6 +32,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: system_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_system_program::id(),
         entrypoint: solana_system_program::system_processor::Entrypoint::vm,
     }),
@@ -39,6 +41,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: vote_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_vote_program::id(),
         entrypoint: solana_vote_program::vote_processor::Entrypoint::vm,
     }),
@@ -46,6 +50,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: stake_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_stake_program::id(),
         entrypoint: solana_stake_program::stake_instruction::Entrypoint::vm,
     }),
@@ -53,6 +59,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: config_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_config_program::id(),
         entrypoint: solana_config_program::config_processor::Entrypoint::vm,
     }),
@@ -60,6 +68,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: solana_bpf_loader_deprecated_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: bpf_loader_deprecated::id(),
         entrypoint: solana_bpf_loader_program::Entrypoint::vm,
     }),
@@ -67,6 +77,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: solana_bpf_loader_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: bpf_loader::id(),
         entrypoint: solana_bpf_loader_program::Entrypoint::vm,
     }),
@@ -74,6 +86,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: solana_bpf_loader_upgradeable_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: bpf_loader_upgradeable::id(),
         entrypoint: solana_bpf_loader_program::Entrypoint::vm,
     }),
@@ -81,6 +95,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: compute_budget_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_sdk::compute_budget::id(),
         entrypoint: solana_compute_budget_program::Entrypoint::vm,
     }),
@@ -88,6 +104,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: address_lookup_table_program,
         enable_feature_id: None,
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_sdk::address_lookup_table::program::id(),
         entrypoint: solana_address_lookup_table_program::processor::Entrypoint::vm,
     }),
@@ -95,6 +113,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: zk_token_proof_program,
         enable_feature_id: Some(feature_set::zk_token_sdk_enabled::id()),
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_zk_token_sdk::zk_token_proof_program::id(),
         entrypoint: solana_zk_token_proof_program::Entrypoint::vm,
     }),
@@ -102,6 +122,8 @@ pub static BUILTINS: &[BuiltinPrototype] = &[
         core_bpf_migration_config: None,
         name: loader_v4,
         enable_feature_id: Some(feature_set::enable_program_runtime_v2_and_loader_v4::id()),
+        disable_feature_id: None,
+        core_bpf_to_builtin_migration_config: None,
         program_id: solana_sdk::loader_v4::id(),
         entrypoint: solana_loader_v4_program::Entrypoint::vm,
     }),
@@ -111,6 +133,8 @@ pub static STATELESS_BUILTINS: &[StatelessBuiltinPrototype] =
     &[testable_prototype!(StatelessBuiltinPrototype {
         core_bpf_migration_config: None,
         name: feature_gate_program,
+        enable_feature_id: None,
+        disable_feature_id: None,
         program_id: solana_sdk::feature::id(),
     })];
 
@@ -1,12 +1,17 @@
 use {
-    super::core_bpf_migration::CoreBpfMigrationConfig,
-    solana_program_runtime::invoke_context::BuiltinFunctionWithContext, solana_sdk::pubkey::Pubkey,
+    super::core_bpf_migration::{CoreBpfMigrationConfig, CoreBpfToBuiltinMigrationConfig},
+    solana_program_runtime::invoke_context::BuiltinFunctionWithContext,
+    solana_sdk::pubkey::Pubkey,
 };
 
 /// Transitions of built-in programs at epoch boundaries when features are activated.
 pub struct BuiltinPrototype {
     pub(crate) core_bpf_migration_config: Option<CoreBpfMigrationConfig>,
+    pub(crate) core_bpf_to_builtin_migration_config: Option<CoreBpfToBuiltinMigrationConfig>,
     pub enable_feature_id: Option<Pubkey>,
+    /// The feature gate retiring the builtin, without a program replacing
+    /// it. It takes precedence over `enable_feature_id`.
+    pub disable_feature_id: Option<Pubkey>,
     pub program_id: Pubkey,
     pub name: &'static str,
     pub entrypoint: BuiltinFunctionWithContext,
@@ -18,7 +23,12 @@ impl std::fmt::Debug for BuiltinPrototype {
         builder.field("program_id", &self.program_id);
         builder.field("name", &self.name);
         builder.field("enable_feature_id", &self.enable_feature_id);
+        builder.field("disable_feature_id", &self.disable_feature_id);
         builder.field("core_bpf_migration_config", &self.core_bpf_migration_config);
+        builder.field(
+            "core_bpf_to_builtin_migration_config",
+            &self.core_bpf_to_builtin_migration_config,
+        );
         builder.finish()
     }
 }
@@ -33,7 +43,9 @@ impl solana_frozen_abi::abi_example::AbiExample for BuiltinPrototype {
         });
         Self {
             core_bpf_migration_config: None,
+            core_bpf_to_builtin_migration_config: None,
             enable_feature_id: None,
+            disable_feature_id: None,
             program_id: Pubkey::default(),
             name: "",
             entrypoint: MockBuiltin::vm,
@@ -49,6 +61,14 @@ impl solana_frozen_abi::abi_example::AbiExample for BuiltinPrototype {
 #[derive(Debug)]
 pub struct StatelessBuiltinPrototype {
     pub(crate) core_bpf_migration_config: Option<CoreBpfMigrationConfig>,
+    /// The feature gate registering the stateless builtin with the bank's
+    /// built-in programs. Unlike for `BuiltinPrototype`, `None` never
+    /// registers it, since stateless builtins which only reserve an address
+    /// for a Core BPF migration must keep failing to load.
+    pub enable_feature_id: Option<Pubkey>,
+    /// The feature gate unregistering the stateless builtin again. It takes
+    /// precedence over `enable_feature_id`.
+    pub disable_feature_id: Option<Pubkey>,
     pub program_id: Pubkey,
     pub name: &'static str,
 }
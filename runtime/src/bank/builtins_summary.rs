@@ -0,0 +1,261 @@
+//! Summary of the builtins of a bank, for offline snapshot tooling.
+//!
+//! Which builtins a snapshot treats as active, which Core BPF migrations it
+//! applied and which features are pending otherwise takes loading a full
+//! bank to find out. The summary is written next to each bank snapshot, and
+//! `BuiltinsSummary::decode()` reads it without any other part of the
+//! snapshot. It also lets a validator check ahead of loading a snapshot that
+//! it knows all of the snapshot's builtins and pending features.
+//!
+//! The encoding is a version byte followed by the bincode serialization of
+//! the summary, so that decoders can refuse versions they don't know.
+
+use {
+    super::{
+        builtins::{BUILTINS, STATELESS_BUILTINS},
+        Bank,
+    },
+    solana_sdk::{
+        clock::{Epoch, Slot},
+        feature_set::FEATURE_NAMES,
+        pubkey::Pubkey,
+    },
+    thiserror::Error,
+};
+
+/// Version of the encoding written by `BuiltinsSummary::encode()`
+pub const BUILTINS_SUMMARY_VERSION: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum BuiltinsSummaryError {
+    #[error("empty builtins summary")]
+    Empty,
+    #[error("unsupported builtins summary version {0}, expected {BUILTINS_SUMMARY_VERSION}")]
+    UnsupportedVersion(u8),
+    #[error("invalid builtins summary: {0}")]
+    Bincode(#[from] bincode::Error),
+}
+
+#[derive(AbiExample, AbiEnumVisitor, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationDirection {
+    BuiltinToCoreBpf,
+    CoreBpfToBuiltin,
+}
+
+/// A Core BPF migration whose feature is active
+#[derive(AbiExample, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    pub program_id: Pubkey,
+    pub feature_id: Pubkey,
+    pub direction: MigrationDirection,
+}
+
+/// A feature whose activation is pending
+#[derive(AbiExample, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingFeature {
+    pub feature_id: Pubkey,
+    pub activation_epoch: Epoch,
+}
+
+#[derive(AbiExample, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuiltinsSummary {
+    pub slot: Slot,
+    pub epoch: Epoch,
+    /// Programs the bank treats as builtins, sorted
+    pub builtin_program_ids: Vec<Pubkey>,
+    /// Migrations of the builtins known to the writing validator whose
+    /// feature is active, in the order of the builtins
+    pub applied_migrations: Vec<AppliedMigration>,
+    /// Sorted by feature id
+    pub pending_features: Vec<PendingFeature>,
+}
+
+impl BuiltinsSummary {
+    pub fn encode(&self) -> bincode::Result<Vec<u8>> {
+        let mut bytes = vec![BUILTINS_SUMMARY_VERSION];
+        bincode::serialize_into(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, BuiltinsSummaryError> {
+        let (version, summary) = bytes.split_first().ok_or(BuiltinsSummaryError::Empty)?;
+        if *version != BUILTINS_SUMMARY_VERSION {
+            return Err(BuiltinsSummaryError::UnsupportedVersion(*version));
+        }
+        Ok(bincode::deserialize(summary)?)
+    }
+
+    /// Builtins of the summary which this version does not know, and so
+    /// would not be able to execute
+    pub fn unknown_builtin_program_ids(&self) -> Vec<Pubkey> {
+        self.builtin_program_ids
+            .iter()
+            .filter(|program_id| {
+                !BUILTINS
+                    .iter()
+                    .any(|builtin| builtin.program_id == **program_id)
+                    && !STATELESS_BUILTINS
+                        .iter()
+                        .any(|builtin| builtin.program_id == **program_id)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Pending features of the summary which this version does not know,
+    /// and so would not activate
+    pub fn unknown_pending_features(&self) -> Vec<Pubkey> {
+        self.pending_features
+            .iter()
+            .map(|pending_feature| pending_feature.feature_id)
+            .filter(|feature_id| !FEATURE_NAMES.contains_key(feature_id))
+            .collect()
+    }
+}
+
+impl Bank {
+    pub fn builtins_summary(&self) -> BuiltinsSummary {
+        let mut builtin_program_ids = self.builtin_program_ids.iter().copied().collect::<Vec<_>>();
+        builtin_program_ids.sort_unstable();
+
+        let is_active = |feature_id: &Pubkey| self.feature_set.is_active(feature_id);
+        let mut applied_migrations = vec![];
+        for builtin in BUILTINS {
+            let migrations = [
+                builtin
+                    .core_bpf_migration_config
+                    .as_ref()
+                    .map(|config| (config.feature_id, MigrationDirection::BuiltinToCoreBpf)),
+                builtin
+                    .core_bpf_to_builtin_migration_config
+                    .as_ref()
+                    .map(|config| (config.feature_id, MigrationDirection::CoreBpfToBuiltin)),
+            ];
+            applied_migrations.extend(migrations.into_iter().flatten().filter_map(
+                |(feature_id, direction)| {
+                    is_active(&feature_id).then_some(AppliedMigration {
+                        program_id: builtin.program_id,
+                        feature_id,
+                        direction,
+                    })
+                },
+            ));
+        }
+        for builtin in STATELESS_BUILTINS {
+            if let Some(config) = &builtin.core_bpf_migration_config {
+                if is_active(&config.feature_id) {
+                    applied_migrations.push(AppliedMigration {
+                        program_id: builtin.program_id,
+                        feature_id: config.feature_id,
+                        direction: MigrationDirection::BuiltinToCoreBpf,
+                    });
+                }
+            }
+        }
+
+        let pending_features = self
+            .pending_feature_activations()
+            .into_iter()
+            .map(|activation| PendingFeature {
+                feature_id: activation.feature_id,
+                activation_epoch: activation.activation_epoch,
+            })
+            .collect();
+
+        BuiltinsSummary {
+            slot: self.slot(),
+            epoch: self.epoch(),
+            builtin_program_ids,
+            applied_migrations,
+            pending_features,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::genesis_utils::create_genesis_config,
+        solana_sdk::{
+            feature::{self, Feature},
+            feature_set::{self, FeatureSet},
+            system_program,
+        },
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_builtins_summary() {
+        let mut bank = Bank::new_for_tests(&create_genesis_config(100_000).genesis_config);
+        let migration_config = BUILTINS[0].core_bpf_migration_config.as_ref().unwrap();
+        let unknown_feature_id = Pubkey::new_unique();
+        let mut feature_set = FeatureSet::default();
+        feature_set.activate(&migration_config.feature_id, 0);
+        feature_set
+            .inactive
+            .extend([feature_set::reject_callx_r10::id(), unknown_feature_id]);
+        bank.feature_set = Arc::new(feature_set);
+        for feature_id in [feature_set::reject_callx_r10::id(), unknown_feature_id] {
+            bank.store_account(
+                &feature_id,
+                &feature::create_account(&Feature::default(), 42),
+            );
+        }
+        let unknown_program_id = Pubkey::new_unique();
+        bank.builtin_program_ids.insert(unknown_program_id);
+
+        let summary = bank.builtins_summary();
+        assert_eq!(summary.slot, bank.slot());
+        assert_eq!(summary.epoch, bank.epoch());
+        assert!(summary.builtin_program_ids.contains(&system_program::id()));
+        assert!(summary
+            .builtin_program_ids
+            .windows(2)
+            .all(|program_ids| program_ids[0] < program_ids[1]));
+        assert_eq!(
+            summary.applied_migrations,
+            vec![AppliedMigration {
+                program_id: BUILTINS[0].program_id,
+                feature_id: migration_config.feature_id,
+                direction: MigrationDirection::BuiltinToCoreBpf,
+            }]
+        );
+        let mut expected_pending_features = vec![
+            PendingFeature {
+                feature_id: feature_set::reject_callx_r10::id(),
+                activation_epoch: bank.epoch() + 1,
+            },
+            PendingFeature {
+                feature_id: unknown_feature_id,
+                activation_epoch: bank.epoch() + 1,
+            },
+        ];
+        expected_pending_features.sort_by_key(|pending_feature| pending_feature.feature_id);
+        assert_eq!(summary.pending_features, expected_pending_features);
+
+        // Compatibility checks against this version
+        assert_eq!(
+            summary.unknown_builtin_program_ids(),
+            vec![unknown_program_id]
+        );
+        assert_eq!(summary.unknown_pending_features(), vec![unknown_feature_id]);
+
+        let mut bytes = summary.encode().unwrap();
+        assert_eq!(BuiltinsSummary::decode(&bytes).unwrap(), summary);
+        assert!(matches!(
+            BuiltinsSummary::decode(&bytes[..bytes.len() - 1]),
+            Err(BuiltinsSummaryError::Bincode(_))
+        ));
+        bytes[0] = BUILTINS_SUMMARY_VERSION + 1;
+        assert!(matches!(
+            BuiltinsSummary::decode(&bytes),
+            Err(BuiltinsSummaryError::UnsupportedVersion(version))
+                if version == BUILTINS_SUMMARY_VERSION + 1
+        ));
+        assert!(matches!(
+            BuiltinsSummary::decode(&[]),
+            Err(BuiltinsSummaryError::Empty)
+        ));
+    }
+}
@@ -0,0 +1,388 @@
+//! Recording and replay of epoch boundaries, to debug consensus divergences
+//! offline.
+//!
+//! The first bank of an epoch activates features, migrates builtins and
+//! calculates rewards from the stakes of its parent. When validators
+//! disagree on such a bank, an `EpochBoundaryArtifact` recorded by each of
+//! them captures digests of the inputs consumed at the boundary and the
+//! account mutations and capitalization it produced. Replaying the artifact
+//! on top of the same parent, e.g. loaded from a snapshot, shows whether
+//! the divergence comes from the inputs or from the boundary processing.
+
+use {
+    super::{builtins::BUILTINS, Bank},
+    serde::{Deserialize, Serialize},
+    solana_accounts_db::accounts_db::AccountsDb,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::{Epoch, Slot},
+        feature_set::FEATURE_NAMES,
+        hash::Hasher,
+        pubkey::Pubkey,
+    },
+    thiserror::Error,
+};
+
+/// Inputs and outputs of the epoch boundary processed by a bank
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EpochBoundaryArtifact {
+    /// The client version
+    pub version: String,
+    pub parent_slot: Slot,
+    pub parent_bank_hash: String,
+    pub slot: Slot,
+    pub epoch: Epoch,
+    pub collector_id: String,
+    pub inputs: EpochBoundaryInputs,
+    pub outputs: EpochBoundaryOutputs,
+}
+
+/// Digests of the parent state consumed at the epoch boundary
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EpochBoundaryInputs {
+    /// Existing feature accounts, sorted by pubkey
+    pub feature_accounts: Vec<RecordedAccount>,
+    /// Digest of the stake delegations and the stake history
+    pub stakes_digest: String,
+    /// Digest of the vote accounts and their delegated stakes
+    pub vote_accounts_digest: String,
+    /// Core BPF migrations configured for builtins
+    pub core_bpf_migrations: Vec<RecordedCoreBpfMigration>,
+}
+
+/// State produced by the epoch boundary
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EpochBoundaryOutputs {
+    /// Accounts stored by the bank before any transaction, sorted by pubkey
+    pub account_mutations: Vec<RecordedAccount>,
+    pub parent_capitalization: u64,
+    pub capitalization: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordedAccount {
+    pub pubkey: String,
+    pub lamports: u64,
+    pub hash: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordedCoreBpfMigration {
+    pub builtin_program_id: String,
+    pub feature_id: String,
+    pub source_program_id: String,
+    /// Hash of the source program account, if it exists
+    pub source_program_hash: Option<String>,
+}
+
+impl EpochBoundaryArtifact {
+    /// Record the epoch boundary processed by `bank`, which must have just
+    /// been created from `parent` and not have executed any transaction.
+    pub fn new(parent: &Bank, bank: &Bank) -> Self {
+        let mut account_mutations = bank
+            .get_all_accounts_modified_since_parent()
+            .into_iter()
+            .map(|(pubkey, account)| RecordedAccount::new(&pubkey, &account))
+            .collect::<Vec<_>>();
+        account_mutations.sort_by(|a, b| a.pubkey.cmp(&b.pubkey));
+
+        Self {
+            version: solana_version::version!().to_string(),
+            parent_slot: parent.slot(),
+            parent_bank_hash: parent.hash().to_string(),
+            slot: bank.slot(),
+            epoch: bank.epoch(),
+            collector_id: bank.collector_id().to_string(),
+            inputs: EpochBoundaryInputs::new(parent),
+            outputs: EpochBoundaryOutputs {
+                account_mutations,
+                parent_capitalization: parent.capitalization(),
+                capitalization: bank.capitalization(),
+            },
+        }
+    }
+}
+
+impl EpochBoundaryInputs {
+    fn new(parent: &Bank) -> Self {
+        let mut feature_ids = FEATURE_NAMES.keys().collect::<Vec<_>>();
+        feature_ids.sort();
+        let feature_accounts = feature_ids
+            .into_iter()
+            .filter_map(|feature_id| {
+                let account = parent.get_account(feature_id)?;
+                Some(RecordedAccount::new(feature_id, &account))
+            })
+            .collect();
+
+        let stakes = parent.stakes_cache.stakes();
+        let mut stake_delegations = stakes.stake_delegations().iter().collect::<Vec<_>>();
+        stake_delegations.sort_by_key(|(pubkey, _)| *pubkey);
+        let mut hasher = Hasher::default();
+        for (pubkey, stake_account) in stake_delegations {
+            let delegation = stake_account.delegation();
+            hasher.hash(pubkey.as_ref());
+            hasher.hash(&stake_account.lamports().to_le_bytes());
+            hasher.hash(delegation.voter_pubkey.as_ref());
+            hasher.hash(&delegation.stake.to_le_bytes());
+            hasher.hash(&delegation.activation_epoch.to_le_bytes());
+            hasher.hash(&delegation.deactivation_epoch.to_le_bytes());
+        }
+        for (epoch, entry) in stakes.history().iter() {
+            hasher.hash(&epoch.to_le_bytes());
+            hasher.hash(&entry.effective.to_le_bytes());
+            hasher.hash(&entry.activating.to_le_bytes());
+            hasher.hash(&entry.deactivating.to_le_bytes());
+        }
+        let stakes_digest = hasher.result().to_string();
+
+        let mut vote_accounts = stakes.vote_accounts().iter().collect::<Vec<_>>();
+        vote_accounts.sort_by_key(|(pubkey, _)| *pubkey);
+        let mut hasher = Hasher::default();
+        for (pubkey, vote_account) in vote_accounts {
+            let delegated_stake = stakes.vote_accounts().get_delegated_stake(pubkey);
+            hasher.hash(pubkey.as_ref());
+            hasher.hash(&delegated_stake.to_le_bytes());
+            hasher.hash(
+                AccountsDb::hash_account(vote_account.account(), pubkey)
+                    .0
+                    .as_ref(),
+            );
+        }
+        let vote_accounts_digest = hasher.result().to_string();
+        drop(stakes);
+
+        let core_bpf_migrations = BUILTINS
+            .iter()
+            .filter_map(|builtin| {
+                let config = builtin.core_bpf_migration_config.as_ref()?;
+                Some(RecordedCoreBpfMigration {
+                    builtin_program_id: builtin.program_id.to_string(),
+                    feature_id: config.feature_id.to_string(),
+                    source_program_id: config.source_program_id.to_string(),
+                    source_program_hash: parent.get_account(&config.source_program_id).map(
+                        |account| {
+                            AccountsDb::hash_account(&account, &config.source_program_id)
+                                .0
+                                .to_string()
+                        },
+                    ),
+                })
+            })
+            .collect();
+
+        Self {
+            feature_accounts,
+            stakes_digest,
+            vote_accounts_digest,
+            core_bpf_migrations,
+        }
+    }
+
+    /// Names of the inputs which differ from `other`
+    #[cfg(feature = "dev-context-only-utils")]
+    fn mismatches(&self, other: &Self) -> Vec<&'static str> {
+        let mut mismatches = vec![];
+        if self.feature_accounts != other.feature_accounts {
+            mismatches.push("feature_accounts");
+        }
+        if self.stakes_digest != other.stakes_digest {
+            mismatches.push("stakes_digest");
+        }
+        if self.vote_accounts_digest != other.vote_accounts_digest {
+            mismatches.push("vote_accounts_digest");
+        }
+        if self.core_bpf_migrations != other.core_bpf_migrations {
+            mismatches.push("core_bpf_migrations");
+        }
+        mismatches
+    }
+}
+
+impl RecordedAccount {
+    fn new(pubkey: &Pubkey, account: &AccountSharedData) -> Self {
+        Self {
+            pubkey: pubkey.to_string(),
+            lamports: account.lamports(),
+            hash: AccountsDb::hash_account(account, pubkey).0.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EpochBoundaryReplayError {
+    #[error("artifact was recorded on top of slot {expected}, not {actual}")]
+    ParentSlotMismatch { expected: Slot, actual: Slot },
+    #[error("parent bank hash is {actual}, artifact was recorded with {expected}")]
+    ParentBankHashMismatch { expected: String, actual: String },
+    #[error("invalid collector id: {0}")]
+    InvalidCollectorId(String),
+    /// The parent state consumed at the boundary differs from the recorded one
+    #[error("parent inputs differ from the recorded ones: {0:?}")]
+    InputsMismatch(Vec<&'static str>),
+}
+
+/// Differences between the recorded and the replayed outputs
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct EpochBoundaryDivergence {
+    /// Accounts whose replayed state differs from the recorded one, including
+    /// accounts which were only recorded or only replayed
+    pub accounts: Vec<String>,
+    /// Recorded and replayed capitalization, if they differ
+    pub capitalization: Option<(u64, u64)>,
+}
+
+impl EpochBoundaryDivergence {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.capitalization.is_none()
+    }
+
+    #[cfg(feature = "dev-context-only-utils")]
+    fn new(recorded: &EpochBoundaryOutputs, replayed: &EpochBoundaryOutputs) -> Self {
+        let mut accounts = recorded
+            .account_mutations
+            .iter()
+            .filter(|account| !replayed.account_mutations.contains(account))
+            .chain(
+                replayed
+                    .account_mutations
+                    .iter()
+                    .filter(|account| !recorded.account_mutations.contains(account)),
+            )
+            .map(|account| account.pubkey.clone())
+            .collect::<Vec<_>>();
+        accounts.sort();
+        accounts.dedup();
+        Self {
+            accounts,
+            capitalization: (recorded.capitalization != replayed.capitalization)
+                .then_some((recorded.capitalization, replayed.capitalization)),
+        }
+    }
+}
+
+#[cfg(feature = "dev-context-only-utils")]
+impl Bank {
+    /// Process the epoch boundary of `artifact` again on top of this bank and
+    /// compare the outputs with the recorded ones.
+    ///
+    /// This bank must be the frozen parent the artifact was recorded with,
+    /// and must not have a child at the slot of the artifact yet, as when
+    /// loaded from a snapshot.
+    pub fn replay_epoch_boundary(
+        self: &std::sync::Arc<Self>,
+        artifact: &EpochBoundaryArtifact,
+    ) -> Result<EpochBoundaryDivergence, EpochBoundaryReplayError> {
+        if self.slot() != artifact.parent_slot {
+            return Err(EpochBoundaryReplayError::ParentSlotMismatch {
+                expected: artifact.parent_slot,
+                actual: self.slot(),
+            });
+        }
+        let bank_hash = self.hash().to_string();
+        if bank_hash != artifact.parent_bank_hash {
+            return Err(EpochBoundaryReplayError::ParentBankHashMismatch {
+                expected: artifact.parent_bank_hash.clone(),
+                actual: bank_hash,
+            });
+        }
+        let collector_id = artifact.collector_id.parse::<Pubkey>().map_err(|_| {
+            EpochBoundaryReplayError::InvalidCollectorId(artifact.collector_id.clone())
+        })?;
+        let mismatches = artifact.inputs.mismatches(&EpochBoundaryInputs::new(self));
+        if !mismatches.is_empty() {
+            return Err(EpochBoundaryReplayError::InputsMismatch(mismatches));
+        }
+
+        let bank = Bank::new_from_parent(self.clone(), &collector_id, artifact.slot);
+        let replayed = EpochBoundaryArtifact::new(self, &bank);
+        Ok(EpochBoundaryDivergence::new(
+            &artifact.outputs,
+            &replayed.outputs,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::genesis_utils::{
+            bootstrap_validator_stake_lamports, create_genesis_config_with_leader,
+        },
+        solana_sdk::genesis_config::GenesisConfig,
+        std::sync::Arc,
+    };
+
+    /// Frozen bank at the last slot of the first epoch
+    fn new_parent(genesis_config: &GenesisConfig) -> Arc<Bank> {
+        let (bank0, _bank_forks) = Bank::new_with_bank_forks_for_tests(genesis_config);
+        let last_slot = bank0.epoch_schedule().get_last_slot_in_epoch(0);
+        let parent = Bank::new_from_parent(bank0, &Pubkey::default(), last_slot);
+        parent.freeze();
+        Arc::new(parent)
+    }
+
+    #[test]
+    fn test_replay_epoch_boundary() {
+        let genesis_config = create_genesis_config_with_leader(
+            1_000_000_000_000,
+            &Pubkey::new_unique(),
+            bootstrap_validator_stake_lamports(),
+        )
+        .genesis_config;
+        let parent = new_parent(&genesis_config);
+        let collector_id = Pubkey::new_unique();
+        let bank = Arc::new(Bank::new_from_parent(
+            parent.clone(),
+            &collector_id,
+            parent.slot() + 1,
+        ));
+        assert_eq!(bank.epoch(), 1);
+
+        let artifact = EpochBoundaryArtifact::new(&parent, &bank);
+        assert!(!artifact.inputs.feature_accounts.is_empty());
+        assert!(!artifact.outputs.account_mutations.is_empty());
+        let json = serde_json::to_string(&artifact).unwrap();
+        let artifact: EpochBoundaryArtifact = serde_json::from_str(&json).unwrap();
+
+        // Same parent, loaded separately
+        let replayed_parent = new_parent(&genesis_config);
+        assert_eq!(
+            replayed_parent.replay_epoch_boundary(&artifact),
+            Ok(EpochBoundaryDivergence::default())
+        );
+
+        // Diverging inputs are detected before replaying
+        let mut diverging_artifact = artifact.clone();
+        diverging_artifact.inputs.stakes_digest = Hasher::default().result().to_string();
+        assert_eq!(
+            new_parent(&genesis_config).replay_epoch_boundary(&diverging_artifact),
+            Err(EpochBoundaryReplayError::InputsMismatch(vec![
+                "stakes_digest"
+            ]))
+        );
+        assert_eq!(
+            bank.replay_epoch_boundary(&artifact),
+            Err(EpochBoundaryReplayError::ParentSlotMismatch {
+                expected: parent.slot(),
+                actual: bank.slot(),
+            })
+        );
+
+        // Diverging outputs are reported
+        let mut diverging_artifact = artifact.clone();
+        diverging_artifact.outputs.capitalization += 1;
+        let mutated_account = diverging_artifact.outputs.account_mutations[0]
+            .pubkey
+            .clone();
+        diverging_artifact.outputs.account_mutations[0].lamports += 1;
+        assert_eq!(
+            new_parent(&genesis_config).replay_epoch_boundary(&diverging_artifact),
+            Ok(EpochBoundaryDivergence {
+                accounts: vec![mutated_account],
+                capitalization: Some((bank.capitalization() + 1, bank.capitalization())),
+            })
+        );
+    }
+}
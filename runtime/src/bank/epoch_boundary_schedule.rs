@@ -0,0 +1,527 @@
+//! Spreading of epoch boundary work over the first slots of an epoch.
+//!
+//! All of the epoch boundary work normally happens in the first block of an
+//! epoch, which makes that block slow to produce and replay. An
+//! `EpochBoundarySchedule` declares which tasks are deferred, and to which
+//! slot of the epoch. A deferred task runs in the first block of the epoch at
+//! or after its slot, so it runs exactly once on every fork, even if slots
+//! are skipped. Deferred tasks observe the state of the block they run in.
+//!
+//! A deferred task may also run in the background: its inputs are the state
+//! of the first block of the epoch, it is computed on a background thread
+//! while the following blocks are replayed, and the block at or after its
+//! slot waits for the result and commits it. The result does not depend on
+//! when the background thread finishes, only on the state of the first
+//! block.
+//!
+//! The schedule affects consensus, so every node must run the same one. It
+//! only takes effect once the `spread_epoch_boundary_work` feature is active.
+//! The schedule of the cluster is the default one, which keeps all of the
+//! work in the first block: it is neither part of snapshots nor derived from
+//! committed state yet, so other schedules are only available to tests.
+
+use {
+    super::{metrics::RewardsMetrics, Bank, RewardCalcTracer},
+    rayon::ThreadPoolBuilder,
+    solana_measure::measure_us,
+    solana_sdk::{
+        clock::{Slot, SlotIndex},
+        feature_set,
+    },
+    std::sync::atomic::Ordering::Relaxed,
+    thiserror::Error,
+};
+
+/// Deferred tasks run within this many slots of the start of the epoch,
+/// which is less than the minimum number of slots per epoch
+pub const MAX_EPOCH_BOUNDARY_SPREAD_SLOTS: SlotIndex = 16;
+
+/// Epoch boundary work which may be deferred to a later slot of the epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EpochBoundaryTask {
+    /// Calculating the rewards of the previous epoch and paying the vote
+    /// rewards, which starts the partitioned distribution of stake rewards
+    RewardsCalculation,
+}
+
+impl EpochBoundaryTask {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::RewardsCalculation => "rewards_calculation",
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EpochBoundaryScheduleError {
+    #[error("slot index {0} is outside of the first {MAX_EPOCH_BOUNDARY_SPREAD_SLOTS} slots of the epoch")]
+    SlotIndexOutOfRange(SlotIndex),
+
+    #[error(
+        "a task running in the background cannot be committed in the first block of the epoch"
+    )]
+    BackgroundTaskInFirstBlock,
+}
+
+/// The slot of the epoch each deferred epoch boundary task runs at. Tasks
+/// which are not deferred run in the first block of the epoch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EpochBoundarySchedule {
+    deferred: Vec<(EpochBoundaryTask, SlotIndex)>,
+    /// Deferred tasks which run in the background
+    background: Vec<EpochBoundaryTask>,
+}
+
+impl EpochBoundarySchedule {
+    /// Defer `task` to the block at or after `slot_index` of the epoch
+    pub fn defer(
+        mut self,
+        task: EpochBoundaryTask,
+        slot_index: SlotIndex,
+    ) -> Result<Self, EpochBoundaryScheduleError> {
+        if slot_index >= MAX_EPOCH_BOUNDARY_SPREAD_SLOTS {
+            return Err(EpochBoundaryScheduleError::SlotIndexOutOfRange(slot_index));
+        }
+        self.deferred
+            .retain(|(deferred_task, _)| *deferred_task != task);
+        self.deferred.push((task, slot_index));
+        self.background
+            .retain(|background_task| *background_task != task);
+        Ok(self)
+    }
+
+    /// Run `task` in the background from the state of the first block of the
+    /// epoch, and commit its result in the block at or after `slot_index` of
+    /// the epoch
+    ///
+    /// The state of the first block is not part of the snapshots of the
+    /// following blocks, so a bank restored from one of the blocks up to the
+    /// committing block would run the task from the state of the committing
+    /// block instead. Background tasks are thus only available to schedules
+    /// overridden in tests, until the schedule and the inputs of its tasks
+    /// are persisted.
+    #[cfg(feature = "dev-context-only-utils")]
+    pub fn defer_in_background(
+        self,
+        task: EpochBoundaryTask,
+        slot_index: SlotIndex,
+    ) -> Result<Self, EpochBoundaryScheduleError> {
+        if slot_index == 0 {
+            return Err(EpochBoundaryScheduleError::BackgroundTaskInFirstBlock);
+        }
+        let mut schedule = self.defer(task, slot_index)?;
+        schedule.background.push(task);
+        Ok(schedule)
+    }
+
+    /// Whether `task` runs in the background
+    pub fn is_in_background(&self, task: EpochBoundaryTask) -> bool {
+        self.background.contains(&task)
+    }
+
+    /// The slot index of the epoch `task` runs at
+    pub fn slot_index(&self, task: EpochBoundaryTask) -> SlotIndex {
+        self.deferred
+            .iter()
+            .find(|(deferred_task, _)| *deferred_task == task)
+            .map(|(_, slot_index)| *slot_index)
+            .unwrap_or(0)
+    }
+
+    /// Whether `task` runs in the block at `slot_index` of the epoch, whose
+    /// parent is at `parent_slot_index` of the same epoch, or in an earlier
+    /// epoch if `None`
+    fn is_due(
+        &self,
+        task: EpochBoundaryTask,
+        parent_slot_index: Option<SlotIndex>,
+        slot_index: SlotIndex,
+    ) -> bool {
+        let task_slot_index = self.slot_index(task);
+        task_slot_index <= slot_index
+            && parent_slot_index.map_or(true, |parent_slot_index| {
+                parent_slot_index < task_slot_index
+            })
+    }
+}
+
+impl Bank {
+    /// Override the schedule of the epoch boundary work, which is the default
+    /// one on every node. A bank restored from a snapshot runs the default
+    /// schedule again.
+    #[cfg(feature = "dev-context-only-utils")]
+    pub fn set_epoch_boundary_schedule(&mut self, schedule: EpochBoundarySchedule) {
+        self.epoch_boundary_schedule = schedule;
+    }
+
+    /// Whether `task` runs in this bank, whose parent is in the same epoch at
+    /// `parent_slot`, or in an earlier epoch if `None`
+    pub(super) fn is_epoch_boundary_task_due(
+        &self,
+        task: EpochBoundaryTask,
+        parent_slot: Option<Slot>,
+    ) -> bool {
+        let (epoch, slot_index) = self.get_epoch_and_slot_index(self.slot());
+        // The first epoch begins at genesis, without an epoch boundary
+        if epoch == 0
+            || !self
+                .feature_set
+                .is_active(&feature_set::spread_epoch_boundary_work::id())
+        {
+            return parent_slot.is_none();
+        }
+        let parent_slot_index =
+            parent_slot.map(|parent_slot| self.get_epoch_and_slot_index(parent_slot).1);
+        self.epoch_boundary_schedule
+            .is_due(task, parent_slot_index, slot_index)
+    }
+
+    /// Run the epoch boundary tasks deferred to this bank, whose parent is in
+    /// the same epoch
+    pub(super) fn run_deferred_epoch_boundary_tasks(
+        &mut self,
+        parent: &Bank,
+        reward_calc_tracer: Option<impl RewardCalcTracer>,
+    ) {
+        let task = EpochBoundaryTask::RewardsCalculation;
+        if !self.is_partitioned_rewards_code_enabled()
+            || !self.is_epoch_boundary_task_due(task, Some(parent.slot()))
+        {
+            return;
+        }
+
+        let thread_pool = ThreadPoolBuilder::new()
+            .thread_name(|i| format!("solBnkDefer{i:02}"))
+            .build()
+            .expect("new rayon threadpool");
+        let mut rewards_metrics = RewardsMetrics::default();
+        let background_calculation = self.background_rewards_calculation.take();
+        let (_, task_time_us) = measure_us!(match background_calculation {
+            Some(background_calculation) => self.commit_background_rewards_calculation(
+                &background_calculation,
+                reward_calc_tracer,
+                parent.slot(),
+                parent.block_height(),
+                &mut rewards_metrics,
+            ),
+            None => self.begin_partitioned_rewards(
+                reward_calc_tracer,
+                &thread_pool,
+                self.epoch().saturating_sub(1),
+                parent.slot(),
+                parent.block_height(),
+                &mut rewards_metrics,
+            ),
+        });
+
+        datapoint_info!(
+            "bank-deferred_epoch_boundary_task",
+            ("epoch", self.epoch(), i64),
+            ("slot", self.slot(), i64),
+            ("task", task.name(), String),
+            ("task_us", task_time_us, i64),
+            (
+                "calculate_points_us",
+                rewards_metrics.calculate_points_us.load(Relaxed),
+                i64
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            bank::partitioned_epoch_rewards::{EpochRewardStatus, RewardInterval},
+            genesis_utils::{
+                create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
+            },
+        },
+        solana_sdk::{
+            genesis_config::GenesisConfig,
+            pubkey::Pubkey,
+            signature::Signer,
+            vote::state::{VoteStateVersions, MAX_LOCKOUT_HISTORY},
+        },
+        solana_vote_program::vote_state,
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_epoch_boundary_schedule() {
+        let task = EpochBoundaryTask::RewardsCalculation;
+        let schedule = EpochBoundarySchedule::default();
+        assert_eq!(schedule.slot_index(task), 0);
+        assert!(schedule.is_due(task, None, 0));
+        assert!(schedule.is_due(task, None, 3));
+        assert!(!schedule.is_due(task, Some(0), 1));
+
+        let schedule = schedule.defer(task, 2).unwrap();
+        assert_eq!(schedule.slot_index(task), 2);
+        assert!(!schedule.is_due(task, None, 0));
+        assert!(!schedule.is_due(task, Some(0), 1));
+        assert!(schedule.is_due(task, Some(1), 2));
+        // the first block at or after the slot runs the task
+        assert!(schedule.is_due(task, None, 2));
+        assert!(schedule.is_due(task, Some(1), 5));
+        assert!(!schedule.is_due(task, Some(2), 3));
+
+        // deferring again replaces the slot
+        let schedule = schedule.defer(task, 1).unwrap();
+        assert_eq!(schedule.slot_index(task), 1);
+
+        let schedule = schedule.defer_in_background(task, 3).unwrap();
+        assert_eq!(schedule.slot_index(task), 3);
+        assert!(schedule.is_in_background(task));
+        let schedule = schedule.defer(task, 3).unwrap();
+        assert!(!schedule.is_in_background(task));
+        assert_eq!(
+            EpochBoundarySchedule::default().defer_in_background(task, 0),
+            Err(EpochBoundaryScheduleError::BackgroundTaskInFirstBlock)
+        );
+
+        assert_eq!(
+            EpochBoundarySchedule::default().defer(task, MAX_EPOCH_BOUNDARY_SPREAD_SLOTS),
+            Err(EpochBoundaryScheduleError::SlotIndexOutOfRange(
+                MAX_EPOCH_BOUNDARY_SPREAD_SLOTS
+            ))
+        );
+    }
+
+    fn new_genesis_config() -> GenesisConfig {
+        let validator_keypairs = (0..4)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect::<Vec<_>>();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            1_000_000_000,
+            &validator_keypairs,
+            vec![2_000_000_000; 4],
+        );
+        genesis_config
+    }
+
+    /// Create banks at `slots` and return the slots whose bank began the
+    /// distribution of the epoch rewards
+    fn rewards_calculation_slots(
+        genesis_config: &GenesisConfig,
+        schedule: EpochBoundarySchedule,
+        slots: impl IntoIterator<Item = Slot>,
+    ) -> Vec<Slot> {
+        let mut bank0 = Bank::new_for_tests(genesis_config);
+        bank0.set_epoch_boundary_schedule(schedule);
+        let mut bank = Arc::new(bank0);
+        let mut calculation_slots = vec![];
+        for slot in slots {
+            let parent_reward_interval = bank.get_reward_interval();
+            bank = Arc::new(Bank::new_from_parent(bank, &Pubkey::default(), slot));
+            if parent_reward_interval == RewardInterval::OutsideInterval
+                && bank.get_reward_interval() == RewardInterval::InsideInterval
+            {
+                calculation_slots.push(slot);
+            }
+        }
+        calculation_slots
+    }
+
+    #[test]
+    fn test_deferred_rewards_calculation() {
+        let genesis_config = new_genesis_config();
+        let slots_in_epoch = genesis_config.epoch_schedule.get_slots_in_epoch(0);
+        let deferred = EpochBoundarySchedule::default()
+            .defer(EpochBoundaryTask::RewardsCalculation, 2)
+            .unwrap();
+
+        // single block by default
+        assert_eq!(
+            rewards_calculation_slots(
+                &genesis_config,
+                EpochBoundarySchedule::default(),
+                1..slots_in_epoch + 4
+            ),
+            vec![slots_in_epoch]
+        );
+        assert_eq!(
+            rewards_calculation_slots(&genesis_config, deferred.clone(), 1..slots_in_epoch + 4),
+            vec![slots_in_epoch + 2]
+        );
+        // the deferred slot is skipped
+        assert_eq!(
+            rewards_calculation_slots(
+                &genesis_config,
+                deferred.clone(),
+                (1..slots_in_epoch + 2).chain([slots_in_epoch + 3, slots_in_epoch + 4])
+            ),
+            vec![slots_in_epoch + 3]
+        );
+        // the first block of the epoch is past the deferred slot
+        assert_eq!(
+            rewards_calculation_slots(
+                &genesis_config,
+                deferred.clone(),
+                (1..slots_in_epoch).chain([slots_in_epoch + 3, slots_in_epoch + 4])
+            ),
+            vec![slots_in_epoch + 3]
+        );
+
+        // the schedule has no effect until the feature is active
+        let mut genesis_config = genesis_config;
+        genesis_config
+            .accounts
+            .remove(&feature_set::spread_epoch_boundary_work::id());
+        assert_eq!(
+            rewards_calculation_slots(&genesis_config, deferred, 1..slots_in_epoch + 4),
+            vec![slots_in_epoch]
+        );
+    }
+
+    /// The stake rewards of the epoch reward status of `bank`, by stake
+    /// account
+    fn stake_rewards(bank: &Bank) -> Vec<(Pubkey, i64)> {
+        let EpochRewardStatus::Active(status) = &bank.epoch_reward_status else {
+            panic!("bank {} is outside of the reward interval", bank.slot());
+        };
+        let mut stake_rewards = status
+            .stake_rewards_by_partition
+            .iter()
+            .flatten()
+            .map(|reward| (reward.stake_pubkey, reward.stake_reward_info.lamports))
+            .collect::<Vec<_>>();
+        stake_rewards.sort_unstable();
+        stake_rewards
+    }
+
+    #[test]
+    fn test_background_rewards_calculation() {
+        let genesis_config = new_genesis_config();
+        let slots_in_epoch = genesis_config.epoch_schedule.get_slots_in_epoch(0);
+        let task = EpochBoundaryTask::RewardsCalculation;
+        let new_bank0 = |schedule| {
+            let mut bank0 = Bank::new_for_tests(&genesis_config);
+            bank0.set_epoch_boundary_schedule(schedule);
+            Arc::new(bank0)
+        };
+
+        let mut bank = new_bank0(
+            EpochBoundarySchedule::default()
+                .defer_in_background(task, 2)
+                .unwrap(),
+        );
+        for slot in 1..slots_in_epoch + 2 {
+            bank = Arc::new(Bank::new_from_parent(bank, &Pubkey::default(), slot));
+            if slot < slots_in_epoch {
+                assert!(bank.background_rewards_calculation.is_none());
+            } else {
+                // stake accounts are not writable while the calculation runs
+                assert!(bank.background_rewards_calculation.is_some());
+                assert_eq!(bank.epoch_reward_status, EpochRewardStatus::Inactive);
+                assert_eq!(bank.get_reward_interval(), RewardInterval::InsideInterval);
+            }
+        }
+        let background_bank = Bank::new_from_parent(bank, &Pubkey::default(), slots_in_epoch + 2);
+        assert!(background_bank.background_rewards_calculation.is_none());
+
+        // the blocks of the window do not change the inputs of the
+        // calculation, so the rewards are the ones calculated in the
+        // committing block
+        let mut bank = new_bank0(EpochBoundarySchedule::default().defer(task, 2).unwrap());
+        for slot in 1..slots_in_epoch + 3 {
+            bank = Arc::new(Bank::new_from_parent(bank, &Pubkey::default(), slot));
+        }
+        assert_eq!(stake_rewards(&background_bank), stake_rewards(&bank));
+        assert_eq!(background_bank.capitalization(), bank.capitalization());
+    }
+
+    /// Update the vote state of `vote_pubkey` in `bank`
+    fn update_vote_state(
+        bank: &Bank,
+        vote_pubkey: &Pubkey,
+        update: impl FnOnce(&mut vote_state::VoteState),
+    ) {
+        let mut vote_account = bank.get_account(vote_pubkey).unwrap();
+        let mut vote_state = vote_state::from(&vote_account).unwrap();
+        update(&mut vote_state);
+        vote_state::to(
+            &VoteStateVersions::new_current(vote_state),
+            &mut vote_account,
+        )
+        .unwrap();
+        bank.store_account_and_update_capitalization(vote_pubkey, &vote_account);
+    }
+
+    #[test]
+    fn test_background_rewards_calculation_vote_commission_change() {
+        let validator_keypairs = (0..4)
+            .map(|_| ValidatorVoteKeypairs::new_rand())
+            .collect::<Vec<_>>();
+        let GenesisConfigInfo { genesis_config, .. } = create_genesis_config_with_vote_accounts(
+            1_000_000_000,
+            &validator_keypairs,
+            vec![2_000_000_000; 4],
+        );
+        let vote_pubkeys = validator_keypairs
+            .iter()
+            .map(|keypairs| keypairs.vote_keypair.pubkey())
+            .collect::<Vec<_>>();
+        let slots_in_epoch = genesis_config.epoch_schedule.get_slots_in_epoch(0);
+        let task = EpochBoundaryTask::RewardsCalculation;
+
+        // The rewards of epoch 0, committed in the third block of epoch 1,
+        // when the first vote account changes its commission to `commission`
+        // in the second block
+        let rewards = |schedule, commission: Option<u8>| {
+            let mut bank0 = Bank::new_for_tests(&genesis_config);
+            bank0.set_epoch_boundary_schedule(schedule);
+            // generate some rewards
+            for vote_pubkey in &vote_pubkeys {
+                update_vote_state(&bank0, vote_pubkey, |vote_state| {
+                    for slot in 0..(MAX_LOCKOUT_HISTORY + 42) as Slot {
+                        vote_state::process_slot_vote_unchecked(vote_state, slot);
+                    }
+                });
+            }
+            let mut bank = Arc::new(bank0);
+            for slot in 1..slots_in_epoch + 3 {
+                let new_bank = Bank::new_from_parent(bank, &Pubkey::default(), slot);
+                if slot == slots_in_epoch + 1 {
+                    if let Some(commission) = commission {
+                        update_vote_state(&new_bank, &vote_pubkeys[0], |vote_state| {
+                            vote_state.commission = commission;
+                        });
+                    }
+                }
+                bank = Arc::new(new_bank);
+            }
+            stake_rewards(&bank)
+        };
+
+        let background = EpochBoundarySchedule::default()
+            .defer_in_background(task, 2)
+            .unwrap();
+        let deferred = EpochBoundarySchedule::default().defer(task, 2).unwrap();
+        let expected_rewards = rewards(deferred.clone(), None);
+        assert!(expected_rewards.iter().any(|(_, lamports)| *lamports > 0));
+        assert_eq!(rewards(background.clone(), None), expected_rewards);
+
+        // A deferred calculation observes the new commission
+        assert_ne!(rewards(deferred, Some(100)), expected_rewards);
+        // The background calculation only observes the first block of the
+        // epoch. A bank restored from a snapshot of the second block would
+        // observe the new commission like a deferred calculation, which is
+        // why the schedule of the cluster runs no task in the background.
+        assert_eq!(rewards(background.clone(), Some(100)), expected_rewards);
+        let mut bank = Arc::new({
+            let mut bank0 = Bank::new_for_tests(&genesis_config);
+            bank0.set_epoch_boundary_schedule(background);
+            bank0
+        });
+        for slot in 1..slots_in_epoch + 3 {
+            bank = Arc::new(Bank::new_from_parent(bank, &Pubkey::default(), slot));
+            assert_eq!(
+                bank.background_rewards_calculation.is_some(),
+                (slots_in_epoch..slots_in_epoch + 2).contains(&slot)
+            );
+        }
+    }
+}
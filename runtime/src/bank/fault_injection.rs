@@ -0,0 +1,227 @@
+//! Fault injection at the epoch boundary code paths of the bank, for tests.
+//!
+//! Core BPF migrations, builtin feature transitions and the distribution of
+//! partitioned rewards modify several parts of the bank at once. A test
+//! injects a `Fault` at a `FaultPoint` of one of these tasks and checks that
+//! the failure leaves no partial state behind, with
+//! `check_failure_isolation` running the task once for every kind of fault.
+//!
+//! Faults are injected for the current thread only, so that tests running in
+//! parallel do not interfere, and are cleared when the returned guard is
+//! dropped.
+
+use {
+    super::Bank,
+    solana_sdk::{account::AccountSharedData, clock::Slot, pubkey::Pubkey},
+    std::{
+        cell::RefCell,
+        collections::HashMap,
+        fmt::Debug,
+        panic::{self, AssertUnwindSafe},
+        sync::atomic::Ordering::Relaxed,
+    },
+    thiserror::Error,
+};
+
+/// A point of an epoch boundary task where a fault can be injected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum FaultPoint {
+    /// Before a Core BPF migration stores its account writes, in either
+    /// direction
+    CoreBpfMigrationCommit,
+    /// Before the stateless builtin feature transitions update the bank's
+    /// builtins, which can only fail by panicking
+    BuiltinTransition,
+    /// Before the rewards of a partition are stored, which panics on failure
+    RewardDistributionCommit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Fault {
+    /// The accounts can not be stored
+    StoreFailure,
+    /// An account can not be serialized
+    SerializationFailure,
+    /// The task panics
+    Panic,
+}
+
+impl Fault {
+    pub(crate) const ALL: [Fault; 3] = [
+        Fault::StoreFailure,
+        Fault::SerializationFailure,
+        Fault::Panic,
+    ];
+}
+
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("injected {fault:?} at {point:?}")]
+pub struct InjectedFault {
+    pub(crate) point: FaultPoint,
+    pub(crate) fault: Fault,
+}
+
+thread_local! {
+    static INJECTED_FAULTS: RefCell<HashMap<FaultPoint, Fault>> = RefCell::default();
+}
+
+/// Clears the injected fault when dropped
+#[must_use]
+pub(crate) struct FaultGuard(FaultPoint);
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        INJECTED_FAULTS.with(|faults| faults.borrow_mut().remove(&self.0));
+    }
+}
+
+/// Inject `fault` at `point` until the returned guard is dropped
+pub(crate) fn inject(point: FaultPoint, fault: Fault) -> FaultGuard {
+    INJECTED_FAULTS.with(|faults| faults.borrow_mut().insert(point, fault));
+    FaultGuard(point)
+}
+
+/// Fail with the fault injected at `point`, if any. Panics if the fault is a
+/// panic.
+pub(crate) fn trigger(point: FaultPoint) -> Result<(), InjectedFault> {
+    let Some(fault) = INJECTED_FAULTS.with(|faults| faults.borrow().get(&point).copied()) else {
+        return Ok(());
+    };
+    let injected_fault = InjectedFault { point, fault };
+    if fault == Fault::Panic {
+        panic!("{injected_fault}");
+    }
+    Err(injected_fault)
+}
+
+/// The parts of the bank modified by the epoch boundary tasks
+#[derive(Debug, PartialEq)]
+pub(crate) struct ObservedBankState {
+    accounts: Vec<Option<AccountSharedData>>,
+    /// (deployment slot, effective slot) of the cached versions of each
+    /// program
+    program_cache_entries: Vec<Vec<(Slot, Slot)>>,
+    builtin_program_ids: Vec<Pubkey>,
+    capitalization: u64,
+    accounts_data_size_delta_off_chain: i64,
+    rewards_len: usize,
+}
+
+impl ObservedBankState {
+    /// Observe `bank`, including the accounts and cached programs at
+    /// `pubkeys`
+    pub(crate) fn new(bank: &Bank, pubkeys: &[Pubkey]) -> Self {
+        let program_cache = bank.transaction_processor.program_cache.read().unwrap();
+        let mut builtin_program_ids = bank.builtin_program_ids.iter().copied().collect::<Vec<_>>();
+        builtin_program_ids.sort_unstable();
+        Self {
+            accounts: pubkeys
+                .iter()
+                .map(|pubkey| bank.get_account(pubkey))
+                .collect(),
+            program_cache_entries: pubkeys
+                .iter()
+                .map(|pubkey| {
+                    program_cache
+                        .get_slot_versions_for_tests(pubkey)
+                        .iter()
+                        .map(|entry| (entry.deployment_slot, entry.effective_slot))
+                        .collect()
+                })
+                .collect(),
+            builtin_program_ids,
+            capitalization: bank.capitalization(),
+            accounts_data_size_delta_off_chain: bank
+                .accounts_data_size_delta_off_chain
+                .load(Relaxed),
+            rewards_len: bank.rewards.read().unwrap().len(),
+        }
+    }
+}
+
+/// Run `task` on a bank created by `setup` once for every kind of fault
+/// injected at `point`, and check that the task fails and leaves the bank as
+/// it was, including the accounts and cached programs at `pubkeys`
+pub(crate) fn check_failure_isolation<T: Debug, E: Debug>(
+    point: FaultPoint,
+    pubkeys: &[Pubkey],
+    setup: impl Fn() -> Bank,
+    task: impl Fn(&mut Bank) -> Result<T, E>,
+) {
+    for fault in Fault::ALL {
+        let mut bank = setup();
+        let expected_state = ObservedBankState::new(&bank, pubkeys);
+
+        let guard = inject(point, fault);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| task(&mut bank)));
+        drop(guard);
+
+        if let Ok(Ok(value)) = result {
+            panic!("{fault:?} at {point:?} did not fail the task: {value:?}");
+        }
+        assert_eq!(
+            ObservedBankState::new(&bank, pubkeys),
+            expected_state,
+            "{fault:?} at {point:?} left partial state behind"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::bank::{builtins::StatelessBuiltinPrototype, tests::create_simple_test_bank},
+        solana_sdk::feature_set::FeatureSet,
+        std::{collections::HashSet, sync::Arc},
+    };
+
+    #[test]
+    fn test_inject_fault() {
+        let point = FaultPoint::CoreBpfMigrationCommit;
+        assert_eq!(trigger(point), Ok(()));
+
+        let guard = inject(point, Fault::StoreFailure);
+        assert_eq!(
+            trigger(point),
+            Err(InjectedFault {
+                point,
+                fault: Fault::StoreFailure
+            })
+        );
+        assert_eq!(trigger(FaultPoint::RewardDistributionCommit), Ok(()));
+        drop(guard);
+        assert_eq!(trigger(point), Ok(()));
+
+        let _guard = inject(point, Fault::Panic);
+        assert!(panic::catch_unwind(|| trigger(point)).is_err());
+    }
+
+    #[test]
+    fn test_builtin_transition_failure_isolation() {
+        let enable_feature_id = Pubkey::new_unique();
+        let stateless_builtins = [StatelessBuiltinPrototype {
+            core_bpf_migration_config: None,
+            enable_feature_id: Some(enable_feature_id),
+            disable_feature_id: None,
+            program_id: Pubkey::new_unique(),
+            name: "gated_stateless_builtin",
+        }];
+        let setup = || {
+            let mut bank = create_simple_test_bank(100_000);
+            let mut feature_set = FeatureSet::default();
+            feature_set.activate(&enable_feature_id, 0);
+            bank.feature_set = Arc::new(feature_set);
+            bank
+        };
+
+        check_failure_isolation(FaultPoint::BuiltinTransition, &[], setup, |bank| {
+            bank.apply_stateless_builtin_feature_transitions(
+                &stateless_builtins,
+                true,
+                &HashSet::from([enable_feature_id]),
+            );
+            Ok::<_, ()>(())
+        });
+    }
+}
@@ -2,28 +2,205 @@ use {
     super::{
         epoch_rewards_hasher::hash_rewards_into_partitions, Bank,
         CalculateRewardsAndDistributeVoteRewardsResult, EpochRewardCalculateParamInfo,
-        PartitionedRewardsCalculation, StakeRewardCalculationPartitioned, VoteRewardsAccounts,
+        EpochRewardsPreview, PartitionedRewardsCalculation, StakeRewardCalculationPartitioned,
+        StakeRewards, VoteRewardsAccounts,
     },
     crate::bank::{
-        PrevEpochInflationRewards, RewardCalcTracer, RewardCalculationEvent, RewardsMetrics,
-        StakeRewardCalculation, VoteAccount,
+        null_tracer, PrevEpochInflationRewards, RewardCalcTracer, RewardCalculationEvent,
+        RewardsMetrics, StakeRewardCalculation, VoteAccount,
     },
     log::info,
     rayon::{
         iter::{IntoParallelRefIterator, ParallelIterator},
-        ThreadPool,
+        ThreadPool, ThreadPoolBuilder,
     },
+    solana_accounts_db::stake_rewards::StakeReward,
     solana_measure::measure_us,
     solana_sdk::{
         clock::{Epoch, Slot},
+        hash::Hash,
         pubkey::Pubkey,
         reward_info::RewardInfo,
     },
-    solana_stake_program::points::PointValue,
-    std::sync::atomic::Ordering::Relaxed,
+    solana_stake_program::points::{InflationPointCalculationEvent, PointValue},
+    std::{
+        fmt,
+        sync::{atomic::Ordering::Relaxed, Arc, Mutex},
+        thread::{Builder, JoinHandle},
+    },
 };
 
+/// Calculation of the rewards of an epoch on a background thread, from the
+/// state of the first block of the epoch, see
+/// `EpochBoundarySchedule::defer_in_background()`
+///
+/// The calculation is shared by the banks of the epoch descending from the
+/// first block, and starts once the first block is frozen, when its first
+/// child is created. Stake accounts are not writable until the result is
+/// committed, so that the state the rewards were calculated from is still
+/// current when they are distributed.
+pub(in crate::bank) struct BackgroundRewardsCalculation {
+    rewarded_epoch: Epoch,
+    /// slot of the first block of the epoch, whose state the rewards are calculated from
+    snapshot_slot: Slot,
+    /// hashes the stake rewards into partitions, the blockhash of the parent of the first block
+    /// like in the synchronous calculation
+    partition_seed: Hash,
+    state: Mutex<BackgroundRewardsCalculationState>,
+}
+
+enum BackgroundRewardsCalculationState {
+    /// the first block of the epoch is not frozen yet
+    NotStarted,
+    Running(JoinHandle<BackgroundRewardsCalculationResult>),
+    Done(Arc<BackgroundRewardsCalculationResult>),
+}
+
+struct BackgroundRewardsCalculationResult {
+    calculation: PartitionedRewardsCalculation,
+    /// events of the reward calculation tracer, replayed to the tracer of the committing block
+    traced_events: Vec<(Pubkey, InflationPointCalculationEvent)>,
+}
+
+impl fmt::Debug for BackgroundRewardsCalculation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BackgroundRewardsCalculation")
+            .field("rewarded_epoch", &self.rewarded_epoch)
+            .field("snapshot_slot", &self.snapshot_slot)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BackgroundRewardsCalculation {
+    pub(in crate::bank) fn new(
+        rewarded_epoch: Epoch,
+        snapshot_slot: Slot,
+        partition_seed: Hash,
+    ) -> Self {
+        Self {
+            rewarded_epoch,
+            snapshot_slot,
+            partition_seed,
+            state: Mutex::new(BackgroundRewardsCalculationState::NotStarted),
+        }
+    }
+
+    /// Start calculating the rewards from the frozen `snapshot_bank`, unless
+    /// the calculation already started. If `trace` is set, the events of the
+    /// calculation are recorded for the reward calculation tracer.
+    fn start(&self, snapshot_bank: Arc<Bank>, trace: bool) {
+        assert_eq!(snapshot_bank.slot(), self.snapshot_slot);
+        assert!(snapshot_bank.is_frozen());
+        let mut state = self.state.lock().unwrap();
+        if !matches!(*state, BackgroundRewardsCalculationState::NotStarted) {
+            return;
+        }
+        let rewarded_epoch = self.rewarded_epoch;
+        let partition_seed = self.partition_seed;
+        let handle = Builder::new()
+            .name("solBnkRwdCalc".to_string())
+            .spawn(move || {
+                let thread_pool = ThreadPoolBuilder::new()
+                    .thread_name(|i| format!("solBnkRwdBg{i:02}"))
+                    .build()
+                    .expect("new rayon threadpool");
+                let traced_events = Mutex::new(vec![]);
+                let reward_calc_tracer = trace.then_some(|event: &RewardCalculationEvent| {
+                    let RewardCalculationEvent::Staking(stake_pubkey, event) = event;
+                    traced_events
+                        .lock()
+                        .unwrap()
+                        .push((**stake_pubkey, (*event).clone()));
+                });
+                let calculation = snapshot_bank.calculate_rewards_for_partitioning_with_seed(
+                    rewarded_epoch,
+                    &partition_seed,
+                    reward_calc_tracer,
+                    &thread_pool,
+                    &mut RewardsMetrics::default(),
+                );
+                BackgroundRewardsCalculationResult {
+                    calculation,
+                    traced_events: traced_events.into_inner().unwrap(),
+                }
+            })
+            .expect("spawn rewards calculation thread");
+        *state = BackgroundRewardsCalculationState::Running(handle);
+    }
+
+    /// Wait for the calculation to finish
+    fn join(&self) -> Arc<BackgroundRewardsCalculationResult> {
+        let mut state = self.state.lock().unwrap();
+        let result =
+            match std::mem::replace(&mut *state, BackgroundRewardsCalculationState::NotStarted) {
+                BackgroundRewardsCalculationState::NotStarted => panic!(
+                    "the rewards calculation of epoch {} has not started",
+                    self.rewarded_epoch
+                ),
+                BackgroundRewardsCalculationState::Running(handle) => {
+                    Arc::new(handle.join().expect("rewards calculation thread panicked"))
+                }
+                BackgroundRewardsCalculationState::Done(result) => result,
+            };
+        *state = BackgroundRewardsCalculationState::Done(Arc::clone(&result));
+        result
+    }
+}
+
 impl Bank {
+    /// Start the background rewards calculation of the epoch if `parent` is
+    /// the block it is calculated from
+    pub(in crate::bank) fn start_background_rewards_calculation(
+        &self,
+        parent: &Arc<Bank>,
+        trace: bool,
+    ) {
+        if let Some(calculation) = &self.background_rewards_calculation {
+            if calculation.snapshot_slot == parent.slot() {
+                calculation.start(Arc::clone(parent), trace);
+            }
+        }
+    }
+
+    /// Verify the result of the background rewards calculation and begin the
+    /// distribution of the rewards with it
+    pub(in crate::bank) fn commit_background_rewards_calculation(
+        &mut self,
+        background_calculation: &BackgroundRewardsCalculation,
+        reward_calc_tracer: Option<impl RewardCalcTracer>,
+        parent_slot: Slot,
+        parent_block_height: u64,
+        rewards_metrics: &mut RewardsMetrics,
+    ) {
+        // The calculation is inherited from the first block of the epoch on
+        // this fork
+        let rewarded_epoch = background_calculation.rewarded_epoch;
+        let snapshot_slot = background_calculation.snapshot_slot;
+        assert_eq!(rewarded_epoch, self.epoch().saturating_sub(1));
+        assert!(snapshot_slot < self.slot());
+        assert_eq!(self.epoch_schedule.get_epoch(snapshot_slot), self.epoch());
+        let result = background_calculation.join();
+        if let Some(reward_calc_tracer) = reward_calc_tracer.as_ref() {
+            for (stake_pubkey, event) in &result.traced_events {
+                reward_calc_tracer(&RewardCalculationEvent::Staking(stake_pubkey, event));
+            }
+        }
+        let mut calculation = result.calculation.clone();
+        // Vote rewards are partitioned, so no vote account is stored when
+        // committing, but the balances of vote accounts changed since the
+        // calculation
+        assert!(calculation.vote_account_rewards.rewards.is_empty());
+        calculation.old_vote_balance_and_staked =
+            self.stakes_cache.stakes().vote_balance_and_staked();
+        self.commit_partitioned_rewards(
+            rewarded_epoch,
+            calculation,
+            parent_slot,
+            parent_block_height,
+            rewards_metrics,
+        );
+    }
+
     /// Begin the process of calculating and distributing rewards.
     /// This process can take multiple slots.
     pub(in crate::bank) fn begin_partitioned_rewards(
@@ -35,16 +212,36 @@ impl Bank {
         parent_block_height: u64,
         rewards_metrics: &mut RewardsMetrics,
     ) {
-        let CalculateRewardsAndDistributeVoteRewardsResult {
-            total_rewards,
-            distributed_rewards,
-            stake_rewards_by_partition,
-        } = self.calculate_rewards_and_distribute_vote_rewards(
+        let calculation = self.calculate_rewards_for_partitioning(
             parent_epoch,
             reward_calc_tracer,
             thread_pool,
             rewards_metrics,
         );
+        self.commit_partitioned_rewards(
+            parent_epoch,
+            calculation,
+            parent_slot,
+            parent_block_height,
+            rewards_metrics,
+        );
+    }
+
+    /// Distribute the vote rewards of `calculation`, if they are not
+    /// partitioned, and begin the partitioned distribution of the rest
+    fn commit_partitioned_rewards(
+        &mut self,
+        parent_epoch: Epoch,
+        calculation: PartitionedRewardsCalculation,
+        parent_slot: Slot,
+        parent_block_height: u64,
+        rewards_metrics: &mut RewardsMetrics,
+    ) {
+        let CalculateRewardsAndDistributeVoteRewardsResult {
+            total_rewards,
+            distributed_rewards,
+            stake_rewards_by_partition,
+        } = self.distribute_vote_rewards(parent_epoch, calculation, rewards_metrics);
 
         let slot = self.slot();
         let credit_start = self.block_height() + self.get_reward_calculation_num_blocks();
@@ -65,12 +262,11 @@ impl Bank {
         );
     }
 
-    // Calculate rewards from previous epoch and distribute vote rewards
-    fn calculate_rewards_and_distribute_vote_rewards(
+    // Distribute the vote rewards calculated from previous epoch
+    fn distribute_vote_rewards(
         &self,
         prev_epoch: Epoch,
-        reward_calc_tracer: Option<impl Fn(&RewardCalculationEvent) + Send + Sync>,
-        thread_pool: &ThreadPool,
+        calculation: PartitionedRewardsCalculation,
         metrics: &mut RewardsMetrics,
     ) -> CalculateRewardsAndDistributeVoteRewardsResult {
         let PartitionedRewardsCalculation {
@@ -82,12 +278,7 @@ impl Bank {
             foundation_rate,
             prev_epoch_duration_in_years,
             capitalization,
-        } = self.calculate_rewards_for_partitioning(
-            prev_epoch,
-            reward_calc_tracer,
-            thread_pool,
-            metrics,
-        );
+        } = calculation;
         let vote_rewards = self.store_vote_accounts_partitioned(vote_account_rewards, metrics);
 
         // update reward history of JUST vote_rewards, stake_rewards is vec![] here
@@ -96,22 +287,24 @@ impl Bank {
         let StakeRewardCalculationPartitioned {
             stake_rewards_by_partition,
             total_stake_rewards_lamports,
+            total_vote_rewards_lamports: partitioned_vote_rewards_lamports,
         } = stake_rewards_by_partition;
+        let remaining_rewards = total_stake_rewards_lamports + partitioned_vote_rewards_lamports;
 
         // the remaining code mirrors `update_rewards_with_thread_pool()`
 
         let new_vote_balance_and_staked = self.stakes_cache.stakes().vote_balance_and_staked();
 
-        // This is for vote rewards only.
+        // This is for vote rewards only, zero if they are partitioned.
         let validator_rewards_paid = new_vote_balance_and_staked - old_vote_balance_and_staked;
         self.assert_validator_rewards_paid(validator_rewards_paid);
 
         // verify that we didn't pay any more than we expected to
-        assert!(validator_rewards >= validator_rewards_paid + total_stake_rewards_lamports);
+        assert!(validator_rewards >= validator_rewards_paid + remaining_rewards);
 
         info!(
             "distributed vote rewards: {} out of {}, remaining {}",
-            validator_rewards_paid, validator_rewards, total_stake_rewards_lamports
+            validator_rewards_paid, validator_rewards, remaining_rewards
         );
 
         let (num_stake_accounts, num_vote_accounts) = {
@@ -148,7 +341,7 @@ impl Bank {
         );
 
         CalculateRewardsAndDistributeVoteRewardsResult {
-            total_rewards: validator_rewards_paid + total_stake_rewards_lamports,
+            total_rewards: validator_rewards_paid + remaining_rewards,
             distributed_rewards: validator_rewards_paid,
             stake_rewards_by_partition,
         }
@@ -180,6 +373,72 @@ impl Bank {
         vote_account_rewards.rewards
     }
 
+    /// Calculate the rewards for `rewarded_epoch` as they would be paid out
+    /// at the next epoch boundary, without storing any account or activating
+    /// the reward distribution. Intended for forecasting the emission and the
+    /// distribution load ahead of the boundary.
+    ///
+    /// The assignment of stake rewards to partitions depends on the blockhash
+    /// of the parent of the first block of the new epoch, which is not known
+    /// yet. The last blockhash of this bank is used instead, so only the
+    /// number and sizes of the partitions are meaningful.
+    pub fn calculate_rewards_preview(&self, rewarded_epoch: Epoch) -> EpochRewardsPreview {
+        let thread_pool = ThreadPoolBuilder::new()
+            .thread_name(|i| format!("solBnkRwdPrev{i:02}"))
+            .build()
+            .expect("new rayon threadpool");
+        let PartitionedRewardsCalculation {
+            vote_account_rewards,
+            stake_rewards_by_partition,
+            old_vote_balance_and_staked: _,
+            validator_rewards,
+            validator_rate,
+            foundation_rate,
+            prev_epoch_duration_in_years,
+            capitalization,
+        } = self.calculate_rewards_for_partitioning_with_seed(
+            rewarded_epoch,
+            &self.last_blockhash(),
+            null_tracer(),
+            &thread_pool,
+            &mut RewardsMetrics::default(),
+        );
+
+        let StakeRewardCalculationPartitioned {
+            stake_rewards_by_partition,
+            total_stake_rewards_lamports,
+            total_vote_rewards_lamports: partitioned_vote_rewards_lamports,
+        } = stake_rewards_by_partition;
+        let vote_rewards = vote_account_rewards.rewards;
+        let total_vote_rewards_lamports = vote_rewards
+            .iter()
+            .map(|(_vote_pubkey, reward_info)| reward_info.lamports as u64)
+            .sum::<u64>()
+            + partitioned_vote_rewards_lamports;
+        let stake_rewards_by_partition = stake_rewards_by_partition
+            .into_iter()
+            .map(|partition| {
+                partition
+                    .into_iter()
+                    .map(|stake_reward| (stake_reward.stake_pubkey, stake_reward.stake_reward_info))
+                    .collect()
+            })
+            .collect();
+
+        EpochRewardsPreview {
+            rewarded_epoch,
+            capitalization,
+            validator_rate,
+            foundation_rate,
+            epoch_duration_in_years: prev_epoch_duration_in_years,
+            validator_rewards,
+            vote_rewards,
+            total_vote_rewards_lamports,
+            stake_rewards_by_partition,
+            total_stake_rewards_lamports,
+        }
+    }
+
     /// Calculate rewards from previous epoch to prepare for partitioned distribution.
     pub(in crate::bank) fn calculate_rewards_for_partitioning(
         &self,
@@ -187,6 +446,29 @@ impl Bank {
         reward_calc_tracer: Option<impl Fn(&RewardCalculationEvent) + Send + Sync>,
         thread_pool: &ThreadPool,
         metrics: &mut RewardsMetrics,
+    ) -> PartitionedRewardsCalculation {
+        let parent_blockhash = self
+            .parent()
+            .expect("Partitioned rewards calculation must still have access to parent Bank.")
+            .last_blockhash();
+        self.calculate_rewards_for_partitioning_with_seed(
+            prev_epoch,
+            &parent_blockhash,
+            reward_calc_tracer,
+            thread_pool,
+            metrics,
+        )
+    }
+
+    /// Calculate rewards from previous epoch, hashing the stake rewards into
+    /// partitions with `partition_seed`
+    fn calculate_rewards_for_partitioning_with_seed(
+        &self,
+        prev_epoch: Epoch,
+        partition_seed: &Hash,
+        reward_calc_tracer: Option<impl Fn(&RewardCalculationEvent) + Send + Sync>,
+        thread_pool: &ThreadPool,
+        metrics: &mut RewardsMetrics,
     ) -> PartitionedRewardsCalculation {
         let capitalization = self.capitalization();
         let PrevEpochInflationRewards {
@@ -198,7 +480,7 @@ impl Bank {
 
         let old_vote_balance_and_staked = self.stakes_cache.stakes().vote_balance_and_staked();
 
-        let (vote_account_rewards, mut stake_rewards) = self
+        let (mut vote_account_rewards, mut stake_rewards) = self
             .calculate_validator_rewards(
                 prev_epoch,
                 validator_rewards,
@@ -208,14 +490,22 @@ impl Bank {
             )
             .unwrap_or_default();
 
+        // partitioned vote rewards are hashed into the same partitions as the stake rewards
+        let mut total_vote_rewards_lamports = 0;
+        if self.is_partitioned_vote_rewards_enabled() {
+            let vote_rewards =
+                Self::vote_rewards_to_partition(std::mem::take(&mut vote_account_rewards));
+            total_vote_rewards_lamports = vote_rewards
+                .iter()
+                .map(|vote_reward| vote_reward.get_stake_reward() as u64)
+                .sum();
+            stake_rewards.stake_rewards.extend(vote_rewards);
+        }
+
         let num_partitions = self.get_reward_distribution_num_blocks(&stake_rewards.stake_rewards);
-        let parent_blockhash = self
-            .parent()
-            .expect("Partitioned rewards calculation must still have access to parent Bank.")
-            .last_blockhash();
         let stake_rewards_by_partition = hash_rewards_into_partitions(
             std::mem::take(&mut stake_rewards.stake_rewards),
-            &parent_blockhash,
+            partition_seed,
             num_partitions as usize,
         );
 
@@ -224,6 +514,7 @@ impl Bank {
             stake_rewards_by_partition: StakeRewardCalculationPartitioned {
                 stake_rewards_by_partition,
                 total_stake_rewards_lamports: stake_rewards.total_stake_rewards_lamports,
+                total_vote_rewards_lamports,
             },
             old_vote_balance_and_staked,
             validator_rewards,
@@ -234,6 +525,25 @@ impl Bank {
         }
     }
 
+    /// Convert vote rewards into rewards distributed in partitions. Only the vote accounts which
+    /// need to be stored are kept, with their account as of the calculation. The account is not
+    /// stored during the distribution, since votes keep changing it: the reward is credited to
+    /// the current vote account instead.
+    fn vote_rewards_to_partition(vote_account_rewards: VoteRewardsAccounts) -> StakeRewards {
+        vote_account_rewards
+            .rewards
+            .into_iter()
+            .zip(vote_account_rewards.accounts_to_store)
+            .filter_map(|((vote_pubkey, reward_info), vote_account)| {
+                Some(StakeReward {
+                    stake_pubkey: vote_pubkey,
+                    stake_reward_info: reward_info,
+                    stake_account: vote_account?,
+                })
+            })
+            .collect()
+    }
+
     /// Calculate epoch reward and return vote and stake rewards.
     fn calculate_validator_rewards(
         &self,
@@ -323,7 +633,7 @@ impl Bank {
         }));
         metrics.calculate_points_us.fetch_add(measure_us, Relaxed);
 
-        (points > 0).then_some(PointValue { rewards, points })
+        (points > 0).then(|| self.with_inflation_curve(|curve| curve.point_value(rewards, points)))
     }
 }
 
@@ -332,16 +642,19 @@ mod tests {
     use {
         super::*,
         crate::{
-            bank::{null_tracer, tests::create_genesis_config, VoteReward},
+            bank::{
+                partitioned_epoch_rewards::EpochRewardStatus, tests::create_genesis_config,
+                VoteReward,
+            },
             genesis_utils::{
                 create_genesis_config_with_vote_accounts, GenesisConfigInfo, ValidatorVoteKeypairs,
             },
             stake_account::StakeAccount,
             stakes::Stakes,
         },
-        rayon::ThreadPoolBuilder,
         solana_sdk::{
             account::{accounts_equal, ReadableAccount, WritableAccount},
+            feature_set,
             native_token::{sol_to_lamports, LAMPORTS_PER_SOL},
             reward_type::RewardType,
             signature::Signer,
@@ -349,7 +662,7 @@ mod tests {
             vote::state::{VoteStateVersions, MAX_LOCKOUT_HISTORY},
         },
         solana_vote_program::vote_state,
-        std::sync::RwLockReadGuard,
+        std::{collections::HashSet, sync::RwLockReadGuard},
     };
 
     /// Helper function to create a bank that pays some rewards
@@ -497,6 +810,77 @@ mod tests {
         assert_eq!(stake_rewards.stake_rewards.len(), expected_num_delegations);
     }
 
+    #[test]
+    fn test_calculate_rewards_preview() {
+        let expected_num_delegations = 4;
+        let (bank, vote_pubkeys, _stake_pubkeys) = create_reward_bank(expected_num_delegations);
+        let capitalization = bank.capitalization();
+        let vote_account = bank.get_account(&vote_pubkeys[0]).unwrap();
+
+        let preview = bank.calculate_rewards_preview(1);
+        assert_eq!(preview.rewarded_epoch, 1);
+        assert_eq!(preview.capitalization, capitalization);
+        // The partitions also hold the vote rewards, which are partitioned
+        // in tests
+        assert_eq!(
+            preview
+                .stake_rewards_by_partition
+                .iter()
+                .flatten()
+                .filter(|(_, reward_info)| reward_info.reward_type == RewardType::Staking)
+                .count(),
+            expected_num_delegations
+        );
+        assert!(preview.num_partitions() > 0);
+        assert!(preview.total_rewards() > 0);
+        assert!(preview.total_rewards() <= preview.validator_rewards);
+
+        // Nothing was paid out
+        assert_eq!(bank.capitalization(), capitalization);
+        assert_eq!(bank.get_account(&vote_pubkeys[0]).unwrap(), vote_account);
+        assert_eq!(bank.epoch_reward_status, EpochRewardStatus::Inactive);
+
+        let serialized = bincode::serialize(&preview).unwrap();
+        assert_eq!(
+            bincode::deserialize::<EpochRewardsPreview>(&serialized).unwrap(),
+            preview
+        );
+    }
+
+    #[test]
+    fn test_calculate_rewards_preview_partitioned_vote_rewards() {
+        let expected_num_delegations = 4;
+        let (mut bank, vote_pubkeys, _stake_pubkeys) = create_reward_bank(expected_num_delegations);
+        bank.deactivate_feature(&feature_set::partition_vote_rewards::id());
+        let preview = bank.calculate_rewards_preview(1);
+        assert_eq!(preview.vote_rewards.len(), expected_num_delegations);
+
+        // vote rewards move into the partitions of the stake rewards
+        bank.activate_feature(&feature_set::partition_vote_rewards::id());
+        let partitioned = bank.calculate_rewards_preview(1);
+        assert!(partitioned.vote_rewards.is_empty());
+        assert_eq!(
+            partitioned.total_vote_rewards_lamports,
+            preview.total_vote_rewards_lamports
+        );
+        assert_eq!(
+            partitioned.total_stake_rewards_lamports,
+            preview.total_stake_rewards_lamports
+        );
+        assert_eq!(partitioned.total_rewards(), preview.total_rewards());
+        let partitioned_vote_pubkeys = partitioned
+            .stake_rewards_by_partition
+            .iter()
+            .flatten()
+            .filter(|(_, reward_info)| reward_info.reward_type == RewardType::Voting)
+            .map(|(vote_pubkey, _)| *vote_pubkey)
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            partitioned_vote_pubkeys,
+            vote_pubkeys.into_iter().collect::<HashSet<_>>()
+        );
+    }
+
     #[test]
     fn test_rewards_point_calculation() {
         solana_logger::setup();
@@ -1,12 +1,26 @@
+#[cfg(test)]
+use crate::bank::fault_injection::{self, FaultPoint};
 use {
-    super::{Bank, EpochRewardStatus},
-    crate::bank::metrics::{report_partitioned_reward_metrics, RewardsStoreMetrics},
+    super::{Bank, EpochRewardStatus, RewardDistributionMetadata},
+    crate::bank::{
+        metrics::{report_partitioned_reward_metrics, RewardsStoreMetrics},
+        staged_writes::{StagedWrites, StagedWritesError},
+    },
     solana_accounts_db::stake_rewards::StakeReward,
-    solana_measure::measure_us,
-    solana_sdk::account::ReadableAccount,
+    solana_measure::{measure::Measure, measure_us},
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        pubkey::Pubkey,
+        reward_type::RewardType,
+    },
     std::sync::atomic::Ordering::Relaxed,
 };
 
+/// true if `reward` is a vote reward distributed along with the stake rewards
+fn is_vote_reward(reward: &StakeReward) -> bool {
+    reward.stake_reward_info.reward_type == RewardType::Voting
+}
+
 impl Bank {
     /// Process reward distribution for the block if it is inside reward interval.
     pub(in crate::bank) fn distribute_partitioned_epoch_rewards(&mut self) {
@@ -25,10 +39,11 @@ impl Bank {
 
         if height >= credit_start && height < credit_end_exclusive {
             let partition_index = height - credit_start;
-            self.distribute_epoch_rewards_in_partition(
+            let metadata = self.distribute_epoch_rewards_in_partition(
                 &status.stake_rewards_by_partition,
                 partition_index,
             );
+            self.reward_distribution_metadata = Some(metadata);
         }
 
         if height.saturating_add(1) >= credit_end_exclusive {
@@ -51,11 +66,13 @@ impl Bank {
 
     /// Process reward credits for a partition of rewards
     /// Store the rewards to AccountsDB, update reward history record and total capitalization.
+    /// Return the record of the distribution for the block metadata.
     fn distribute_epoch_rewards_in_partition(
         &self,
         all_stake_rewards: &[Vec<StakeReward>],
         partition_index: u64,
-    ) {
+    ) -> RewardDistributionMetadata {
+        let mut measure = Measure::start("distribute_epoch_rewards_in_partition");
         let pre_capitalization = self.capitalization();
         let this_partition_stake_rewards = &all_stake_rewards[partition_index as usize];
 
@@ -83,6 +100,15 @@ impl Bank {
         };
 
         report_partitioned_reward_metrics(self, metrics);
+        measure.stop();
+
+        RewardDistributionMetadata {
+            partition_index,
+            num_partitions: all_stake_rewards.len() as u64,
+            accounts_credited: this_partition_stake_rewards.len(),
+            distributed_lamports: total_rewards_in_lamports,
+            elapsed_us: measure.as_us(),
+        }
     }
 
     /// insert non-zero stake rewards to self.rewards
@@ -94,41 +120,97 @@ impl Bank {
         stake_rewards
             .iter()
             .filter(|x| x.get_stake_reward() > 0)
-            .for_each(|x| rewards.push((x.stake_pubkey, x.stake_reward_info)));
+            .for_each(|x| {
+                let mut reward_info = x.stake_reward_info;
+                if is_vote_reward(x) {
+                    // the balance of the vote account may have changed since the calculation
+                    let Some(vote_account) = self.current_vote_account(&x.stake_pubkey) else {
+                        // the reward was burned, see `credit_vote_reward()`
+                        return;
+                    };
+                    reward_info.post_balance = vote_account.lamports();
+                }
+                rewards.push((x.stake_pubkey, reward_info))
+            });
         rewards.len().saturating_sub(initial_len)
     }
 
+    /// The account at `vote_pubkey`, if it is still a vote account
+    fn current_vote_account(&self, vote_pubkey: &Pubkey) -> Option<AccountSharedData> {
+        self.get_account_with_fixed_root(vote_pubkey)
+            .filter(|account| solana_vote_program::check_id(account.owner()))
+    }
+
+    /// The current vote account of a partitioned vote reward, credited with the reward
+    ///
+    /// Returns `None` if the vote account was closed since the calculation. Its reward is burned
+    /// along with the other undistributed rewards left in the EpochRewards sysvar: crediting it
+    /// would create an account owned by another program holding the reward.
+    fn credit_vote_reward(&self, vote_reward: &StakeReward) -> Option<AccountSharedData> {
+        let mut vote_account = self.current_vote_account(&vote_reward.stake_pubkey)?;
+        vote_account
+            .saturating_add_lamports(u64::try_from(vote_reward.get_stake_reward()).unwrap());
+        Some(vote_account)
+    }
+
     /// store stake rewards in partition
     /// return the sum of all the stored rewards
     ///
     /// Note: even if staker's reward is 0, the stake account still needs to be stored because
     /// credits observed has changed
+    ///
+    /// Vote rewards are credited to the current vote account instead of storing the account as of
+    /// the calculation, since votes keep changing vote accounts during the distribution. The
+    /// rewards of vote accounts closed in the meantime are burned and not part of the sum.
     fn store_stake_accounts_in_partition(&self, stake_rewards: &[StakeReward]) -> u64 {
-        // Verify that stake account `lamports + reward_amount` matches what we have in the
-        // rewarded account. This code will have a performance hit - an extra load and compare of
-        // the stake accounts. This is for debugging. Once we are confident, we can disable the
-        // check.
-        const VERIFY_REWARD_LAMPORT: bool = true;
+        let mut staged_writes = StagedWrites::with_capacity(stake_rewards.len());
+        let mut burned_vote_rewards = 0;
+        for stake_reward in stake_rewards {
+            let account = if is_vote_reward(stake_reward) {
+                let Some(vote_account) = self.credit_vote_reward(stake_reward) else {
+                    burned_vote_rewards += stake_reward.stake_reward_info.lamports;
+                    continue;
+                };
+                vote_account
+            } else {
+                stake_reward.stake_account.clone()
+            };
+            staged_writes.stage(stake_reward.stake_pubkey, account);
+        }
 
-        if VERIFY_REWARD_LAMPORT {
-            for r in stake_rewards {
+        // Verify that stake account `lamports + reward_amount` matches what we have in the
+        // rewarded account, then store all of them or none. This code will have a performance
+        // hit - an extra load and compare of the stake accounts.
+        let verify_reward_lamports = |_staged_writes: &StagedWrites| {
+            #[cfg(test)]
+            fault_injection::trigger(FaultPoint::RewardDistributionCommit)?;
+            for r in stake_rewards.iter().filter(|r| !is_vote_reward(r)) {
                 let stake_pubkey = r.stake_pubkey;
                 let reward_amount = r.get_stake_reward();
-                let post_stake_account = &r.stake_account;
+                let post_lamport = r.stake_account.lamports();
                 if let Some(curr_stake_account) = self.get_account_with_fixed_root(&stake_pubkey) {
-                    let pre_lamport = curr_stake_account.lamports();
-                    let post_lamport = post_stake_account.lamports();
-                    assert_eq!(pre_lamport + u64::try_from(reward_amount).unwrap(), post_lamport,
-                               "stake account balance has changed since the reward calculation! account: {stake_pubkey}, pre balance: {pre_lamport}, post balance: {post_lamport}, rewards: {reward_amount}");
+                    let expected =
+                        curr_stake_account.lamports() + u64::try_from(reward_amount).unwrap();
+                    if expected != post_lamport {
+                        return Err(StagedWritesError::AccountLamportsMismatch {
+                            pubkey: stake_pubkey,
+                            expected,
+                            actual: post_lamport,
+                        });
+                    }
                 }
             }
+            Ok(())
+        };
+        if let Err(err) = self.commit_staged_writes(staged_writes, verify_reward_lamports) {
+            panic!("stake account balance has changed since the reward calculation! {err}");
         }
 
-        self.store_accounts((self.slot(), stake_rewards));
-        stake_rewards
+        (stake_rewards
             .iter()
             .map(|stake_reward| stake_reward.stake_reward_info.lamports)
-            .sum::<i64>() as u64
+            .sum::<i64>()
+            - burned_vote_rewards) as u64
     }
 }
 
@@ -143,7 +225,7 @@ mod tests {
         rand::Rng,
         solana_sdk::{
             account::from_account, epoch_schedule::EpochSchedule, feature_set, hash::Hash,
-            native_token::LAMPORTS_PER_SOL, sysvar,
+            native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, reward_info::RewardInfo, sysvar,
         },
     };
 
@@ -248,6 +330,40 @@ mod tests {
         assert_eq!(pre_cap, post_cap);
     }
 
+    #[test]
+    fn test_reward_distribution_metadata() {
+        let (mut genesis_config, _mint_keypair) =
+            create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
+        genesis_config.epoch_schedule = EpochSchedule::custom(432000, 432000, false);
+        let mut bank = Bank::new_for_tests(&genesis_config);
+        bank.activate_feature(&feature_set::enable_partitioned_epoch_reward::id());
+        bank.create_epoch_rewards_sysvar(1_000_000_000, 0, 42);
+
+        let mut stake_rewards = (0..10)
+            .map(|_| StakeReward::new_random())
+            .collect::<Vec<_>>();
+        for stake_reward in &mut stake_rewards {
+            stake_reward.credit(100);
+        }
+        let stake_rewards = hash_rewards_into_partitions(stake_rewards, &Hash::new(&[1; 32]), 2);
+        bank.set_epoch_reward_status_active(stake_rewards.clone());
+
+        // No partition is distributed in the block calculating the rewards
+        bank.distribute_partitioned_epoch_rewards();
+        assert_eq!(bank.reward_distribution_metadata(), None);
+
+        bank.block_height += bank.get_reward_calculation_num_blocks();
+        bank.distribute_partitioned_epoch_rewards();
+        let metadata = bank.reward_distribution_metadata().unwrap();
+        assert_eq!(metadata.partition_index, 0);
+        assert_eq!(metadata.num_partitions, 2);
+        assert_eq!(metadata.accounts_credited, stake_rewards[0].len());
+        assert_eq!(
+            metadata.distributed_lamports,
+            100 * stake_rewards[0].len() as u64
+        );
+    }
+
     /// Test partitioned credits and reward history updates of epoch rewards do cover all the rewards
     /// slice.
     #[test]
@@ -381,6 +497,106 @@ mod tests {
         assert_eq!(expected_total, total_rewards_in_lamports);
     }
 
+    /// Test that vote rewards are credited to the vote account as of the distribution, which votes
+    /// may have changed since the calculation
+    #[test]
+    fn test_store_vote_rewards_in_partition() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
+        let bank = Bank::new_for_tests(&genesis_config);
+
+        let vote_pubkey = Pubkey::new_unique();
+        let calculated_vote_account = AccountSharedData::new(1_000, 8, &solana_vote_program::id());
+        let mut current_vote_account = calculated_vote_account.clone();
+        current_vote_account.set_data_from_slice(&[1; 8]);
+        current_vote_account.checked_add_lamports(500).unwrap();
+        bank.store_account(&vote_pubkey, &current_vote_account);
+
+        let mut vote_reward = StakeReward {
+            stake_pubkey: vote_pubkey,
+            stake_reward_info: RewardInfo {
+                reward_type: RewardType::Voting,
+                lamports: 0,
+                post_balance: calculated_vote_account.lamports(),
+                commission: Some(10),
+            },
+            stake_account: calculated_vote_account,
+        };
+        vote_reward.credit(100);
+        let stake_reward = StakeReward::new_random();
+        let expected_total = 100 + stake_reward.get_stake_reward() as u64;
+        let rewards = vec![vote_reward, stake_reward];
+
+        let total_rewards_in_lamports = bank.store_stake_accounts_in_partition(&rewards);
+        assert_eq!(total_rewards_in_lamports, expected_total);
+        let vote_account = bank.get_account(&vote_pubkey).unwrap();
+        assert_eq!(vote_account.lamports(), 1_600);
+        assert_eq!(vote_account.data(), &[1; 8]);
+        assert_eq!(
+            bank.get_account(&rewards[1].stake_pubkey),
+            Some(rewards[1].stake_account.clone())
+        );
+
+        // the reward history holds the balance of the vote account after the distribution
+        assert_eq!(bank.update_reward_history_in_partition(&rewards), 2);
+        assert_eq!(
+            bank.rewards.read().unwrap()[0],
+            (
+                vote_pubkey,
+                RewardInfo {
+                    reward_type: RewardType::Voting,
+                    lamports: 100,
+                    post_balance: 1_600,
+                    commission: Some(10),
+                }
+            )
+        );
+    }
+
+    /// Test that the rewards of vote accounts closed since the calculation are burned instead of
+    /// creating an account holding the reward
+    #[test]
+    fn test_store_vote_rewards_of_closed_vote_account() {
+        let (genesis_config, _mint_keypair) = create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
+        let bank = Bank::new_for_tests(&genesis_config);
+
+        let vote_pubkey = Pubkey::new_unique();
+        let calculated_vote_account = AccountSharedData::new(1_000, 8, &solana_vote_program::id());
+        let mut vote_reward = StakeReward {
+            stake_pubkey: vote_pubkey,
+            stake_reward_info: RewardInfo {
+                reward_type: RewardType::Voting,
+                lamports: 0,
+                post_balance: calculated_vote_account.lamports(),
+                commission: Some(10),
+            },
+            stake_account: calculated_vote_account,
+        };
+        vote_reward.credit(100);
+        let stake_reward = StakeReward::new_random();
+        let expected_total = stake_reward.get_stake_reward() as u64;
+        let rewards = vec![vote_reward, stake_reward];
+
+        let total_rewards_in_lamports = bank.store_stake_accounts_in_partition(&rewards);
+        assert_eq!(total_rewards_in_lamports, expected_total);
+        assert_eq!(bank.get_account(&vote_pubkey), None);
+        assert_eq!(
+            bank.get_account(&rewards[1].stake_pubkey),
+            Some(rewards[1].stake_account.clone())
+        );
+
+        // the burned reward is not part of the reward history
+        assert_eq!(bank.update_reward_history_in_partition(&rewards), 1);
+        assert_eq!(bank.rewards.read().unwrap()[0].0, rewards[1].stake_pubkey);
+
+        // neither is the reward of a vote account reused by another program
+        bank.store_account(
+            &vote_pubkey,
+            &AccountSharedData::new(1_000, 0, &Pubkey::default()),
+        );
+        assert_eq!(bank.store_stake_accounts_in_partition(&rewards[..1]), 0);
+        assert_eq!(bank.get_balance(&vote_pubkey), 1_000);
+    }
+
     #[test]
     fn test_store_stake_accounts_in_partition_empty() {
         let (genesis_config, _mint_keypair) = create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
@@ -393,4 +609,38 @@ mod tests {
         let total_rewards_in_lamports = bank.store_stake_accounts_in_partition(&stake_rewards);
         assert_eq!(expected_total, total_rewards_in_lamports);
     }
+
+    #[test]
+    fn test_reward_distribution_failure_isolation() {
+        let mut stake_rewards = (0..10)
+            .map(|_| StakeReward::new_random())
+            .collect::<Vec<_>>();
+        for stake_reward in &mut stake_rewards {
+            stake_reward.credit(100);
+        }
+        let mut pubkeys = stake_rewards
+            .iter()
+            .map(|stake_reward| stake_reward.stake_pubkey)
+            .collect::<Vec<_>>();
+        pubkeys.push(sysvar::epoch_rewards::id());
+        let stake_rewards = hash_rewards_into_partitions(stake_rewards, &Hash::new(&[1; 32]), 1);
+        let setup = || {
+            let (mut genesis_config, _mint_keypair) =
+                create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
+            genesis_config.epoch_schedule = EpochSchedule::custom(432000, 432000, false);
+            let mut bank = Bank::new_for_tests(&genesis_config);
+            bank.activate_feature(&feature_set::enable_partitioned_epoch_reward::id());
+            bank.create_epoch_rewards_sysvar(1_000_000_000, 0, 42);
+            bank
+        };
+
+        // A failure to store the rewards of the partition panics, without
+        // crediting any of them
+        fault_injection::check_failure_isolation(
+            FaultPoint::RewardDistributionCommit,
+            &pubkeys,
+            setup,
+            |bank| Ok::<_, ()>(bank.distribute_epoch_rewards_in_partition(&stake_rewards, 0)),
+        );
+    }
 }
@@ -0,0 +1,75 @@
+use {solana_sdk::inflation::Inflation, solana_stake_program::points::PointValue, std::fmt::Debug};
+
+/// Emission schedule used by the epoch rewards calculation.
+///
+/// The schedule of a cluster is implemented by `Inflation`, whose parameters
+/// come from genesis. Tests can install other curves with
+/// `Bank::set_inflation_curve` to exercise the partitioned distribution
+/// machinery with a different emission.
+pub trait InflationCurve: Debug + Send + Sync {
+    /// Fraction of the capitalization paid to validators per year, at `year`
+    /// years since inflation activation
+    fn validator_rate(&self, year: f64) -> f64;
+
+    /// Fraction of the capitalization paid to the foundation per year, at
+    /// `year` years since inflation activation
+    fn foundation_rate(&self, year: f64) -> f64;
+
+    /// Total lamports to be paid to validators for an epoch lasting
+    /// `epoch_duration_in_years`
+    fn validator_rewards(
+        &self,
+        validator_rate: f64,
+        capitalization: u64,
+        epoch_duration_in_years: f64,
+    ) -> u64 {
+        (validator_rate * capitalization as f64 * epoch_duration_in_years) as u64
+    }
+
+    /// Conversion rate between the stake points earned in an epoch and the
+    /// lamports they are paid out as
+    fn point_value(&self, validator_rewards: u64, total_points: u128) -> PointValue {
+        PointValue {
+            rewards: validator_rewards,
+            points: total_points,
+        }
+    }
+}
+
+impl InflationCurve for Inflation {
+    fn validator_rate(&self, year: f64) -> f64 {
+        self.validator(year)
+    }
+
+    fn foundation_rate(&self, year: f64) -> f64 {
+        self.foundation(year)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inflation_curve_matches_inflation() {
+        let inflation = Inflation::default();
+        for year in [0.0, 0.5, 1.0, 10.0] {
+            assert_eq!(
+                InflationCurve::validator_rate(&inflation, year),
+                inflation.validator(year)
+            );
+            assert_eq!(
+                InflationCurve::foundation_rate(&inflation, year),
+                inflation.foundation(year)
+            );
+        }
+        assert_eq!(inflation.validator_rewards(0.05, 1_000_000, 0.5), 25_000);
+        assert_eq!(
+            inflation.point_value(42, 7),
+            PointValue {
+                rewards: 42,
+                points: 7
+            }
+        );
+    }
+}
@@ -2,8 +2,12 @@ mod calculation;
 mod compare;
 mod distribution;
 mod epoch_rewards_hasher;
+mod inflation_curve;
 mod sysvar;
 
+pub(super) use calculation::BackgroundRewardsCalculation;
+pub use inflation_curve::InflationCurve;
+
 use {
     super::Bank,
     crate::{stake_account::StakeAccount, stake_history::StakeHistory},
@@ -11,8 +15,12 @@ use {
         partitioned_rewards::PartitionedEpochRewardsConfig, stake_rewards::StakeReward,
     },
     solana_sdk::{
-        account::AccountSharedData, clock::Slot, feature_set, pubkey::Pubkey,
-        reward_info::RewardInfo, stake::state::Delegation,
+        account::AccountSharedData,
+        clock::{Epoch, Slot},
+        feature_set,
+        pubkey::Pubkey,
+        reward_info::RewardInfo,
+        stake::state::Delegation,
     },
     solana_vote::vote_account::VoteAccounts,
     std::sync::Arc,
@@ -31,6 +39,7 @@ pub(crate) struct StartBlockHeightAndRewards {
     /// the block height of the slot at which rewards distribution began
     pub(crate) start_block_height: u64,
     /// calculated epoch rewards pending distribution, outer Vec is by partition (one partition per block)
+    /// includes the vote rewards if they are partitioned, see `Bank::is_partitioned_vote_rewards_enabled()`
     pub(crate) stake_rewards_by_partition: Arc<Vec<StakeRewards>>,
 }
 
@@ -47,7 +56,7 @@ pub(crate) enum EpochRewardStatus {
     Inactive,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(super) struct VoteRewardsAccounts {
     /// reward info for each vote account pubkey.
     /// This type is used by `update_reward_history()`
@@ -68,6 +77,7 @@ pub(super) struct EpochRewardCalculateParamInfo<'a> {
 /// Hold all results from calculating the rewards for partitioned distribution.
 /// This struct exists so we can have a function which does all the calculation with no
 /// side effects.
+#[derive(Clone)]
 pub(super) struct PartitionedRewardsCalculation {
     pub(super) vote_account_rewards: VoteRewardsAccounts,
     pub(super) stake_rewards_by_partition: StakeRewardCalculationPartitioned,
@@ -80,11 +90,15 @@ pub(super) struct PartitionedRewardsCalculation {
 }
 
 /// result of calculating the stake rewards at beginning of new epoch
+#[derive(Clone)]
 pub(super) struct StakeRewardCalculationPartitioned {
     /// each individual stake account to reward, grouped by partition
+    /// includes the vote accounts to reward if vote rewards are partitioned
     pub(super) stake_rewards_by_partition: Vec<StakeRewards>,
-    /// total lamports across all `stake_rewards`
+    /// total lamports across all stake rewards in `stake_rewards_by_partition`
     pub(super) total_stake_rewards_lamports: u64,
+    /// total lamports across all vote rewards in `stake_rewards_by_partition`
+    pub(super) total_vote_rewards_lamports: u64,
 }
 
 pub(super) struct CalculateRewardsAndDistributeVoteRewardsResult {
@@ -98,12 +112,78 @@ pub(super) struct CalculateRewardsAndDistributeVoteRewardsResult {
 
 pub(crate) type StakeRewards = Vec<StakeReward>;
 
+/// Result of `Bank::calculate_rewards_preview()`: the rewards an epoch
+/// boundary would pay out, in a form suitable for reporting
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochRewardsPreview {
+    /// the epoch the rewards are paid for
+    pub rewarded_epoch: Epoch,
+    /// capitalization the rewards are calculated from
+    pub capitalization: u64,
+    pub validator_rate: f64,
+    pub foundation_rate: f64,
+    pub epoch_duration_in_years: f64,
+    /// upper bound of the lamports paid to vote and stake accounts
+    pub validator_rewards: u64,
+    /// rewards paid to vote accounts in the first block of the epoch, empty
+    /// if vote rewards are partitioned
+    pub vote_rewards: Vec<(Pubkey, RewardInfo)>,
+    /// total lamports paid to vote accounts, whether partitioned or not
+    pub total_vote_rewards_lamports: u64,
+    /// rewards paid to stake accounts, and to vote accounts if vote rewards are partitioned,
+    /// outer Vec is by partition (one partition per block)
+    pub stake_rewards_by_partition: Vec<Vec<(Pubkey, RewardInfo)>>,
+    pub total_stake_rewards_lamports: u64,
+}
+
+impl EpochRewardsPreview {
+    /// total lamports minted by the rewards
+    pub fn total_rewards(&self) -> u64 {
+        self.total_vote_rewards_lamports
+            .saturating_add(self.total_stake_rewards_lamports)
+    }
+
+    /// number of blocks over which the stake rewards are distributed
+    pub fn num_partitions(&self) -> usize {
+        self.stake_rewards_by_partition.len()
+    }
+}
+
+/// Record of the rewards distributed in a block of the reward interval,
+/// see `Bank::reward_distribution_metadata()`
+#[derive(AbiExample, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardDistributionMetadata {
+    /// index of the partition distributed in the block
+    pub partition_index: u64,
+    /// number of partitions the rewards of the epoch are distributed in
+    pub num_partitions: u64,
+    /// number of accounts credited, including accounts with a zero reward
+    pub accounts_credited: usize,
+    /// lamports credited to the accounts
+    pub distributed_lamports: u64,
+    /// time spent distributing the partition
+    pub elapsed_us: u64,
+}
+
 impl Bank {
+    /// The rewards distributed by this bank, if its block is in the reward
+    /// interval and has distributed its partition
+    pub fn reward_distribution_metadata(&self) -> Option<RewardDistributionMetadata> {
+        self.reward_distribution_metadata
+    }
+
     pub(super) fn is_partitioned_rewards_feature_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::enable_partitioned_epoch_reward::id())
     }
 
+    /// true if vote rewards are distributed in partitions along with the stake rewards,
+    /// instead of in the first block of the epoch
+    pub(super) fn is_partitioned_vote_rewards_enabled(&self) -> bool {
+        self.feature_set
+            .is_active(&feature_set::partition_vote_rewards::id())
+    }
+
     pub(crate) fn set_epoch_reward_status_active(
         &mut self,
         stake_rewards_by_partition: Vec<StakeRewards>,
@@ -135,7 +215,8 @@ impl Bank {
             .reward_calculation_num_blocks
     }
 
-    /// Calculate the number of blocks required to distribute rewards to all stake accounts.
+    /// Calculate the number of blocks required to distribute rewards to all stake accounts,
+    /// and vote accounts if vote rewards are partitioned.
     pub(super) fn get_reward_distribution_num_blocks(&self, rewards: &StakeRewards) -> u64 {
         let total_stake_accounts = rewards.len();
         if self.epoch_schedule.warmup && self.epoch < self.first_normal_epoch() {
@@ -155,9 +236,12 @@ impl Bank {
         }
     }
 
-    /// Return `RewardInterval` enum for current bank
+    /// Return `RewardInterval` enum for current bank. The interval includes
+    /// the blocks waiting for a background rewards calculation.
     pub(super) fn get_reward_interval(&self) -> RewardInterval {
-        if matches!(self.epoch_reward_status, EpochRewardStatus::Active(_)) {
+        if matches!(self.epoch_reward_status, EpochRewardStatus::Active(_))
+            || self.background_rewards_calculation.is_some()
+        {
             RewardInterval::InsideInterval
         } else {
             RewardInterval::OutsideInterval
@@ -204,7 +288,7 @@ mod tests {
             accounts_index::AccountSecondaryIndexes,
             partitioned_rewards::TestPartitionedEpochRewards,
         },
-        solana_program_runtime::runtime_config::RuntimeConfig,
+        solana_program_runtime::{float_audit::find_float_usage, runtime_config::RuntimeConfig},
         solana_sdk::{
             epoch_schedule::EpochSchedule,
             native_token::LAMPORTS_PER_SOL,
@@ -476,14 +560,15 @@ mod tests {
         );
         genesis_config.epoch_schedule = EpochSchedule::custom(32, 32, false);
 
-        // Config stake reward distribution to be 50 per block
+        // Config stake reward distribution to be 100 per block, for the 100
+        // stake rewards and the 100 vote rewards distributed along with them
         // We will need two blocks for reward distribution. And we can assert that the expected bank
         // capital changes before/during/after reward distribution.
         let mut accounts_db_config: AccountsDbConfig = ACCOUNTS_DB_CONFIG_FOR_TESTING.clone();
         accounts_db_config.test_partitioned_epoch_rewards =
             TestPartitionedEpochRewards::PartitionedEpochRewardsConfigRewardBlocks {
                 reward_calculation_num_blocks: 1,
-                stake_account_stores_per_block: 50,
+                stake_account_stores_per_block: 100,
             };
 
         let bank0 = Bank::new_with_paths(
@@ -650,4 +735,24 @@ mod tests {
             previous_bank = bank;
         }
     }
+
+    #[test]
+    fn test_reward_partitioning_is_float_free() {
+        // The inflation rates in `inflation_curve` and `calculation` are
+        // floats, everything downstream of the calculated rewards is not
+        for (name, source) in [
+            (
+                "epoch_rewards_hasher.rs",
+                include_str!("epoch_rewards_hasher.rs"),
+            ),
+            ("distribution.rs", include_str!("distribution.rs")),
+            ("sysvar.rs", include_str!("sysvar.rs")),
+            (
+                "fee_distribution.rs",
+                include_str!("../fee_distribution.rs"),
+            ),
+        ] {
+            assert_eq!(find_float_usage(source), vec![], "floats in {name}");
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use {
     super::Bank,
+    crate::bank::accounts_data_size_audit::AccountsDataSizeSource,
     log::info,
     solana_sdk::{
         account::{
@@ -32,6 +33,7 @@ impl Bank {
         distribution_starting_block_height: u64,
     ) {
         assert!(self.is_partitioned_rewards_code_enabled());
+        let delta_off_chain_before = self.load_accounts_data_size_delta_off_chain();
 
         let epoch_rewards = sysvar::epoch_rewards::EpochRewards {
             total_rewards,
@@ -50,6 +52,10 @@ impl Bank {
             inherited_account_fields.0 = total_rewards - distributed_rewards;
             create_account(&epoch_rewards, inherited_account_fields)
         });
+        self.audit_accounts_data_size_delta(
+            AccountsDataSizeSource::EpochRewardsSysvar,
+            delta_off_chain_before,
+        );
 
         self.log_epoch_rewards_sysvar("create");
     }
@@ -60,6 +66,7 @@ impl Bank {
         distributed: u64,
     ) {
         assert!(self.is_partitioned_rewards_code_enabled());
+        let delta_off_chain_before = self.load_accounts_data_size_delta_off_chain();
 
         let mut epoch_rewards: sysvar::epoch_rewards::EpochRewards =
             from_account(&self.get_account(&sysvar::epoch_rewards::id()).unwrap()).unwrap();
@@ -74,6 +81,10 @@ impl Bank {
             inherited_account_fields.0 = lamports - distributed;
             create_account(&epoch_rewards, inherited_account_fields)
         });
+        self.audit_accounts_data_size_delta(
+            AccountsDataSizeSource::EpochRewardsSysvar,
+            delta_off_chain_before,
+        );
 
         self.log_epoch_rewards_sysvar("update");
     }
@@ -87,7 +98,12 @@ impl Bank {
                     self.slot()
                 );
                 self.log_epoch_rewards_sysvar("burn");
+                let delta_off_chain_before = self.load_accounts_data_size_delta_off_chain();
                 self.burn_and_purge_account(&sysvar::epoch_rewards::id(), account);
+                self.audit_accounts_data_size_delta(
+                    AccountsDataSizeSource::EpochRewardsSysvar,
+                    delta_off_chain_before,
+                );
             }
         }
     }
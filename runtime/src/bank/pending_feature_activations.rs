@@ -0,0 +1,273 @@
+//! Features whose activation is pending, for review ahead of the epoch
+//! boundary.
+//!
+//! A feature is activated by creating its account, owned by the feature
+//! program and without an activation slot. The runtime picks it up in the
+//! first block of the next epoch, along with the transitions of the builtins
+//! and precompiles gated on it. `Bank::pending_feature_activations()` lists
+//! these features, when they are projected to activate and which transitions
+//! they trigger, so that dashboards and review tooling don't have to
+//! reimplement the activation rules.
+
+use {
+    super::{
+        builtins::{BUILTINS, STATELESS_BUILTINS},
+        Bank,
+    },
+    solana_sdk::{
+        clock::{Epoch, Slot},
+        feature,
+        feature_set::FEATURE_NAMES,
+        precompiles::get_precompiles,
+        pubkey::Pubkey,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingFeatureActivation {
+    pub feature_id: Pubkey,
+    /// Description of the feature, if it is known to this version
+    pub description: Option<&'static str>,
+    /// Epoch in which the feature is projected to activate
+    pub activation_epoch: Epoch,
+    /// First slot of `activation_epoch`. The feature activates in the first
+    /// block at or after it.
+    pub activation_slot: Slot,
+    /// Transitions of builtins and precompiles the activation triggers
+    pub transitions: Vec<FeatureTransition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureTransition {
+    /// The builtin program is added, or the stateless builtin is registered
+    EnableBuiltin {
+        program_id: Pubkey,
+        name: &'static str,
+    },
+    /// The builtin program is retired, or the stateless builtin is
+    /// unregistered
+    DisableBuiltin {
+        program_id: Pubkey,
+        name: &'static str,
+    },
+    /// The builtin program, or the address reserved for a stateless
+    /// builtin, is replaced with the Core BPF program at `source_program_id`
+    MigrateBuiltinToCoreBpf {
+        program_id: Pubkey,
+        name: &'static str,
+        source_program_id: Pubkey,
+    },
+    /// The Core BPF program at the address of the builtin program is
+    /// replaced with the builtin again
+    MigrateCoreBpfToBuiltin {
+        program_id: Pubkey,
+        name: &'static str,
+    },
+    /// The precompile is added
+    EnablePrecompile { program_id: Pubkey },
+}
+
+impl FeatureTransition {
+    /// Transitions triggered by the activation of `feature_id`
+    fn triggered_by(feature_id: &Pubkey) -> Vec<Self> {
+        let mut transitions = vec![];
+        for builtin in BUILTINS {
+            if builtin.enable_feature_id.as_ref() == Some(feature_id) {
+                transitions.push(Self::EnableBuiltin {
+                    program_id: builtin.program_id,
+                    name: builtin.name,
+                });
+            }
+            if builtin.disable_feature_id.as_ref() == Some(feature_id) {
+                transitions.push(Self::DisableBuiltin {
+                    program_id: builtin.program_id,
+                    name: builtin.name,
+                });
+            }
+        }
+        for builtin in STATELESS_BUILTINS {
+            if builtin.enable_feature_id.as_ref() == Some(feature_id) {
+                transitions.push(Self::EnableBuiltin {
+                    program_id: builtin.program_id,
+                    name: builtin.name,
+                });
+            }
+            if builtin.disable_feature_id.as_ref() == Some(feature_id) {
+                transitions.push(Self::DisableBuiltin {
+                    program_id: builtin.program_id,
+                    name: builtin.name,
+                });
+            }
+        }
+        let migrations = BUILTINS
+            .iter()
+            .map(|builtin| {
+                (
+                    builtin.program_id,
+                    builtin.name,
+                    &builtin.core_bpf_migration_config,
+                )
+            })
+            .chain(STATELESS_BUILTINS.iter().map(|builtin| {
+                (
+                    builtin.program_id,
+                    builtin.name,
+                    &builtin.core_bpf_migration_config,
+                )
+            }));
+        for (program_id, name, config) in migrations {
+            if let Some(config) = config
+                .as_ref()
+                .filter(|config| config.feature_id == *feature_id)
+            {
+                transitions.push(Self::MigrateBuiltinToCoreBpf {
+                    program_id,
+                    name,
+                    source_program_id: config.source_program_id,
+                });
+            }
+        }
+        for builtin in BUILTINS {
+            if builtin
+                .core_bpf_to_builtin_migration_config
+                .as_ref()
+                .is_some_and(|config| config.feature_id == *feature_id)
+            {
+                transitions.push(Self::MigrateCoreBpfToBuiltin {
+                    program_id: builtin.program_id,
+                    name: builtin.name,
+                });
+            }
+        }
+        for precompile in get_precompiles() {
+            if precompile.feature.as_ref() == Some(feature_id) {
+                transitions.push(Self::EnablePrecompile {
+                    program_id: precompile.program_id,
+                });
+            }
+        }
+        transitions
+    }
+}
+
+impl Bank {
+    /// Features whose activation has been requested but not performed yet,
+    /// ordered by feature id. They are projected to activate at the start of
+    /// the next epoch.
+    pub fn pending_feature_activations(&self) -> Vec<PendingFeatureActivation> {
+        let activation_epoch = self.epoch().saturating_add(1);
+        let activation_slot = self
+            .epoch_schedule()
+            .get_first_slot_in_epoch(activation_epoch);
+        let mut pending = self
+            .feature_set
+            .inactive
+            .iter()
+            .filter(|feature_id| {
+                self.get_account_with_fixed_root(feature_id)
+                    .and_then(|account| feature::from_account(&account))
+                    .is_some_and(|feature| feature.activated_at.is_none())
+            })
+            .map(|feature_id| PendingFeatureActivation {
+                feature_id: *feature_id,
+                description: FEATURE_NAMES.get(feature_id).copied(),
+                activation_epoch,
+                activation_slot,
+                transitions: FeatureTransition::triggered_by(feature_id),
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|activation| activation.feature_id);
+        pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::genesis_utils::create_genesis_config,
+        solana_sdk::{
+            feature::Feature,
+            feature_set::{self, FeatureSet},
+        },
+        std::sync::Arc,
+    };
+
+    #[test]
+    fn test_pending_feature_activations() {
+        let mut bank = Bank::new_for_tests(&create_genesis_config(100_000).genesis_config);
+        let migration_config = BUILTINS[0].core_bpf_migration_config.as_ref().unwrap();
+        let feature_ids = [
+            feature_set::zk_token_sdk_enabled::id(),
+            feature_set::reject_callx_r10::id(),
+            migration_config.feature_id,
+        ];
+        let mut feature_set = FeatureSet::default();
+        feature_set.inactive.extend(feature_ids);
+        bank.feature_set = Arc::new(feature_set);
+        assert_eq!(bank.pending_feature_activations(), vec![]);
+
+        // Only features whose account requests an activation are pending
+        for feature_id in &feature_ids[..2] {
+            bank.store_account(
+                feature_id,
+                &feature::create_account(&Feature::default(), 42),
+            );
+        }
+        bank.store_account(
+            &feature_ids[2],
+            &feature::create_account(
+                &Feature {
+                    activated_at: Some(1_000),
+                },
+                42,
+            ),
+        );
+
+        let activation_epoch = bank.epoch() + 1;
+        let activation_slot = bank
+            .epoch_schedule()
+            .get_first_slot_in_epoch(activation_epoch);
+        let mut expected = vec![
+            PendingFeatureActivation {
+                feature_id: feature_ids[0],
+                description: FEATURE_NAMES.get(&feature_ids[0]).copied(),
+                activation_epoch,
+                activation_slot,
+                transitions: vec![FeatureTransition::EnableBuiltin {
+                    program_id: solana_zk_token_sdk::zk_token_proof_program::id(),
+                    name: "zk_token_proof_program",
+                }],
+            },
+            PendingFeatureActivation {
+                feature_id: feature_ids[1],
+                description: FEATURE_NAMES.get(&feature_ids[1]).copied(),
+                activation_epoch,
+                activation_slot,
+                transitions: vec![],
+            },
+        ];
+        expected.sort_by_key(|activation| activation.feature_id);
+        assert_eq!(bank.pending_feature_activations(), expected);
+
+        // Migrations of builtins are reported as well
+        bank.store_account(
+            &feature_ids[2],
+            &feature::create_account(&Feature::default(), 42),
+        );
+        let pending = bank.pending_feature_activations();
+        let migration = pending
+            .iter()
+            .find(|activation| activation.feature_id == feature_ids[2])
+            .unwrap();
+        assert_eq!(
+            migration.transitions,
+            vec![FeatureTransition::MigrateBuiltinToCoreBpf {
+                program_id: BUILTINS[0].program_id,
+                name: BUILTINS[0].name,
+                source_program_id: migration_config.source_program_id,
+            }]
+        );
+        assert_eq!(migration.description, None);
+    }
+}
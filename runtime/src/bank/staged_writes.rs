@@ -0,0 +1,235 @@
+//! Multi-account writes which are validated and stored as a unit.
+//!
+//! Epoch boundary tasks like Core BPF migrations and partitioned reward
+//! distribution replace several accounts at once. Storing them one at a time
+//! leaves the bank in an inconsistent state if a later step fails or panics.
+//! Instead, the writes are staged, validated against the invariants of the
+//! task, and stored with a single call, which is hinted to accounts-db as a
+//! bulk write. Dropping `StagedWrites` without committing them discards them.
+
+use {
+    super::Bank,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        pubkey::Pubkey,
+    },
+    std::collections::HashMap,
+    thiserror::Error,
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum StagedWritesError {
+    /// The staged accounts would not hold the expected amount of lamports
+    #[error("staged writes change the total lamports by {actual}, expected {expected}")]
+    LamportsDeltaMismatch { expected: i128, actual: i128 },
+    /// A staged account does not hold the expected amount of lamports
+    #[error("staged account {pubkey} holds {actual} lamports, expected {expected}")]
+    AccountLamportsMismatch {
+        pubkey: Pubkey,
+        expected: u64,
+        actual: u64,
+    },
+    /// A fault injected by a test
+    #[cfg(test)]
+    #[error(transparent)]
+    InjectedFault(#[from] super::fault_injection::InjectedFault),
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct StagedWrites {
+    writes: Vec<(Pubkey, AccountSharedData)>,
+    /// index of each staged pubkey in `writes`
+    indexes: HashMap<Pubkey, usize>,
+}
+
+impl StagedWrites {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            writes: Vec::with_capacity(capacity),
+            indexes: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// Stage `account` to be stored at `pubkey`, replacing a previously
+    /// staged write to the same address
+    pub(crate) fn stage(&mut self, pubkey: Pubkey, account: AccountSharedData) {
+        if let Some(index) = self.indexes.get(&pubkey) {
+            self.writes[*index].1 = account;
+        } else {
+            self.indexes.insert(pubkey, self.writes.len());
+            self.writes.push((pubkey, account));
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &(Pubkey, AccountSharedData)> {
+        self.writes.iter()
+    }
+}
+
+impl Bank {
+    /// Lamports the staged writes add to the accounts they replace, negative
+    /// if they remove lamports
+    pub(crate) fn staged_lamports_delta(&self, staged_writes: &StagedWrites) -> i128 {
+        staged_writes
+            .iter()
+            .map(|(pubkey, account)| {
+                let current_lamports = self
+                    .get_account_with_fixed_root(pubkey)
+                    .map(|account| account.lamports())
+                    .unwrap_or_default();
+                i128::from(account.lamports()) - i128::from(current_lamports)
+            })
+            .sum()
+    }
+
+    /// Check that the staged writes change the lamports held by the accounts
+    /// they replace by `expected_lamports_delta`
+    pub(crate) fn check_staged_lamports_delta(
+        &self,
+        staged_writes: &StagedWrites,
+        expected_lamports_delta: i128,
+    ) -> Result<(), StagedWritesError> {
+        let actual = self.staged_lamports_delta(staged_writes);
+        if actual != expected_lamports_delta {
+            return Err(StagedWritesError::LamportsDeltaMismatch {
+                expected: expected_lamports_delta,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the staged writes with `validate` and store all of them, or none
+    /// if the validation fails
+    pub(crate) fn commit_staged_writes(
+        &self,
+        staged_writes: StagedWrites,
+        validate: impl FnOnce(&StagedWrites) -> Result<(), StagedWritesError>,
+    ) -> Result<(), StagedWritesError> {
+        validate(&staged_writes)?;
+        let bytes = staged_writes
+            .iter()
+            .map(|(_, account)| account.data().len() as u64)
+            .sum();
+        self.rc
+            .accounts
+            .accounts_db
+            .hint_bulk_write(self.slot(), bytes);
+        let to_store = staged_writes
+            .writes
+            .iter()
+            .map(|(pubkey, account)| (pubkey, account))
+            .collect::<Vec<_>>();
+        self.store_accounts((self.slot(), &to_store[..]));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::bank::{
+            fault_injection::{self, FaultPoint},
+            tests::create_simple_test_bank,
+        },
+        std::panic::{self, AssertUnwindSafe},
+    };
+
+    #[test]
+    fn test_staged_writes() {
+        let bank = create_simple_test_bank(1_000_000);
+        let owner = Pubkey::new_unique();
+        let pubkey_a = Pubkey::new_unique();
+        let pubkey_b = Pubkey::new_unique();
+        let account_a = AccountSharedData::new(100, 0, &owner);
+        bank.store_account(&pubkey_a, &account_a);
+
+        // Move the lamports of a to b, staging b twice
+        let mut staged_writes = StagedWrites::default();
+        staged_writes.stage(pubkey_b, AccountSharedData::new(1, 0, &owner));
+        staged_writes.stage(pubkey_a, AccountSharedData::default());
+        staged_writes.stage(pubkey_b, AccountSharedData::new(100, 0, &owner));
+        assert_eq!(staged_writes.writes.len(), 2);
+        assert_eq!(bank.staged_lamports_delta(&staged_writes), 0);
+
+        // Nothing is stored if the validation fails
+        let mut invalid_writes = StagedWrites::default();
+        invalid_writes.stage(pubkey_a, AccountSharedData::default());
+        invalid_writes.stage(pubkey_b, AccountSharedData::new(101, 0, &owner));
+        assert_eq!(
+            bank.commit_staged_writes(invalid_writes, |staged_writes| {
+                bank.check_staged_lamports_delta(staged_writes, 0)
+            }),
+            Err(StagedWritesError::LamportsDeltaMismatch {
+                expected: 0,
+                actual: 1
+            })
+        );
+        assert_eq!(bank.get_account(&pubkey_a), Some(account_a.clone()));
+        assert_eq!(bank.get_account(&pubkey_b), None);
+
+        // Nothing is stored if the task panics before committing
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut staged_writes = StagedWrites::default();
+            staged_writes.stage(pubkey_a, AccountSharedData::default());
+            panic!("interrupted");
+        }));
+        assert!(result.is_err());
+        assert_eq!(bank.get_account(&pubkey_a), Some(account_a));
+
+        assert_eq!(
+            bank.commit_staged_writes(staged_writes, |staged_writes| {
+                bank.check_staged_lamports_delta(staged_writes, 0)
+            }),
+            Ok(())
+        );
+        assert_eq!(bank.get_account(&pubkey_a), None);
+        assert_eq!(bank.get_balance(&pubkey_b), 100);
+    }
+
+    #[test]
+    fn test_commit_staged_writes_failure_isolation() {
+        let owner = Pubkey::new_unique();
+        let pubkeys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let setup = || {
+            let bank = create_simple_test_bank(1_000_000);
+            bank.store_account(&pubkeys[0], &AccountSharedData::new(300, 0, &owner));
+            bank
+        };
+        // Move the lamports of the first account to the others, failing at
+        // `point` during the validation like the epoch boundary tasks do
+        let task = |point| {
+            move |bank: &mut Bank| {
+                let mut staged_writes = StagedWrites::with_capacity(pubkeys.len());
+                staged_writes.stage(pubkeys[0], AccountSharedData::default());
+                staged_writes.stage(pubkeys[1], AccountSharedData::new(100, 0, &owner));
+                staged_writes.stage(pubkeys[2], AccountSharedData::new(200, 0, &owner));
+                bank.commit_staged_writes(staged_writes, |staged_writes| {
+                    fault_injection::trigger(point)?;
+                    bank.check_staged_lamports_delta(staged_writes, 0)
+                })
+            }
+        };
+
+        for point in [
+            FaultPoint::CoreBpfMigrationCommit,
+            FaultPoint::RewardDistributionCommit,
+        ] {
+            // None of the writes is visible after a fault
+            fault_injection::check_failure_isolation(point, &pubkeys, setup, task(point));
+
+            // All of them are without one
+            let mut bank = setup();
+            assert_eq!(task(point)(&mut bank), Ok(()));
+            assert_eq!(
+                pubkeys.map(|pubkey| bank.get_balance(&pubkey)),
+                [0, 100, 200]
+            );
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Adapter running the transactions of a bank through a standalone
+//! `TransactionBatchProcessor`, for tests.
+//!
+//! The processor of a bank shares the program cache of the bank forks and is
+//! driven by the bank. Embedders of the SVM instead run a processor of their
+//! own, with their own program cache, against an account store. The adapter
+//! sets up such a processor from a bank: the bank provides the accounts, the
+//! feature set, the sysvars and the checks of the transactions, and the
+//! processor gets a program cache holding only the builtins of the bank,
+//! including those the tests added to it. Test
+//! suites written against a bank can run their transactions through both
+//! paths and compare the outcomes, so the two stay in parity as the SVM is
+//! extracted from the bank.
+//!
+//! Nothing executed through the adapter is committed to the bank.
+
+use {
+    super::Bank,
+    crate::transaction_batch::TransactionBatch,
+    solana_program_runtime::{
+        loaded_programs::{BlockRelation, ForkGraph, LoadedProgramType, ProgramCache},
+        timings::ExecuteTimings,
+    },
+    solana_sdk::clock::Slot,
+    solana_svm::{
+        transaction_error_metrics::TransactionErrorMetrics,
+        transaction_processor::{
+            ExecutionRecordingConfig, LoadAndExecuteSanitizedTransactionsOutput,
+            TransactionBatchProcessor,
+        },
+    },
+    std::{
+        cmp::Ordering,
+        sync::{Arc, RwLock},
+    },
+};
+
+/// The fork graph of a standalone processor, which only ever sees the slots
+/// of a single fork
+#[derive(Debug, Default)]
+pub struct LinearForkGraph;
+
+impl ForkGraph for LinearForkGraph {
+    fn relationship(&self, a: Slot, b: Slot) -> BlockRelation {
+        match a.cmp(&b) {
+            Ordering::Less => BlockRelation::Ancestor,
+            Ordering::Equal => BlockRelation::Equal,
+            Ordering::Greater => BlockRelation::Descendant,
+        }
+    }
+}
+
+pub struct StandaloneProcessorAdapter<'a> {
+    bank: &'a Bank,
+    processor: TransactionBatchProcessor<LinearForkGraph>,
+}
+
+impl<'a> StandaloneProcessorAdapter<'a> {
+    pub fn new(bank: &'a Bank) -> Self {
+        let bank_program_cache = bank.transaction_processor.program_cache.read().unwrap();
+        let mut program_cache =
+            ProgramCache::new_with_config(bank.slot(), bank.epoch(), &bank.runtime_config);
+        program_cache.environments = bank_program_cache.environments.clone();
+        program_cache.fork_graph = Some(Arc::new(RwLock::new(LinearForkGraph)));
+        // The builtins the bank sees on its fork. Stateless builtins have no
+        // entrypoint, and fail to load from their account like they do in the
+        // bank.
+        let is_on_fork = |slot: Slot| {
+            slot <= bank.slot()
+                && bank_program_cache
+                    .fork_graph
+                    .as_ref()
+                    .map_or(true, |fork_graph| {
+                        matches!(
+                            fork_graph.read().unwrap().relationship(slot, bank.slot()),
+                            BlockRelation::Ancestor | BlockRelation::Equal
+                        )
+                    })
+        };
+        for program_id in &bank.builtin_program_ids {
+            let builtin = bank_program_cache
+                .get_slot_versions_for_tests(program_id)
+                .iter()
+                .rev()
+                .find(|entry| {
+                    matches!(entry.program, LoadedProgramType::Builtin(_))
+                        && is_on_fork(entry.deployment_slot)
+                });
+            if let Some(builtin) = builtin {
+                program_cache.assign_program(*program_id, builtin.clone());
+            }
+        }
+        drop(bank_program_cache);
+
+        let processor = TransactionBatchProcessor::new(
+            bank.slot(),
+            bank.epoch(),
+            bank.epoch_schedule().clone(),
+            bank.fee_structure.clone(),
+            bank.runtime_config.clone(),
+            Arc::new(RwLock::new(program_cache)),
+        );
+        processor.fill_missing_sysvar_cache_entries(bank);
+        Self { bank, processor }
+    }
+
+    pub fn processor(&self) -> &TransactionBatchProcessor<LinearForkGraph> {
+        &self.processor
+    }
+
+    /// Check the transactions of `batch` against the bank and execute them
+    /// with the standalone processor, like `Bank::load_and_execute_transactions()`
+    /// does with the processor of the bank
+    pub fn load_and_execute_transactions(
+        &self,
+        batch: &TransactionBatch,
+        max_age: usize,
+        recording_config: ExecutionRecordingConfig,
+        timings: &mut ExecuteTimings,
+    ) -> LoadAndExecuteSanitizedTransactionsOutput {
+        let sanitized_txs = batch.sanitized_transactions();
+        let mut error_counters = TransactionErrorMetrics::default();
+        let mut check_results = self.bank.check_transactions(
+            sanitized_txs,
+            batch.lock_results(),
+            max_age,
+            &mut error_counters,
+        );
+        self.processor.load_and_execute_sanitized_transactions(
+            self.bank,
+            sanitized_txs,
+            &mut check_results,
+            &mut error_counters,
+            recording_config,
+            timings,
+            None,
+            self.bank.builtin_program_ids.iter(),
+            self.bank.runtime_config.log_messages_bytes_limit,
+            false,
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::genesis_utils::{create_genesis_config, GenesisConfigInfo},
+        solana_program_runtime::declare_process_instruction,
+        solana_sdk::{
+            clock::MAX_PROCESSING_AGE,
+            hash::Hash,
+            instruction::Instruction,
+            pubkey::Pubkey,
+            signature::{Keypair, Signer},
+            system_transaction,
+            transaction::{self, Transaction, TransactionError},
+        },
+        solana_svm::transaction_results::TransactionExecutionResult,
+    };
+
+    /// Status, executed units and logs of a transaction
+    type ComparableResult = (transaction::Result<()>, Option<u64>, Option<Vec<String>>);
+
+    /// The parts of the execution results which do not depend on the
+    /// processor
+    fn comparable_results(
+        execution_results: &[TransactionExecutionResult],
+    ) -> Vec<ComparableResult> {
+        execution_results
+            .iter()
+            .map(|result| {
+                (
+                    result.flattened_result(),
+                    result.details().map(|details| details.executed_units),
+                    result
+                        .details()
+                        .and_then(|details| details.log_messages.clone()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_standalone_processor_parity() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let (bank, _bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+        let payer = Keypair::new();
+        bank.transfer(100_000, &mint_keypair, &payer.pubkey())
+            .unwrap();
+        let transactions = vec![
+            system_transaction::transfer(
+                &mint_keypair,
+                &Pubkey::new_unique(),
+                1_000,
+                bank.last_blockhash(),
+            ),
+            // Fails during execution
+            system_transaction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                10_000_000,
+                bank.last_blockhash(),
+            ),
+            // Fails the checks of the bank
+            system_transaction::transfer(
+                &Keypair::new(),
+                &Pubkey::new_unique(),
+                1,
+                Hash::new_unique(),
+            ),
+        ];
+        let batch = bank.prepare_batch_for_tests(transactions);
+
+        let bank_output = bank.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+            None,
+            None,
+            false,
+            false,
+        );
+        let adapter = StandaloneProcessorAdapter::new(&bank);
+        let standalone_output = adapter.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+        );
+
+        assert_eq!(
+            standalone_output.loaded_transactions,
+            bank_output.loaded_transactions
+        );
+        assert_eq!(
+            comparable_results(&standalone_output.execution_results),
+            comparable_results(&bank_output.execution_results)
+        );
+        assert!(standalone_output.execution_results[0].was_executed_successfully());
+        assert!(matches!(
+            standalone_output.execution_results[1].flattened_result(),
+            Err(TransactionError::InstructionError(..))
+        ));
+        assert_eq!(
+            standalone_output.execution_results[2].flattened_result(),
+            Err(TransactionError::BlockhashNotFound)
+        );
+    }
+
+    #[test]
+    fn test_standalone_processor_custom_builtin() {
+        declare_process_instruction!(MockBuiltin, 1, |_invoke_context| { Ok(()) });
+
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(1_000_000);
+        let program_id = Pubkey::new_unique();
+        let (bank, _bank_forks) =
+            Bank::new_with_mockup_builtin_for_tests(&genesis_config, program_id, MockBuiltin::vm);
+        let transaction = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(program_id, &[], vec![])],
+            Some(&mint_keypair.pubkey()),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        let batch = bank.prepare_batch_for_tests(vec![transaction]);
+
+        let bank_output = bank.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+            None,
+            None,
+            false,
+            false,
+        );
+        let adapter = StandaloneProcessorAdapter::new(&bank);
+        let standalone_output = adapter.load_and_execute_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+        );
+
+        // The builtin added by the test executes like in the bank
+        assert!(standalone_output.execution_results[0].was_executed_successfully());
+        assert_eq!(
+            comparable_results(&standalone_output.execution_results),
+            comparable_results(&bank_output.execution_results)
+        );
+    }
+}
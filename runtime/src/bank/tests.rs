@@ -105,7 +105,10 @@ use {
         transaction_context::TransactionAccount,
     },
     solana_stake_program::stake_state::{self, StakeStateV2},
-    solana_svm::transaction_results::DurableNonceFee,
+    solana_svm::{
+        transaction_processing_callback::{self, TransactionProcessingCallback},
+        transaction_results::{AccountLifecycle, DurableNonceFee},
+    },
     solana_vote_program::{
         vote_instruction,
         vote_state::{
@@ -244,6 +247,14 @@ fn new_execution_result(
             return_data: None,
             executed_units: 0,
             accounts_data_len_delta: 0,
+            accounts_data_growth: 0,
+            heap_allocation_stats: None,
+            error_location: None,
+            capability_manifest: None,
+            decoded_instructions: None,
+            compute_budget_report: None,
+            account_lifecycle: AccountLifecycle::default(),
+            signature_policy_decision: None,
         },
         programs_modified_by_tx: Box::<LoadedProgramsForTxBatch>::default(),
     }
@@ -2004,6 +2015,50 @@ where
     assert!(bank1.calculate_and_verify_capitalization(true));
 }
 
+#[test]
+fn test_bank_custom_inflation_curve() {
+    #[derive(Debug)]
+    struct FixedRate;
+    impl InflationCurve for FixedRate {
+        fn validator_rate(&self, _year: f64) -> f64 {
+            0.1
+        }
+        fn foundation_rate(&self, _year: f64) -> f64 {
+            0.0
+        }
+    }
+
+    let (genesis_config, _mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+    let mut bank = Bank::new_for_tests(&genesis_config);
+    let capitalization = 1_000_000_000;
+    let default_rewards = bank.calculate_previous_epoch_inflation_rewards(capitalization, 0);
+
+    bank.set_inflation_curve(Arc::new(FixedRate));
+    let PrevEpochInflationRewards {
+        validator_rewards,
+        prev_epoch_duration_in_years,
+        validator_rate,
+        foundation_rate,
+    } = bank.calculate_previous_epoch_inflation_rewards(capitalization, 0);
+    assert_eq!(validator_rate, 0.1);
+    assert_eq!(foundation_rate, 0.0);
+    assert_ne!(validator_rate, default_rewards.validator_rate);
+    assert_eq!(
+        validator_rewards,
+        (0.1 * capitalization as f64 * prev_epoch_duration_in_years) as u64
+    );
+
+    // Child banks keep using the installed curve
+    let bank = Arc::new(bank);
+    let child = Bank::new_from_parent(bank.clone(), &Pubkey::default(), 1);
+    assert_eq!(
+        child
+            .calculate_previous_epoch_inflation_rewards(capitalization, 0)
+            .validator_rate,
+        0.1
+    );
+}
+
 fn do_test_bank_update_rewards_determinism() -> u64 {
     // create a bank that ticks really slowly...
     let bank = Arc::new(Bank::new_for_tests(&GenesisConfig {
@@ -4608,6 +4663,49 @@ fn test_add_builtin() {
     );
 }
 
+#[test]
+fn test_override_builtin_entrypoint() {
+    let (genesis_config, mint_keypair) = create_genesis_config_no_tx_fee_no_rent(500);
+    let mut bank = Bank::new_for_tests(&genesis_config);
+
+    declare_process_instruction!(MockBuiltin, 1, |_invoke_context| {
+        Err(InstructionError::Custom(42))
+    });
+
+    assert!(bank.builtin_entrypoint_overrides().is_empty());
+    bank.override_builtin_entrypoint(system_program::id(), MockBuiltin::vm);
+    assert!(bank
+        .builtin_entrypoint_overrides()
+        .contains(&system_program::id()));
+
+    let bank = bank.wrap_with_bank_forks_for_tests().0;
+    let recipient = Pubkey::new_unique();
+    assert_eq!(
+        bank.transfer(1, &mint_keypair, &recipient),
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(42)
+        ))
+    );
+
+    // The override is inherited by child banks
+    let child = Bank::new_from_parent(bank, &Pubkey::default(), 1);
+    assert!(child
+        .builtin_entrypoint_overrides()
+        .contains(&system_program::id()));
+}
+
+#[test]
+#[should_panic(expected = "which is not a builtin program")]
+fn test_override_builtin_entrypoint_not_builtin() {
+    let (genesis_config, _mint_keypair) = create_genesis_config_no_tx_fee_no_rent(500);
+    let mut bank = Bank::new_for_tests(&genesis_config);
+
+    declare_process_instruction!(MockBuiltin, 1, |_invoke_context| { Ok(()) });
+
+    bank.override_builtin_entrypoint(Pubkey::new_unique(), MockBuiltin::vm);
+}
+
 #[test]
 fn test_add_duplicate_static_program() {
     let GenesisConfigInfo {
@@ -9202,6 +9300,11 @@ fn test_tx_log_order() {
                 enable_cpi_recording: false,
                 enable_log_recording: true,
                 enable_return_data_recording: false,
+                enable_heap_stats_recording: false,
+                enable_error_location_recording: false,
+                enable_capability_recording: false,
+                enable_instruction_decoding: false,
+                enable_compute_budget_report: false,
             },
             &mut ExecuteTimings::default(),
             None,
@@ -9312,6 +9415,11 @@ fn test_tx_return_data() {
                     enable_cpi_recording: false,
                     enable_log_recording: false,
                     enable_return_data_recording: true,
+                    enable_heap_stats_recording: false,
+                    enable_error_location_recording: false,
+                    enable_capability_recording: false,
+                    enable_instruction_decoding: false,
+                    enable_compute_budget_report: false,
                 },
                 &mut ExecuteTimings::default(),
                 None,
@@ -12991,3 +13099,82 @@ fn test_deploy_last_epoch_slot() {
     let result_with_feature_enabled = bank.process_transaction(&transaction);
     assert_eq!(result_with_feature_enabled, Ok(()));
 }
+
+#[test]
+fn test_accounts_lt_hash() {
+    let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+    let bank = Bank::new_for_tests(&genesis_config);
+    assert_eq!(bank.accounts_lt_hash(), None);
+    assert_eq!(bank.accounts_lt_hash_checksum(), None);
+
+    let accounts_db_config = AccountsDbConfig {
+        enable_accounts_lt_hash: true,
+        ..ACCOUNTS_DB_CONFIG_FOR_TESTING
+    };
+    let (bank0, bank_forks) = Bank::new_with_paths(
+        &genesis_config,
+        Arc::new(RuntimeConfig::default()),
+        Vec::default(),
+        None,
+        None,
+        AccountSecondaryIndexes::default(),
+        AccountShrinkThreshold::default(),
+        false,
+        Some(accounts_db_config),
+        None,
+        Some(Pubkey::new_unique()),
+        Arc::new(AtomicBool::new(false)),
+    )
+    .wrap_with_bank_forks_for_tests();
+    bank0.freeze();
+    assert_eq!(
+        bank0.accounts_lt_hash(),
+        Some(bank0.calculate_accounts_lt_hash())
+    );
+
+    let bank1 =
+        new_bank_from_parent_with_bank_forks(&bank_forks, bank0.clone(), &Pubkey::default(), 1);
+    let pubkey = solana_sdk::pubkey::new_rand();
+    bank1
+        .transfer(sol_to_lamports(0.5), &mint_keypair, &pubkey)
+        .unwrap();
+    bank1.freeze();
+
+    // The incrementally maintained hash matches the one from a full scan
+    assert_eq!(
+        bank1.accounts_lt_hash(),
+        Some(bank1.calculate_accounts_lt_hash())
+    );
+    assert_ne!(
+        bank1.accounts_lt_hash_checksum(),
+        bank0.accounts_lt_hash_checksum()
+    );
+    // Each slot keeps its own commitment
+    assert_eq!(
+        bank0.accounts_lt_hash(),
+        Some(bank0.calculate_accounts_lt_hash())
+    );
+}
+
+#[test]
+fn test_peek_account_meta() {
+    let (genesis_config, mint_keypair) = create_genesis_config(sol_to_lamports(1.));
+    let bank = Bank::new_for_tests(&genesis_config);
+    let pubkey = solana_sdk::pubkey::new_rand();
+    let owner = solana_sdk::pubkey::new_rand();
+    bank.store_account(&pubkey, &AccountSharedData::new(42, 7, &owner));
+
+    // The metadata matches the loaded account
+    for pubkey in [pubkey, mint_keypair.pubkey()] {
+        assert_eq!(
+            bank.peek_account_meta(&pubkey),
+            bank.get_account_shared_data(&pubkey)
+                .map(|account| transaction_processing_callback::AccountMeta::from(&account))
+        );
+    }
+    assert_eq!(bank.peek_account_meta(&pubkey).unwrap().data_len, 7);
+    assert_eq!(
+        bank.peek_account_meta(&solana_sdk::pubkey::new_rand()),
+        None
+    );
+}
@@ -147,6 +147,19 @@ pub fn add_bank_snapshot(
         )
         .map_err(|err| AddBankSnapshotError::WriteSnapshotVersionFile(err, version_path))?);
 
+        let builtins_summary_path =
+            bank_snapshot_dir.join(snapshot_utils::SNAPSHOT_BUILTINS_SUMMARY_FILENAME);
+        let builtins_summary = bank
+            .builtins_summary()
+            .encode()
+            .map_err(AddBankSnapshotError::SerializeBuiltinsSummary)?;
+        let (_, measure_write_builtins_summary_file) =
+            measure!(
+                fs::write(&builtins_summary_path, builtins_summary).map_err(|err| {
+                    AddBankSnapshotError::WriteBuiltinsSummaryFile(err, builtins_summary_path)
+                })?
+            );
+
         // Mark this directory complete so it can be used.  Check this flag first before selecting for deserialization.
         let state_complete_path =
             bank_snapshot_dir.join(snapshot_utils::SNAPSHOT_STATE_COMPLETE_FILENAME);
@@ -176,6 +189,11 @@ pub fn add_bank_snapshot(
                 measure_write_version_file.as_us(),
                 i64
             ),
+            (
+                "write_builtins_summary_file_us",
+                measure_write_builtins_summary_file.as_us(),
+                i64
+            ),
             (
                 "write_state_complete_file_us",
                 measure_write_state_complete_file.as_us(),
@@ -2066,6 +2084,100 @@ mod tests {
         assert!(hardlink_dirs.iter().all(|dir| fs::metadata(dir).is_err()));
     }
 
+    #[test]
+    fn test_bank_snapshot_builtins_summary() {
+        let genesis_config = GenesisConfig::default();
+        let bank = Bank::new_for_tests(&genesis_config);
+        bank.fill_bank_with_ticks_for_tests();
+        bank.squash();
+        bank.force_flush_accounts_cache();
+
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_storages = bank.get_snapshot_storages(None);
+        let slot_deltas = bank.status_cache.read().unwrap().root_slot_deltas();
+        let bank_snapshot_info = add_bank_snapshot(
+            &bank_snapshots_dir,
+            &bank,
+            &snapshot_storages,
+            SnapshotVersion::default(),
+            slot_deltas,
+        )
+        .unwrap();
+
+        let builtins_summary =
+            snapshot_utils::read_builtins_summary_file(&bank_snapshot_info.snapshot_dir).unwrap();
+        assert_eq!(builtins_summary, bank.builtins_summary());
+        assert_eq!(builtins_summary.unknown_builtin_program_ids(), vec![]);
+
+        fs::write(
+            bank_snapshot_info
+                .snapshot_dir
+                .join(snapshot_utils::SNAPSHOT_BUILTINS_SUMMARY_FILENAME),
+            [0xff],
+        )
+        .unwrap();
+        let err = snapshot_utils::read_builtins_summary_file(&bank_snapshot_info.snapshot_dir)
+            .unwrap_err();
+        assert!(err.to_string().starts_with("invalid builtins summary file"));
+    }
+
+    #[test]
+    fn test_snapshot_archive_builtins_summary() {
+        let genesis_config = GenesisConfig::default();
+        let original_bank = Bank::new_for_tests(&genesis_config);
+        original_bank.fill_bank_with_ticks_for_tests();
+
+        let (_tmp_dir, accounts_dir) = create_tmp_accounts_dir_for_tests();
+        let bank_snapshots_dir = tempfile::TempDir::new().unwrap();
+        let full_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let incremental_snapshot_archives_dir = tempfile::TempDir::new().unwrap();
+        let snapshot_archive_format = ArchiveFormat::TarZstd;
+
+        let snapshot_archive_info = bank_to_full_snapshot_archive(
+            &bank_snapshots_dir,
+            &original_bank,
+            None,
+            full_snapshot_archives_dir.path(),
+            incremental_snapshot_archives_dir.path(),
+            snapshot_archive_format,
+            snapshot_utils::DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+            snapshot_utils::DEFAULT_MAX_INCREMENTAL_SNAPSHOT_ARCHIVES_TO_RETAIN,
+        )
+        .unwrap();
+
+        // The summary is read from the archive without unpacking it
+        let builtins_summary = snapshot_utils::read_builtins_summary_from_archive(
+            snapshot_archive_info.path(),
+            snapshot_archive_format,
+        )
+        .unwrap();
+        assert_eq!(builtins_summary, original_bank.builtins_summary());
+
+        // And describes the bank restored from the archive
+        let (roundtrip_bank, _) = bank_from_snapshot_archives(
+            &[accounts_dir],
+            bank_snapshots_dir.path(),
+            &snapshot_archive_info,
+            None,
+            &genesis_config,
+            &RuntimeConfig::default(),
+            None,
+            None,
+            AccountSecondaryIndexes::default(),
+            None,
+            AccountShrinkThreshold::default(),
+            false,
+            false,
+            false,
+            false,
+            Some(ACCOUNTS_DB_CONFIG_FOR_TESTING),
+            None,
+            Arc::default(),
+        )
+        .unwrap();
+        assert_eq!(builtins_summary, roundtrip_bank.builtins_summary());
+    }
+
     #[test]
     fn test_get_highest_bank_snapshot() {
         let genesis_config = GenesisConfig::default();
@@ -1,5 +1,6 @@
 use {
     crate::{
+        bank::builtins_summary::BuiltinsSummary,
         serde_snapshot::SnapshotStreams,
         snapshot_archive_info::{
             FullSnapshotArchiveInfo, IncrementalSnapshotArchiveInfo, SnapshotArchiveInfoGetter,
@@ -60,6 +61,7 @@ pub const SNAPSHOT_STATE_COMPLETE_FILENAME: &str = "state_complete";
 pub const SNAPSHOT_ACCOUNTS_HARDLINKS: &str = "accounts_hardlinks";
 pub const SNAPSHOT_ARCHIVE_DOWNLOAD_DIR: &str = "remote";
 pub const SNAPSHOT_FULL_SNAPSHOT_SLOT_FILENAME: &str = "full_snapshot_slot";
+pub const SNAPSHOT_BUILTINS_SUMMARY_FILENAME: &str = "builtins_summary";
 pub const MAX_SNAPSHOT_DATA_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024; // 32 GiB
 const MAX_SNAPSHOT_VERSION_FILE_SIZE: u64 = 8; // byte
 const VERSION_STRING_V1_2_0: &str = "1.2.0";
@@ -433,6 +435,12 @@ pub enum AddBankSnapshotError {
     #[error("failed to write snapshot version file '{1}': {0}")]
     WriteSnapshotVersionFile(#[source] IoError, PathBuf),
 
+    #[error("failed to serialize builtins summary: {0}")]
+    SerializeBuiltinsSummary(#[source] bincode::Error),
+
+    #[error("failed to write builtins summary file '{1}': {0}")]
+    WriteBuiltinsSummaryFile(#[source] IoError, PathBuf),
+
     #[error("failed to mark snapshot as 'complete': failed to create file '{1}': {0}")]
     CreateStateCompleteFile(#[source] IoError, PathBuf),
 }
@@ -658,6 +666,68 @@ pub fn read_full_snapshot_slot_file(bank_snapshot_dir: impl AsRef<Path>) -> IoRe
     Ok(slot)
 }
 
+/// Reads the builtins summary file from the bank snapshot dir
+///
+/// The summary is written along with the bank snapshot, and can be read
+/// without deserializing the bank.
+pub fn read_builtins_summary_file(
+    bank_snapshot_dir: impl AsRef<Path>,
+) -> IoResult<BuiltinsSummary> {
+    let builtins_summary_path = bank_snapshot_dir
+        .as_ref()
+        .join(SNAPSHOT_BUILTINS_SUMMARY_FILENAME);
+    let bytes = fs::read(&builtins_summary_path)?;
+    BuiltinsSummary::decode(&bytes).map_err(|err| {
+        IoError::other(format!(
+            "invalid builtins summary file '{}': {err}",
+            builtins_summary_path.display(),
+        ))
+    })
+}
+
+/// Reads the builtins summary from a snapshot archive
+///
+/// The summary is archived along with the bank snapshot, before the accounts,
+/// so it is read without unpacking the archive.
+pub fn read_builtins_summary_from_archive(
+    snapshot_archive_path: impl AsRef<Path>,
+    archive_format: ArchiveFormat,
+) -> IoResult<BuiltinsSummary> {
+    let snapshot_archive_path = snapshot_archive_path.as_ref();
+    let file = BufReader::new(fs::File::open(snapshot_archive_path)?);
+    let reader: Box<dyn Read> = match archive_format {
+        ArchiveFormat::TarBzip2 => Box::new(BzDecoder::new(file)),
+        ArchiveFormat::TarGzip => Box::new(GzDecoder::new(file)),
+        ArchiveFormat::TarZstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        ArchiveFormat::TarLz4 => Box::new(lz4::Decoder::new(file)?),
+        ArchiveFormat::Tar => Box::new(file),
+    };
+    let mut archive = Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?;
+        if path.starts_with("snapshots")
+            && path.file_name() == Some(SNAPSHOT_BUILTINS_SUMMARY_FILENAME.as_ref())
+        {
+            let mut bytes = vec![];
+            entry.read_to_end(&mut bytes)?;
+            return BuiltinsSummary::decode(&bytes).map_err(|err| {
+                IoError::other(format!(
+                    "invalid builtins summary in snapshot archive '{}': {err}",
+                    snapshot_archive_path.display(),
+                ))
+            });
+        }
+    }
+    Err(IoError::new(
+        std::io::ErrorKind::NotFound,
+        format!(
+            "no builtins summary in snapshot archive '{}'",
+            snapshot_archive_path.display(),
+        ),
+    ))
+}
+
 /// Gets the highest, loadable, bank snapshot
 ///
 /// The highest bank snapshot is the one with the highest slot.
@@ -765,6 +835,16 @@ pub fn archive_snapshot_package(
     symlink::symlink_file(&src_snapshot_file, &staging_snapshot_file)
         .map_err(|err| E::SymlinkSnapshot(err, src_snapshot_file, staging_snapshot_file))?;
 
+    // The builtins summary is archived next to the bank snapshot, if the bank snapshot has one
+    let src_builtins_summary = src_snapshot_dir.join(SNAPSHOT_BUILTINS_SUMMARY_FILENAME);
+    if src_builtins_summary.is_file() {
+        let staging_builtins_summary =
+            staging_snapshot_dir.join(SNAPSHOT_BUILTINS_SUMMARY_FILENAME);
+        symlink::symlink_file(&src_builtins_summary, &staging_builtins_summary).map_err(|err| {
+            E::SymlinkSnapshot(err, src_builtins_summary, staging_builtins_summary)
+        })?;
+    }
+
     // Following the existing archive format, the status cache is under snapshots/, not under <slot>/
     // like in the snapshot dir.
     let staging_status_cache = staging_snapshots_dir.join(SNAPSHOT_STATUS_CACHE_FILENAME);
@@ -0,0 +1,305 @@
+//! Cross-validation of the declared costs of the builtin programs against
+//! their execution.
+//!
+//! Builtins charge a fixed `DEFAULT_COMPUTE_UNITS` per instruction instead of
+//! metering the instructions they execute, so nothing but review keeps the
+//! declared cost of a builtin in line with the work it does. These tests
+//! execute common instructions of each builtin in a bank. The execution
+//! charges the declared cost of each builtin, and the execution time per
+//! compute unit of the builtins is compared with each other: a builtin
+//! whose time per unit exceeds the median of all builtins by more than
+//! `MAX_DRIFT` is underpriced, and its declared cost has to be revised along
+//! with the change which made it slower.
+//!
+//! Time is measured in microseconds per instruction, so the comparison is
+//! coarse, and `MAX_DRIFT` leaves room for the noise of shared machines. As it
+//! depends on wall-clock time, the comparison is ignored by default and run
+//! explicitly when reviewing the cost of the builtins.
+//! Overpriced builtins, e.g. the compute budget program whose instructions
+//! are processed before execution, are not failures.
+#![allow(clippy::arithmetic_side_effects)]
+
+use {
+    serde_derive::Serialize,
+    solana_config_program::{config_instruction, ConfigState},
+    solana_program_runtime::timings::ExecuteTimings,
+    solana_runtime::{
+        bank::Bank,
+        genesis_utils::{create_genesis_config, GenesisConfigInfo},
+    },
+    solana_sdk::{
+        address_lookup_table::instruction as address_lookup_table_instruction,
+        clock::MAX_PROCESSING_AGE,
+        compute_budget::{self, ComputeBudgetInstruction},
+        instruction::Instruction,
+        native_token::LAMPORTS_PER_SOL,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        stake::{
+            self, instruction as stake_instruction,
+            state::{Authorized, Lockup},
+        },
+        system_instruction, system_program,
+        transaction::Transaction,
+        vote::{
+            self,
+            instruction::{self as vote_instruction, CreateVoteAccountConfig},
+            state::{VoteInit, VoteStateVersions},
+        },
+    },
+    solana_svm::transaction_processor::ExecutionRecordingConfig,
+};
+
+/// Instructions executed per builtin, after a round to warm up
+const INSTRUCTIONS_PER_BUILTIN: usize = 64;
+/// Maximum ratio of the time per compute unit of a builtin to the median
+const MAX_DRIFT: u64 = 10;
+
+struct Builtin {
+    name: &'static str,
+    program_id: Pubkey,
+    declared_units: u64,
+    /// Top-level instructions of the builtin per round
+    instructions_per_round: u32,
+    /// Whether the instructions of the builtin invoke other programs, whose
+    /// units are charged on top of the declared units
+    invokes_programs: bool,
+}
+
+const BUILTINS: [Builtin; 6] = [
+    Builtin {
+        name: "system",
+        program_id: system_program::id(),
+        declared_units: solana_system_program::system_processor::DEFAULT_COMPUTE_UNITS,
+        // A transfer, and the creation of the stake, vote and config accounts
+        instructions_per_round: 4,
+        invokes_programs: false,
+    },
+    Builtin {
+        name: "compute budget",
+        program_id: compute_budget::id(),
+        declared_units: solana_compute_budget_program::DEFAULT_COMPUTE_UNITS,
+        instructions_per_round: 1,
+        invokes_programs: false,
+    },
+    Builtin {
+        name: "stake",
+        program_id: stake::program::id(),
+        declared_units: solana_stake_program::stake_instruction::DEFAULT_COMPUTE_UNITS,
+        instructions_per_round: 1,
+        invokes_programs: false,
+    },
+    Builtin {
+        name: "vote",
+        program_id: vote::program::id(),
+        declared_units: solana_vote_program::vote_processor::DEFAULT_COMPUTE_UNITS,
+        instructions_per_round: 1,
+        invokes_programs: false,
+    },
+    Builtin {
+        name: "config",
+        program_id: solana_config_program::id(),
+        declared_units: solana_config_program::config_processor::DEFAULT_COMPUTE_UNITS,
+        instructions_per_round: 1,
+        invokes_programs: false,
+    },
+    Builtin {
+        name: "address lookup table",
+        program_id: solana_sdk::address_lookup_table::program::id(),
+        declared_units: solana_address_lookup_table_program::processor::DEFAULT_COMPUTE_UNITS,
+        instructions_per_round: 1,
+        invokes_programs: true,
+    },
+];
+
+#[derive(Default, Serialize)]
+struct ThresholdConfig {
+    threshold: u64,
+}
+
+impl ConfigState for ThresholdConfig {
+    fn max_space() -> u64 {
+        std::mem::size_of::<u64>() as u64
+    }
+}
+
+fn process_transaction(
+    bank: &Bank,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    timings: &mut ExecuteTimings,
+) {
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&signers[0].pubkey()),
+        signers,
+        bank.last_blockhash(),
+    );
+    let batch = bank.prepare_batch_for_tests(vec![transaction]);
+    let results = bank
+        .load_execute_and_commit_transactions(
+            &batch,
+            MAX_PROCESSING_AGE,
+            false,
+            ExecutionRecordingConfig::new_single_setting(false),
+            timings,
+            None,
+        )
+        .0;
+    assert_eq!(
+        results.execution_results[0].flattened_result(),
+        Ok(()),
+        "{instructions:?}"
+    );
+}
+
+/// Execute one instruction of each builtin
+fn process_round(bank: &Bank, payer: &Keypair, timings: &mut ExecuteTimings) {
+    let lamports = LAMPORTS_PER_SOL;
+
+    process_transaction(
+        bank,
+        &[
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), lamports),
+        ],
+        &[payer],
+        timings,
+    );
+
+    let stake = Keypair::new();
+    process_transaction(
+        bank,
+        &stake_instruction::create_account(
+            &payer.pubkey(),
+            &stake.pubkey(),
+            &Authorized::auto(&payer.pubkey()),
+            &Lockup::default(),
+            lamports,
+        ),
+        &[payer, &stake],
+        timings,
+    );
+
+    let vote = Keypair::new();
+    let node = Keypair::new();
+    process_transaction(
+        bank,
+        &vote_instruction::create_account_with_config(
+            &payer.pubkey(),
+            &vote.pubkey(),
+            &VoteInit {
+                node_pubkey: node.pubkey(),
+                authorized_voter: payer.pubkey(),
+                authorized_withdrawer: payer.pubkey(),
+                commission: 0,
+            },
+            lamports,
+            CreateVoteAccountConfig {
+                space: VoteStateVersions::vote_state_size_of(true) as u64,
+                ..CreateVoteAccountConfig::default()
+            },
+        ),
+        &[payer, &vote, &node],
+        timings,
+    );
+
+    let config = Keypair::new();
+    process_transaction(
+        bank,
+        &config_instruction::create_account::<ThresholdConfig>(
+            &payer.pubkey(),
+            &config.pubkey(),
+            lamports,
+            vec![],
+        ),
+        &[payer, &config],
+        timings,
+    );
+
+    // Tables are derived from their authority and a recent slot, which is the
+    // parent of `bank`
+    let authority = Keypair::new();
+    let (create_lookup_table, _) = address_lookup_table_instruction::create_lookup_table_signed(
+        authority.pubkey(),
+        payer.pubkey(),
+        bank.parent_slot(),
+    );
+    process_transaction(bank, &[create_lookup_table], &[payer, &authority], timings);
+}
+
+/// Execute `INSTRUCTIONS_PER_BUILTIN` instructions of each builtin, after a
+/// warm-up round
+fn execute_builtins() -> ExecuteTimings {
+    let GenesisConfigInfo {
+        genesis_config,
+        mint_keypair,
+        ..
+    } = create_genesis_config(1_000_000 * LAMPORTS_PER_SOL);
+    let (parent, bank_forks) = Bank::new_with_bank_forks_for_tests(&genesis_config);
+    // A child bank, whose slot hashes contain the slot of its parent
+    let bank = bank_forks
+        .write()
+        .unwrap()
+        .insert(Bank::new_from_parent(parent, &Pubkey::default(), 1))
+        .clone_without_scheduler();
+
+    process_round(&bank, &mint_keypair, &mut ExecuteTimings::default());
+    let mut timings = ExecuteTimings::default();
+    for _ in 0..INSTRUCTIONS_PER_BUILTIN {
+        process_round(&bank, &mint_keypair, &mut timings);
+    }
+    timings
+}
+
+#[test]
+fn test_builtin_costs_match_declared_units() {
+    let timings = execute_builtins();
+    for builtin in BUILTINS {
+        let timing = &timings.details.per_program_timings[&builtin.program_id];
+        let count = builtin.instructions_per_round * INSTRUCTIONS_PER_BUILTIN as u32;
+        assert_eq!(timing.count, count, "{}", builtin.name);
+        if builtin.invokes_programs {
+            assert!(
+                timing.accumulated_units > u64::from(count) * builtin.declared_units,
+                "{}",
+                builtin.name
+            );
+        } else {
+            assert_eq!(
+                timing.accumulated_units,
+                u64::from(count) * builtin.declared_units,
+                "{}",
+                builtin.name
+            );
+        }
+    }
+}
+
+// Measures wall-clock time, so it only runs on request:
+// cargo test -p solana-runtime --test builtin_costs -- --ignored
+#[test]
+#[ignore]
+fn test_builtin_costs_match_execution_time() {
+    let timings = execute_builtins();
+
+    // Nanoseconds per compute unit, by builtin
+    let nanos_per_unit = BUILTINS.map(|builtin| {
+        let timing = &timings.details.per_program_timings[&builtin.program_id];
+        timing.accumulated_us * 1_000 / timing.accumulated_units
+    });
+
+    let mut sorted = nanos_per_unit;
+    sorted.sort_unstable();
+    let median = sorted[sorted.len() / 2].max(1);
+    for (builtin, nanos_per_unit) in BUILTINS.iter().zip(nanos_per_unit) {
+        assert!(
+            nanos_per_unit <= median * MAX_DRIFT,
+            "the {} program takes {nanos_per_unit}ns per compute unit, more than {MAX_DRIFT} \
+             times the median of {median}ns of all builtins: its DEFAULT_COMPUTE_UNITS of {} \
+             is too low",
+            builtin.name,
+            builtin.declared_units,
+        );
+    }
+}
@@ -261,6 +261,10 @@ pub enum InstructionError {
     /// Builtin programs must consume compute units
     #[error("Builtin programs must consume compute units")]
     BuiltinProgramsMustConsumeComputeUnits,
+
+    /// Accounts data growth exceeded the limit configured per transaction
+    #[error("Accounts data growth exceeded the limit configured per transaction")]
+    MaxAccountsDataGrowthExceeded,
     // Note: For any new error added here an equivalent ProgramError and its
     // conversions must also be added
 }
@@ -67,6 +67,8 @@ pub enum ProgramError {
     Immutable,
     #[error("Incorrect authority provided")]
     IncorrectAuthority,
+    #[error("Accounts data growth exceeded the limit configured per transaction")]
+    MaxAccountsDataGrowthExceeded,
 }
 
 pub trait PrintProgramError {
@@ -119,6 +121,7 @@ impl PrintProgramError for ProgramError {
             Self::ArithmeticOverflow => msg!("Error: ArithmeticOverflow"),
             Self::Immutable => msg!("Error: Immutable"),
             Self::IncorrectAuthority => msg!("Error: IncorrectAuthority"),
+            Self::MaxAccountsDataGrowthExceeded => msg!("Error: MaxAccountsDataGrowthExceeded"),
         }
     }
 }
@@ -157,6 +160,7 @@ pub const INVALID_ACCOUNT_OWNER: u64 = to_builtin!(23);
 pub const ARITHMETIC_OVERFLOW: u64 = to_builtin!(24);
 pub const IMMUTABLE: u64 = to_builtin!(25);
 pub const INCORRECT_AUTHORITY: u64 = to_builtin!(26);
+pub const MAX_ACCOUNTS_DATA_GROWTH_EXCEEDED: u64 = to_builtin!(27);
 // Warning: Any new program errors added here must also be:
 // - Added to the below conversions
 // - Added as an equivalent to InstructionError
@@ -197,6 +201,7 @@ impl From<ProgramError> for u64 {
             ProgramError::ArithmeticOverflow => ARITHMETIC_OVERFLOW,
             ProgramError::Immutable => IMMUTABLE,
             ProgramError::IncorrectAuthority => INCORRECT_AUTHORITY,
+            ProgramError::MaxAccountsDataGrowthExceeded => MAX_ACCOUNTS_DATA_GROWTH_EXCEEDED,
             ProgramError::Custom(error) => {
                 if error == 0 {
                     CUSTOM_ZERO
@@ -239,6 +244,7 @@ impl From<u64> for ProgramError {
             ARITHMETIC_OVERFLOW => Self::ArithmeticOverflow,
             IMMUTABLE => Self::Immutable,
             INCORRECT_AUTHORITY => Self::IncorrectAuthority,
+            MAX_ACCOUNTS_DATA_GROWTH_EXCEEDED => Self::MaxAccountsDataGrowthExceeded,
             _ => Self::Custom(error as u32),
         }
     }
@@ -281,6 +287,7 @@ impl TryFrom<InstructionError> for ProgramError {
             Self::Error::ArithmeticOverflow => Ok(Self::ArithmeticOverflow),
             Self::Error::Immutable => Ok(Self::Immutable),
             Self::Error::IncorrectAuthority => Ok(Self::IncorrectAuthority),
+            Self::Error::MaxAccountsDataGrowthExceeded => Ok(Self::MaxAccountsDataGrowthExceeded),
             _ => Err(error),
         }
     }
@@ -321,6 +328,7 @@ where
             ARITHMETIC_OVERFLOW => Self::ArithmeticOverflow,
             IMMUTABLE => Self::Immutable,
             INCORRECT_AUTHORITY => Self::IncorrectAuthority,
+            MAX_ACCOUNTS_DATA_GROWTH_EXCEEDED => Self::MaxAccountsDataGrowthExceeded,
             _ => {
                 // A valid custom error has no bits set in the upper 32
                 if error >> BUILTIN_BIT_SHIFT == 0 {
@@ -789,6 +789,18 @@ pub mod deprecate_unused_legacy_vote_plumbing {
     solana_sdk::declare_id!("6Uf8S75PVh91MYgPQSHnjRAPQq6an5BDv9vomrCwDqLe");
 }
 
+pub mod spread_epoch_boundary_work {
+    solana_sdk::declare_id!("G8yj22Kg1gf7FvV5SvJSBhWjwrRUGAuHumR9714hiyJb");
+}
+
+pub mod partition_vote_rewards {
+    solana_sdk::declare_id!("7ALhxmGC2gueWY289mXKBBuqmmUKxMsNMB5W1BxaDbot");
+}
+
+pub mod report_load_budget_overrun {
+    solana_sdk::declare_id!("57ZJLwb2AdBUDUgCnYGXQRTJA72TDocB1YsFFZJFTfzZ");
+}
+
 lazy_static! {
     /// Map of feature identifiers to user-visible description
     pub static ref FEATURE_NAMES: HashMap<Pubkey, &'static str> = [
@@ -981,6 +993,9 @@ lazy_static! {
         (remove_rounding_in_fee_calculation::id(), "Removing unwanted rounding in fee calculation #34982"),
         (deprecate_unused_legacy_vote_plumbing::id(), "Deprecate unused legacy vote tx plumbing"),
         (enable_tower_sync_ix::id(), "Enable tower sync vote instruction"),
+        (spread_epoch_boundary_work::id(), "Spread epoch boundary work over the first slots of the epoch"),
+        (partition_vote_rewards::id(), "Distribute vote rewards in partitions along with stake rewards"),
+        (report_load_budget_overrun::id(), "Report the account over the loaded accounts data size limit in the transaction error"),
         /*************** ADD NEW FEATURES HERE ***************/
     ]
     .iter()
@@ -150,7 +150,9 @@ pub enum TransactionError {
     )]
     InsufficientFundsForRent { account_index: u8 },
 
-    /// Transaction exceeded max loaded accounts data size cap
+    /// Transaction exceeded max loaded accounts data size cap. Superseded by
+    /// `LoadBudgetExceeded` once the `report_load_budget_overrun` feature is
+    /// active.
     #[error("Transaction exceeded max loaded accounts data size cap")]
     MaxLoadedAccountsDataSizeExceeded,
 
@@ -169,6 +171,54 @@ pub enum TransactionError {
     /// The total balance before the transaction does not equal the total balance after the transaction
     #[error("Sum of account balances before and after transaction do not match")]
     UnbalancedTransaction,
+
+    /// The write policy of the runtime denied the modification of an account
+    #[error("Modification of the account at index {account_index} was vetoed by the write policy")]
+    AccountWriteVetoed { account_index: u8 },
+
+    /// Loading an account brought the data loaded by the transaction over its
+    /// loaded accounts data size limit. `needed` is the size of the data
+    /// loaded up to and including the account, `budget` the limit. Returned
+    /// in place of `MaxLoadedAccountsDataSizeExceeded` once the
+    /// `report_load_budget_overrun` feature is active.
+    #[error("Loading the account at index {account_index} needs {needed} bytes of account data, over the budget of {budget} bytes")]
+    LoadBudgetExceeded {
+        account_index: u8,
+        needed: u32,
+        budget: u32,
+    },
+
+    /// The owner transition validator of the runtime rejected the change of
+    /// the owner of an account
+    #[error("Changing the owner of the account at index {account_index} was rejected: {error}")]
+    OwnerTransitionRejected {
+        account_index: u8,
+        error: OwnerTransitionError,
+    },
+
+    /// The transaction was processed after the last slot of its validity
+    /// window
+    #[error("Transaction expired before it was processed")]
+    TransactionExpired,
+}
+
+/// Reasons an owner transition validator rejects the change of the owner of
+/// an account
+#[derive(
+    Error, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy, AbiExample, AbiEnumVisitor,
+)]
+pub enum OwnerTransitionError {
+    /// The owner of the account may not change
+    #[error("the owner of the account may not change")]
+    ImmutableOwner,
+
+    /// The new owner is not known to the runtime
+    #[error("the new owner is not known to the runtime")]
+    UnknownOwner,
+
+    /// Rule specific to the runtime, identified by a code
+    #[error("custom owner transition error: {0:#x}")]
+    Custom(u32),
 }
 
 impl From<SanitizeError> for TransactionError {
@@ -142,6 +142,9 @@ pub struct TransactionContext {
     instruction_trace: Vec<InstructionContext>,
     return_data: TransactionReturnData,
     accounts_resize_delta: RefCell<i64>,
+    accounts_data_growth: RefCell<u64>,
+    #[cfg(not(target_os = "solana"))]
+    max_accounts_data_growth: Option<u64>,
     #[cfg(not(target_os = "solana"))]
     rent: Rent,
     /// Useful for debugging to filter by or to look it up on the explorer
@@ -171,6 +174,8 @@ impl TransactionContext {
             instruction_trace: vec![InstructionContext::default()],
             return_data: TransactionReturnData::default(),
             accounts_resize_delta: RefCell::new(0),
+            accounts_data_growth: RefCell::new(0),
+            max_accounts_data_growth: None,
             rent,
             #[cfg(all(not(target_os = "solana"), debug_assertions))]
             signature: Signature::default(),
@@ -206,6 +211,15 @@ impl TransactionContext {
         &self.signature
     }
 
+    /// Limits the sum of the increases of account data lengths over the
+    /// transaction, in addition to the limit of the net change in
+    /// `MAX_PERMITTED_ACCOUNTS_DATA_ALLOCATIONS_PER_TRANSACTION`. Shrinking an
+    /// account does not give back growth.
+    #[cfg(not(target_os = "solana"))]
+    pub fn set_max_accounts_data_growth(&mut self, max_accounts_data_growth: Option<u64>) {
+        self.max_accounts_data_growth = max_accounts_data_growth;
+    }
+
     /// Returns the total number of accounts loaded in this Transaction
     pub fn get_number_of_accounts(&self) -> IndexOfAccount {
         self.accounts.len() as IndexOfAccount
@@ -437,6 +451,14 @@ impl TransactionContext {
             .map_err(|_| InstructionError::GenericError)
             .map(|value_ref| *value_ref)
     }
+
+    /// Returns the sum of the increases of account data lengths
+    pub fn accounts_data_growth(&self) -> Result<u64, InstructionError> {
+        self.accounts_data_growth
+            .try_borrow()
+            .map_err(|_| InstructionError::GenericError)
+            .map(|value_ref| *value_ref)
+    }
 }
 
 /// Return data at the end of a transaction
@@ -1115,6 +1137,18 @@ impl<'a> BorrowedAccount<'a> {
         {
             return Err(InstructionError::MaxAccountsDataAllocationsExceeded);
         }
+        // The growth can not exceed the per-transaction limit, if any
+        if let Some(max_accounts_data_growth) = self.transaction_context.max_accounts_data_growth {
+            let growth = (new_length as u64).saturating_sub(old_length as u64);
+            if self
+                .transaction_context
+                .accounts_data_growth()?
+                .saturating_add(growth)
+                > max_accounts_data_growth
+            {
+                return Err(InstructionError::MaxAccountsDataGrowthExceeded);
+            }
+        }
         Ok(())
     }
 
@@ -1134,6 +1168,13 @@ impl<'a> BorrowedAccount<'a> {
             .map_err(|_| InstructionError::GenericError)?;
         *accounts_resize_delta = accounts_resize_delta
             .saturating_add((new_len as i64).saturating_sub(self.get_data().len() as i64));
+        let mut accounts_data_growth = self
+            .transaction_context
+            .accounts_data_growth
+            .try_borrow_mut()
+            .map_err(|_| InstructionError::GenericError)?;
+        *accounts_data_growth = accounts_data_growth
+            .saturating_add((new_len as u64).saturating_sub(self.get_data().len() as u64));
         Ok(())
     }
 }
@@ -1145,6 +1186,7 @@ pub struct ExecutionRecord {
     pub return_data: TransactionReturnData,
     pub touched_account_count: u64,
     pub accounts_resize_delta: i64,
+    pub accounts_data_growth: u64,
 }
 
 /// Used by the bank in the runtime to write back the processed accounts and recorded instructions
@@ -1163,6 +1205,7 @@ impl From<TransactionContext> for ExecutionRecord {
             return_data: context.return_data,
             touched_account_count,
             accounts_resize_delta: RefCell::into_inner(context.accounts_resize_delta),
+            accounts_data_growth: RefCell::into_inner(context.accounts_data_growth),
         }
     }
 }
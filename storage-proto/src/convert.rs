@@ -11,7 +11,7 @@ use {
         },
         pubkey::Pubkey,
         signature::Signature,
-        transaction::{Transaction, TransactionError, VersionedTransaction},
+        transaction::{OwnerTransitionError, Transaction, TransactionError, VersionedTransaction},
         transaction_context::TransactionReturnData,
     },
     solana_transaction_status::{
@@ -752,6 +752,7 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
                     51 => InstructionError::MaxAccountsExceeded,
                     52 => InstructionError::MaxInstructionTraceLengthExceeded,
                     53 => InstructionError::BuiltinProgramsMustConsumeComputeUnits,
+                    54 => InstructionError::MaxAccountsDataGrowthExceeded,
                     _ => return Err("Invalid InstructionError"),
                 };
 
@@ -780,6 +781,38 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
                         account_index: transaction_details.index as u8,
                     });
                 }
+                37 => {
+                    return Ok(TransactionError::AccountWriteVetoed {
+                        account_index: transaction_details.index as u8,
+                    });
+                }
+                38 => {
+                    return Ok(TransactionError::LoadBudgetExceeded {
+                        account_index: transaction_details.index as u8,
+                        needed: transaction_details.needed,
+                        budget: transaction_details.budget,
+                    });
+                }
+                39 => {
+                    let owner_transition_error = transaction_details
+                        .owner_transition_error
+                        .ok_or("Missing owner transition error")?;
+                    let error = match owner_transition_error.error {
+                        0 => OwnerTransitionError::ImmutableOwner,
+                        1 => OwnerTransitionError::UnknownOwner,
+                        2 => OwnerTransitionError::Custom(
+                            owner_transition_error
+                                .custom
+                                .ok_or("Missing custom owner transition error")?
+                                .custom,
+                        ),
+                        _ => return Err("Invalid OwnerTransitionError"),
+                    };
+                    return Ok(TransactionError::OwnerTransitionRejected {
+                        account_index: transaction_details.index as u8,
+                        error,
+                    });
+                }
                 _ => {}
             }
         }
@@ -818,6 +851,7 @@ impl TryFrom<tx_by_addr::TransactionError> for TransactionError {
             33 => TransactionError::InvalidLoadedAccountsDataSizeLimit,
             34 => TransactionError::ResanitizationNeeded,
             36 => TransactionError::UnbalancedTransaction,
+            40 => TransactionError::TransactionExpired,
             _ => return Err("Invalid TransactionError"),
         })
     }
@@ -936,6 +970,18 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                 TransactionError::UnbalancedTransaction => {
                     tx_by_addr::TransactionErrorType::UnbalancedTransaction
                 }
+                TransactionError::AccountWriteVetoed { .. } => {
+                    tx_by_addr::TransactionErrorType::AccountWriteVetoed
+                }
+                TransactionError::LoadBudgetExceeded { .. } => {
+                    tx_by_addr::TransactionErrorType::LoadBudgetExceeded
+                }
+                TransactionError::OwnerTransitionRejected { .. } => {
+                    tx_by_addr::TransactionErrorType::OwnerTransitionRejected
+                }
+                TransactionError::TransactionExpired => {
+                    tx_by_addr::TransactionErrorType::TransactionExpired
+                }
             } as i32,
             instruction_error: match transaction_error {
                 TransactionError::InstructionError(index, ref instruction_error) => {
@@ -1102,6 +1148,9 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                             InstructionError::BuiltinProgramsMustConsumeComputeUnits => {
                                 tx_by_addr::InstructionErrorType::BuiltinProgramsMustConsumeComputeUnits
                             }
+                            InstructionError::MaxAccountsDataGrowthExceeded => {
+                                tx_by_addr::InstructionErrorType::MaxAccountsDataGrowthExceeded
+                            }
                         } as i32,
                         custom: match instruction_error {
                             InstructionError::Custom(custom) => {
@@ -1117,18 +1166,63 @@ impl From<TransactionError> for tx_by_addr::TransactionError {
                 TransactionError::DuplicateInstruction(index) => {
                     Some(tx_by_addr::TransactionDetails {
                         index: index as u32,
+                        ..tx_by_addr::TransactionDetails::default()
                     })
                 }
                 TransactionError::InsufficientFundsForRent { account_index } => {
                     Some(tx_by_addr::TransactionDetails {
                         index: account_index as u32,
+                        ..tx_by_addr::TransactionDetails::default()
                     })
                 }
                 TransactionError::ProgramExecutionTemporarilyRestricted { account_index } => {
                     Some(tx_by_addr::TransactionDetails {
                         index: account_index as u32,
+                        ..tx_by_addr::TransactionDetails::default()
                     })
                 }
+                TransactionError::AccountWriteVetoed { account_index } => {
+                    Some(tx_by_addr::TransactionDetails {
+                        index: account_index as u32,
+                        ..tx_by_addr::TransactionDetails::default()
+                    })
+                }
+                TransactionError::LoadBudgetExceeded {
+                    account_index,
+                    needed,
+                    budget,
+                } => Some(tx_by_addr::TransactionDetails {
+                    index: account_index as u32,
+                    needed,
+                    budget,
+                    ..tx_by_addr::TransactionDetails::default()
+                }),
+                TransactionError::OwnerTransitionRejected {
+                    account_index,
+                    error,
+                } => Some(tx_by_addr::TransactionDetails {
+                    index: account_index as u32,
+                    owner_transition_error: Some(tx_by_addr::OwnerTransitionError {
+                        error: match error {
+                            OwnerTransitionError::ImmutableOwner => {
+                                tx_by_addr::OwnerTransitionErrorType::ImmutableOwner
+                            }
+                            OwnerTransitionError::UnknownOwner => {
+                                tx_by_addr::OwnerTransitionErrorType::UnknownOwner
+                            }
+                            OwnerTransitionError::Custom(_) => {
+                                tx_by_addr::OwnerTransitionErrorType::CustomOwnerTransitionError
+                            }
+                        } as i32,
+                        custom: match error {
+                            OwnerTransitionError::Custom(custom) => {
+                                Some(tx_by_addr::CustomError { custom })
+                            }
+                            _ => None,
+                        },
+                    }),
+                    ..tx_by_addr::TransactionDetails::default()
+                }),
 
                 _ => None,
             },
@@ -1851,6 +1945,51 @@ mod test {
             transaction_error,
             tx_by_addr_transaction_error.try_into().unwrap()
         );
+
+        let transaction_error = TransactionError::TransactionExpired;
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::AccountWriteVetoed { account_index: 10 };
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        let transaction_error = TransactionError::LoadBudgetExceeded {
+            account_index: 10,
+            needed: 1025,
+            budget: 1024,
+        };
+        let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+            transaction_error.clone().into();
+        assert_eq!(
+            transaction_error,
+            tx_by_addr_transaction_error.try_into().unwrap()
+        );
+
+        for error in [
+            OwnerTransitionError::ImmutableOwner,
+            OwnerTransitionError::UnknownOwner,
+            OwnerTransitionError::Custom(42),
+        ] {
+            let transaction_error = TransactionError::OwnerTransitionRejected {
+                account_index: 10,
+                error,
+            };
+            let tx_by_addr_transaction_error: tx_by_addr::TransactionError =
+                transaction_error.clone().into();
+            assert_eq!(
+                transaction_error,
+                tx_by_addr_transaction_error.try_into().unwrap()
+            );
+        }
     }
 
     #[test]
@@ -1861,12 +2000,15 @@ mod test {
             match error {
                 tx_by_addr::TransactionErrorType::DuplicateInstruction
                 | tx_by_addr::TransactionErrorType::InsufficientFundsForRent
-                | tx_by_addr::TransactionErrorType::ProgramExecutionTemporarilyRestricted => {
+                | tx_by_addr::TransactionErrorType::ProgramExecutionTemporarilyRestricted
+                | tx_by_addr::TransactionErrorType::AccountWriteVetoed
+                | tx_by_addr::TransactionErrorType::LoadBudgetExceeded => {
                     let tx_by_addr_error = tx_by_addr::TransactionError {
                         transaction_error: error as i32,
                         instruction_error: None,
                         transaction_details: Some(tx_by_addr::TransactionDetails {
                             index: ix_index,
+                            ..tx_by_addr::TransactionDetails::default()
                         }),
                     };
                     let transaction_error: TransactionError = tx_by_addr_error
@@ -1875,6 +2017,27 @@ mod test {
                         .unwrap_or_else(|_| panic!("{error:?} conversion implemented?"));
                     assert_eq!(tx_by_addr_error, transaction_error.into());
                 }
+                tx_by_addr::TransactionErrorType::OwnerTransitionRejected => {
+                    let tx_by_addr_error = tx_by_addr::TransactionError {
+                        transaction_error: error as i32,
+                        instruction_error: None,
+                        transaction_details: Some(tx_by_addr::TransactionDetails {
+                            index: ix_index,
+                            owner_transition_error: Some(tx_by_addr::OwnerTransitionError {
+                                error:
+                                    tx_by_addr::OwnerTransitionErrorType::CustomOwnerTransitionError
+                                        as i32,
+                                custom: Some(tx_by_addr::CustomError {
+                                    custom: custom_error,
+                                }),
+                            }),
+                            ..tx_by_addr::TransactionDetails::default()
+                        }),
+                    };
+                    let transaction_error: TransactionError =
+                        tx_by_addr_error.clone().try_into().unwrap();
+                    assert_eq!(tx_by_addr_error, transaction_error.into());
+                }
                 tx_by_addr::TransactionErrorType::InstructionError => {
                     for ix_error in all::<tx_by_addr::InstructionErrorType>() {
                         if ix_error != tx_by_addr::InstructionErrorType::Custom {
@@ -0,0 +1,155 @@
+use {
+    criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput},
+    solana_program_runtime::loaded_programs::LoadedProgramsForTxBatch,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        bpf_loader,
+        feature_set::FeatureSet,
+        fee::FeeStructure,
+        hash::Hash,
+        instruction::Instruction,
+        message::Message,
+        native_loader,
+        pubkey::Pubkey,
+        rent_collector::RentCollector,
+        transaction::{SanitizedTransaction, Transaction},
+    },
+    solana_svm::{
+        account_loader::{load_accounts, TransactionCheckResult},
+        precompile_registry::PrecompileRegistry,
+        transaction_error_metrics::TransactionErrorMetrics,
+        transaction_processing_callback::{AccountMeta, TransactionProcessingCallback},
+    },
+    std::{collections::HashMap, sync::Arc},
+};
+
+const KB: usize = 1024;
+const MB: usize = KB * KB;
+
+const NUM_TRANSACTIONS: usize = 64;
+
+/// Accounts which are copied out of the store when loaded, like the
+/// accounts of a bank are copied out of their storages
+struct StoreCallback {
+    accounts: HashMap<Pubkey, AccountSharedData>,
+    rent_collector: RentCollector,
+    feature_set: Arc<FeatureSet>,
+    peek_meta_only: bool,
+}
+
+impl TransactionProcessingCallback for StoreCallback {
+    fn account_matches_owners(&self, account: &Pubkey, owners: &[Pubkey]) -> Option<usize> {
+        let account = self.accounts.get(account)?;
+        owners.iter().position(|owner| account.owner() == owner)
+    }
+
+    fn get_account_shared_data(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
+        self.accounts.get(pubkey).map(|account| {
+            AccountSharedData::create(
+                account.lamports(),
+                account.data().to_vec(),
+                *account.owner(),
+                account.executable(),
+                account.rent_epoch(),
+            )
+        })
+    }
+
+    fn peek_account_meta(&self, pubkey: &Pubkey) -> Option<AccountMeta> {
+        if self.peek_meta_only {
+            self.accounts.get(pubkey).map(AccountMeta::from)
+        } else {
+            self.get_account_shared_data(pubkey)
+                .map(|account| AccountMeta::from(&account))
+        }
+    }
+
+    fn get_last_blockhash_and_lamports_per_signature(&self) -> (Hash, u64) {
+        (Hash::default(), 5000)
+    }
+
+    fn get_rent_collector(&self) -> &RentCollector {
+        &self.rent_collector
+    }
+
+    fn get_feature_set(&self) -> Arc<FeatureSet> {
+        self.feature_set.clone()
+    }
+}
+
+/// Transactions of distinct fee payers invoking a program of a loader whose
+/// account holds `loader_data_size` bytes, and the accounts they load
+fn setup(
+    loader_data_size: usize,
+) -> (
+    Vec<SanitizedTransaction>,
+    HashMap<Pubkey, AccountSharedData>,
+) {
+    let mut accounts = HashMap::new();
+    let mut loader_account = AccountSharedData::new(1, loader_data_size, &native_loader::id());
+    loader_account.set_executable(true);
+    accounts.insert(bpf_loader::id(), loader_account);
+    let program_id = Pubkey::new_unique();
+    let mut program_account = AccountSharedData::new(1, 0, &bpf_loader::id());
+    program_account.set_executable(true);
+    accounts.insert(program_id, program_account);
+
+    let transactions = std::iter::repeat_with(|| {
+        let fee_payer = Pubkey::new_unique();
+        accounts.insert(
+            fee_payer,
+            AccountSharedData::new(1_000_000_000, 0, &Pubkey::default()),
+        );
+        let instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let message = Message::new(&[instruction], Some(&fee_payer));
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_unsigned(message))
+    })
+    .take(NUM_TRANSACTIONS)
+    .collect();
+    (transactions, accounts)
+}
+
+fn bench_load_accounts(c: &mut Criterion) {
+    const LOADER_DATA_SIZES: [usize; 3] = [0, KB, MB];
+
+    let mut group = c.benchmark_group("load_accounts");
+    group.throughput(Throughput::Elements(NUM_TRANSACTIONS as u64));
+    for loader_data_size in LOADER_DATA_SIZES {
+        let (transactions, accounts) = setup(loader_data_size);
+        let check_results: Vec<TransactionCheckResult> =
+            vec![(Ok(()), None, Some(5000)); NUM_TRANSACTIONS];
+        for peek_meta_only in [false, true] {
+            let callback = StoreCallback {
+                accounts: accounts.clone(),
+                rent_collector: RentCollector::default(),
+                feature_set: Arc::new(FeatureSet::all_enabled()),
+                peek_meta_only,
+            };
+            let name = if peek_meta_only {
+                "peek_meta_only"
+            } else {
+                "peek_whole_account"
+            };
+            group.bench_function(BenchmarkId::new(name, loader_data_size), |b| {
+                b.iter(|| {
+                    let results = load_accounts(
+                        &callback,
+                        &transactions,
+                        &check_results,
+                        &mut TransactionErrorMetrics::default(),
+                        &FeeStructure::default(),
+                        None,
+                        &HashMap::new(),
+                        &LoadedProgramsForTxBatch::default(),
+                        &PrecompileRegistry::default(),
+                    );
+                    assert!(results.iter().all(|(result, _nonce)| result.is_ok()));
+                    results
+                });
+            });
+        }
+    }
+}
+
+criterion_group!(benches, bench_load_accounts);
+criterion_main!(benches);
@@ -0,0 +1,215 @@
+//! Contention on the accounts locked by a processed batch of transactions.
+//!
+//! Transactions which lock the same account, at least one of them for
+//! writing, can not execute in parallel. When many transactions of a batch
+//! touch a hot account, e.g. the pool of a popular AMM, they serialize
+//! behind one another. The report lists such accounts with the number of
+//! transactions which contended for them and the compute units those
+//! executed, so block producers can tune their packing heuristics and fee
+//! markets can price the hot accounts.
+//!
+//! Only executed transactions are counted, as the others did not hold their
+//! locks for any meaningful time. An account is contended if at least two
+//! of them lock it and at least one writes it.
+
+use {
+    crate::transaction_results::TransactionExecutionResult,
+    solana_sdk::{pubkey::Pubkey, saturating_add_assign, transaction::SanitizedTransaction},
+    std::collections::HashMap,
+};
+
+/// Contention on a single account by a batch of transactions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AccountContention {
+    /// Number of executed transactions which write the account
+    pub writers: u64,
+    /// Number of executed transactions which only read the account
+    pub readers: u64,
+    /// Compute units executed by the transactions locking the account, which
+    /// can not overlap with the writes of the account
+    pub serialized_compute_units: u64,
+}
+
+impl AccountContention {
+    /// Number of transactions which contended for the account
+    pub fn conflicting_transactions(&self) -> u64 {
+        self.writers.saturating_add(self.readers)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountContentionReport {
+    pub accounts: HashMap<Pubkey, AccountContention>,
+}
+
+impl AccountContentionReport {
+    /// Build the report from a processed batch, `execution_results` being in
+    /// the order of `sanitized_txs`
+    pub fn new(
+        sanitized_txs: &[SanitizedTransaction],
+        execution_results: &[TransactionExecutionResult],
+    ) -> Self {
+        let mut report = Self::default();
+        for (tx, execution_result) in sanitized_txs.iter().zip(execution_results) {
+            report.record(tx, execution_result);
+        }
+        report.retain_contended()
+    }
+
+    /// Count the locks of a transaction of the batch. The report lists all
+    /// locked accounts until `retain_contended()` is called.
+    pub(crate) fn record(
+        &mut self,
+        tx: &SanitizedTransaction,
+        execution_result: &TransactionExecutionResult,
+    ) {
+        let TransactionExecutionResult::Executed { details, .. } = execution_result else {
+            return;
+        };
+        let account_locks = tx.get_account_locks_unchecked();
+        for account in account_locks.writable {
+            let contention = self.accounts.entry(*account).or_default();
+            saturating_add_assign!(contention.writers, 1);
+            saturating_add_assign!(contention.serialized_compute_units, details.executed_units);
+        }
+        for account in account_locks.readonly {
+            let contention = self.accounts.entry(*account).or_default();
+            saturating_add_assign!(contention.readers, 1);
+            saturating_add_assign!(contention.serialized_compute_units, details.executed_units);
+        }
+    }
+
+    /// Drop the accounts which were not contended, once all transactions
+    /// of the batch are recorded
+    pub(crate) fn retain_contended(mut self) -> Self {
+        self.accounts.retain(|_, contention| {
+            contention.writers > 0 && contention.conflicting_transactions() > 1
+        });
+        self
+    }
+
+    pub fn get(&self, account: &Pubkey) -> Option<&AccountContention> {
+        self.accounts.get(account)
+    }
+
+    /// The `count` most contended accounts, by serialized compute units
+    pub fn hottest(&self, count: usize) -> Vec<(Pubkey, AccountContention)> {
+        let mut accounts = self
+            .accounts
+            .iter()
+            .map(|(account, contention)| (*account, *contention))
+            .collect::<Vec<_>>();
+        accounts.sort_by(|(a_account, a), (b_account, b)| {
+            b.serialized_compute_units
+                .cmp(&a.serialized_compute_units)
+                .then_with(|| a_account.cmp(b_account))
+        });
+        accounts.truncate(count);
+        accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::transaction_results::{AccountLifecycle, TransactionExecutionDetails},
+        solana_program_runtime::loaded_programs::LoadedProgramsForTxBatch,
+        solana_sdk::{
+            hash::Hash,
+            instruction::{AccountMeta, Instruction},
+            signature::{Keypair, Signer},
+            transaction::{Transaction, TransactionError},
+        },
+    };
+
+    fn new_tx(writable: &[Pubkey], readonly: &[Pubkey]) -> SanitizedTransaction {
+        let payer = Keypair::new();
+        let accounts = writable
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .chain(
+                readonly
+                    .iter()
+                    .map(|account| AccountMeta::new_readonly(*account, false)),
+            )
+            .collect();
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], accounts);
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        ))
+    }
+
+    fn new_executed(executed_units: u64) -> TransactionExecutionResult {
+        TransactionExecutionResult::Executed {
+            details: TransactionExecutionDetails {
+                status: Ok(()),
+                log_messages: None,
+                inner_instructions: None,
+                durable_nonce_fee: None,
+                return_data: None,
+                executed_units,
+                accounts_data_len_delta: 0,
+                accounts_data_growth: 0,
+                heap_allocation_stats: None,
+                error_location: None,
+                capability_manifest: None,
+                decoded_instructions: None,
+                compute_budget_report: None,
+                account_lifecycle: AccountLifecycle::default(),
+                signature_policy_decision: None,
+            },
+            programs_modified_by_tx: Box::<LoadedProgramsForTxBatch>::default(),
+        }
+    }
+
+    #[test]
+    fn test_account_contention_report() {
+        let pool = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let sanitized_txs = [
+            new_tx(&[pool], &[oracle, config]),
+            new_tx(&[pool, oracle], &[config]),
+            new_tx(&[pool], &[config]),
+            // Not executed
+            new_tx(&[pool], &[]),
+            new_tx(&[other], &[config]),
+        ];
+        let execution_results = [
+            new_executed(100),
+            new_executed(200),
+            new_executed(400),
+            TransactionExecutionResult::NotExecuted(TransactionError::AccountInUse),
+            new_executed(800),
+        ];
+
+        let report = AccountContentionReport::new(&sanitized_txs, &execution_results);
+        let pool_contention = AccountContention {
+            writers: 3,
+            readers: 0,
+            serialized_compute_units: 700,
+        };
+        assert_eq!(report.get(&pool), Some(&pool_contention));
+        assert_eq!(pool_contention.conflicting_transactions(), 3);
+        assert_eq!(
+            report.get(&oracle),
+            Some(&AccountContention {
+                writers: 1,
+                readers: 1,
+                serialized_compute_units: 300,
+            })
+        );
+        // Only ever read, or locked by a single transaction
+        assert_eq!(report.get(&config), None);
+        assert_eq!(report.get(&other), None);
+        assert_eq!(report.accounts.len(), 2);
+
+        assert_eq!(report.hottest(1), vec![(pool, pool_contention)]);
+        assert_eq!(report.hottest(3).len(), 2);
+    }
+}
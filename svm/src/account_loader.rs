@@ -1,8 +1,12 @@
+#[cfg(feature = "dev-context-only-utils")]
+use qualifier_attr::qualifiers;
 use {
     crate::{
-        account_overrides::AccountOverrides, account_rent_state::RentState,
+        account_overrides::AccountOverrides,
+        account_rent_state::RentState,
+        precompile_registry::PrecompileRegistry,
         transaction_error_metrics::TransactionErrorMetrics,
-        transaction_processing_callback::TransactionProcessingCallback,
+        transaction_processing_callback::{AccountMeta, TransactionProcessingCallback},
     },
     itertools::Itertools,
     log::warn,
@@ -25,7 +29,7 @@ use {
         rent::RentDue,
         rent_collector::{RentCollector, RENT_EXEMPT_RENT_EPOCH},
         rent_debits::RentDebits,
-        saturating_add_assign,
+        saturating_add_assign, system_program,
         sysvar::{self, instructions::construct_instructions_data},
         transaction::{self, Result, SanitizedTransaction, TransactionError},
         transaction_context::{IndexOfAccount, TransactionAccount},
@@ -46,6 +50,8 @@ pub struct LoadedTransaction {
     pub program_indices: TransactionProgramIndices,
     pub rent: TransactionRent,
     pub rent_debits: RentDebits,
+    /// Fee charged to the fee payer
+    pub fee: u64,
 }
 
 /// Check whether the payer_account is capable of paying the fee. The
@@ -102,11 +108,41 @@ pub fn validate_fee_payer(
     )
 }
 
+/// Reject a fee payer based on its metadata alone, before any account of the
+/// transaction is loaded. Only failures which `validate_fee_payer()` reports
+/// for the loaded account as well are detected, so that the outcome of
+/// loading the transaction does not change.
+fn precheck_fee_payer(
+    payer_meta: &AccountMeta,
+    error_counters: &mut TransactionErrorMetrics,
+    fee: u64,
+) -> Result<()> {
+    if payer_meta.lamports == 0 {
+        error_counters.account_not_found += 1;
+        return Err(TransactionError::AccountNotFound);
+    }
+    if !system_program::check_id(&payer_meta.owner)
+        || (payer_meta.data_len != 0 && payer_meta.data_len != NonceState::size())
+    {
+        error_counters.invalid_account_for_fee += 1;
+        return Err(TransactionError::InvalidAccountForFee);
+    }
+    // Rent collection only ever decreases the balance. Nonce accounts are
+    // left to the full validation, which needs their state.
+    if payer_meta.data_len == 0 && payer_meta.lamports < fee {
+        error_counters.insufficient_funds += 1;
+        return Err(TransactionError::InsufficientFundsForFee);
+    }
+    Ok(())
+}
+
 /// Collect information about accounts used in txs transactions and
 /// return vector of tuples, one for each transaction in the
 /// batch. Each tuple contains struct of information about accounts as
 /// its first element and an optional transaction nonce info as its
 /// second element.
+#[cfg_attr(feature = "dev-context-only-utils", qualifiers(pub))]
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn load_accounts<CB: TransactionProcessingCallback>(
     callbacks: &CB,
     txs: &[SanitizedTransaction],
@@ -116,64 +152,102 @@ pub(crate) fn load_accounts<CB: TransactionProcessingCallback>(
     account_overrides: Option<&AccountOverrides>,
     program_accounts: &HashMap<Pubkey, (&Pubkey, u64)>,
     loaded_programs: &LoadedProgramsForTxBatch,
+    precompiles: &PrecompileRegistry,
 ) -> Vec<TransactionLoadResult> {
-    let feature_set = callbacks.get_feature_set();
     txs.iter()
         .zip(lock_results)
-        .map(|etx| match etx {
-            (tx, (Ok(()), nonce, lamports_per_signature)) => {
-                let message = tx.message();
-                let fee = if let Some(lamports_per_signature) = lamports_per_signature {
-                    fee_structure.calculate_fee(
-                        message,
-                        *lamports_per_signature,
-                        &process_compute_budget_instructions(message.program_instructions_iter())
-                            .unwrap_or_default()
-                            .into(),
-                        feature_set
-                            .is_active(&include_loaded_accounts_data_size_in_fee_calculation::id()),
-                        feature_set.is_active(&remove_rounding_in_fee_calculation::id()),
-                    )
-                } else {
-                    return (Err(TransactionError::BlockhashNotFound), None);
-                };
+        .map(|(tx, check_result)| {
+            load_transaction(
+                callbacks,
+                tx,
+                check_result,
+                error_counters,
+                fee_structure,
+                account_overrides,
+                program_accounts,
+                loaded_programs,
+                precompiles,
+            )
+        })
+        .collect()
+}
 
-                // load transactions
-                let loaded_transaction = match load_transaction_accounts(
-                    callbacks,
-                    message,
-                    fee,
-                    error_counters,
-                    account_overrides,
-                    program_accounts,
-                    loaded_programs,
-                ) {
-                    Ok(loaded_transaction) => loaded_transaction,
-                    Err(e) => return (Err(e), None),
-                };
+/// Load the accounts of a single transaction of a batch, see `load_accounts()`
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn load_transaction<CB: TransactionProcessingCallback>(
+    callbacks: &CB,
+    tx: &SanitizedTransaction,
+    check_result: &TransactionCheckResult,
+    error_counters: &mut TransactionErrorMetrics,
+    fee_structure: &FeeStructure,
+    account_overrides: Option<&AccountOverrides>,
+    program_accounts: &HashMap<Pubkey, (&Pubkey, u64)>,
+    loaded_programs: &LoadedProgramsForTxBatch,
+    precompiles: &PrecompileRegistry,
+) -> TransactionLoadResult {
+    let (nonce, lamports_per_signature) = match check_result {
+        (Ok(()), nonce, lamports_per_signature) => (nonce, lamports_per_signature),
+        (Err(e), _nonce, _lamports_per_signature) => return (Err(e.clone()), None),
+    };
+    let feature_set = callbacks.get_feature_set();
+    let message = tx.message();
+    if let Err(e) = precompiles.verify(message, &feature_set) {
+        return (Err(e), None);
+    }
+    let fee = if let Some(lamports_per_signature) = lamports_per_signature {
+        let fee = fee_structure.calculate_fee(
+            message,
+            *lamports_per_signature,
+            &process_compute_budget_instructions(message.program_instructions_iter())
+                .unwrap_or_default()
+                .into(),
+            feature_set.is_active(&include_loaded_accounts_data_size_in_fee_calculation::id()),
+            feature_set.is_active(&remove_rounding_in_fee_calculation::id()),
+        );
+        // Signatures of the registered precompiles are charged like the
+        // ones of the SDK precompiles, which `calculate_fee()` counts, at the
+        // rate of the blockhash of the transaction
+        fee.saturating_add(
+            precompiles
+                .num_signatures(message)
+                .saturating_mul(*lamports_per_signature),
+        )
+    } else {
+        return (Err(TransactionError::BlockhashNotFound), None);
+    };
 
-                // Update nonce with fee-subtracted accounts
-                let nonce = if let Some(nonce) = nonce {
-                    match NonceFull::from_partial(
-                        nonce,
-                        message,
-                        &loaded_transaction.accounts,
-                        &loaded_transaction.rent_debits,
-                    ) {
-                        Ok(nonce) => Some(nonce),
-                        // This error branch is never reached, because `load_transaction_accounts`
-                        // already validates the fee payer account.
-                        Err(e) => return (Err(e), None),
-                    }
-                } else {
-                    None
-                };
+    // load transactions
+    let loaded_transaction = match load_transaction_accounts(
+        callbacks,
+        message,
+        fee,
+        error_counters,
+        account_overrides,
+        program_accounts,
+        loaded_programs,
+    ) {
+        Ok(loaded_transaction) => loaded_transaction,
+        Err(e) => return (Err(e), None),
+    };
 
-                (Ok(loaded_transaction), nonce)
-            }
-            (_, (Err(e), _nonce, _lamports_per_signature)) => (Err(e.clone()), None),
-        })
-        .collect()
+    // Update nonce with fee-subtracted accounts
+    let nonce = if let Some(nonce) = nonce {
+        match NonceFull::from_partial(
+            nonce,
+            message,
+            &loaded_transaction.accounts,
+            &loaded_transaction.rent_debits,
+        ) {
+            Ok(nonce) => Some(nonce),
+            // This error branch is never reached, because `load_transaction_accounts`
+            // already validates the fee payer account.
+            Err(e) => return (Err(e), None),
+        }
+    } else {
+        None
+    };
+
+    (Ok(loaded_transaction), nonce)
 }
 
 fn load_transaction_accounts<CB: TransactionProcessingCallback>(
@@ -198,8 +272,25 @@ fn load_transaction_accounts<CB: TransactionProcessingCallback>(
 
     let requested_loaded_accounts_data_size_limit =
         get_requested_loaded_accounts_data_size_limit(message)?;
+    let report_load_budget_overrun =
+        feature_set.is_active(&feature_set::report_load_budget_overrun::id());
     let mut accumulated_accounts_data_size: usize = 0;
 
+    let fee_payer = message.fee_payer();
+    if account_overrides
+        .and_then(|overrides| overrides.get(fee_payer))
+        .is_none()
+    {
+        if let Some(payer_meta) = callbacks.peek_account_meta(fee_payer) {
+            // An oversized fee payer fails the loaded accounts data size check first
+            if requested_loaded_accounts_data_size_limit
+                .map_or(true, |limit| payer_meta.data_len <= limit.get())
+            {
+                precheck_fee_payer(&payer_meta, error_counters, fee)?;
+            }
+        }
+    }
+
     let instruction_accounts = message
         .instructions()
         .iter()
@@ -276,8 +367,11 @@ fn load_transaction_accounts<CB: TransactionProcessingCallback>(
                 };
                 accumulate_and_check_loaded_account_data_size(
                     &mut accumulated_accounts_data_size,
+                    i,
+                    account.owner(),
                     account_size,
                     requested_loaded_accounts_data_size_limit,
+                    report_load_budget_overrun,
                     error_counters,
                 )?;
 
@@ -355,19 +449,24 @@ fn load_transaction_accounts<CB: TransactionProcessingCallback>(
                 builtins_start_index.saturating_add(owner_index)
             } else {
                 let owner_index = accounts.len();
-                if let Some(owner_account) = callbacks.get_account_shared_data(owner_id) {
-                    if !native_loader::check_id(owner_account.owner())
-                        || !owner_account.executable()
-                    {
+                // Check the owner before loading its data
+                if let Some(owner_meta) = callbacks.peek_account_meta(owner_id) {
+                    if !native_loader::check_id(&owner_meta.owner) || !owner_meta.executable {
                         error_counters.invalid_program_for_execution += 1;
                         return Err(TransactionError::InvalidProgramForExecution);
                     }
                     accumulate_and_check_loaded_account_data_size(
                         &mut accumulated_accounts_data_size,
-                        owner_account.data().len(),
+                        owner_index,
+                        &owner_meta.owner,
+                        owner_meta.data_len,
                         requested_loaded_accounts_data_size_limit,
+                        report_load_budget_overrun,
                         error_counters,
                     )?;
+                    let owner_account = callbacks
+                        .get_account_shared_data(owner_id)
+                        .ok_or(TransactionError::ProgramAccountNotFound)?;
                     accounts.push((*owner_id, owner_account));
                 } else {
                     error_counters.account_not_found += 1;
@@ -385,6 +484,7 @@ fn load_transaction_accounts<CB: TransactionProcessingCallback>(
         program_indices,
         rent: tx_rent,
         rent_debits,
+        fee,
     })
 }
 
@@ -429,18 +529,40 @@ fn account_shared_data_from_program(
 /// Accumulate loaded account data size into `accumulated_accounts_data_size`.
 /// Returns TransactionErr::MaxLoadedAccountsDataSizeExceeded if
 /// `requested_loaded_accounts_data_size_limit` is specified and
-/// `accumulated_accounts_data_size` exceeds it.
+/// `accumulated_accounts_data_size` exceeds it, or
+/// TransactionErr::LoadBudgetExceeded if `report_load_budget_overrun`.
+/// `account_index` is the index of the account among the loaded accounts of
+/// the transaction, where loaders not referenced by the message follow the
+/// accounts of the message, and the overrun is counted against
+/// `account_owner`.
 fn accumulate_and_check_loaded_account_data_size(
     accumulated_loaded_accounts_data_size: &mut usize,
+    account_index: usize,
+    account_owner: &Pubkey,
     account_data_size: usize,
     requested_loaded_accounts_data_size_limit: Option<NonZeroUsize>,
+    report_load_budget_overrun: bool,
     error_counters: &mut TransactionErrorMetrics,
 ) -> Result<()> {
     if let Some(requested_loaded_accounts_data_size) = requested_loaded_accounts_data_size_limit {
         saturating_add_assign!(*accumulated_loaded_accounts_data_size, account_data_size);
         if *accumulated_loaded_accounts_data_size > requested_loaded_accounts_data_size.get() {
             error_counters.max_loaded_accounts_data_size_exceeded += 1;
-            Err(TransactionError::MaxLoadedAccountsDataSizeExceeded)
+            *error_counters
+                .max_loaded_accounts_data_size_exceeded_by_owner
+                .entry(*account_owner)
+                .or_default() += 1;
+            if report_load_budget_overrun {
+                Err(TransactionError::LoadBudgetExceeded {
+                    account_index: u8::try_from(account_index).unwrap_or(u8::MAX),
+                    needed: u32::try_from(*accumulated_loaded_accounts_data_size)
+                        .unwrap_or(u32::MAX),
+                    budget: u32::try_from(requested_loaded_accounts_data_size.get())
+                        .unwrap_or(u32::MAX),
+                })
+            } else {
+                Err(TransactionError::MaxLoadedAccountsDataSizeExceeded)
+            }
         } else {
             Ok(())
         }
@@ -480,7 +602,7 @@ mod tests {
             feature_set::FeatureSet,
             fee::FeeStructure,
             hash::Hash,
-            instruction::CompiledInstruction,
+            instruction::{CompiledInstruction, Instruction, InstructionError},
             message::{
                 v0::{LoadedAddresses, LoadedMessage},
                 LegacyMessage, Message, MessageHeader, SanitizedMessage,
@@ -489,6 +611,7 @@ mod tests {
             native_token::sol_to_lamports,
             nonce,
             nonce_info::{NonceFull, NoncePartial},
+            precompiles::PrecompileError,
             pubkey::Pubkey,
             rent::Rent,
             rent_collector::{RentCollector, RENT_EXEMPT_RENT_EPOCH},
@@ -559,6 +682,7 @@ mod tests {
             None,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         )
     }
 
@@ -1036,6 +1160,7 @@ mod tests {
             account_overrides,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         )
     }
 
@@ -1090,6 +1215,7 @@ mod tests {
     #[test]
     fn test_accumulate_and_check_loaded_account_data_size() {
         let mut error_counter = TransactionErrorMetrics::default();
+        let owner = Pubkey::new_unique();
 
         // assert check is OK if data limit is not enabled
         {
@@ -1099,8 +1225,11 @@ mod tests {
 
             assert!(accumulate_and_check_loaded_account_data_size(
                 &mut accumulated_data_size,
+                0,
+                &owner,
                 data_size,
                 requested_data_size_limit,
+                true,
                 &mut error_counter
             )
             .is_ok());
@@ -1115,8 +1244,11 @@ mod tests {
             // OK - loaded data size is up to limit
             assert!(accumulate_and_check_loaded_account_data_size(
                 &mut accumulated_data_size,
+                0,
+                &owner,
                 data_size,
                 requested_data_size_limit,
+                true,
                 &mut error_counter
             )
             .is_ok());
@@ -1127,12 +1259,43 @@ mod tests {
             assert_eq!(
                 accumulate_and_check_loaded_account_data_size(
                     &mut accumulated_data_size,
+                    1,
+                    &owner,
                     another_byte,
                     requested_data_size_limit,
+                    false,
                     &mut error_counter
                 ),
                 Err(TransactionError::MaxLoadedAccountsDataSizeExceeded)
             );
+            assert_eq!(error_counter.max_loaded_accounts_data_size_exceeded, 1);
+            assert_eq!(
+                error_counter.max_loaded_accounts_data_size_exceeded_by_owner,
+                HashMap::from([(owner, 1)])
+            );
+        }
+
+        // assert the overrun is reported in the error once the feature is active
+        {
+            let mut accumulated_data_size: usize = 123;
+            let requested_data_size_limit = NonZeroUsize::new(123);
+            assert_eq!(
+                accumulate_and_check_loaded_account_data_size(
+                    &mut accumulated_data_size,
+                    1,
+                    &owner,
+                    1,
+                    requested_data_size_limit,
+                    true,
+                    &mut error_counter
+                ),
+                Err(TransactionError::LoadBudgetExceeded {
+                    account_index: 1,
+                    needed: 124,
+                    budget: 123,
+                })
+            );
+            assert_eq!(error_counter.max_loaded_accounts_data_size_exceeded, 2);
         }
     }
 
@@ -1467,6 +1630,102 @@ mod tests {
         assert_eq!(result.err(), Some(TransactionError::AccountNotFound));
     }
 
+    #[test]
+    fn test_load_transaction_accounts_precheck_fee_payer() {
+        struct MetaOnlyCallbacks {
+            payer_meta: AccountMeta,
+            rent_collector: RentCollector,
+        }
+
+        impl TransactionProcessingCallback for MetaOnlyCallbacks {
+            fn account_matches_owners(
+                &self,
+                _account: &Pubkey,
+                _owners: &[Pubkey],
+            ) -> Option<usize> {
+                None
+            }
+
+            fn get_account_shared_data(&self, _pubkey: &Pubkey) -> Option<AccountSharedData> {
+                panic!("accounts must not be loaded");
+            }
+
+            fn peek_account_meta(&self, _pubkey: &Pubkey) -> Option<AccountMeta> {
+                Some(self.payer_meta)
+            }
+
+            fn get_last_blockhash_and_lamports_per_signature(&self) -> (Hash, u64) {
+                (Hash::new_unique(), 0)
+            }
+
+            fn get_rent_collector(&self) -> &RentCollector {
+                &self.rent_collector
+            }
+
+            fn get_feature_set(&self) -> Arc<FeatureSet> {
+                Arc::new(FeatureSet::default())
+            }
+        }
+
+        let payer = Keypair::new();
+        let tx = system_transaction::transfer(&payer, &Pubkey::new_unique(), 1, Hash::default());
+        let sanitized_transaction = SanitizedTransaction::from_transaction_for_tests(tx);
+        let payer_meta = AccountMeta {
+            lamports: 10,
+            owner: system_program::id(),
+            data_len: 0,
+            executable: false,
+            rent_epoch: RENT_EXEMPT_RENT_EPOCH,
+        };
+        let load = |payer_meta: AccountMeta, error_counters: &mut TransactionErrorMetrics| {
+            load_transaction_accounts(
+                &MetaOnlyCallbacks {
+                    payer_meta,
+                    rent_collector: RentCollector::default(),
+                },
+                sanitized_transaction.message(),
+                32,
+                error_counters,
+                None,
+                &HashMap::new(),
+                &LoadedProgramsForTxBatch::default(),
+            )
+        };
+
+        let mut error_counters = TransactionErrorMetrics::default();
+        assert_eq!(
+            load(payer_meta, &mut error_counters).err(),
+            Some(TransactionError::InsufficientFundsForFee)
+        );
+        assert_eq!(error_counters.insufficient_funds, 1);
+
+        assert_eq!(
+            load(
+                AccountMeta {
+                    owner: Pubkey::new_unique(),
+                    ..payer_meta
+                },
+                &mut error_counters
+            )
+            .err(),
+            Some(TransactionError::InvalidAccountForFee)
+        );
+        assert_eq!(error_counters.invalid_account_for_fee, 1);
+
+        assert_eq!(
+            load(
+                AccountMeta {
+                    lamports: 0,
+                    ..payer_meta
+                },
+                &mut error_counters
+            )
+            .err(),
+            Some(TransactionError::AccountNotFound)
+        );
+        assert_eq!(error_counters.account_not_found, 1);
+    }
+
     #[test]
     fn test_load_transaction_accounts_native_loader() {
         let key1 = Keypair::new();
@@ -1529,7 +1788,8 @@ mod tests {
                 ],
                 program_indices: vec![vec![]],
                 rent: 0,
-                rent_debits: RentDebits::default()
+                rent_debits: RentDebits::default(),
+                fee: 32
             }
         );
     }
@@ -1735,7 +1995,8 @@ mod tests {
                 ],
                 program_indices: vec![vec![1]],
                 rent: 0,
-                rent_debits: RentDebits::default()
+                rent_debits: RentDebits::default(),
+                fee: 32
             }
         );
     }
@@ -1929,7 +2190,8 @@ mod tests {
                 ],
                 program_indices: vec![vec![2, 1]],
                 rent: 0,
-                rent_debits: RentDebits::default()
+                rent_debits: RentDebits::default(),
+                fee: 32
             }
         );
     }
@@ -2021,7 +2283,8 @@ mod tests {
                 ],
                 program_indices: vec![vec![3, 1], vec![3, 1]],
                 rent: 0,
-                rent_debits: RentDebits::default()
+                rent_debits: RentDebits::default(),
+                fee: 32
             }
         );
     }
@@ -2064,6 +2327,7 @@ mod tests {
             None,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         );
 
         let compute_budget = ComputeBudget::new(u64::from(
@@ -2149,6 +2413,7 @@ mod tests {
             None,
             &HashMap::new(),
             &loaded_programs,
+            &PrecompileRegistry::default(),
         );
 
         let mut account_data = AccountSharedData::default();
@@ -2176,7 +2441,8 @@ mod tests {
                 ],
                 program_indices: vec![vec![3, 1], vec![3, 1]],
                 rent: 0,
-                rent_debits: RentDebits::default()
+                rent_debits: RentDebits::default(),
+                fee: 0
             }
         );
 
@@ -2224,6 +2490,7 @@ mod tests {
             None,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         );
 
         assert_eq!(
@@ -2243,6 +2510,7 @@ mod tests {
             None,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         );
 
         assert_eq!(result, vec![(Err(TransactionError::AccountNotFound), None)]);
@@ -2262,6 +2530,7 @@ mod tests {
             None,
             &HashMap::new(),
             &LoadedProgramsForTxBatch::default(),
+            &PrecompileRegistry::default(),
         );
 
         assert_eq!(
@@ -2269,4 +2538,83 @@ mod tests {
             vec![(Err(TransactionError::InvalidWritableAccount), None)]
         );
     }
+
+    #[test]
+    fn test_load_accounts_registered_precompiles() {
+        let precompile_id = Pubkey::new_unique();
+        let mut precompiles = PrecompileRegistry::default();
+        precompiles.register(
+            precompile_id,
+            |data, _, _| {
+                (data.get(1) == Some(&42))
+                    .then_some(())
+                    .ok_or(PrecompileError::InvalidSignature)
+            },
+            crate::precompile_registry::first_byte_signature_count,
+        );
+
+        let payer = Keypair::new();
+        let mut precompile_account = AccountSharedData::new(1, 0, &native_loader::id());
+        precompile_account.set_executable(true);
+        let callbacks = TestCallbacks {
+            accounts_map: HashMap::from([
+                (
+                    payer.pubkey(),
+                    AccountSharedData::new(1_000_000, 0, &system_program::id()),
+                ),
+                (precompile_id, precompile_account),
+            ]),
+            rent_collector: RentCollector::default(),
+            feature_set: Arc::new(FeatureSet::all_enabled()),
+        };
+        // The signatures of the registered precompiles are charged at the
+        // rate of the blockhash, which differs from the fee structure's here
+        let lamports_per_signature = 7_000;
+        let fee_structure = FeeStructure {
+            lamports_per_signature: 5_000,
+            ..FeeStructure::default()
+        };
+        let load = |data: &[u8]| {
+            let tx = SanitizedTransaction::from_transaction_for_tests(
+                Transaction::new_signed_with_payer(
+                    &[Instruction::new_with_bytes(precompile_id, data, vec![])],
+                    Some(&payer.pubkey()),
+                    &[&payer],
+                    Hash::default(),
+                ),
+            );
+            load_accounts(
+                &callbacks,
+                &[tx],
+                &[(Ok(()), None, Some(lamports_per_signature))],
+                &mut TransactionErrorMetrics::default(),
+                &fee_structure,
+                None,
+                &HashMap::new(),
+                &LoadedProgramsForTxBatch::default(),
+                &precompiles,
+            )
+        };
+
+        // The signature of the transaction and the two of the instruction
+        match &load(&[2, 42])[0] {
+            (Ok(loaded_transaction), _nonce) => {
+                assert_eq!(
+                    loaded_transaction.fee,
+                    fee_structure.lamports_per_signature + 2 * lamports_per_signature
+                );
+            }
+            (Err(e), _nonce) => panic!("{e}"),
+        }
+        assert_eq!(
+            load(&[2, 0]),
+            vec![(
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(PrecompileError::InvalidSignature as u32)
+                )),
+                None
+            )]
+        );
+    }
 }
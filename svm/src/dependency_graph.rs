@@ -0,0 +1,245 @@
+//! Account conflicts between the transactions of a batch, as a DAG.
+//!
+//! External schedulers and block builders need to know which transactions
+//! may run in parallel. Rather than reimplementing the lock semantics of the
+//! SVM, they can consume a `DependencyGraph`: one node per transaction and an
+//! edge from a transaction to every later transaction which must wait for
+//! it, because both lock the same account and at least one of them writes it.
+//!
+//! Only the direct dependency on an account is recorded: a write depends on
+//! the reads since the previous write or, without such reads, on the previous
+//! write itself, and a read depends on the previous write. Conflicts implied
+//! by transitivity are left out.
+
+use {
+    serde::Serialize,
+    solana_sdk::{pubkey::Pubkey, transaction::SanitizedTransaction},
+    std::collections::HashMap,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    pub nodes: Vec<DependencyNode>,
+    /// Edges ordered by the index of their dependent transaction
+    pub edges: Vec<DependencyEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyNode {
+    /// Position of the transaction in the batch
+    pub index: usize,
+    /// First signature of the transaction, base58 encoded
+    pub signature: String,
+    /// Why the transaction can't lock its accounts, e.g. too many account
+    /// locks. Such a transaction fails without executing and has no edges.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyEdge {
+    /// Index of the transaction which must execute first
+    pub from: usize,
+    /// Index of the dependent transaction
+    pub to: usize,
+    /// The account both transactions lock, base58 encoded
+    pub account: String,
+    pub conflict: ConflictKind,
+}
+
+/// How the transactions of an edge access the account, `from` first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    WriteWrite,
+    WriteRead,
+    ReadWrite,
+}
+
+#[derive(Default)]
+struct AccountAccess {
+    last_writer: Option<usize>,
+    /// Readers since `last_writer`
+    readers: Vec<usize>,
+}
+
+impl DependencyGraph {
+    /// Build the graph of `sanitized_txs`, in batch order. Account locks are
+    /// validated against `tx_account_lock_limit`, as when locking a batch.
+    pub fn new(sanitized_txs: &[SanitizedTransaction], tx_account_lock_limit: usize) -> Self {
+        let mut graph = Self::default();
+        let mut accesses: HashMap<&Pubkey, AccountAccess> = HashMap::new();
+        for (index, tx) in sanitized_txs.iter().enumerate() {
+            let account_locks = tx.get_account_locks(tx_account_lock_limit);
+            graph.nodes.push(DependencyNode {
+                index,
+                signature: tx.signature().to_string(),
+                error: account_locks.as_ref().err().map(ToString::to_string),
+            });
+            let Ok(account_locks) = account_locks else {
+                continue;
+            };
+
+            for account in account_locks.writable {
+                let access = accesses.entry(account).or_default();
+                if !access.readers.is_empty() {
+                    for reader in access.readers.drain(..) {
+                        graph.add_edge(reader, index, account, ConflictKind::ReadWrite);
+                    }
+                } else if let Some(writer) = access.last_writer {
+                    graph.add_edge(writer, index, account, ConflictKind::WriteWrite);
+                }
+                access.last_writer = Some(index);
+            }
+            for account in account_locks.readonly {
+                let access = accesses.entry(account).or_default();
+                if let Some(writer) = access.last_writer {
+                    graph.add_edge(writer, index, account, ConflictKind::WriteRead);
+                }
+                access.readers.push(index);
+            }
+        }
+        graph
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, account: &Pubkey, conflict: ConflictKind) {
+        self.edges.push(DependencyEdge {
+            from,
+            to,
+            account: account.to_string(),
+            conflict,
+        });
+    }
+
+    /// Serialize the graph into a JSON object
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("dependency graphs are always serializable")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash,
+            instruction::{AccountMeta, Instruction},
+            signature::{Keypair, Signer},
+            system_transaction,
+            transaction::{Transaction, MAX_TX_ACCOUNT_LOCKS},
+        },
+    };
+
+    fn new_transfer(to: &Pubkey) -> SanitizedTransaction {
+        SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &Keypair::new(),
+            to,
+            1,
+            Hash::default(),
+        ))
+    }
+
+    fn new_read(account: &Pubkey) -> SanitizedTransaction {
+        let payer = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(*account, false)],
+        );
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        ))
+    }
+
+    fn edge(from: usize, to: usize, account: &Pubkey, conflict: ConflictKind) -> DependencyEdge {
+        DependencyEdge {
+            from,
+            to,
+            account: account.to_string(),
+            conflict,
+        }
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let shared = Pubkey::new_unique();
+        let txs = vec![
+            new_transfer(&shared),
+            new_transfer(&shared),
+            new_transfer(&Pubkey::new_unique()),
+            new_read(&shared),
+            new_read(&shared),
+            new_transfer(&shared),
+        ];
+
+        let graph = DependencyGraph::new(&txs, MAX_TX_ACCOUNT_LOCKS);
+        assert_eq!(graph.nodes.len(), txs.len());
+        for (index, (node, tx)) in graph.nodes.iter().zip(&txs).enumerate() {
+            assert_eq!(node.index, index);
+            assert_eq!(node.signature, tx.signature().to_string());
+            assert_eq!(node.error, None);
+        }
+        // The transfers only read the system program, which never conflicts
+        assert_eq!(
+            graph.edges,
+            vec![
+                edge(0, 1, &shared, ConflictKind::WriteWrite),
+                edge(1, 3, &shared, ConflictKind::WriteRead),
+                edge(1, 4, &shared, ConflictKind::WriteRead),
+                edge(3, 5, &shared, ConflictKind::ReadWrite),
+                edge(4, 5, &shared, ConflictKind::ReadWrite),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependency_graph_lock_error() {
+        let shared = Pubkey::new_unique();
+        let txs = vec![new_transfer(&shared), new_transfer(&shared)];
+
+        // Transfers lock three accounts
+        let graph = DependencyGraph::new(&txs, 2);
+        assert!(graph.edges.is_empty());
+        for node in &graph.nodes {
+            assert_eq!(
+                node.error.as_deref(),
+                Some("Transaction locked too many accounts")
+            );
+        }
+    }
+
+    #[test]
+    fn test_dependency_graph_to_json() {
+        let shared = Pubkey::new_unique();
+        let txs = vec![new_transfer(&shared), new_read(&shared)];
+        let graph = DependencyGraph::new(&txs, MAX_TX_ACCOUNT_LOCKS);
+        assert_eq!(
+            graph.to_json(),
+            serde_json::json!({
+                "nodes": [
+                    {
+                        "index": 0,
+                        "signature": txs[0].signature().to_string(),
+                        "error": null,
+                    },
+                    {
+                        "index": 1,
+                        "signature": txs[1].signature().to_string(),
+                        "error": null,
+                    },
+                ],
+                "edges": [{
+                    "from": 0,
+                    "to": 1,
+                    "account": shared.to_string(),
+                    "conflict": "writeRead",
+                }],
+            })
+        );
+    }
+}
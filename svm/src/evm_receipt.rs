@@ -0,0 +1,331 @@
+//! Conversion of transaction execution results into EVM-style receipts.
+//!
+//! Indexers built for EVM chains expect one receipt per included
+//! transaction with a status, gas accounting and a list of emitted logs.
+//! `EvmReceipt` mirrors that shape with stable camelCase field names:
+//! consumed compute units stand in for gas, program logs and `sol_log_data`
+//! output become structured events attributed to the emitting program, and
+//! return data is hex encoded.
+
+use {
+    crate::transaction_results::TransactionExecutionResult,
+    serde::Serialize,
+    solana_sdk::{clock::Slot, transaction::SanitizedTransaction},
+};
+
+const STATUS_SUCCESS: &str = "0x1";
+const STATUS_FAILURE: &str = "0x0";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmReceipt {
+    /// First signature of the transaction, base58 encoded
+    pub transaction_hash: String,
+    /// Position of the transaction among the executed transactions
+    pub transaction_index: u64,
+    pub block_number: Slot,
+    /// `0x1` on success, `0x0` on failure
+    pub status: &'static str,
+    /// Compute units consumed by this transaction
+    pub gas_used: u64,
+    /// Compute units consumed by this and all preceding transactions
+    pub cumulative_gas_used: u64,
+    pub logs: Vec<EvmLog>,
+    pub return_data: Option<EvmReturnData>,
+    /// Transaction error, if the transaction failed
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmLog {
+    /// Program which emitted the event
+    pub address: String,
+    /// Position of the event within the receipt
+    pub log_index: u64,
+    pub kind: EvmLogKind,
+    /// Message of a `Log` event, or the space separated base64 fields of a
+    /// `Data` event
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EvmLogKind {
+    /// Emitted with `sol_log`
+    Log,
+    /// Emitted with `sol_log_data`
+    Data,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmReturnData {
+    pub address: String,
+    /// `0x` prefixed hex encoding of the returned bytes
+    pub data: String,
+}
+
+/// Build receipts for the transactions of a batch which were executed.
+/// Transactions which were not executed are not part of the block and get
+/// no receipt.
+pub fn export_receipts(
+    slot: Slot,
+    sanitized_txs: &[SanitizedTransaction],
+    execution_results: &[TransactionExecutionResult],
+) -> Vec<EvmReceipt> {
+    let mut cumulative_gas_used = 0u64;
+    sanitized_txs
+        .iter()
+        .zip(execution_results)
+        .filter_map(|(tx, result)| Some((tx, result.details()?)))
+        .enumerate()
+        .map(|(index, (tx, details))| {
+            cumulative_gas_used = cumulative_gas_used.saturating_add(details.executed_units);
+            EvmReceipt {
+                transaction_hash: tx.signature().to_string(),
+                transaction_index: index as u64,
+                block_number: slot,
+                status: if details.status.is_ok() {
+                    STATUS_SUCCESS
+                } else {
+                    STATUS_FAILURE
+                },
+                gas_used: details.executed_units,
+                cumulative_gas_used,
+                logs: details
+                    .log_messages
+                    .as_deref()
+                    .map(parse_logs)
+                    .unwrap_or_default(),
+                return_data: details
+                    .return_data
+                    .as_ref()
+                    .map(|return_data| EvmReturnData {
+                        address: return_data.program_id.to_string(),
+                        data: to_hex(&return_data.data),
+                    }),
+                error: details.status.as_ref().err().map(ToString::to_string),
+            }
+        })
+        .collect()
+}
+
+/// Serialize receipts into a JSON array
+pub fn receipts_to_json(receipts: &[EvmReceipt]) -> serde_json::Value {
+    serde_json::to_value(receipts).expect("receipts are always serializable")
+}
+
+/// Turn the program log messages of a transaction into events, attributing
+/// each one to the program on top of the invocation stack.
+fn parse_logs(log_messages: &[String]) -> Vec<EvmLog> {
+    let mut invocation_stack: Vec<&str> = vec![];
+    let mut logs = vec![];
+    for message in log_messages {
+        let Some(rest) = message.strip_prefix("Program ") else {
+            continue;
+        };
+        let (kind, data) = if let Some(data) = rest.strip_prefix("log: ") {
+            (EvmLogKind::Log, data)
+        } else if let Some(data) = rest.strip_prefix("data: ") {
+            (EvmLogKind::Data, data)
+        } else {
+            let mut words = rest.split(' ');
+            match (words.next(), words.next()) {
+                (Some(program_id), Some("invoke")) => invocation_stack.push(program_id),
+                (Some(_), Some("success")) | (Some(_), Some("failed:")) => {
+                    invocation_stack.pop();
+                }
+                _ => {}
+            }
+            continue;
+        };
+        let Some(address) = invocation_stack.last() else {
+            continue;
+        };
+        logs.push(EvmLog {
+            address: address.to_string(),
+            log_index: logs.len() as u64,
+            kind,
+            data: data.to_string(),
+        });
+    }
+    logs
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(2 + bytes.len() * 2);
+    hex.push_str("0x");
+    for byte in bytes {
+        hex.push_str(&format!("{byte:02x}"));
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::transaction_results::{AccountLifecycle, TransactionExecutionDetails},
+        solana_program_runtime::loaded_programs::LoadedProgramsForTxBatch,
+        solana_sdk::{
+            hash::Hash,
+            instruction::InstructionError,
+            pubkey::Pubkey,
+            signature::Keypair,
+            system_transaction,
+            transaction::{Result, TransactionError},
+            transaction_context::TransactionReturnData,
+        },
+    };
+
+    fn new_tx() -> SanitizedTransaction {
+        let payer = Keypair::new();
+        SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &payer,
+            &Pubkey::new_unique(),
+            1,
+            Hash::default(),
+        ))
+    }
+
+    fn executed(
+        status: Result<()>,
+        executed_units: u64,
+        log_messages: Option<Vec<String>>,
+        return_data: Option<TransactionReturnData>,
+    ) -> TransactionExecutionResult {
+        TransactionExecutionResult::Executed {
+            details: TransactionExecutionDetails {
+                status,
+                log_messages,
+                inner_instructions: None,
+                durable_nonce_fee: None,
+                return_data,
+                executed_units,
+                accounts_data_len_delta: 0,
+                accounts_data_growth: 0,
+                heap_allocation_stats: None,
+                error_location: None,
+                capability_manifest: None,
+                decoded_instructions: None,
+                compute_budget_report: None,
+                account_lifecycle: AccountLifecycle::default(),
+                signature_policy_decision: None,
+            },
+            programs_modified_by_tx: Box::<LoadedProgramsForTxBatch>::default(),
+        }
+    }
+
+    #[test]
+    fn test_export_receipts() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let log_messages = vec![
+            format!("Program {program_a} invoke [1]"),
+            "Program log: hello".to_string(),
+            format!("Program {program_b} invoke [2]"),
+            "Program data: AQID BAU=".to_string(),
+            format!("Program {program_b} consumed 100 of 200 compute units"),
+            format!("Program {program_b} success"),
+            "Program log: bye".to_string(),
+            format!("Program {program_a} success"),
+        ];
+        let txs = vec![new_tx(), new_tx(), new_tx()];
+        let results = vec![
+            executed(
+                Ok(()),
+                150,
+                Some(log_messages),
+                Some(TransactionReturnData {
+                    program_id: program_a,
+                    data: vec![0xde, 0xad, 0x01],
+                }),
+            ),
+            TransactionExecutionResult::NotExecuted(TransactionError::AccountNotFound),
+            executed(
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::Custom(7),
+                )),
+                50,
+                None,
+                None,
+            ),
+        ];
+
+        let receipts = export_receipts(42, &txs, &results);
+        assert_eq!(receipts.len(), 2);
+
+        let receipt = &receipts[0];
+        assert_eq!(receipt.transaction_hash, txs[0].signature().to_string());
+        assert_eq!(receipt.transaction_index, 0);
+        assert_eq!(receipt.block_number, 42);
+        assert_eq!(receipt.status, STATUS_SUCCESS);
+        assert_eq!(receipt.gas_used, 150);
+        assert_eq!(receipt.cumulative_gas_used, 150);
+        assert_eq!(
+            receipt.logs,
+            vec![
+                EvmLog {
+                    address: program_a.to_string(),
+                    log_index: 0,
+                    kind: EvmLogKind::Log,
+                    data: "hello".to_string(),
+                },
+                EvmLog {
+                    address: program_b.to_string(),
+                    log_index: 1,
+                    kind: EvmLogKind::Data,
+                    data: "AQID BAU=".to_string(),
+                },
+                EvmLog {
+                    address: program_a.to_string(),
+                    log_index: 2,
+                    kind: EvmLogKind::Log,
+                    data: "bye".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            receipt.return_data,
+            Some(EvmReturnData {
+                address: program_a.to_string(),
+                data: "0xdead01".to_string(),
+            })
+        );
+        assert_eq!(receipt.error, None);
+
+        let receipt = &receipts[1];
+        assert_eq!(receipt.transaction_hash, txs[2].signature().to_string());
+        assert_eq!(receipt.transaction_index, 1);
+        assert_eq!(receipt.status, STATUS_FAILURE);
+        assert_eq!(receipt.gas_used, 50);
+        assert_eq!(receipt.cumulative_gas_used, 200);
+        assert!(receipt.logs.is_empty());
+        assert_eq!(
+            receipt.error.as_deref(),
+            Some("Error processing Instruction 0: custom program error: 0x7")
+        );
+    }
+
+    #[test]
+    fn test_receipts_to_json() {
+        let tx = new_tx();
+        let receipts = export_receipts(1, &[tx.clone()], &[executed(Ok(()), 10, None, None)]);
+        assert_eq!(
+            receipts_to_json(&receipts),
+            serde_json::json!([{
+                "transactionHash": tx.signature().to_string(),
+                "transactionIndex": 0,
+                "blockNumber": 1,
+                "status": "0x1",
+                "gasUsed": 10,
+                "cumulativeGasUsed": 10,
+                "logs": [],
+                "returnData": null,
+                "error": null,
+            }])
+        );
+    }
+}
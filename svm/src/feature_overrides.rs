@@ -0,0 +1,96 @@
+//! Per-transaction feature overrides, for testing feature gated behavior.
+//!
+//! Testing both sides of a feature gate normally requires a bank per
+//! feature set. Overrides activate or deactivate features for single
+//! transactions of a batch instead, on top of the feature set of the
+//! environment, so that the old and new behavior can be compared within one
+//! batch.
+//!
+//! Overrides are layered over the feature set seen by the invoke context,
+//! which covers the feature checks of builtins, syscalls and the runtime
+//! during execution. The program runtime environments are shared by the
+//! batch, so the set of registered syscalls and the verification of
+//! programs are not affected.
+
+use {
+    solana_sdk::{feature_set::FeatureSet, pubkey::Pubkey, signature::Signature},
+    std::{collections::HashMap, sync::Arc},
+};
+
+/// Features to activate and deactivate for one transaction
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FeatureOverride {
+    pub activate: Vec<Pubkey>,
+    pub deactivate: Vec<Pubkey>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct FeatureOverrides {
+    /// Overrides by signature of the transaction
+    overrides: HashMap<Signature, FeatureOverride>,
+}
+
+impl FeatureOverrides {
+    /// Override the features of the transaction with the given signature
+    pub fn insert(&mut self, signature: Signature, feature_override: FeatureOverride) {
+        self.overrides.insert(signature, feature_override);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.overrides.is_empty()
+    }
+
+    /// The feature set of the transaction with the given signature, or
+    /// `None` if it has no overrides. Features are activated at `slot`.
+    pub fn feature_set_for(
+        &self,
+        signature: &Signature,
+        feature_set: &FeatureSet,
+        slot: u64,
+    ) -> Option<Arc<FeatureSet>> {
+        let feature_override = self.overrides.get(signature)?;
+        let mut feature_set = feature_set.clone();
+        for feature_id in &feature_override.activate {
+            feature_set.activate(feature_id, slot);
+        }
+        for feature_id in &feature_override.deactivate {
+            feature_set.deactivate(feature_id);
+        }
+        Some(Arc::new(feature_set))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::feature_set};
+
+    #[test]
+    fn test_feature_set_for() {
+        let mut base = FeatureSet::default();
+        base.activate(&feature_set::enable_partitioned_epoch_reward::id(), 0);
+        let signature = Signature::new_unique();
+        let mut overrides = FeatureOverrides::default();
+        assert!(overrides.is_empty());
+        overrides.insert(
+            signature,
+            FeatureOverride {
+                activate: vec![feature_set::reject_callx_r10::id()],
+                deactivate: vec![feature_set::enable_partitioned_epoch_reward::id()],
+            },
+        );
+
+        assert_eq!(
+            overrides.feature_set_for(&Signature::new_unique(), &base, 5),
+            None
+        );
+        let feature_set = overrides.feature_set_for(&signature, &base, 5).unwrap();
+        assert_eq!(
+            feature_set.activated_slot(&feature_set::reject_callx_r10::id()),
+            Some(5)
+        );
+        assert!(!feature_set.is_active(&feature_set::enable_partitioned_epoch_reward::id()));
+        // The base feature set is left untouched
+        assert!(base.is_active(&feature_set::enable_partitioned_epoch_reward::id()));
+        assert!(!base.is_active(&feature_set::reject_callx_r10::id()));
+    }
+}
@@ -0,0 +1,127 @@
+//! Hooks for distributing the fees collected by a transaction batch.
+//!
+//! The SVM only charges fees to fee payers. What happens to the collected
+//! lamports afterwards is up to the embedder: the L1 bank distributes them
+//! itself when the block is frozen, while rollups may want to burn a share,
+//! credit a collector account or pay out sequencer revenue. A `FeeDistributor`
+//! is invoked after every batch with the fees collected per transaction and
+//! returns a `FeeDistribution`, which the embedder applies when committing the
+//! batch.
+
+use solana_sdk::pubkey::Pubkey;
+
+/// Fees collected by a batch of transactions
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CollectedFees {
+    /// Sum of all collected fees
+    pub total: u64,
+    /// Fee charged to each transaction of the batch, in batch order. `None`
+    /// for transactions which were not executed and thus not charged.
+    pub transaction_fees: Vec<Option<u64>>,
+}
+
+impl CollectedFees {
+    pub fn new(transaction_fees: Vec<Option<u64>>) -> Self {
+        Self {
+            total: transaction_fees
+                .iter()
+                .flatten()
+                .fold(0u64, |total, fee| total.saturating_add(*fee)),
+            transaction_fees,
+        }
+    }
+}
+
+/// How the collected fees of a batch are to be distributed. Lamports which
+/// are neither burned nor credited remain undistributed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FeeDistribution {
+    /// Lamports to remove from circulation
+    pub burned: u64,
+    /// Lamports to credit to accounts
+    pub credits: Vec<(Pubkey, u64)>,
+}
+
+pub trait FeeDistributor: Send + Sync {
+    fn distribute(&self, collected_fees: &CollectedFees) -> FeeDistribution;
+}
+
+/// Leaves fee distribution to the embedder, as the L1 bank does
+#[derive(Debug, Default)]
+pub struct NoopFeeDistributor;
+
+impl FeeDistributor for NoopFeeDistributor {
+    fn distribute(&self, _collected_fees: &CollectedFees) -> FeeDistribution {
+        FeeDistribution::default()
+    }
+}
+
+/// Burns a percentage of the collected fees and credits the remainder to a
+/// collector account
+#[derive(Debug)]
+pub struct BurnAndCollectFeeDistributor {
+    pub burn_percent: u8,
+    pub collector_id: Pubkey,
+}
+
+impl FeeDistributor for BurnAndCollectFeeDistributor {
+    fn distribute(&self, collected_fees: &CollectedFees) -> FeeDistribution {
+        let burn_percent = u64::from(self.burn_percent.min(100));
+        let burned = (u128::from(collected_fees.total) * u128::from(burn_percent) / 100) as u64;
+        let collected = collected_fees.total.saturating_sub(burned);
+        FeeDistribution {
+            burned,
+            credits: if collected > 0 {
+                vec![(self.collector_id, collected)]
+            } else {
+                vec![]
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collected_fees() {
+        let collected_fees = CollectedFees::new(vec![Some(5000), None, Some(10_000)]);
+        assert_eq!(collected_fees.total, 15_000);
+        assert_eq!(
+            NoopFeeDistributor.distribute(&collected_fees),
+            FeeDistribution::default()
+        );
+    }
+
+    #[test]
+    fn test_burn_and_collect() {
+        let collector_id = Pubkey::new_unique();
+        let collected_fees = CollectedFees::new(vec![Some(5001), Some(10_000)]);
+
+        let distributor = BurnAndCollectFeeDistributor {
+            burn_percent: 50,
+            collector_id,
+        };
+        assert_eq!(
+            distributor.distribute(&collected_fees),
+            FeeDistribution {
+                burned: 7500,
+                credits: vec![(collector_id, 7501)],
+            }
+        );
+
+        // Everything burned, nothing to credit
+        let distributor = BurnAndCollectFeeDistributor {
+            burn_percent: 200,
+            collector_id,
+        };
+        assert_eq!(
+            distributor.distribute(&collected_fees),
+            FeeDistribution {
+                burned: 15_001,
+                credits: vec![],
+            }
+        );
+    }
+}
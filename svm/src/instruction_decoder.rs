@@ -0,0 +1,289 @@
+//! Decoding of the instructions of well known programs, for human-readable
+//! receipts.
+//!
+//! Explorers and wallets built on the output of the SVM render instructions
+//! as actions, e.g. "transfer 5 lamports from A to B". Instead of each of
+//! them maintaining a decoding stack for the builtin programs, the
+//! transaction processor decodes the top-level instructions of a
+//! transaction when `ExecutionRecordingConfig::enable_instruction_decoding`
+//! is set, with an `InstructionDecoderRegistry`. The default registry knows
+//! the system, stake, vote and compute budget programs, and embedders can
+//! register decoders for their own programs.
+//!
+//! The arguments of a decoded instruction are the JSON representation of
+//! the instruction enum of its program. Addresses and hashes in them are
+//! rendered as base58 strings.
+
+use {
+    serde_json::Value,
+    solana_sdk::{
+        borsh1::try_from_slice_unchecked, compute_budget::ComputeBudgetInstruction,
+        message::SanitizedMessage, program_utils::limited_deserialize, pubkey::Pubkey,
+        stake::instruction::StakeInstruction, system_instruction::SystemInstruction,
+        vote::instruction::VoteInstruction,
+    },
+    std::collections::HashMap,
+};
+
+/// Decode the data of an instruction into the JSON representation of the
+/// instruction enum of the program, or `None` if it is not a valid
+/// instruction of the program
+pub type InstructionDecoderFn = fn(data: &[u8]) -> Option<Value>;
+
+/// An instruction of a known program, decoded
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedInstruction {
+    pub program_id: Pubkey,
+    pub program_name: &'static str,
+    /// Name of the variant of the instruction enum, e.g. `Transfer`
+    pub instruction_name: String,
+    /// Accounts passed to the instruction, in order
+    pub accounts: Vec<Pubkey>,
+    /// Arguments of the instruction, `Null` if it has none
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone)]
+struct InstructionDecoder {
+    program_name: &'static str,
+    decode: InstructionDecoderFn,
+}
+
+/// Decoders by program id
+#[derive(Debug, Clone)]
+pub struct InstructionDecoderRegistry {
+    decoders: HashMap<Pubkey, InstructionDecoder>,
+}
+
+impl Default for InstructionDecoderRegistry {
+    /// A registry with the decoders of the builtin programs
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(
+            solana_sdk::system_program::id(),
+            "system",
+            decode_system_instruction,
+        );
+        registry.register(
+            solana_sdk::stake::program::id(),
+            "stake",
+            decode_stake_instruction,
+        );
+        registry.register(
+            solana_sdk::vote::program::id(),
+            "vote",
+            decode_vote_instruction,
+        );
+        registry.register(
+            solana_sdk::compute_budget::id(),
+            "compute_budget",
+            decode_compute_budget_instruction,
+        );
+        registry
+    }
+}
+
+impl InstructionDecoderRegistry {
+    /// A registry without any decoder
+    pub fn empty() -> Self {
+        Self {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Decode the instructions of `program_id` with `decode`, replacing its
+    /// previous decoder, if any
+    pub fn register(
+        &mut self,
+        program_id: Pubkey,
+        program_name: &'static str,
+        decode: InstructionDecoderFn,
+    ) {
+        self.decoders.insert(
+            program_id,
+            InstructionDecoder {
+                program_name,
+                decode,
+            },
+        );
+    }
+
+    /// Decode an instruction, or return `None` if its program is unknown or
+    /// its data is not a valid instruction of the program
+    pub fn decode(
+        &self,
+        program_id: &Pubkey,
+        accounts: Vec<Pubkey>,
+        data: &[u8],
+    ) -> Option<DecodedInstruction> {
+        let decoder = self.decoders.get(program_id)?;
+        let (instruction_name, arguments) = match (decoder.decode)(data)? {
+            // Variants without arguments
+            Value::String(name) => (name, Value::Null),
+            Value::Object(object) if object.len() == 1 => {
+                let (name, arguments) = object.into_iter().next()?;
+                (name, arguments)
+            }
+            _ => return None,
+        };
+        Some(DecodedInstruction {
+            program_id: *program_id,
+            program_name: decoder.program_name,
+            instruction_name,
+            accounts,
+            arguments: with_base58_addresses(arguments),
+        })
+    }
+
+    /// Decode the top-level instructions of `message`, in order
+    pub fn decode_message(&self, message: &SanitizedMessage) -> Vec<Option<DecodedInstruction>> {
+        let account_keys = message.account_keys();
+        message
+            .program_instructions_iter()
+            .map(|(program_id, instruction)| {
+                let accounts = instruction
+                    .accounts
+                    .iter()
+                    .filter_map(|index| account_keys.get(usize::from(*index)).copied())
+                    .collect();
+                self.decode(program_id, accounts, &instruction.data)
+            })
+            .collect()
+    }
+}
+
+fn decode_system_instruction(data: &[u8]) -> Option<Value> {
+    serde_json::to_value(limited_deserialize::<SystemInstruction>(data).ok()?).ok()
+}
+
+fn decode_stake_instruction(data: &[u8]) -> Option<Value> {
+    serde_json::to_value(limited_deserialize::<StakeInstruction>(data).ok()?).ok()
+}
+
+fn decode_vote_instruction(data: &[u8]) -> Option<Value> {
+    serde_json::to_value(limited_deserialize::<VoteInstruction>(data).ok()?).ok()
+}
+
+fn decode_compute_budget_instruction(data: &[u8]) -> Option<Value> {
+    serde_json::to_value(try_from_slice_unchecked::<ComputeBudgetInstruction>(data).ok()?).ok()
+}
+
+/// Replace the 32 byte arrays of `value`, which is how addresses and hashes
+/// are serialized, with their base58 representation
+fn with_base58_addresses(value: Value) -> Value {
+    match value {
+        Value::Array(elements) => {
+            let bytes = elements
+                .iter()
+                .map(|element| element.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<Vec<u8>>>()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+            match bytes {
+                Some(bytes) => Value::String(Pubkey::new_from_array(bytes).to_string()),
+                None => Value::Array(elements.into_iter().map(with_base58_addresses).collect()),
+            }
+        }
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, with_base58_addresses(value)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        serde_json::json,
+        solana_sdk::{
+            instruction::{AccountMeta, Instruction},
+            message::Message,
+            stake::{
+                instruction as stake_instruction,
+                state::{Authorized, Lockup},
+            },
+            system_instruction,
+        },
+    };
+
+    #[test]
+    fn test_decode_message() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let stake = Pubkey::new_unique();
+        let staker = Pubkey::new_unique();
+        let unknown_program = Pubkey::new_unique();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            system_instruction::transfer(&from, &to, 42),
+            stake_instruction::initialize(&stake, &Authorized::auto(&staker), &Lockup::default()),
+            Instruction::new_with_bytes(
+                unknown_program,
+                &[1, 2, 3],
+                vec![AccountMeta::new(to, false)],
+            ),
+            // Not a valid system instruction
+            Instruction::new_with_bytes(
+                solana_sdk::system_program::id(),
+                &[255; 4],
+                vec![AccountMeta::new(from, true)],
+            ),
+        ];
+        let message =
+            SanitizedMessage::try_from_legacy_message(Message::new(&instructions, Some(&from)))
+                .unwrap();
+
+        let decoded = InstructionDecoderRegistry::default().decode_message(&message);
+        assert_eq!(decoded.len(), instructions.len());
+        assert_eq!(
+            decoded[0],
+            Some(DecodedInstruction {
+                program_id: solana_sdk::compute_budget::id(),
+                program_name: "compute_budget",
+                instruction_name: "SetComputeUnitLimit".to_string(),
+                accounts: vec![],
+                arguments: json!(10_000),
+            })
+        );
+        assert_eq!(
+            decoded[1],
+            Some(DecodedInstruction {
+                program_id: solana_sdk::system_program::id(),
+                program_name: "system",
+                instruction_name: "Transfer".to_string(),
+                accounts: vec![from, to],
+                arguments: json!({ "lamports": 42 }),
+            })
+        );
+        let initialize = decoded[2].as_ref().unwrap();
+        assert_eq!(initialize.program_name, "stake");
+        assert_eq!(initialize.instruction_name, "Initialize");
+        assert_eq!(
+            initialize.arguments[0],
+            json!({ "staker": staker.to_string(), "withdrawer": staker.to_string() })
+        );
+        assert_eq!(decoded[3], None);
+        assert_eq!(decoded[4], None);
+
+        // Embedders can decode their own programs
+        let mut registry = InstructionDecoderRegistry::empty();
+        registry.register(unknown_program, "custom", |data| {
+            Some(json!({ "Custom": data }))
+        });
+        let decoded = registry.decode_message(&message);
+        assert_eq!(decoded[0], None);
+        assert_eq!(
+            decoded[3],
+            Some(DecodedInstruction {
+                program_id: unknown_program,
+                program_name: "custom",
+                instruction_name: "Custom".to_string(),
+                accounts: vec![to],
+                arguments: json!([1, 2, 3]),
+            })
+        );
+    }
+}
@@ -1,16 +1,35 @@
 #![cfg_attr(RUSTC_WITH_SPECIALIZATION, feature(min_specialization))]
 #![allow(clippy::arithmetic_side_effects)]
 
+pub mod account_contention;
 pub mod account_loader;
 pub mod account_overrides;
 pub mod account_rent_state;
+pub mod dependency_graph;
+pub mod evm_receipt;
+pub mod feature_overrides;
+pub mod fee_distributor;
+pub mod instruction_decoder;
+pub mod load_signal;
 pub mod message_processor;
+pub mod owner_transition;
+pub mod precompile_registry;
+pub mod prelude;
+pub mod program_cache_manifest;
 pub mod program_loader;
+pub mod program_usage;
+#[cfg(any(test, feature = "remote-loader"))]
+pub mod remote_loader;
+pub mod result_retention;
+pub mod signature_policy;
 pub mod transaction_account_state_info;
+pub mod transaction_checks;
 pub mod transaction_error_metrics;
+pub mod transaction_expiration;
 pub mod transaction_processing_callback;
 pub mod transaction_processor;
 pub mod transaction_results;
+pub mod write_policy;
 
 #[macro_use]
 extern crate solana_metrics;
@@ -0,0 +1,230 @@
+//! Load of the transaction processor, for backpressure upstream.
+//!
+//! Ingestion layers in front of the SVM, e.g. the sequencer of a rollup,
+//! need to know when to slow down admission. Instead of guessing from
+//! external heuristics, they can poll a `ProcessorLoadSignal`, which the
+//! transaction processor updates as it processes batches: the batches and
+//! transactions currently in flight, the compute units executed per second
+//! and the rate of program cache misses, both over a rolling window.
+//!
+//! Updating the signal takes a short lock twice per batch, so polling it is
+//! cheap and never blocks the processor for long.
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Window over which throughput and miss rate are computed by default
+pub const DEFAULT_LOAD_SIGNAL_WINDOW: Duration = Duration::from_secs(1);
+
+/// A snapshot of the load of the transaction processor
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProcessorLoad {
+    /// Batches currently being loaded and executed
+    pub batch_queue_depth: usize,
+    /// Transactions of the batches in flight
+    pub transactions_in_flight: usize,
+    /// Compute units executed over the window, per second
+    pub compute_units_per_second: u64,
+    /// Share of the program lookups over the window which missed the program
+    /// cache, between 0 and 1
+    pub program_cache_miss_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LoadSample {
+    at: Instant,
+    executed_units: u64,
+    program_lookups: usize,
+    program_cache_misses: usize,
+}
+
+/// Load signal updated by the transaction processor. Share one signal
+/// between the processors of all banks with
+/// `TransactionBatchProcessor::set_load_signal` to observe the load of the
+/// whole pipeline.
+#[derive(Debug)]
+pub struct ProcessorLoadSignal {
+    batches_in_flight: AtomicUsize,
+    transactions_in_flight: AtomicUsize,
+    window: Duration,
+    samples: Mutex<VecDeque<LoadSample>>,
+}
+
+impl Default for ProcessorLoadSignal {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOAD_SIGNAL_WINDOW)
+    }
+}
+
+impl ProcessorLoadSignal {
+    /// A signal whose rates are computed over `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            batches_in_flight: AtomicUsize::default(),
+            transactions_in_flight: AtomicUsize::default(),
+            window,
+            samples: Mutex::default(),
+        }
+    }
+
+    /// The current load of the processor
+    pub fn load(&self) -> ProcessorLoad {
+        self.load_at(Instant::now())
+    }
+
+    fn load_at(&self, now: Instant) -> ProcessorLoad {
+        let mut samples = self.samples.lock().unwrap();
+        Self::prune(&mut samples, now, self.window);
+        let (executed_units, program_lookups, program_cache_misses) = samples.iter().fold(
+            (0u64, 0usize, 0usize),
+            |(units, lookups, misses), sample| {
+                (
+                    units.saturating_add(sample.executed_units),
+                    lookups.saturating_add(sample.program_lookups),
+                    misses.saturating_add(sample.program_cache_misses),
+                )
+            },
+        );
+        drop(samples);
+
+        let window_us = self.window.as_micros().max(1);
+        let compute_units_per_second =
+            (u128::from(executed_units) * 1_000_000 / window_us).min(u128::from(u64::MAX)) as u64;
+        let program_cache_miss_rate = if program_lookups == 0 {
+            0.0
+        } else {
+            program_cache_misses as f64 / program_lookups as f64
+        };
+        ProcessorLoad {
+            batch_queue_depth: self.batches_in_flight.load(Ordering::Relaxed),
+            transactions_in_flight: self.transactions_in_flight.load(Ordering::Relaxed),
+            compute_units_per_second,
+            program_cache_miss_rate,
+        }
+    }
+
+    /// Count a batch of `num_transactions` as in flight until the returned
+    /// guard is dropped
+    pub(crate) fn begin_batch(&self, num_transactions: usize) -> InFlightBatch<'_> {
+        self.batches_in_flight.fetch_add(1, Ordering::Relaxed);
+        self.transactions_in_flight
+            .fetch_add(num_transactions, Ordering::Relaxed);
+        InFlightBatch {
+            signal: self,
+            num_transactions,
+        }
+    }
+
+    pub(crate) fn record_executed_units(&self, executed_units: u64) {
+        self.record(LoadSample {
+            at: Instant::now(),
+            executed_units,
+            program_lookups: 0,
+            program_cache_misses: 0,
+        });
+    }
+
+    pub(crate) fn record_program_lookups(&self, program_lookups: usize, misses: usize) {
+        self.record(LoadSample {
+            at: Instant::now(),
+            executed_units: 0,
+            program_lookups,
+            program_cache_misses: misses,
+        });
+    }
+
+    fn record(&self, sample: LoadSample) {
+        let mut samples = self.samples.lock().unwrap();
+        Self::prune(&mut samples, sample.at, self.window);
+        samples.push_back(sample);
+    }
+
+    /// Drop the samples which are older than `window` at `now`
+    fn prune(samples: &mut VecDeque<LoadSample>, now: Instant, window: Duration) {
+        while samples
+            .front()
+            .is_some_and(|sample| now.saturating_duration_since(sample.at) > window)
+        {
+            samples.pop_front();
+        }
+    }
+}
+
+/// A batch counted as in flight by a `ProcessorLoadSignal`
+pub(crate) struct InFlightBatch<'a> {
+    signal: &'a ProcessorLoadSignal,
+    num_transactions: usize,
+}
+
+impl Drop for InFlightBatch<'_> {
+    fn drop(&mut self) {
+        self.signal
+            .batches_in_flight
+            .fetch_sub(1, Ordering::Relaxed);
+        self.signal
+            .transactions_in_flight
+            .fetch_sub(self.num_transactions, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_in_flight() {
+        let signal = ProcessorLoadSignal::default();
+        assert_eq!(signal.load(), ProcessorLoad::default());
+
+        let first = signal.begin_batch(3);
+        let second = signal.begin_batch(2);
+        let load = signal.load();
+        assert_eq!(load.batch_queue_depth, 2);
+        assert_eq!(load.transactions_in_flight, 5);
+
+        drop(first);
+        let load = signal.load();
+        assert_eq!(load.batch_queue_depth, 1);
+        assert_eq!(load.transactions_in_flight, 2);
+
+        drop(second);
+        assert_eq!(signal.load(), ProcessorLoad::default());
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let window = Duration::from_secs(2);
+        let signal = ProcessorLoadSignal::new(window);
+        let start = Instant::now();
+        signal.record(LoadSample {
+            at: start,
+            executed_units: 1_000_000,
+            program_lookups: 4,
+            program_cache_misses: 3,
+        });
+        signal.record(LoadSample {
+            at: start + Duration::from_secs(1),
+            executed_units: 3_000_000,
+            program_lookups: 4,
+            program_cache_misses: 1,
+        });
+
+        let load = signal.load_at(start + Duration::from_secs(1));
+        assert_eq!(load.compute_units_per_second, 2_000_000);
+        assert_eq!(load.program_cache_miss_rate, 0.5);
+
+        // The first sample leaves the window
+        let load = signal.load_at(start + Duration::from_millis(2_500));
+        assert_eq!(load.compute_units_per_second, 1_500_000);
+        assert_eq!(load.program_cache_miss_rate, 0.25);
+
+        let load = signal.load_at(start + Duration::from_secs(4));
+        assert_eq!(load, ProcessorLoad::default());
+    }
+}
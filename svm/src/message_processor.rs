@@ -1,4 +1,5 @@
 use {
+    crate::precompile_registry::PrecompileRegistry,
     serde::{Deserialize, Serialize},
     solana_measure::measure::Measure,
     solana_program_runtime::{
@@ -40,6 +41,7 @@ impl MessageProcessor {
         invoke_context: &mut InvokeContext,
         timings: &mut ExecuteTimings,
         accumulated_consumed_units: &mut u64,
+        precompiles: &PrecompileRegistry,
     ) -> Result<(), TransactionError> {
         debug_assert_eq!(program_indices.len(), message.instructions().len());
         for (instruction_index, ((program_id, instruction), program_indices)) in message
@@ -47,8 +49,10 @@ impl MessageProcessor {
             .zip(program_indices.iter())
             .enumerate()
         {
+            // Registered precompiles were verified while loading the transaction
             let is_precompile =
-                is_precompile(program_id, |id| invoke_context.feature_set.is_active(id));
+                is_precompile(program_id, |id| invoke_context.feature_set.is_active(id))
+                    || precompiles.is_precompile(program_id);
 
             // Fixup the special instructions key if present
             // before the account pre-values are taken care of
@@ -287,6 +291,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert!(result.is_ok());
         assert_eq!(
@@ -338,6 +343,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert_eq!(
             result,
@@ -379,6 +385,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert_eq!(
             result,
@@ -511,6 +518,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert_eq!(
             result,
@@ -547,6 +555,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert!(result.is_ok());
 
@@ -580,6 +589,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
         assert!(result.is_ok());
         assert_eq!(
@@ -674,6 +684,7 @@ mod tests {
             &mut invoke_context,
             &mut ExecuteTimings::default(),
             &mut 0,
+            &PrecompileRegistry::default(),
         );
 
         assert_eq!(
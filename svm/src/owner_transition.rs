@@ -0,0 +1,88 @@
+//! Embedder defined rules over the changes of account owners.
+//!
+//! The runtime enforces the L1 rules of owner changes during execution: only
+//! the current owner can assign a writable, non-executable account whose data
+//! is zeroed. Chains built on the SVM may need stricter rules, e.g. that
+//! accounts are only assigned to the programs they know of. An
+//! `OwnerTransitionValidator` is consulted for every account whose owner a
+//! successfully executed transaction changed. If it rejects any of the
+//! changes, the transaction fails with
+//! `TransactionError::OwnerTransitionRejected` carrying the error of the
+//! validator, and none of its writes are committed.
+//!
+//! Without a validator, only the L1 rules apply.
+
+use {
+    solana_sdk::{account::AccountSharedData, pubkey::Pubkey, transaction::OwnerTransitionError},
+    std::collections::HashSet,
+};
+
+pub trait OwnerTransitionValidator: Send + Sync {
+    /// Whether the owner of `account`, at `pubkey`, may change from
+    /// `old_owner` to `new_owner`
+    fn validate_owner_transition(
+        &self,
+        pubkey: &Pubkey,
+        old_owner: &Pubkey,
+        new_owner: &Pubkey,
+        account: &AccountSharedData,
+    ) -> Result<(), OwnerTransitionError>;
+}
+
+/// Rejects the assignment of accounts to owners which are not listed, with
+/// `OwnerTransitionError::UnknownOwner`
+#[derive(Debug, Default)]
+pub struct KnownOwnersValidator {
+    pub known_owners: HashSet<Pubkey>,
+}
+
+impl KnownOwnersValidator {
+    pub fn new(known_owners: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self {
+            known_owners: known_owners.into_iter().collect(),
+        }
+    }
+}
+
+impl OwnerTransitionValidator for KnownOwnersValidator {
+    fn validate_owner_transition(
+        &self,
+        _pubkey: &Pubkey,
+        _old_owner: &Pubkey,
+        new_owner: &Pubkey,
+        _account: &AccountSharedData,
+    ) -> Result<(), OwnerTransitionError> {
+        if self.known_owners.contains(new_owner) {
+            Ok(())
+        } else {
+            Err(OwnerTransitionError::UnknownOwner)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_sdk::system_program};
+
+    #[test]
+    fn test_known_owners_validator() {
+        let known = Pubkey::new_unique();
+        let validator = KnownOwnersValidator::new([known]);
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::new(1, 0, &known);
+
+        assert_eq!(
+            validator.validate_owner_transition(&pubkey, &system_program::id(), &known, &account),
+            Ok(())
+        );
+        assert_eq!(
+            validator.validate_owner_transition(
+                &pubkey,
+                &system_program::id(),
+                &Pubkey::new_unique(),
+                &account
+            ),
+            Err(OwnerTransitionError::UnknownOwner)
+        );
+    }
+}
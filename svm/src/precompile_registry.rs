@@ -0,0 +1,210 @@
+//! Precompiles registered by the embedder of the SVM.
+//!
+//! The precompiles of the SDK (ed25519, secp256k1) are fixed, and verified
+//! by the bank before transactions reach the processor. Chains built on the
+//! SVM may want to offer more of them, e.g. for BLS signatures, without
+//! forking the checks of transactions. A `PrecompileRegistry` holds such
+//! precompiles, each with the function verifying its instructions and the
+//! number of signatures charged for them.
+//!
+//! The processor verifies the instructions of registered precompiles while
+//! loading transactions. A transaction with an instruction which fails
+//! verification is rejected with a `TransactionError::InstructionError` for
+//! that instruction, holding the `PrecompileError` as a custom error, and no
+//! fee is charged. Verified instructions are not executed, like the
+//! instructions of the SDK precompiles, and the signatures they declare are
+//! charged like the signatures of the transaction.
+//!
+//! The account of a registered precompile must exist and be owned by the
+//! native loader, like the accounts of the SDK precompiles.
+
+use {
+    solana_sdk::{
+        feature_set::FeatureSet,
+        instruction::InstructionError,
+        message::SanitizedMessage,
+        precompiles::{get_precompiles, Verify},
+        pubkey::Pubkey,
+        transaction::{Result, TransactionError},
+    },
+    std::collections::HashMap,
+};
+
+/// The number of signatures charged for an instruction of a precompile, from
+/// its data
+pub type PrecompileSignatureCount = fn(data: &[u8]) -> u64;
+
+/// Charge the count of signatures held in the first byte of the data, like
+/// the SDK precompiles do
+pub fn first_byte_signature_count(data: &[u8]) -> u64 {
+    data.first().copied().map(u64::from).unwrap_or_default()
+}
+
+#[derive(Debug, Clone)]
+struct RegisteredPrecompile {
+    verify_fn: Verify,
+    signature_count: PrecompileSignatureCount,
+}
+
+/// Precompiles by program id
+#[derive(Debug, Clone, Default)]
+pub struct PrecompileRegistry {
+    precompiles: HashMap<Pubkey, RegisteredPrecompile>,
+}
+
+impl PrecompileRegistry {
+    /// Verify the instructions of `program_id` with `verify_fn` and charge
+    /// `signature_count` signatures for each of them, replacing the previous
+    /// registration of `program_id`, if any
+    ///
+    /// Panics if `program_id` is a precompile of the SDK.
+    pub fn register(
+        &mut self,
+        program_id: Pubkey,
+        verify_fn: Verify,
+        signature_count: PrecompileSignatureCount,
+    ) {
+        assert!(
+            !get_precompiles()
+                .iter()
+                .any(|precompile| precompile.program_id == program_id),
+            "{program_id} is a precompile of the SDK"
+        );
+        self.precompiles.insert(
+            program_id,
+            RegisteredPrecompile {
+                verify_fn,
+                signature_count,
+            },
+        );
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.precompiles.is_empty()
+    }
+
+    pub fn is_precompile(&self, program_id: &Pubkey) -> bool {
+        self.precompiles.contains_key(program_id)
+    }
+
+    /// Verify the instructions of the registered precompiles in `message`.
+    /// A failure is reported as a custom error of the failing instruction.
+    pub fn verify(&self, message: &SanitizedMessage, feature_set: &FeatureSet) -> Result<()> {
+        if self.precompiles.is_empty() {
+            return Ok(());
+        }
+        let mut instruction_datas = None;
+        for (index, (program_id, instruction)) in message.program_instructions_iter().enumerate() {
+            if let Some(precompile) = self.precompiles.get(program_id) {
+                let instruction_datas: &Vec<&[u8]> = instruction_datas.get_or_insert_with(|| {
+                    message
+                        .instructions()
+                        .iter()
+                        .map(|instruction| instruction.data.as_ref())
+                        .collect()
+                });
+                (precompile.verify_fn)(&instruction.data, instruction_datas, feature_set).map_err(
+                    |err| {
+                        TransactionError::InstructionError(
+                            index as u8,
+                            InstructionError::Custom(err as u32),
+                        )
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of signatures charged for the instructions of the
+    /// registered precompiles in `message`
+    pub fn num_signatures(&self, message: &SanitizedMessage) -> u64 {
+        message
+            .program_instructions_iter()
+            .filter_map(|(program_id, instruction)| {
+                self.precompiles
+                    .get(program_id)
+                    .map(|precompile| (precompile.signature_count)(&instruction.data))
+            })
+            .fold(0, u64::saturating_add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            ed25519_program,
+            hash::Hash,
+            instruction::Instruction,
+            precompiles::PrecompileError,
+            signature::{Keypair, Signer},
+            transaction::{SanitizedTransaction, Transaction},
+        },
+    };
+
+    fn verify_magic(
+        data: &[u8],
+        _instruction_datas: &[&[u8]],
+        _feature_set: &FeatureSet,
+    ) -> std::result::Result<(), PrecompileError> {
+        (data.get(1) == Some(&42))
+            .then_some(())
+            .ok_or(PrecompileError::InvalidSignature)
+    }
+
+    fn new_message(instructions: &[Instruction]) -> SanitizedMessage {
+        let payer = Keypair::new();
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        ))
+        .message()
+        .clone()
+    }
+
+    #[test]
+    fn test_precompile_registry() {
+        let program_id = Pubkey::new_unique();
+        let mut registry = PrecompileRegistry::default();
+        registry.register(program_id, verify_magic, first_byte_signature_count);
+        assert!(registry.is_precompile(&program_id));
+        assert!(!registry.is_precompile(&ed25519_program::id()));
+
+        let feature_set = FeatureSet::all_enabled();
+        let valid = Instruction::new_with_bytes(program_id, &[3, 42], vec![]);
+        let invalid = Instruction::new_with_bytes(program_id, &[1, 0], vec![]);
+        let other = Instruction::new_with_bytes(Pubkey::new_unique(), &[7, 0], vec![]);
+
+        let message = new_message(&[valid.clone(), other.clone(), valid.clone()]);
+        assert_eq!(registry.verify(&message, &feature_set), Ok(()));
+        assert_eq!(registry.num_signatures(&message), 6);
+
+        let message = new_message(&[valid, invalid, other]);
+        assert_eq!(
+            registry.verify(&message, &feature_set),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(PrecompileError::InvalidSignature as u32)
+            ))
+        );
+        assert_eq!(registry.num_signatures(&message), 4);
+
+        let empty = PrecompileRegistry::default();
+        assert_eq!(empty.verify(&message, &feature_set), Ok(()));
+        assert_eq!(empty.num_signatures(&message), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is a precompile of the SDK")]
+    fn test_register_sdk_precompile() {
+        PrecompileRegistry::default().register(
+            ed25519_program::id(),
+            verify_magic,
+            first_byte_signature_count,
+        );
+    }
+}
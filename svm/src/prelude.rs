@@ -0,0 +1,252 @@
+//! The surface of the SVM meant for embedders.
+//!
+//! The modules of this crate are public, but their contents change as the
+//! processor evolves. Embedders should import from this module instead: the
+//! items it re-exports are the ones kept stable across minor releases, and
+//! removing or renaming any of them is a breaking change.
+//!
+//! Loading, rent state and message processing internals are deliberately
+//! left out. The tests of this module check the exported items and the
+//! signatures embedders build against, so that a change to the surface has
+//! to be made on purpose.
+
+pub use {
+    crate::{
+        account_contention::{AccountContention, AccountContentionReport},
+        account_loader::TransactionCheckResult,
+        account_overrides::AccountOverrides,
+        dependency_graph::{ConflictKind, DependencyEdge, DependencyGraph, DependencyNode},
+        fee_distributor::{
+            BurnAndCollectFeeDistributor, CollectedFees, FeeDistribution, FeeDistributor,
+            NoopFeeDistributor,
+        },
+        instruction_decoder::{
+            DecodedInstruction, InstructionDecoderFn, InstructionDecoderRegistry,
+        },
+        load_signal::{ProcessorLoad, ProcessorLoadSignal},
+        owner_transition::{KnownOwnersValidator, OwnerTransitionValidator},
+        precompile_registry::{PrecompileRegistry, PrecompileSignatureCount},
+        program_cache_manifest::{
+            ProgramCacheImport, ProgramCacheManifest, ProgramCacheManifestEntry,
+            ProgramCacheManifestError,
+        },
+        program_usage::{ProgramUsage, ProgramUsageReport},
+        result_retention::{ResultRetention, TransactionDetailsSink},
+        signature_policy::{
+            AllSignersPolicy, SessionKeysPolicy, SignaturePolicy, SignaturePolicyDecision,
+        },
+        transaction_checks::{
+            AlreadyProcessedCheck, BlockhashCheck, CheckedTransactionDetails, NonceCheck,
+            TransactionCheck,
+        },
+        transaction_error_metrics::TransactionErrorMetrics,
+        transaction_expiration::{TransactionExpiration, ValidUntilSlots},
+        transaction_processing_callback::{AccountMeta, TransactionProcessingCallback},
+        transaction_processor::{
+            ExecutionRecordingConfig, LoadAndExecuteSanitizedTransactionsOutput, ProcessorGcReport,
+            ReExecutionError, StreamedBatchOutput, TransactionBatchProcessor,
+            TransactionLogMessages, ViewCallResult,
+        },
+        transaction_results::{
+            AccountLifecycle, DurableNonceFee, TransactionExecutionDetails,
+            TransactionExecutionResult, TransactionResults,
+        },
+        write_policy::{FrozenAccountsWritePolicy, WritePolicy},
+    },
+    solana_program_runtime::{
+        loaded_programs::{BlockRelation, ForkGraph, ProgramCache},
+        runtime_config::RuntimeConfig,
+    },
+};
+
+// The surface is asserted at compile time: the items are imported from this
+// module, and the signatures embedders build against are coerced to function
+// pointers, so that removing an item or changing one of these signatures fails
+// to build the tests
+#[cfg(test)]
+#[allow(dead_code, clippy::type_complexity)]
+mod tests {
+    use {
+        super::{
+            AccountContention, AccountContentionReport, AccountLifecycle, AccountMeta,
+            AccountOverrides, AllSignersPolicy, AlreadyProcessedCheck, BlockRelation,
+            BlockhashCheck, BurnAndCollectFeeDistributor, CheckedTransactionDetails, CollectedFees,
+            ConflictKind, DecodedInstruction, DependencyEdge, DependencyGraph, DependencyNode,
+            DurableNonceFee, ExecutionRecordingConfig, FeeDistribution, FeeDistributor, ForkGraph,
+            FrozenAccountsWritePolicy, InstructionDecoderFn, InstructionDecoderRegistry,
+            KnownOwnersValidator, LoadAndExecuteSanitizedTransactionsOutput, NonceCheck,
+            NoopFeeDistributor, OwnerTransitionValidator, PrecompileRegistry,
+            PrecompileSignatureCount, ProcessorGcReport, ProcessorLoad, ProcessorLoadSignal,
+            ProgramCache, ProgramCacheImport, ProgramCacheManifest, ProgramCacheManifestEntry,
+            ProgramCacheManifestError, ProgramUsage, ProgramUsageReport, ReExecutionError,
+            ResultRetention, RuntimeConfig, SessionKeysPolicy, SignaturePolicy,
+            SignaturePolicyDecision, StreamedBatchOutput, TransactionBatchProcessor,
+            TransactionCheck, TransactionCheckResult, TransactionDetailsSink,
+            TransactionErrorMetrics, TransactionExecutionDetails, TransactionExecutionResult,
+            TransactionExpiration, TransactionLogMessages, TransactionProcessingCallback,
+            TransactionResults, ValidUntilSlots, ViewCallResult, WritePolicy,
+        },
+        solana_program_runtime::timings::ExecuteTimings,
+        solana_sdk::{
+            account::AccountSharedData,
+            clock::{Epoch, Slot},
+            epoch_schedule::EpochSchedule,
+            feature_set::FeatureSet,
+            fee::FeeStructure,
+            hash::Hash,
+            pubkey::Pubkey,
+            rent_collector::RentCollector,
+            transaction::{self, OwnerTransitionError, SanitizedTransaction},
+        },
+        std::sync::{Arc, RwLock},
+    };
+
+    fn processor_signatures<FG: ForkGraph, CB: TransactionProcessingCallback>() {
+        let _: fn(&FG, Slot, Slot) -> BlockRelation = FG::relationship;
+        let _: fn(
+            Slot,
+            Epoch,
+            EpochSchedule,
+            FeeStructure,
+            Arc<RuntimeConfig>,
+            Arc<RwLock<ProgramCache<FG>>>,
+        ) -> TransactionBatchProcessor<FG> = TransactionBatchProcessor::new;
+        let _: fn(
+            &TransactionBatchProcessor<FG>,
+            &CB,
+            &[SanitizedTransaction],
+            &mut [TransactionCheckResult],
+            &mut TransactionErrorMetrics,
+            ExecutionRecordingConfig,
+            &mut ExecuteTimings,
+            Option<&AccountOverrides>,
+            std::iter::Empty<&'static Pubkey>,
+            Option<usize>,
+            bool,
+            bool,
+        ) -> LoadAndExecuteSanitizedTransactionsOutput =
+            TransactionBatchProcessor::load_and_execute_sanitized_transactions;
+        let _: fn(
+            &TransactionBatchProcessor<FG>,
+            &CB,
+            &[SanitizedTransaction],
+            &LoadAndExecuteSanitizedTransactionsOutput,
+            &[usize],
+            Arc<RuntimeConfig>,
+        ) -> Result<LoadAndExecuteSanitizedTransactionsOutput, ReExecutionError> =
+            TransactionBatchProcessor::re_execute_subset;
+        let _: fn(&TransactionBatchProcessor<FG>, Epoch) -> ProcessorGcReport =
+            TransactionBatchProcessor::gc;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<dyn FeeDistributor>) =
+            TransactionBatchProcessor::set_fee_distributor;
+        let _: fn(&mut TransactionBatchProcessor<FG>, ResultRetention) =
+            TransactionBatchProcessor::set_result_retention;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<dyn WritePolicy>) =
+            TransactionBatchProcessor::set_write_policy;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<dyn OwnerTransitionValidator>) =
+            TransactionBatchProcessor::set_owner_transition_validator;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<dyn TransactionExpiration>) =
+            TransactionBatchProcessor::set_transaction_expiration;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<ProcessorLoadSignal>) =
+            TransactionBatchProcessor::set_load_signal;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<InstructionDecoderRegistry>) =
+            TransactionBatchProcessor::set_instruction_decoders;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<PrecompileRegistry>) =
+            TransactionBatchProcessor::set_precompiles;
+        let _: fn(&mut TransactionBatchProcessor<FG>, Arc<dyn SignaturePolicy>) =
+            TransactionBatchProcessor::set_signature_policy;
+    }
+
+    fn callback_signatures<CB: TransactionProcessingCallback>() {
+        let _: fn(&CB, &Pubkey, &[Pubkey]) -> Option<usize> = CB::account_matches_owners;
+        let _: fn(&CB, &Pubkey) -> Option<AccountSharedData> = CB::get_account_shared_data;
+        let _: fn(&CB, &Pubkey) -> Option<AccountMeta> = CB::peek_account_meta;
+        let _: fn(&CB) -> (Hash, u64) = CB::get_last_blockhash_and_lamports_per_signature;
+        let _: fn(&CB) -> &RentCollector = CB::get_rent_collector;
+        let _: fn(&CB) -> Arc<FeatureSet> = CB::get_feature_set;
+    }
+
+    fn policy_signatures<
+        F: FeeDistributor,
+        S: SignaturePolicy,
+        C: TransactionCheck,
+        W: WritePolicy,
+        O: OwnerTransitionValidator,
+        D: TransactionDetailsSink,
+        E: TransactionExpiration,
+    >() {
+        let _: fn(&F, &CollectedFees) -> FeeDistribution = F::distribute;
+        let _: fn(&S, &SanitizedTransaction) -> transaction::Result<SignaturePolicyDecision> =
+            S::evaluate;
+        let _: fn(
+            &C,
+            &SanitizedTransaction,
+            CheckedTransactionDetails,
+        ) -> transaction::Result<CheckedTransactionDetails> = C::check;
+        let _: fn(
+            &W,
+            &SanitizedTransaction,
+            &Pubkey,
+            &AccountSharedData,
+            &AccountSharedData,
+        ) -> bool = W::allow_write;
+        let _: fn(
+            &O,
+            &Pubkey,
+            &Pubkey,
+            &Pubkey,
+            &AccountSharedData,
+        ) -> Result<(), OwnerTransitionError> = O::validate_owner_transition;
+        let _: fn(&D, usize, &SanitizedTransaction, &TransactionExecutionDetails) = D::consume;
+        let _: fn(&E, &SanitizedTransaction) -> Option<Slot> = E::valid_until_slot;
+        let _: fn(&E, &SanitizedTransaction, Slot) -> bool = E::is_expired;
+    }
+
+    fn implementations() {
+        fn fee_distributor<F: FeeDistributor>() {}
+        fn signature_policy<S: SignaturePolicy>() {}
+        fn transaction_check<C: TransactionCheck>() {}
+        fn write_policy<W: WritePolicy>() {}
+        fn owner_transition_validator<O: OwnerTransitionValidator>() {}
+        fn transaction_expiration<E: TransactionExpiration>() {}
+
+        fee_distributor::<BurnAndCollectFeeDistributor>();
+        fee_distributor::<NoopFeeDistributor>();
+        signature_policy::<AllSignersPolicy>();
+        signature_policy::<SessionKeysPolicy>();
+        transaction_check::<AlreadyProcessedCheck<fn(&SanitizedTransaction) -> bool>>();
+        transaction_check::<BlockhashCheck<fn(&Hash) -> Option<u64>>>();
+        transaction_check::<NonceCheck<fn(&Pubkey) -> Option<AccountSharedData>>>();
+        write_policy::<FrozenAccountsWritePolicy>();
+        owner_transition_validator::<KnownOwnersValidator>();
+        transaction_expiration::<ValidUntilSlots>();
+    }
+
+    // The remaining items are data types of the surface
+    type DataTypes = (
+        AccountContention,
+        AccountContentionReport,
+        AccountLifecycle,
+        BlockRelation,
+        ConflictKind,
+        DecodedInstruction,
+        DependencyEdge,
+        DependencyGraph,
+        DependencyNode,
+        DurableNonceFee,
+        InstructionDecoderFn,
+        ProcessorLoad,
+        PrecompileSignatureCount,
+        ProgramCacheImport,
+        ProgramCacheManifest,
+        ProgramCacheManifestEntry,
+        ProgramCacheManifestError,
+        ProgramUsage,
+        ProgramUsageReport,
+        StreamedBatchOutput,
+        TransactionExecutionResult,
+        TransactionLogMessages,
+        TransactionResults,
+        ViewCallResult,
+    );
+}
@@ -0,0 +1,59 @@
+//! Manifests of the programs in the program cache, for restarting warm.
+//!
+//! Embedders which snapshot their account state lose the program cache on
+//! restart, so the first batches after a restart pay for loading, verifying
+//! and compiling every program again. A `ProgramCacheManifest` lists the
+//! programs which were in the cache, by program id and deployment slot, along
+//! with the hash of the environments they were verified in. Stored next to
+//! the account snapshot, it lets `TransactionBatchProcessor` warm the cache
+//! up before processing the first batch.
+//!
+//! Manifests hold no program code: every program is loaded from its account
+//! and verified again on import, and programs whose account does not hold
+//! the listed deployment anymore are skipped, so the cache stays consistent
+//! with the restored accounts.
+
+use {
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, hash::Hash, pubkey::Pubkey},
+    thiserror::Error,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramCacheManifest {
+    /// Slot of the processor which exported the manifest
+    pub slot: Slot,
+    /// `ProgramRuntimeEnvironments::hash()` of the environments the programs
+    /// were verified in
+    pub environments_hash: Hash,
+    /// Ordered by program id
+    pub programs: Vec<ProgramCacheManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgramCacheManifestEntry {
+    pub program_id: Pubkey,
+    /// Slot in which the cached version of the program was deployed
+    pub deployment_slot: Slot,
+    /// How often the program was used by transactions, which decides which
+    /// programs are evicted first
+    pub usage_count: u64,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ProgramCacheManifestError {
+    #[error("manifest exported for environments {manifest}, the processor uses {processor}")]
+    EnvironmentsMismatch { manifest: Hash, processor: Hash },
+}
+
+/// Outcome of importing a `ProgramCacheManifest`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramCacheImport {
+    /// Programs of the manifest which are in the cache after the import
+    pub imported: Vec<Pubkey>,
+    /// Programs whose account does not hold the listed deployment anymore,
+    /// or which failed verification
+    pub skipped: Vec<Pubkey>,
+}
@@ -0,0 +1,96 @@
+//! Per program usage of a processed batch of transactions.
+//!
+//! Block producers can use the report to throttle individual programs, e.g.
+//! to limit the share of block compute units a single program may consume,
+//! without re-deriving program usage from logs.
+
+use {
+    solana_program_runtime::timings::ExecuteDetailsTimings,
+    solana_sdk::{pubkey::Pubkey, saturating_add_assign},
+    std::collections::HashMap,
+};
+
+/// Usage of a single program by a batch of transactions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramUsage {
+    /// Number of times the program was invoked, including CPIs
+    pub invocation_count: u64,
+    /// Compute units consumed by the instructions of the batch which invoke
+    /// the program, including failed instructions. Units consumed by CPIs
+    /// are attributed to the program of the calling instruction.
+    pub compute_units: u64,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProgramUsageReport {
+    pub programs: HashMap<Pubkey, ProgramUsage>,
+}
+
+impl ProgramUsageReport {
+    /// Build the report from the timings collected while executing a batch
+    pub fn new(timings: &ExecuteDetailsTimings) -> Self {
+        let programs = timings
+            .per_program_timings
+            .iter()
+            .map(|(program_id, program_timing)| {
+                let mut compute_units = program_timing.accumulated_units;
+                saturating_add_assign!(compute_units, program_timing.total_errored_units);
+                (
+                    *program_id,
+                    ProgramUsage {
+                        invocation_count: program_timing.invocation_count,
+                        compute_units,
+                    },
+                )
+            })
+            .collect();
+        Self { programs }
+    }
+
+    /// Compute units consumed by all programs of the batch
+    pub fn total_compute_units(&self) -> u64 {
+        self.programs.values().fold(0u64, |total, usage| {
+            total.saturating_add(usage.compute_units)
+        })
+    }
+
+    pub fn get(&self, program_id: &Pubkey) -> Option<&ProgramUsage> {
+        self.programs.get(program_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_program_usage_report() {
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+        let mut timings = ExecuteDetailsTimings::default();
+        timings.accumulate_program_invocation(&program_a, 0, 0);
+        timings.accumulate_program(&program_a, 10, 300, false);
+        timings.accumulate_program_invocation(&program_a, 0, 0);
+        timings.accumulate_program(&program_a, 10, 200, true);
+        // Invoked through CPI only
+        timings.accumulate_program_invocation(&program_b, 0, 0);
+
+        let report = ProgramUsageReport::new(&timings);
+        assert_eq!(
+            report.get(&program_a),
+            Some(&ProgramUsage {
+                invocation_count: 2,
+                compute_units: 500,
+            })
+        );
+        assert_eq!(
+            report.get(&program_b),
+            Some(&ProgramUsage {
+                invocation_count: 1,
+                compute_units: 0,
+            })
+        );
+        assert_eq!(report.get(&Pubkey::new_unique()), None);
+        assert_eq!(report.total_compute_units(), 500);
+    }
+}
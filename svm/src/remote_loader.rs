@@ -0,0 +1,523 @@
+//! A reference implementation of `TransactionProcessingCallback` backed by an
+//! external account store.
+//!
+//! The loader speaks a minimal request/response protocol (`GetAccountsRequest`
+//! and `GetAccountsResponse`) over a pluggable `RemoteAccountStore` transport,
+//! so the same loader can sit on top of a gRPC service, an HTTP endpoint or an
+//! in-process mock. Accounts are fetched in batches, cached for the lifetime of
+//! the loader, and requests are retried according to a `RetryPolicy`.
+//!
+//! Since every cache miss is a network round trip, embedders should call
+//! `RemoteLoader::prefetch` with the transactions of a batch before handing
+//! the loader to the transaction processor.
+//!
+//! `TransactionProcessingCallback` can't report errors, so the processor sees
+//! an account the loader failed to fetch as missing. The loader records the
+//! failure instead, and embedders must check `RemoteLoader::take_load_failure`
+//! after every batch and discard the results of the batch if a load failed.
+
+use {
+    crate::transaction_processing_callback::TransactionProcessingCallback,
+    log::warn,
+    serde::{Deserialize, Serialize},
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        feature_set::FeatureSet,
+        hash::Hash,
+        pubkey::Pubkey,
+        rent_collector::RentCollector,
+        transaction::SanitizedTransaction,
+    },
+    std::{
+        collections::{HashMap, HashSet},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, RwLock,
+        },
+        thread::sleep,
+        time::Duration,
+    },
+    thiserror::Error,
+};
+
+/// Request for the current state of a set of accounts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GetAccountsRequest {
+    pub pubkeys: Vec<Pubkey>,
+}
+
+/// Response to a `GetAccountsRequest`, holding one entry per requested
+/// pubkey, in request order. Accounts that do not exist are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GetAccountsResponse {
+    pub accounts: Vec<Option<AccountSharedData>>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RemoteLoaderError {
+    #[error("transport error: {0}")]
+    Transport(String),
+    #[error("expected {expected} accounts in response, got {actual}")]
+    MalformedResponse { expected: usize, actual: usize },
+}
+
+/// Transport used by `RemoteLoader` to reach the external account store
+pub trait RemoteAccountStore {
+    fn get_accounts(
+        &self,
+        request: &GetAccountsRequest,
+    ) -> Result<GetAccountsResponse, RemoteLoaderError>;
+}
+
+/// How failed requests to the account store are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the initial attempt
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled on every subsequent retry
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(50),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteLoaderConfig {
+    /// Maximum number of accounts requested in a single round trip
+    pub max_batch_size: usize,
+    pub retry_policy: RetryPolicy,
+}
+
+impl Default for RemoteLoaderConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 100,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Counters describing the loader's interaction with the account store
+#[derive(Debug, Default)]
+pub struct RemoteLoaderStats {
+    pub requests: AtomicU64,
+    pub retries: AtomicU64,
+    pub failures: AtomicU64,
+    pub cache_hits: AtomicU64,
+    pub cache_misses: AtomicU64,
+}
+
+pub struct RemoteLoader<S: RemoteAccountStore> {
+    store: S,
+    config: RemoteLoaderConfig,
+    cache: RwLock<HashMap<Pubkey, Option<AccountSharedData>>>,
+    stats: RemoteLoaderStats,
+    load_failure: RwLock<Option<RemoteLoaderError>>,
+    blockhash: Hash,
+    lamports_per_signature: u64,
+    rent_collector: RentCollector,
+    feature_set: Arc<FeatureSet>,
+}
+
+impl<S: RemoteAccountStore> RemoteLoader<S> {
+    pub fn new(
+        store: S,
+        config: RemoteLoaderConfig,
+        blockhash: Hash,
+        lamports_per_signature: u64,
+        rent_collector: RentCollector,
+        feature_set: Arc<FeatureSet>,
+    ) -> Self {
+        Self {
+            store,
+            config,
+            cache: RwLock::default(),
+            stats: RemoteLoaderStats::default(),
+            load_failure: RwLock::default(),
+            blockhash,
+            lamports_per_signature,
+            rent_collector,
+            feature_set,
+        }
+    }
+
+    pub fn stats(&self) -> &RemoteLoaderStats {
+        &self.stats
+    }
+
+    /// Fetch all accounts referenced by `transactions` which are not cached
+    /// yet, in as few round trips as `max_batch_size` allows.
+    pub fn prefetch(&self, transactions: &[SanitizedTransaction]) -> Result<(), RemoteLoaderError> {
+        let missing: Vec<Pubkey> = {
+            let cache = self.cache.read().unwrap();
+            let mut seen = HashSet::new();
+            transactions
+                .iter()
+                .flat_map(|tx| {
+                    tx.message()
+                        .account_keys()
+                        .iter()
+                        .copied()
+                        .collect::<Vec<_>>()
+                })
+                .filter(|pubkey| !cache.contains_key(pubkey) && seen.insert(*pubkey))
+                .collect()
+        };
+        self.fetch(&missing)
+    }
+
+    /// The first failure to load an account on a cache miss since the last
+    /// call, if any
+    ///
+    /// The results of a batch processed while a load failed don't reflect
+    /// the state of the account store, since the failed accounts were seen
+    /// as missing, and must be discarded. Until the failure is taken, cache
+    /// misses are not fetched anymore, so a failed batch doesn't wait for
+    /// the store on each of its remaining accounts.
+    pub fn take_load_failure(&self) -> Option<RemoteLoaderError> {
+        self.load_failure.write().unwrap().take()
+    }
+
+    /// Drop all cached accounts, e.g. after the embedder committed a batch
+    pub fn clear_cache(&self) {
+        self.cache.write().unwrap().clear();
+    }
+
+    fn fetch(&self, pubkeys: &[Pubkey]) -> Result<(), RemoteLoaderError> {
+        for chunk in pubkeys.chunks(self.config.max_batch_size.max(1)) {
+            let request = GetAccountsRequest {
+                pubkeys: chunk.to_vec(),
+            };
+            let response = self.send_with_retries(&request)?;
+            let mut cache = self.cache.write().unwrap();
+            for (pubkey, account) in chunk.iter().zip(response.accounts) {
+                cache.insert(*pubkey, account);
+            }
+        }
+        Ok(())
+    }
+
+    fn send_with_retries(
+        &self,
+        request: &GetAccountsRequest,
+    ) -> Result<GetAccountsResponse, RemoteLoaderError> {
+        let RetryPolicy {
+            max_retries,
+            initial_backoff,
+        } = self.config.retry_policy;
+        let mut backoff = initial_backoff;
+        let mut attempt = 0;
+        loop {
+            self.stats.requests.fetch_add(1, Ordering::Relaxed);
+            let result = self.store.get_accounts(request).and_then(|response| {
+                if response.accounts.len() == request.pubkeys.len() {
+                    Ok(response)
+                } else {
+                    Err(RemoteLoaderError::MalformedResponse {
+                        expected: request.pubkeys.len(),
+                        actual: response.accounts.len(),
+                    })
+                }
+            });
+            match result {
+                Ok(response) => return Ok(response),
+                Err(err) if attempt < max_retries => {
+                    warn!("Retrying remote account request: {err}");
+                    self.stats.retries.fetch_add(1, Ordering::Relaxed);
+                    sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(err) => {
+                    self.stats.failures.fetch_add(1, Ordering::Relaxed);
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
+        if let Some(account) = self.cache.read().unwrap().get(pubkey) {
+            self.stats.cache_hits.fetch_add(1, Ordering::Relaxed);
+            return account.clone();
+        }
+        self.stats.cache_misses.fetch_add(1, Ordering::Relaxed);
+        if self.load_failure.read().unwrap().is_some() {
+            return None;
+        }
+        if let Err(err) = self.fetch(&[*pubkey]) {
+            warn!("Failed to load account {pubkey} from remote store: {err}");
+            self.load_failure.write().unwrap().get_or_insert(err);
+            return None;
+        }
+        self.cache.read().unwrap().get(pubkey).cloned().flatten()
+    }
+}
+
+impl<S: RemoteAccountStore> TransactionProcessingCallback for RemoteLoader<S> {
+    fn account_matches_owners(&self, account: &Pubkey, owners: &[Pubkey]) -> Option<usize> {
+        self.get_account(account)
+            .filter(|account| account.lamports() != 0)
+            .and_then(|account| owners.iter().position(|entry| account.owner() == entry))
+    }
+
+    fn get_account_shared_data(&self, pubkey: &Pubkey) -> Option<AccountSharedData> {
+        self.get_account(pubkey)
+    }
+
+    fn get_last_blockhash_and_lamports_per_signature(&self) -> (Hash, u64) {
+        (self.blockhash, self.lamports_per_signature)
+    }
+
+    fn get_rent_collector(&self) -> &RentCollector {
+        &self.rent_collector
+    }
+
+    fn get_feature_set(&self) -> Arc<FeatureSet> {
+        self.feature_set.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            message::Message, signature::Keypair, signer::Signer, system_instruction,
+            system_program, transaction::Transaction,
+        },
+        std::sync::atomic::AtomicUsize,
+    };
+
+    #[derive(Default)]
+    struct MockStore {
+        accounts: HashMap<Pubkey, AccountSharedData>,
+        requested: RwLock<Vec<usize>>,
+        failures_left: AtomicUsize,
+    }
+
+    impl RemoteAccountStore for MockStore {
+        fn get_accounts(
+            &self,
+            request: &GetAccountsRequest,
+        ) -> Result<GetAccountsResponse, RemoteLoaderError> {
+            self.requested.write().unwrap().push(request.pubkeys.len());
+            if self
+                .failures_left
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok()
+            {
+                return Err(RemoteLoaderError::Transport("unavailable".to_string()));
+            }
+            Ok(GetAccountsResponse {
+                accounts: request
+                    .pubkeys
+                    .iter()
+                    .map(|pubkey| self.accounts.get(pubkey).cloned())
+                    .collect(),
+            })
+        }
+    }
+
+    fn new_loader(store: MockStore, config: RemoteLoaderConfig) -> RemoteLoader<MockStore> {
+        RemoteLoader::new(
+            store,
+            config,
+            Hash::default(),
+            5000,
+            RentCollector::default(),
+            Arc::new(FeatureSet::all_enabled()),
+        )
+    }
+
+    fn no_backoff(max_retries: usize) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_prefetch_batches_and_caches() {
+        let payer = Keypair::new();
+        let recipients: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut store = MockStore::default();
+        store.accounts.insert(
+            payer.pubkey(),
+            AccountSharedData::new(10, 0, &system_program::id()),
+        );
+        let transactions: Vec<SanitizedTransaction> = recipients
+            .iter()
+            .map(|recipient| {
+                let instruction = system_instruction::transfer(&payer.pubkey(), recipient, 1);
+                let message = Message::new(&[instruction], Some(&payer.pubkey()));
+                SanitizedTransaction::from_transaction_for_tests(Transaction::new(
+                    &[&payer],
+                    message,
+                    Hash::default(),
+                ))
+            })
+            .collect();
+
+        let loader = new_loader(
+            store,
+            RemoteLoaderConfig {
+                max_batch_size: 2,
+                retry_policy: no_backoff(0),
+            },
+        );
+        loader.prefetch(&transactions).unwrap();
+        // payer, system program and three recipients, in batches of two
+        assert_eq!(*loader.store.requested.read().unwrap(), vec![2, 2, 1]);
+
+        assert_eq!(
+            loader
+                .get_account_shared_data(&payer.pubkey())
+                .unwrap()
+                .lamports(),
+            10
+        );
+        assert!(loader.get_account_shared_data(&recipients[0]).is_none());
+        assert_eq!(loader.stats().cache_hits.load(Ordering::Relaxed), 2);
+        assert_eq!(loader.stats().cache_misses.load(Ordering::Relaxed), 0);
+
+        // Prefetching again doesn't hit the store
+        loader.prefetch(&transactions).unwrap();
+        assert_eq!(loader.stats().requests.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_cache_miss_fetches_single_account() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut store = MockStore::default();
+        store
+            .accounts
+            .insert(pubkey, AccountSharedData::new(1, 0, &owner));
+
+        let loader = new_loader(store, RemoteLoaderConfig::default());
+        assert_eq!(
+            loader.account_matches_owners(&pubkey, &[Pubkey::new_unique(), owner]),
+            Some(1)
+        );
+        assert_eq!(loader.stats().cache_misses.load(Ordering::Relaxed), 1);
+        assert!(loader.get_account_shared_data(&pubkey).is_some());
+        assert_eq!(loader.stats().cache_hits.load(Ordering::Relaxed), 1);
+        assert_eq!(*loader.store.requested.read().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let pubkey = Pubkey::new_unique();
+        let mut store = MockStore::default();
+        store
+            .accounts
+            .insert(pubkey, AccountSharedData::new(1, 0, &Pubkey::new_unique()));
+        store.failures_left.store(2, Ordering::Relaxed);
+
+        // Two failures are absorbed by two retries
+        let loader = new_loader(
+            store,
+            RemoteLoaderConfig {
+                retry_policy: no_backoff(2),
+                ..RemoteLoaderConfig::default()
+            },
+        );
+        assert!(loader.get_account_shared_data(&pubkey).is_some());
+        assert_eq!(loader.stats().retries.load(Ordering::Relaxed), 2);
+        assert_eq!(loader.stats().failures.load(Ordering::Relaxed), 0);
+        assert_eq!(loader.take_load_failure(), None);
+
+        // Running out of retries surfaces the transport error
+        let store = MockStore {
+            failures_left: AtomicUsize::new(2),
+            ..MockStore::default()
+        };
+        let loader = new_loader(
+            store,
+            RemoteLoaderConfig {
+                retry_policy: no_backoff(1),
+                ..RemoteLoaderConfig::default()
+            },
+        );
+        assert_eq!(
+            loader.fetch(&[pubkey]),
+            Err(RemoteLoaderError::Transport("unavailable".to_string()))
+        );
+        assert_eq!(loader.stats().failures.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_load_failure() {
+        let pubkey = Pubkey::new_unique();
+        let mut store = MockStore::default();
+        store
+            .accounts
+            .insert(pubkey, AccountSharedData::new(1, 0, &Pubkey::new_unique()));
+        store.failures_left.store(1, Ordering::Relaxed);
+        let loader = new_loader(
+            store,
+            RemoteLoaderConfig {
+                retry_policy: no_backoff(0),
+                ..RemoteLoaderConfig::default()
+            },
+        );
+
+        // The failed account looks missing to the processor, but the failure
+        // is recorded and not cached as the absence of the account
+        assert!(loader.get_account_shared_data(&pubkey).is_none());
+        assert!(loader
+            .get_account_shared_data(&Pubkey::new_unique())
+            .is_none());
+        assert_eq!(*loader.store.requested.read().unwrap(), vec![1]);
+        assert_eq!(
+            loader.take_load_failure(),
+            Some(RemoteLoaderError::Transport("unavailable".to_string()))
+        );
+        assert_eq!(loader.take_load_failure(), None);
+
+        // The next batch fetches the account again
+        assert!(loader.get_account_shared_data(&pubkey).is_some());
+        assert_eq!(*loader.store.requested.read().unwrap(), vec![1, 1]);
+    }
+
+    #[test]
+    fn test_malformed_response() {
+        struct TruncatingStore;
+        impl RemoteAccountStore for TruncatingStore {
+            fn get_accounts(
+                &self,
+                _request: &GetAccountsRequest,
+            ) -> Result<GetAccountsResponse, RemoteLoaderError> {
+                Ok(GetAccountsResponse { accounts: vec![] })
+            }
+        }
+
+        let loader = RemoteLoader::new(
+            TruncatingStore,
+            RemoteLoaderConfig {
+                retry_policy: no_backoff(0),
+                ..RemoteLoaderConfig::default()
+            },
+            Hash::default(),
+            5000,
+            RentCollector::default(),
+            Arc::new(FeatureSet::all_enabled()),
+        );
+        assert_eq!(
+            loader.fetch(&[Pubkey::new_unique()]),
+            Err(RemoteLoaderError::MalformedResponse {
+                expected: 1,
+                actual: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Retention of transaction execution details.
+//!
+//! The logs, inner instructions and return data of every transaction of a
+//! batch are held in memory until the batch is committed, which is costly for
+//! batches of 100k+ transactions. A `ResultRetention` policy prunes them as
+//! each transaction finishes executing, keeping a summary of the transaction:
+//! its status, consumed compute units and accounts data size change.
+//!
+//! Whenever details may be pruned, the processor digests the details of
+//! every executed transaction before applying the policy, so that a
+//! commitment to them, e.g. for receipts, can still be produced for the batch.
+
+use {
+    crate::{
+        transaction_processor::TransactionLogMessages,
+        transaction_results::TransactionExecutionDetails,
+    },
+    solana_sdk::{
+        hash::{Hash, Hasher},
+        transaction::SanitizedTransaction,
+    },
+    std::{fmt, sync::Arc},
+};
+
+/// Receives the execution details of transactions before they are pruned
+pub trait TransactionDetailsSink: Send + Sync {
+    /// Called with the details of each executed transaction, in batch order.
+    /// `index` is the position of the transaction in the batch.
+    fn consume(
+        &self,
+        index: usize,
+        transaction: &SanitizedTransaction,
+        details: &TransactionExecutionDetails,
+    );
+}
+
+#[derive(Clone, Default)]
+pub enum ResultRetention {
+    /// Keep the details of all transactions
+    #[default]
+    KeepAll,
+    /// Keep the details of failed transactions only
+    KeepFailed,
+    /// Hand the details of every transaction to the sink and keep summaries
+    Stream(Arc<dyn TransactionDetailsSink>),
+}
+
+impl fmt::Debug for ResultRetention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KeepAll => write!(f, "KeepAll"),
+            Self::KeepFailed => write!(f, "KeepFailed"),
+            Self::Stream(_) => write!(f, "Stream"),
+        }
+    }
+}
+
+impl ResultRetention {
+    /// Whether the policy may prune the details of some transactions
+    pub fn may_prune(&self) -> bool {
+        !matches!(self, Self::KeepAll)
+    }
+
+    /// Apply the policy to the details of the executed transaction at
+    /// `index` of the batch. Returns the log messages pruned, if any, so
+    /// their buffers can be reused.
+    pub fn apply(
+        &self,
+        index: usize,
+        transaction: &SanitizedTransaction,
+        details: &mut TransactionExecutionDetails,
+    ) -> Option<TransactionLogMessages> {
+        match self {
+            Self::KeepAll => None,
+            Self::KeepFailed => {
+                if details.status.is_ok() {
+                    prune(details)
+                } else {
+                    None
+                }
+            }
+            Self::Stream(sink) => {
+                sink.consume(index, transaction, details);
+                prune(details)
+            }
+        }
+    }
+}
+
+fn prune(details: &mut TransactionExecutionDetails) -> Option<TransactionLogMessages> {
+    details.inner_instructions = None;
+    details.return_data = None;
+    details.log_messages.take()
+}
+
+/// Streaming digest of the execution details of a batch. Covers the inputs
+/// of a receipt: the status, consumed compute units, log messages and return
+/// data of each executed transaction.
+#[derive(Default)]
+pub struct ExecutionDetailsHasher {
+    hasher: Hasher,
+}
+
+impl ExecutionDetailsHasher {
+    /// Digest the details of the executed transaction at `index` of the batch
+    pub fn hash_details(&mut self, index: usize, details: &TransactionExecutionDetails) {
+        self.hasher.hash(&(index as u64).to_le_bytes());
+        match &details.status {
+            Ok(()) => self.hasher.hash(&[0]),
+            Err(err) => {
+                self.hasher.hash(&[1]);
+                self.hash_bytes(err.to_string().as_bytes());
+            }
+        }
+        self.hasher.hash(&details.executed_units.to_le_bytes());
+        let log_messages = details.log_messages.as_deref().unwrap_or_default();
+        self.hasher.hash(&(log_messages.len() as u64).to_le_bytes());
+        for log_message in log_messages {
+            self.hash_bytes(log_message.as_bytes());
+        }
+        match &details.return_data {
+            Some(return_data) => {
+                self.hasher.hash(&[1]);
+                self.hasher.hash(return_data.program_id.as_ref());
+                self.hash_bytes(&return_data.data);
+            }
+            None => self.hasher.hash(&[0]),
+        }
+    }
+
+    /// Length prefixed, so that consecutive fields cannot be confused
+    fn hash_bytes(&mut self, bytes: &[u8]) {
+        self.hasher.hash(&(bytes.len() as u64).to_le_bytes());
+        self.hasher.hash(bytes);
+    }
+
+    pub fn result(self) -> Hash {
+        self.hasher.result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::transaction_results::AccountLifecycle,
+        solana_sdk::{
+            instruction::InstructionError, pubkey::Pubkey, signature::Keypair, system_transaction,
+            transaction::TransactionError, transaction_context::TransactionReturnData,
+        },
+        std::sync::Mutex,
+    };
+
+    fn new_details(status: solana_sdk::transaction::Result<()>) -> TransactionExecutionDetails {
+        TransactionExecutionDetails {
+            status,
+            log_messages: Some(vec!["Program log: hello".to_string()]),
+            inner_instructions: Some(vec![vec![]]),
+            durable_nonce_fee: None,
+            return_data: Some(TransactionReturnData {
+                program_id: Pubkey::new_unique(),
+                data: vec![1, 2, 3],
+            }),
+            executed_units: 100,
+            accounts_data_len_delta: 0,
+            accounts_data_growth: 0,
+            heap_allocation_stats: None,
+            error_location: None,
+            capability_manifest: None,
+            decoded_instructions: None,
+            compute_budget_report: None,
+            account_lifecycle: AccountLifecycle::default(),
+            signature_policy_decision: None,
+        }
+    }
+
+    fn new_tx() -> SanitizedTransaction {
+        SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            1,
+            Hash::default(),
+        ))
+    }
+
+    fn is_pruned(details: &TransactionExecutionDetails) -> bool {
+        details.log_messages.is_none()
+            && details.inner_instructions.is_none()
+            && details.return_data.is_none()
+    }
+
+    #[derive(Default)]
+    struct CountingSink(Mutex<Vec<usize>>);
+
+    impl TransactionDetailsSink for CountingSink {
+        fn consume(
+            &self,
+            index: usize,
+            _transaction: &SanitizedTransaction,
+            details: &TransactionExecutionDetails,
+        ) {
+            assert!(!is_pruned(details));
+            self.0.lock().unwrap().push(index);
+        }
+    }
+
+    #[test]
+    fn test_result_retention() {
+        let tx = new_tx();
+        let failed = Err(TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(1),
+        ));
+
+        let mut details = new_details(Ok(()));
+        assert_eq!(ResultRetention::KeepAll.apply(0, &tx, &mut details), None);
+        assert!(!is_pruned(&details));
+
+        assert_eq!(
+            ResultRetention::KeepFailed.apply(0, &tx, &mut details),
+            Some(vec!["Program log: hello".to_string()])
+        );
+        assert!(is_pruned(&details));
+        assert_eq!(details.executed_units, 100);
+        let mut details = new_details(failed.clone());
+        ResultRetention::KeepFailed.apply(0, &tx, &mut details);
+        assert!(!is_pruned(&details));
+
+        let sink = Arc::new(CountingSink::default());
+        let retention = ResultRetention::Stream(sink.clone());
+        let mut details = new_details(failed);
+        retention.apply(3, &tx, &mut details);
+        assert!(is_pruned(&details));
+        assert_eq!(*sink.0.lock().unwrap(), vec![3]);
+
+        assert!(!ResultRetention::KeepAll.may_prune());
+        assert!(retention.may_prune());
+    }
+
+    #[test]
+    fn test_execution_details_hasher() {
+        let details = new_details(Ok(()));
+        let hash = |index, details: &TransactionExecutionDetails| {
+            let mut hasher = ExecutionDetailsHasher::default();
+            hasher.hash_details(index, details);
+            hasher.result()
+        };
+        assert_eq!(hash(0, &details), hash(0, &details));
+        assert_ne!(hash(0, &details), hash(1, &details));
+
+        let mut pruned = details.clone();
+        prune(&mut pruned);
+        assert_ne!(hash(0, &details), hash(0, &pruned));
+    }
+}
@@ -0,0 +1,317 @@
+//! Rules for which signatures a transaction needs.
+//!
+//! On L1, every signer of a message must sign the transaction, and the
+//! signatures are verified before transactions reach the processor. Chains
+//! built on the SVM may want to accept transactions signed otherwise, e.g. by
+//! a threshold of session keys acting for a fee payer, to experiment with
+//! account abstraction. A `SignaturePolicy` decides whether a transaction is
+//! signed well enough, and how: the decision is recorded in the execution
+//! details of the transaction.
+//!
+//! A missing signature is represented by the default signature. The
+//! embedder verifies the signatures which are present, e.g. with
+//! `verify_present_signatures()`, and the processor consults the policy
+//! before loading the accounts of the transactions. Transactions the policy
+//! rejects fail with `TransactionError::SignatureFailure`, and no fee is
+//! charged.
+//!
+//! Without a policy, the processor relies on the embedder having verified
+//! all signatures, like the L1 bank does.
+
+use {
+    solana_sdk::{
+        message::SanitizedMessage,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{Result, SanitizedTransaction, TransactionError},
+    },
+    std::collections::HashMap,
+};
+
+/// How a transaction satisfied the signature policy of the processor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignaturePolicyDecision {
+    /// Every signer of the message signed, like on L1
+    AllSigners,
+    /// The fee payer did not sign, but enough of its session keys did
+    SessionKeys {
+        fee_payer: Pubkey,
+        /// The session keys which signed
+        signers: Vec<Pubkey>,
+    },
+}
+
+pub trait SignaturePolicy: Send + Sync {
+    /// Whether `transaction`, whose present signatures are verified, is
+    /// signed well enough to be processed, and how
+    fn evaluate(&self, transaction: &SanitizedTransaction) -> Result<SignaturePolicyDecision>;
+}
+
+/// The signers of `transaction` whose signature is present, in the order
+/// of the account keys
+pub fn present_signers(transaction: &SanitizedTransaction) -> impl Iterator<Item = &Pubkey> {
+    transaction
+        .signatures()
+        .iter()
+        .zip(transaction.message().account_keys().iter())
+        .filter(|(signature, _signer)| **signature != Signature::default())
+        .map(|(_signature, signer)| signer)
+}
+
+/// Verify the signatures of `transaction` which are present, skipping the
+/// missing ones for the signature policy to judge
+pub fn verify_present_signatures(transaction: &SanitizedTransaction) -> Result<()> {
+    let message_data = match transaction.message() {
+        SanitizedMessage::Legacy(message) => message.message.serialize(),
+        SanitizedMessage::V0(message) => message.message.serialize(),
+    };
+    let all_verified = transaction
+        .signatures()
+        .iter()
+        .zip(transaction.message().account_keys().iter())
+        .filter(|(signature, _signer)| **signature != Signature::default())
+        .all(|(signature, signer)| signature.verify(signer.as_ref(), &message_data));
+    if all_verified {
+        Ok(())
+    } else {
+        Err(TransactionError::SignatureFailure)
+    }
+}
+
+/// Every signer must sign, the L1 rule
+#[derive(Debug, Default)]
+pub struct AllSignersPolicy;
+
+impl SignaturePolicy for AllSignersPolicy {
+    fn evaluate(&self, transaction: &SanitizedTransaction) -> Result<SignaturePolicyDecision> {
+        if present_signers(transaction).count() == transaction.signatures().len() {
+            Ok(SignaturePolicyDecision::AllSigners)
+        } else {
+            Err(TransactionError::SignatureFailure)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SessionKeys {
+    keys: Vec<Pubkey>,
+    threshold: usize,
+}
+
+/// Fee payers may leave their signature out if a threshold of their session
+/// keys sign instead. The session keys must be signers of the message. Any
+/// other signer must sign, like on L1.
+#[derive(Debug, Clone, Default)]
+pub struct SessionKeysPolicy {
+    session_keys: HashMap<Pubkey, SessionKeys>,
+}
+
+impl SessionKeysPolicy {
+    /// Let `threshold` of `keys` sign for `fee_payer`, replacing its previous
+    /// session keys, if any
+    ///
+    /// Panics if `threshold` is zero or exceeds the number of keys.
+    pub fn register(&mut self, fee_payer: Pubkey, keys: Vec<Pubkey>, threshold: usize) {
+        assert!(
+            threshold > 0 && threshold <= keys.len(),
+            "invalid threshold {threshold} of {} session keys",
+            keys.len()
+        );
+        self.session_keys
+            .insert(fee_payer, SessionKeys { keys, threshold });
+    }
+}
+
+impl SignaturePolicy for SessionKeysPolicy {
+    fn evaluate(&self, transaction: &SanitizedTransaction) -> Result<SignaturePolicyDecision> {
+        let signers = present_signers(transaction).collect::<Vec<_>>();
+        if signers.len() == transaction.signatures().len() {
+            return Ok(SignaturePolicyDecision::AllSigners);
+        }
+
+        // Only the signature of the fee payer may be missing
+        let fee_payer = transaction.message().fee_payer();
+        let fee_payer_signed = signers.first() == Some(&fee_payer);
+        if fee_payer_signed || signers.len() + 1 != transaction.signatures().len() {
+            return Err(TransactionError::SignatureFailure);
+        }
+        let session_keys = self
+            .session_keys
+            .get(fee_payer)
+            .ok_or(TransactionError::SignatureFailure)?;
+        let signers = signers
+            .into_iter()
+            .filter(|signer| session_keys.keys.contains(*signer))
+            .copied()
+            .collect::<Vec<_>>();
+        if signers.len() < session_keys.threshold {
+            return Err(TransactionError::SignatureFailure);
+        }
+
+        Ok(SignaturePolicyDecision::SessionKeys {
+            fee_payer: *fee_payer,
+            signers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash,
+            instruction::{AccountMeta, Instruction},
+            message::Message,
+            signature::{Keypair, Signer},
+            transaction::Transaction,
+        },
+    };
+
+    /// A transaction of `fee_payer` with `signers`, signed by `signing`
+    fn new_transaction(
+        fee_payer: &Pubkey,
+        signers: &[&Keypair],
+        signing: &[&Keypair],
+    ) -> SanitizedTransaction {
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new_readonly(signer.pubkey(), true))
+                .collect(),
+        );
+        let message = Message::new(&[instruction], Some(fee_payer));
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.partial_sign(signing, Hash::default());
+        SanitizedTransaction::from_transaction_for_tests(transaction)
+    }
+
+    #[test]
+    fn test_all_signers_policy() {
+        let fee_payer = Keypair::new();
+        let signer = Keypair::new();
+
+        let transaction = new_transaction(&fee_payer.pubkey(), &[&signer], &[&fee_payer, &signer]);
+        assert_eq!(verify_present_signatures(&transaction), Ok(()));
+        assert_eq!(
+            AllSignersPolicy.evaluate(&transaction),
+            Ok(SignaturePolicyDecision::AllSigners)
+        );
+
+        let transaction = new_transaction(&fee_payer.pubkey(), &[&signer], &[&signer]);
+        assert_eq!(verify_present_signatures(&transaction), Ok(()));
+        assert_eq!(
+            present_signers(&transaction).collect::<Vec<_>>(),
+            vec![&signer.pubkey()]
+        );
+        assert_eq!(
+            AllSignersPolicy.evaluate(&transaction),
+            Err(TransactionError::SignatureFailure)
+        );
+    }
+
+    #[test]
+    fn test_verify_present_signatures() {
+        let fee_payer = Keypair::new();
+        let signer = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(signer.pubkey(), true)],
+        );
+        let message = Message::new(&[instruction], Some(&fee_payer.pubkey()));
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction.signatures[1] = signer.sign_message(b"forged");
+        let transaction = SanitizedTransaction::from_transaction_for_tests(transaction);
+        assert_eq!(
+            verify_present_signatures(&transaction),
+            Err(TransactionError::SignatureFailure)
+        );
+    }
+
+    #[test]
+    fn test_session_keys_policy() {
+        let fee_payer = Keypair::new();
+        let session_keys = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let other = Keypair::new();
+        let mut policy = SessionKeysPolicy::default();
+        policy.register(
+            fee_payer.pubkey(),
+            session_keys.iter().map(Keypair::pubkey).collect(),
+            2,
+        );
+
+        // Signed by the fee payer, like on L1
+        let transaction = new_transaction(
+            &fee_payer.pubkey(),
+            &[&session_keys[0]],
+            &[&fee_payer, &session_keys[0]],
+        );
+        assert_eq!(
+            policy.evaluate(&transaction),
+            Ok(SignaturePolicyDecision::AllSigners)
+        );
+
+        // Signed by enough session keys, listed in the order of the message,
+        // which sorts the signers after the fee payer
+        let transaction = new_transaction(
+            &fee_payer.pubkey(),
+            &[&session_keys[0], &session_keys[2]],
+            &[&session_keys[0], &session_keys[2]],
+        );
+        let mut signers = vec![session_keys[0].pubkey(), session_keys[2].pubkey()];
+        signers.sort();
+        assert_eq!(
+            policy.evaluate(&transaction),
+            Ok(SignaturePolicyDecision::SessionKeys {
+                fee_payer: fee_payer.pubkey(),
+                signers,
+            })
+        );
+
+        // Not enough session keys
+        let transaction = new_transaction(
+            &fee_payer.pubkey(),
+            &[&session_keys[0], &other],
+            &[&session_keys[0], &other],
+        );
+        assert_eq!(
+            policy.evaluate(&transaction),
+            Err(TransactionError::SignatureFailure)
+        );
+
+        // Another signature is missing
+        let transaction = new_transaction(
+            &fee_payer.pubkey(),
+            &[&session_keys[0], &session_keys[1], &other],
+            &[&session_keys[0], &session_keys[1]],
+        );
+        assert_eq!(
+            policy.evaluate(&transaction),
+            Err(TransactionError::SignatureFailure)
+        );
+
+        // The fee payer has no session keys
+        let transaction = new_transaction(
+            &other.pubkey(),
+            &[&session_keys[0], &session_keys[1]],
+            &[&session_keys[0], &session_keys[1]],
+        );
+        assert_eq!(
+            policy.evaluate(&transaction),
+            Err(TransactionError::SignatureFailure)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid threshold 4 of 3 session keys")]
+    fn test_session_keys_policy_invalid_threshold() {
+        SessionKeysPolicy::default().register(
+            Pubkey::new_unique(),
+            vec![Pubkey::new_unique(); 3],
+            4,
+        );
+    }
+}
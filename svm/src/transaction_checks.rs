@@ -0,0 +1,420 @@
+//! Checks of transactions before their accounts are loaded.
+//!
+//! The processor expects every transaction to come with a
+//! `TransactionCheckResult`: whether it passed the checks of the embedder,
+//! the durable nonce it uses, if any, and the lamports per signature its fee
+//! is calculated with. On L1, the bank produces them from its blockhash queue,
+//! its accounts and its status cache. The checks of this module produce them
+//! from plain lookups instead, so that embedders without a bank can run the
+//! same checks, and combine them with checks of their own.
+//!
+//! A transaction passes its checks with `CheckedTransactionDetails`. The
+//! details must name the lamports per signature of the transaction, which
+//! come from its recent blockhash, or from its nonce account if it uses a
+//! durable nonce: `check_transactions()` rejects transactions whose checks
+//! did not provide them with `TransactionError::BlockhashNotFound`, like
+//! the processor would.
+
+use {
+    crate::{
+        account_loader::TransactionCheckResult, transaction_error_metrics::TransactionErrorMetrics,
+    },
+    solana_sdk::{
+        account::AccountSharedData,
+        hash::Hash,
+        message::SanitizedMessage,
+        nonce::{state::DurableNonce, NONCED_TX_MARKER_IX_INDEX},
+        nonce_account,
+        nonce_info::{NonceInfo, NoncePartial},
+        pubkey::Pubkey,
+        transaction::{Result, SanitizedTransaction, TransactionError},
+        transaction_context::TransactionAccount,
+    },
+    std::borrow::Borrow,
+};
+
+/// What the checks of a transaction found out about it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CheckedTransactionDetails {
+    /// The nonce account of the transaction, if it uses a durable nonce
+    pub nonce: Option<NoncePartial>,
+    /// The lamports per signature the fee of the transaction is calculated
+    /// with
+    pub lamports_per_signature: Option<u64>,
+}
+
+impl CheckedTransactionDetails {
+    /// The check result of a transaction which passed its checks with these
+    /// details
+    pub fn into_check_result(self) -> TransactionCheckResult {
+        (Ok(()), self.nonce, self.lamports_per_signature)
+    }
+}
+
+/// A rule transactions must satisfy before their accounts are loaded
+pub trait TransactionCheck {
+    /// Check `transaction`, which passed the previous checks with `details`,
+    /// and return the details it passes this check with
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails>;
+
+    /// Check transactions with this check, then with `other`
+    fn and<C: TransactionCheck>(self, other: C) -> AndCheck<Self, C>
+    where
+        Self: Sized,
+    {
+        AndCheck(self, other)
+    }
+
+    /// Check transactions with this check or, if they fail it, with `other`
+    fn or<C: TransactionCheck>(self, other: C) -> OrCheck<Self, C>
+    where
+        Self: Sized,
+    {
+        OrCheck(self, other)
+    }
+}
+
+/// Transactions must pass both checks, see `TransactionCheck::and()`
+pub struct AndCheck<A, B>(A, B);
+
+impl<A: TransactionCheck, B: TransactionCheck> TransactionCheck for AndCheck<A, B> {
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails> {
+        let details = self.0.check(transaction, details)?;
+        self.1.check(transaction, details)
+    }
+}
+
+/// Transactions must pass either check, see `TransactionCheck::or()`. The
+/// error of the second check is kept if both fail.
+pub struct OrCheck<A, B>(A, B);
+
+impl<A: TransactionCheck, B: TransactionCheck> TransactionCheck for OrCheck<A, B> {
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails> {
+        self.0
+            .check(transaction, details.clone())
+            .or_else(|_| self.1.check(transaction, details))
+    }
+}
+
+/// Transactions must have a recent blockhash which is still valid
+pub struct BlockhashCheck<F> {
+    lamports_per_signature: F,
+}
+
+impl<F: Fn(&Hash) -> Option<u64>> BlockhashCheck<F> {
+    /// `lamports_per_signature` returns the lamports per signature of a
+    /// blockhash, or `None` if it is not valid for the age of transactions
+    pub fn new(lamports_per_signature: F) -> Self {
+        Self {
+            lamports_per_signature,
+        }
+    }
+}
+
+impl<F: Fn(&Hash) -> Option<u64>> TransactionCheck for BlockhashCheck<F> {
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        _details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails> {
+        let lamports_per_signature =
+            (self.lamports_per_signature)(transaction.message().recent_blockhash())
+                .ok_or(TransactionError::BlockhashNotFound)?;
+        Ok(CheckedTransactionDetails {
+            nonce: None,
+            lamports_per_signature: Some(lamports_per_signature),
+        })
+    }
+}
+
+/// Transactions must use a durable nonce which can be advanced
+pub struct NonceCheck<F> {
+    next_durable_nonce: DurableNonce,
+    load_account: F,
+}
+
+impl<F: Fn(&Pubkey) -> Option<AccountSharedData>> NonceCheck<F> {
+    /// `next_durable_nonce` is the durable nonce of the block the
+    /// transactions are processed in, and `load_account` loads the nonce
+    /// accounts
+    pub fn new(next_durable_nonce: DurableNonce, load_account: F) -> Self {
+        Self {
+            next_durable_nonce,
+            load_account,
+        }
+    }
+
+    /// The nonce account of `transaction`, if it uses a durable nonce which
+    /// can be advanced
+    pub fn nonce_account(&self, transaction: &SanitizedTransaction) -> Option<TransactionAccount> {
+        let nonce_is_advanceable =
+            transaction.message().recent_blockhash() != self.next_durable_nonce.as_hash();
+        if nonce_is_advanceable {
+            durable_nonce_account(transaction.message(), &self.load_account)
+        } else {
+            None
+        }
+    }
+}
+
+impl<F: Fn(&Pubkey) -> Option<AccountSharedData>> TransactionCheck for NonceCheck<F> {
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        _details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails> {
+        let (address, account) = self
+            .nonce_account(transaction)
+            .ok_or(TransactionError::BlockhashNotFound)?;
+        let nonce = NoncePartial::new(address, account);
+        Ok(CheckedTransactionDetails {
+            lamports_per_signature: nonce.lamports_per_signature(),
+            nonce: Some(nonce),
+        })
+    }
+}
+
+/// The nonce account of `message`, if it uses a durable nonce authorized by
+/// the authority of the account
+pub fn durable_nonce_account(
+    message: &SanitizedMessage,
+    load_account: impl Fn(&Pubkey) -> Option<AccountSharedData>,
+) -> Option<TransactionAccount> {
+    let nonce_address = message.get_durable_nonce()?;
+    let nonce_account = load_account(nonce_address)?;
+    let nonce_data =
+        nonce_account::verify_nonce_account(&nonce_account, message.recent_blockhash())?;
+
+    let nonce_is_authorized = message
+        .get_ix_signers(NONCED_TX_MARKER_IX_INDEX as usize)
+        .any(|signer| signer == &nonce_data.authority);
+    if !nonce_is_authorized {
+        return None;
+    }
+
+    Some((*nonce_address, nonce_account))
+}
+
+/// Transactions must not have been processed already
+pub struct AlreadyProcessedCheck<F> {
+    is_processed: F,
+}
+
+impl<F: Fn(&SanitizedTransaction) -> bool> AlreadyProcessedCheck<F> {
+    /// `is_processed` returns whether a transaction was processed already,
+    /// e.g. from a status cache
+    pub fn new(is_processed: F) -> Self {
+        Self { is_processed }
+    }
+}
+
+impl<F: Fn(&SanitizedTransaction) -> bool> TransactionCheck for AlreadyProcessedCheck<F> {
+    fn check(
+        &self,
+        transaction: &SanitizedTransaction,
+        details: CheckedTransactionDetails,
+    ) -> Result<CheckedTransactionDetails> {
+        if (self.is_processed)(transaction) {
+            Err(TransactionError::AlreadyProcessed)
+        } else {
+            Ok(details)
+        }
+    }
+}
+
+/// Check the transactions which could be locked with `check`
+pub fn check_transactions(
+    check: &impl TransactionCheck,
+    sanitized_txs: &[impl Borrow<SanitizedTransaction>],
+    lock_results: &[Result<()>],
+    error_counters: &mut TransactionErrorMetrics,
+) -> Vec<TransactionCheckResult> {
+    sanitized_txs
+        .iter()
+        .zip(lock_results)
+        .map(|(tx, lock_result)| {
+            lock_result.clone()?;
+            check
+                .check(tx.borrow(), CheckedTransactionDetails::default())
+                .and_then(|details| {
+                    details
+                        .lamports_per_signature
+                        .is_some()
+                        .then_some(details)
+                        .ok_or(TransactionError::BlockhashNotFound)
+                })
+                .map_err(|err| {
+                    match err {
+                        TransactionError::BlockhashNotFound => {
+                            error_counters.blockhash_not_found += 1
+                        }
+                        TransactionError::AlreadyProcessed => error_counters.already_processed += 1,
+                        _ => {}
+                    }
+                    err
+                })
+        })
+        .map(|result| match result {
+            Ok(details) => details.into_check_result(),
+            Err(err) => (Err(err), None, None),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            nonce::{self, state::Versions as NonceVersions},
+            signature::{Keypair, Signer},
+            system_instruction, system_program, system_transaction,
+            transaction::Transaction,
+        },
+        std::collections::HashMap,
+    };
+
+    fn new_nonce_account(authority: &Pubkey, durable_nonce: DurableNonce) -> AccountSharedData {
+        let data = nonce::state::Data::new(*authority, durable_nonce, 5_000);
+        AccountSharedData::new_data(
+            1_000_000,
+            &NonceVersions::new(nonce::State::Initialized(data)),
+            &system_program::id(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_transactions() {
+        let recent_blockhash = Hash::new_unique();
+        let processed_blockhash = Hash::new_unique();
+        let next_durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+
+        let payer = Keypair::new();
+        let nonce_address = Pubkey::new_unique();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let nonce_account = new_nonce_account(&payer.pubkey(), durable_nonce);
+        let accounts = HashMap::from([(nonce_address, nonce_account.clone())]);
+
+        let transfer = |blockhash| {
+            SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+                blockhash,
+            ))
+        };
+        let nonced_transaction =
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[
+                    system_instruction::advance_nonce_account(&nonce_address, &payer.pubkey()),
+                    system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1),
+                ],
+                Some(&payer.pubkey()),
+                &[&payer],
+                *durable_nonce.as_hash(),
+            ));
+        let sanitized_txs = [
+            transfer(recent_blockhash),
+            nonced_transaction,
+            transfer(Hash::new_unique()),
+            transfer(processed_blockhash),
+            transfer(recent_blockhash),
+        ];
+        let lock_results = [
+            Ok(()),
+            Ok(()),
+            Ok(()),
+            Ok(()),
+            Err(TransactionError::AccountInUse),
+        ];
+
+        let check = BlockhashCheck::new(|blockhash: &Hash| {
+            (*blockhash == recent_blockhash || *blockhash == processed_blockhash).then_some(10)
+        })
+        .or(NonceCheck::new(next_durable_nonce, |address: &Pubkey| {
+            accounts.get(address).cloned()
+        }))
+        .and(AlreadyProcessedCheck::new(|tx: &SanitizedTransaction| {
+            *tx.message().recent_blockhash() == processed_blockhash
+        }));
+        let mut error_counters = TransactionErrorMetrics::default();
+        let check_results =
+            check_transactions(&check, &sanitized_txs, &lock_results, &mut error_counters);
+
+        assert_eq!(
+            check_results,
+            vec![
+                (Ok(()), None, Some(10)),
+                (
+                    Ok(()),
+                    Some(NoncePartial::new(nonce_address, nonce_account.clone())),
+                    Some(5_000)
+                ),
+                (Err(TransactionError::BlockhashNotFound), None, None),
+                (Err(TransactionError::AlreadyProcessed), None, None),
+                (Err(TransactionError::AccountInUse), None, None),
+            ]
+        );
+        assert_eq!(error_counters.blockhash_not_found, 1);
+        assert_eq!(error_counters.already_processed, 1);
+    }
+
+    #[test]
+    fn test_nonce_check() {
+        let authority = Keypair::new();
+        let nonce_address = Pubkey::new_unique();
+        let durable_nonce = DurableNonce::from_blockhash(&Hash::new_unique());
+        let nonce_account = new_nonce_account(&authority.pubkey(), durable_nonce);
+        let load_account =
+            |address: &Pubkey| (*address == nonce_address).then(|| nonce_account.clone());
+        let nonced_transaction = |signer: &Keypair| {
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[system_instruction::advance_nonce_account(
+                    &nonce_address,
+                    &signer.pubkey(),
+                )],
+                Some(&signer.pubkey()),
+                &[signer],
+                *durable_nonce.as_hash(),
+            ))
+        };
+
+        let check = NonceCheck::new(
+            DurableNonce::from_blockhash(&Hash::new_unique()),
+            load_account,
+        );
+        assert_eq!(
+            check.nonce_account(&nonced_transaction(&authority)),
+            Some((nonce_address, nonce_account.clone()))
+        );
+        // not signed by the authority of the nonce account
+        assert_eq!(
+            check.nonce_account(&nonced_transaction(&Keypair::new())),
+            None
+        );
+
+        // the nonce was already advanced in this block
+        let check = NonceCheck::new(durable_nonce, load_account);
+        assert_eq!(check.nonce_account(&nonced_transaction(&authority)), None);
+        assert_eq!(
+            check.check(
+                &nonced_transaction(&authority),
+                CheckedTransactionDetails::default()
+            ),
+            Err(TransactionError::BlockhashNotFound)
+        );
+    }
+}
@@ -1,4 +1,11 @@
-use solana_sdk::{clock::Slot, saturating_add_assign};
+use {
+    solana_sdk::{clock::Slot, pubkey::Pubkey, saturating_add_assign},
+    std::collections::HashMap,
+};
+
+/// Owners reported with the most transactions over their loaded accounts
+/// data size limit, to bound the number of datapoints per slot
+const MAX_REPORTED_LOAD_BUDGET_OWNERS: usize = 16;
 
 #[derive(Debug, Default)]
 pub struct TransactionErrorMetrics {
@@ -24,6 +31,9 @@ pub struct TransactionErrorMetrics {
     pub would_exceed_max_vote_cost_limit: usize,
     pub would_exceed_account_data_block_limit: usize,
     pub max_loaded_accounts_data_size_exceeded: usize,
+    /// Transactions over their loaded accounts data size limit, by owner of
+    /// the account which brought them over it
+    pub max_loaded_accounts_data_size_exceeded_by_owner: HashMap<Pubkey, usize>,
     pub program_execution_temporarily_restricted: usize,
 }
 
@@ -82,6 +92,15 @@ impl TransactionErrorMetrics {
             self.max_loaded_accounts_data_size_exceeded,
             other.max_loaded_accounts_data_size_exceeded
         );
+        for (owner, count) in &other.max_loaded_accounts_data_size_exceeded_by_owner {
+            saturating_add_assign!(
+                *self
+                    .max_loaded_accounts_data_size_exceeded_by_owner
+                    .entry(*owner)
+                    .or_default(),
+                *count
+            );
+        }
         saturating_add_assign!(
             self.program_execution_temporarily_restricted,
             other.program_execution_temporarily_restricted
@@ -173,5 +192,20 @@ impl TransactionErrorMetrics {
                 i64
             ),
         );
+
+        let mut owners = self
+            .max_loaded_accounts_data_size_exceeded_by_owner
+            .iter()
+            .collect::<Vec<_>>();
+        owners.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        for (owner, count) in owners.into_iter().take(MAX_REPORTED_LOAD_BUDGET_OWNERS) {
+            datapoint_info!(
+                "banking_stage-leader_slot_load_budget_exceeded",
+                ("id", id as i64, i64),
+                ("slot", slot as i64, i64),
+                ("owner", owner.to_string(), String),
+                ("count", *count as i64, i64),
+            );
+        }
     }
 }
@@ -0,0 +1,81 @@
+//! Bounded lifetimes of transactions, by slot.
+//!
+//! On L1, the lifetime of a transaction is bounded by the age of its recent
+//! blockhash. Chains built on the SVM may want to offer transactions which
+//! expire at a given slot instead, without overloading the blockhash age
+//! semantics. A `TransactionExpiration` tells the processor the last slot in
+//! which a transaction may be processed. Transactions whose slot has passed
+//! fail with `TransactionError::TransactionExpired` before their accounts are
+//! loaded, so no fee is charged.
+//!
+//! Without an expiration, only the blockhash age bounds the lifetime of
+//! transactions.
+
+use {
+    solana_sdk::{clock::Slot, signature::Signature, transaction::SanitizedTransaction},
+    std::collections::HashMap,
+};
+
+pub trait TransactionExpiration: Send + Sync {
+    /// The last slot in which `transaction` may be processed, or `None` if
+    /// its lifetime is not bounded by slot
+    fn valid_until_slot(&self, transaction: &SanitizedTransaction) -> Option<Slot>;
+
+    /// Whether `transaction` may no longer be processed in `slot`
+    fn is_expired(&self, transaction: &SanitizedTransaction, slot: Slot) -> bool {
+        self.valid_until_slot(transaction)
+            .is_some_and(|valid_until_slot| slot > valid_until_slot)
+    }
+}
+
+/// Validity windows of transactions, by their signature
+#[derive(Debug, Default)]
+pub struct ValidUntilSlots {
+    pub valid_until_slots: HashMap<Signature, Slot>,
+}
+
+impl ValidUntilSlots {
+    pub fn new(valid_until_slots: impl IntoIterator<Item = (Signature, Slot)>) -> Self {
+        Self {
+            valid_until_slots: valid_until_slots.into_iter().collect(),
+        }
+    }
+}
+
+impl TransactionExpiration for ValidUntilSlots {
+    fn valid_until_slot(&self, transaction: &SanitizedTransaction) -> Option<Slot> {
+        self.valid_until_slots.get(transaction.signature()).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Keypair, system_transaction},
+    };
+
+    fn new_transaction() -> SanitizedTransaction {
+        SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &Keypair::new(),
+            &Pubkey::new_unique(),
+            1,
+            Hash::default(),
+        ))
+    }
+
+    #[test]
+    fn test_valid_until_slots() {
+        let bounded = new_transaction();
+        let unbounded = new_transaction();
+        let expiration = ValidUntilSlots::new([(*bounded.signature(), 10)]);
+
+        assert_eq!(expiration.valid_until_slot(&bounded), Some(10));
+        assert!(!expiration.is_expired(&bounded, 9));
+        assert!(!expiration.is_expired(&bounded, 10));
+        assert!(expiration.is_expired(&bounded, 11));
+
+        assert_eq!(expiration.valid_until_slot(&unbounded), None);
+        assert!(!expiration.is_expired(&unbounded, Slot::MAX));
+    }
+}
@@ -2,24 +2,69 @@ use {
     crate::transaction_error_metrics::TransactionErrorMetrics,
     solana_program_runtime::loaded_programs::LoadedProgramMatchCriteria,
     solana_sdk::{
-        account::AccountSharedData, feature_set::FeatureSet, hash::Hash, message::SanitizedMessage,
-        pubkey::Pubkey, rent_collector::RentCollector, transaction,
+        account::{AccountSharedData, ReadableAccount},
+        clock::{Epoch, Slot},
+        feature_set::FeatureSet,
+        hash::Hash,
+        message::SanitizedMessage,
+        pubkey::Pubkey,
+        rent_collector::RentCollector,
+        transaction,
     },
     std::sync::Arc,
 };
 
+/// Metadata of an account, without its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountMeta {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data_len: usize,
+    pub executable: bool,
+    pub rent_epoch: Epoch,
+}
+
+impl<T: ReadableAccount> From<&T> for AccountMeta {
+    fn from(account: &T) -> Self {
+        Self {
+            lamports: account.lamports(),
+            owner: *account.owner(),
+            data_len: account.data().len(),
+            executable: account.executable(),
+            rent_epoch: account.rent_epoch(),
+        }
+    }
+}
+
 /// Runtime callbacks for transaction processing.
 pub trait TransactionProcessingCallback {
     fn account_matches_owners(&self, account: &Pubkey, owners: &[Pubkey]) -> Option<usize>;
 
     fn get_account_shared_data(&self, pubkey: &Pubkey) -> Option<AccountSharedData>;
 
+    /// Metadata of an account, used to reject transactions before loading
+    /// the data of their accounts. Implementors which can look up metadata
+    /// without loading the account data should override the default, which
+    /// loads the whole account.
+    fn peek_account_meta(&self, pubkey: &Pubkey) -> Option<AccountMeta> {
+        self.get_account_shared_data(pubkey)
+            .map(|account| AccountMeta::from(&account))
+    }
+
     fn get_last_blockhash_and_lamports_per_signature(&self) -> (Hash, u64);
 
     fn get_rent_collector(&self) -> &RentCollector;
 
     fn get_feature_set(&self) -> Arc<FeatureSet>;
 
+    /// The slot and bank hash of the fork whose sysvar cache snapshot the
+    /// transactions execute with, for embedders processing several forks
+    /// with one `TransactionBatchProcessor`. `None` executes them with the
+    /// sysvar cache of the processor.
+    fn get_sysvar_cache_snapshot_id(&self) -> Option<(Slot, Hash)> {
+        None
+    }
+
     fn check_account_access(
         &self,
         _message: &SanitizedMessage,
@@ -1,33 +1,52 @@
 use {
     crate::{
+        account_contention::AccountContentionReport,
         account_loader::{
-            load_accounts, LoadedTransaction, TransactionCheckResult, TransactionLoadResult,
+            load_accounts, load_transaction, LoadedTransaction, TransactionCheckResult,
+            TransactionLoadResult,
         },
         account_overrides::AccountOverrides,
+        feature_overrides::FeatureOverrides,
+        fee_distributor::{CollectedFees, FeeDistribution, FeeDistributor, NoopFeeDistributor},
+        instruction_decoder::InstructionDecoderRegistry,
+        load_signal::{InFlightBatch, ProcessorLoadSignal},
         message_processor::MessageProcessor,
+        owner_transition::OwnerTransitionValidator,
+        precompile_registry::PrecompileRegistry,
+        program_cache_manifest::{
+            ProgramCacheImport, ProgramCacheManifest, ProgramCacheManifestEntry,
+            ProgramCacheManifestError,
+        },
         program_loader::{
             load_program_accounts, load_program_from_bytes, ProgramAccountLoadResult,
         },
+        program_usage::ProgramUsageReport,
+        result_retention::{ExecutionDetailsHasher, ResultRetention},
+        signature_policy::{SignaturePolicy, SignaturePolicyDecision},
         transaction_account_state_info::TransactionAccountStateInfo,
         transaction_error_metrics::TransactionErrorMetrics,
+        transaction_expiration::TransactionExpiration,
         transaction_processing_callback::TransactionProcessingCallback,
         transaction_results::{
-            DurableNonceFee, TransactionExecutionDetails, TransactionExecutionResult,
+            AccountLifecycle, DurableNonceFee, TransactionExecutionDetails,
+            TransactionExecutionResult,
         },
+        write_policy::WritePolicy,
     },
     log::debug,
     percentage::Percentage,
     solana_measure::measure::Measure,
     solana_program_runtime::{
         compute_budget::ComputeBudget,
+        compute_budget_processor::canonicalize_compute_budget_instructions,
         invoke_context::InvokeContext,
         loaded_programs::{
             ForkGraph, LoadProgramMetrics, LoadedProgram, LoadedProgramMatchCriteria,
             LoadedProgramType, LoadedProgramsForTxBatch, ProgramCache,
         },
-        log_collector::LogCollector,
+        log_collector::{LogBufferPool, LogCollector, LOG_MESSAGES_BYTES_LIMIT},
         runtime_config::RuntimeConfig,
-        sysvar_cache::SysvarCache,
+        sysvar_cache::{SysvarCache, SysvarCacheSnapshots},
         timings::{ExecuteDetailsTimings, ExecuteTimingType, ExecuteTimings},
     },
     solana_sdk::{
@@ -37,33 +56,122 @@ use {
         clock::{Epoch, Slot},
         epoch_schedule::EpochSchedule,
         fee::FeeStructure,
+        hash::Hash,
         inner_instruction::{InnerInstruction, InnerInstructionsList},
         instruction::{CompiledInstruction, InstructionError, TRANSACTION_LEVEL_STACK_HEIGHT},
         loader_v4::{self, LoaderV4State},
         message::SanitizedMessage,
         native_loader,
+        nonce_info::{NonceInfo, NoncePartial},
         pubkey::Pubkey,
-        saturating_add_assign,
-        transaction::{self, SanitizedTransaction, TransactionError},
-        transaction_context::{ExecutionRecord, TransactionContext},
+        saturating_add_assign, system_program,
+        transaction::{self, OwnerTransitionError, SanitizedTransaction, TransactionError},
+        transaction_context::{
+            ExecutionRecord, TransactionAccount, TransactionContext, TransactionReturnData,
+        },
     },
     std::{
         cell::RefCell,
-        collections::{hash_map::Entry, HashMap},
+        collections::{btree_map, hash_map::Entry, BTreeMap, HashMap, HashSet},
         fmt::{Debug, Formatter},
         rc::Rc,
         sync::{atomic::Ordering, Arc, RwLock},
     },
+    thiserror::Error,
 };
 
 /// A list of log messages emitted during a transaction
 pub type TransactionLogMessages = Vec<String>;
 
+/// Upper bound of the log buffers kept for reuse within a batch
+const LOG_BUFFER_POOL_MAX_BYTES: usize = 4 * 1024 * 1024;
+
 pub struct LoadAndExecuteSanitizedTransactionsOutput {
     pub loaded_transactions: Vec<TransactionLoadResult>,
     // Vector of results indicating whether a transaction was executed or could not
     // be executed. Note executed transactions can still have failed!
     pub execution_results: Vec<TransactionExecutionResult>,
+    // Distribution of the fees collected from the executed transactions, as
+    // decided by the processor's `FeeDistributor`
+    pub fee_distribution: FeeDistribution,
+    // Invocations and consumed compute units of each program invoked by the
+    // executed transactions
+    pub program_usage_report: ProgramUsageReport,
+    // Accounts locked by several of the executed transactions, at least one
+    // of which writes them
+    pub account_contention_report: AccountContentionReport,
+    // Digest of the execution details of the executed transactions, taken
+    // before the processor's `ResultRetention` policy was applied. Only
+    // computed if the policy may prune details.
+    pub execution_details_digest: Option<Hash>,
+}
+
+/// What `TransactionBatchProcessor::load_and_execute_sanitized_transactions_streamed`
+/// returns once the results of all transactions were handed out, see
+/// `LoadAndExecuteSanitizedTransactionsOutput` for the fields
+#[derive(Debug, Default)]
+pub struct StreamedBatchOutput {
+    pub fee_distribution: FeeDistribution,
+    pub program_usage_report: ProgramUsageReport,
+    pub account_contention_report: AccountContentionReport,
+    pub execution_details_digest: Option<Hash>,
+}
+
+/// State of a batch shared by the loading and the execution of its
+/// transactions, see `TransactionBatchProcessor::prepare_batch()`
+struct PreparedBatch<'a> {
+    /// Counts the batch as in flight until it is finished
+    _in_flight_batch: Option<InFlightBatch<'a>>,
+    dry_run: bool,
+    program_accounts_map: HashMap<Pubkey, (&'static Pubkey, u64)>,
+    programs_loaded_for_tx_batch: Rc<RefCell<LoadedProgramsForTxBatch>>,
+    program_cache_us: u64,
+    signature_policy_decisions: Option<Vec<Option<SignaturePolicyDecision>>>,
+    /// The sysvars the batch executes with, if the callbacks selected a
+    /// snapshot, see `TransactionProcessingCallback::get_sysvar_cache_snapshot_id`
+    sysvar_cache_snapshot: Option<SysvarCache>,
+    batch_timings: ExecuteTimings,
+    details_hasher: Option<ExecutionDetailsHasher>,
+    log_buffer_pool: Option<Rc<RefCell<LogBufferPool>>>,
+}
+
+/// Results of a batch which do not depend on how its transactions were
+/// handed out, see `TransactionBatchProcessor::finish_batch()`
+struct FinishedBatch {
+    fee_distribution: FeeDistribution,
+    program_usage_report: ProgramUsageReport,
+    execution_details_digest: Option<Hash>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ReExecutionError {
+    #[error("index {index} is out of bounds of the batch of {len} transactions")]
+    IndexOutOfBounds { index: usize, len: usize },
+}
+
+/// Upper bound on the compute units a view call may consume
+pub const MAX_VIEW_CALL_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// Outcome of a read-only view call, see
+/// `TransactionBatchProcessor::process_view_call`
+#[derive(Debug, Clone)]
+pub struct ViewCallResult {
+    pub status: transaction::Result<()>,
+    pub log_messages: Option<TransactionLogMessages>,
+    pub return_data: Option<TransactionReturnData>,
+    pub executed_units: u64,
+}
+
+/// What `TransactionBatchProcessor::gc` reclaimed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessorGcReport {
+    /// Program cache entries verified for outdated environments
+    pub removed_program_entries: usize,
+    /// Approximate memory reclaimed, the account sizes of the removed
+    /// programs which were loaded
+    pub reclaimed_bytes: usize,
+    /// Sysvar cache snapshots of the slots before the root
+    pub removed_sysvar_cache_snapshots: usize,
 }
 
 /// Configuration of the recording capabilities for transaction execution
@@ -72,6 +180,11 @@ pub struct ExecutionRecordingConfig {
     pub enable_cpi_recording: bool,
     pub enable_log_recording: bool,
     pub enable_return_data_recording: bool,
+    pub enable_heap_stats_recording: bool,
+    pub enable_error_location_recording: bool,
+    pub enable_capability_recording: bool,
+    pub enable_instruction_decoding: bool,
+    pub enable_compute_budget_report: bool,
 }
 
 impl ExecutionRecordingConfig {
@@ -80,11 +193,15 @@ impl ExecutionRecordingConfig {
             enable_return_data_recording: option,
             enable_log_recording: option,
             enable_cpi_recording: option,
+            enable_heap_stats_recording: option,
+            enable_error_location_recording: option,
+            enable_capability_recording: option,
+            enable_instruction_decoding: option,
+            enable_compute_budget_report: option,
         }
     }
 }
 
-#[derive(AbiExample)]
 pub struct TransactionBatchProcessor<FG: ForkGraph> {
     /// Bank slot (i.e. block)
     slot: Slot,
@@ -106,8 +223,51 @@ pub struct TransactionBatchProcessor<FG: ForkGraph> {
     /// client code (e.g. Bank) and forwarded to the MessageProcessor.
     pub sysvar_cache: RwLock<SysvarCache>,
 
+    /// Snapshots of the sysvar cache per fork, selected by the callbacks of
+    /// a batch with `get_sysvar_cache_snapshot_id`
+    sysvar_cache_snapshots: Arc<RwLock<SysvarCacheSnapshots>>,
+
     /// Programs required for transaction batch processing
     pub program_cache: Arc<RwLock<ProgramCache<FG>>>,
+
+    /// Decides what happens to the fees collected by a batch
+    fee_distributor: Arc<dyn FeeDistributor>,
+
+    /// Decides which execution details are kept in the batch output
+    result_retention: ResultRetention,
+
+    /// Embedder defined rules the writes of transactions must satisfy
+    write_policy: Option<Arc<dyn WritePolicy>>,
+
+    /// Embedder defined rules the owner changes of transactions must satisfy
+    owner_transition_validator: Option<Arc<dyn OwnerTransitionValidator>>,
+
+    /// Embedder defined validity windows of transactions, by slot
+    transaction_expiration: Option<Arc<dyn TransactionExpiration>>,
+
+    /// Features overridden for single transactions, only set in tests
+    feature_overrides: FeatureOverrides,
+
+    /// Load of the processor, for backpressure upstream
+    load_signal: Arc<ProcessorLoadSignal>,
+
+    /// Decoders of the instructions recorded in the execution details
+    instruction_decoders: Arc<InstructionDecoderRegistry>,
+
+    /// Embedder defined precompiles, on top of the ones of the SDK
+    precompiles: Arc<PrecompileRegistry>,
+
+    /// Embedder defined rules for which signatures transactions need
+    signature_policy: Option<Arc<dyn SignaturePolicy>>,
+}
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl<FG: ForkGraph> ::solana_frozen_abi::abi_example::AbiExample for TransactionBatchProcessor<FG> {
+    fn example() -> Self {
+        // The hooks of the processor are trait objects which have no
+        // example, so rely on the default processor and its no-op hooks
+        TransactionBatchProcessor::default()
+    }
 }
 
 impl<FG: ForkGraph> Debug for TransactionBatchProcessor<FG> {
@@ -120,7 +280,8 @@ impl<FG: ForkGraph> Debug for TransactionBatchProcessor<FG> {
             .field("runtime_config", &self.runtime_config)
             .field("sysvar_cache", &self.sysvar_cache)
             .field("program_cache", &self.program_cache)
-            .finish()
+            .field("result_retention", &self.result_retention)
+            .finish_non_exhaustive()
     }
 }
 
@@ -133,10 +294,21 @@ impl<FG: ForkGraph> Default for TransactionBatchProcessor<FG> {
             fee_structure: FeeStructure::default(),
             runtime_config: Arc::<RuntimeConfig>::default(),
             sysvar_cache: RwLock::<SysvarCache>::default(),
+            sysvar_cache_snapshots: Arc::default(),
             program_cache: Arc::new(RwLock::new(ProgramCache::new(
                 Slot::default(),
                 Epoch::default(),
             ))),
+            fee_distributor: Arc::new(NoopFeeDistributor),
+            result_retention: ResultRetention::default(),
+            write_policy: None,
+            owner_transition_validator: None,
+            transaction_expiration: None,
+            feature_overrides: FeatureOverrides::default(),
+            load_signal: Arc::default(),
+            instruction_decoders: Arc::default(),
+            precompiles: Arc::default(),
+            signature_policy: None,
         }
     }
 }
@@ -157,11 +329,265 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             fee_structure,
             runtime_config,
             sysvar_cache: RwLock::<SysvarCache>::default(),
+            sysvar_cache_snapshots: Arc::default(),
             program_cache,
+            fee_distributor: Arc::new(NoopFeeDistributor),
+            result_retention: ResultRetention::default(),
+            write_policy: None,
+            owner_transition_validator: None,
+            transaction_expiration: None,
+            feature_overrides: FeatureOverrides::default(),
+            load_signal: Arc::default(),
+            instruction_decoders: Arc::default(),
+            precompiles: Arc::default(),
+            signature_policy: None,
+        }
+    }
+
+    /// Install the hook invoked with the fees collected by every processed
+    /// batch. Defaults to `NoopFeeDistributor`, which leaves distribution to
+    /// the caller like the L1 bank does.
+    pub fn set_fee_distributor(&mut self, fee_distributor: Arc<dyn FeeDistributor>) {
+        self.fee_distributor = fee_distributor;
+    }
+
+    /// Install the policy deciding which execution details are kept in the
+    /// output of a batch. Defaults to keeping all of them.
+    pub fn set_result_retention(&mut self, result_retention: ResultRetention) {
+        self.result_retention = result_retention;
+    }
+
+    /// Install the policy consulted for every account modified by a
+    /// successfully executed transaction. A vetoed write fails the
+    /// transaction with `TransactionError::AccountWriteVetoed`. By default,
+    /// all writes are allowed.
+    pub fn set_write_policy(&mut self, write_policy: Arc<dyn WritePolicy>) {
+        self.write_policy = Some(write_policy);
+    }
+
+    /// Install the validator consulted for every account whose owner a
+    /// successfully executed transaction changed. A rejected change fails the
+    /// transaction with `TransactionError::OwnerTransitionRejected`. By
+    /// default, only the L1 rules, enforced during execution, apply.
+    pub fn set_owner_transition_validator(
+        &mut self,
+        owner_transition_validator: Arc<dyn OwnerTransitionValidator>,
+    ) {
+        self.owner_transition_validator = Some(owner_transition_validator);
+    }
+
+    /// Install the validity windows of transactions. Transactions processed
+    /// after the last slot of their window fail with
+    /// `TransactionError::TransactionExpired` before their accounts are
+    /// loaded. By default, only the blockhash age bounds the lifetime of
+    /// transactions.
+    pub fn set_transaction_expiration(
+        &mut self,
+        transaction_expiration: Arc<dyn TransactionExpiration>,
+    ) {
+        self.transaction_expiration = Some(transaction_expiration);
+    }
+
+    /// Activate or deactivate features for single transactions, on top of
+    /// the feature set of the callback. See `FeatureOverrides`.
+    #[cfg(feature = "dev-context-only-utils")]
+    pub fn set_feature_overrides(&mut self, feature_overrides: FeatureOverrides) {
+        self.feature_overrides = feature_overrides;
+    }
+
+    /// Install the signal updated with the load of this processor. Sharing
+    /// one signal between the processors of all banks makes it reflect the
+    /// load of the whole pipeline.
+    pub fn set_load_signal(&mut self, load_signal: Arc<ProcessorLoadSignal>) {
+        self.load_signal = load_signal;
+    }
+
+    /// The load of this processor, for backpressure and admission control
+    /// upstream
+    pub fn load_signal(&self) -> &Arc<ProcessorLoadSignal> {
+        &self.load_signal
+    }
+
+    /// Install the decoders of the instructions recorded when
+    /// `ExecutionRecordingConfig::enable_instruction_decoding` is set.
+    /// Defaults to the decoders of the builtin programs.
+    pub fn set_instruction_decoders(
+        &mut self,
+        instruction_decoders: Arc<InstructionDecoderRegistry>,
+    ) {
+        self.instruction_decoders = instruction_decoders;
+    }
+
+    /// Install the precompiles of the embedder. Their instructions are
+    /// verified while loading transactions, which fail with a
+    /// `TransactionError::InstructionError` for the instruction which fails
+    /// verification. By default, only the precompiles of the SDK, verified
+    /// before transactions reach the processor, exist.
+    pub fn set_precompiles(&mut self, precompiles: Arc<PrecompileRegistry>) {
+        self.precompiles = precompiles;
+    }
+
+    /// Install the policy deciding which signatures transactions need.
+    /// Transactions it rejects fail with `TransactionError::SignatureFailure`
+    /// before their accounts are loaded, and its decision is recorded in the
+    /// execution details. By default, the embedder is expected to have
+    /// verified all signatures, like the L1 bank does.
+    pub fn set_signature_policy(&mut self, signature_policy: Arc<dyn SignaturePolicy>) {
+        self.signature_policy = Some(signature_policy);
+    }
+
+    /// Invalidate the cached versions of the program `program_id` whose
+    /// account was mutated at `slot` outside of the loaders, so that it is
+    /// reloaded from its account by the batches of `slot` and its descendants.
+    /// See `ProgramCache::invalidate_program()`.
+    pub fn invalidate_program(&self, program_id: Pubkey, slot: Slot) {
+        self.program_cache
+            .write()
+            .unwrap()
+            .invalidate_program(program_id, slot);
+    }
+
+    /// List the programs which are cached for the environments of the
+    /// current epoch, in their latest deployment up to the current slot. See
+    /// `ProgramCacheManifest`.
+    pub fn export_cache_manifest(&self) -> ProgramCacheManifest {
+        let program_cache = self.program_cache.read().unwrap();
+        let environments = program_cache.get_environments_for_epoch(self.epoch);
+        let mut programs = BTreeMap::<Pubkey, ProgramCacheManifestEntry>::new();
+        for (program_id, program) in program_cache.get_flattened_entries(true, true) {
+            let verified_for_current_environments =
+                program
+                    .program
+                    .get_environment()
+                    .is_some_and(|environment| {
+                        Arc::ptr_eq(environment, &environments.program_runtime_v1)
+                            || Arc::ptr_eq(environment, &environments.program_runtime_v2)
+                    });
+            if !verified_for_current_environments || program.deployment_slot > self.slot {
+                continue;
+            }
+            let entry = ProgramCacheManifestEntry {
+                program_id,
+                deployment_slot: program.deployment_slot,
+                usage_count: program.tx_usage_counter.load(Ordering::Relaxed),
+            };
+            match programs.entry(program_id) {
+                btree_map::Entry::Vacant(vacant) => {
+                    vacant.insert(entry);
+                }
+                btree_map::Entry::Occupied(mut occupied) => {
+                    if occupied.get().deployment_slot < entry.deployment_slot {
+                        occupied.insert(entry);
+                    }
+                }
+            }
+        }
+        ProgramCacheManifest {
+            slot: self.slot,
+            environments_hash: environments.hash(),
+            programs: programs.into_values().collect(),
+        }
+    }
+
+    /// Warm the program cache up with the programs of `manifest`, loading
+    /// and verifying each of them again from its account. Fails if the
+    /// manifest was exported for other environments than the ones of the
+    /// current epoch.
+    pub fn import_cache_manifest<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        manifest: &ProgramCacheManifest,
+    ) -> Result<ProgramCacheImport, ProgramCacheManifestError> {
+        let cached_programs = {
+            let program_cache = self.program_cache.read().unwrap();
+            let environments_hash = program_cache.get_environments_for_epoch(self.epoch).hash();
+            if manifest.environments_hash != environments_hash {
+                return Err(ProgramCacheManifestError::EnvironmentsMismatch {
+                    manifest: manifest.environments_hash,
+                    processor: environments_hash,
+                });
+            }
+            program_cache
+                .get_flattened_entries(true, true)
+                .into_iter()
+                .map(|(program_id, program)| (program_id, program.deployment_slot))
+                .collect::<HashSet<_>>()
+        };
+
+        let mut import = ProgramCacheImport::default();
+        for entry in &manifest.programs {
+            if cached_programs.contains(&(entry.program_id, entry.deployment_slot)) {
+                import.imported.push(entry.program_id);
+                continue;
+            }
+            // Loading without the reload flag verifies the program again
+            let program =
+                self.load_program_with_pubkey(callbacks, &entry.program_id, false, self.epoch);
+            if program.is_tombstone() || program.deployment_slot != entry.deployment_slot {
+                import.skipped.push(entry.program_id);
+                continue;
+            }
+            program
+                .tx_usage_counter
+                .store(entry.usage_count, Ordering::Relaxed);
+            self.program_cache
+                .write()
+                .unwrap()
+                .assign_program(entry.program_id, program);
+            import.imported.push(entry.program_id);
+        }
+        Ok(import)
+    }
+
+    /// Release what a long-lived processor accumulated and no longer needs,
+    /// meant to be called between blocks: the program cache entries verified
+    /// for the environments of epochs before `current_epoch` are dropped,
+    /// along with the environments themselves once nothing else refers to
+    /// them, and the index of the cache is compacted. No batch of an earlier
+    /// epoch may be in flight. The sysvar cache snapshots of the slots before
+    /// the root of the program cache are dropped too, as their forks are
+    /// either rooted or abandoned.
+    ///
+    /// Builtins are kept, as they are not tied to an environment.
+    pub fn gc(&self, current_epoch: Epoch) -> ProcessorGcReport {
+        let (removed, root_slot) = {
+            let mut program_cache = self.program_cache.write().unwrap();
+            let removed = program_cache.prune_outdated_environments(current_epoch);
+            (removed, program_cache.latest_root_slot)
+        };
+        let removed_sysvar_cache_snapshots = self
+            .sysvar_cache_snapshots
+            .write()
+            .unwrap()
+            .purge_older_than(root_slot);
+        let reclaimed_bytes = removed
+            .iter()
+            .filter(|program| {
+                matches!(
+                    program.program,
+                    LoadedProgramType::LegacyV0(_)
+                        | LoadedProgramType::LegacyV1(_)
+                        | LoadedProgramType::Typed(_)
+                )
+            })
+            .map(|program| program.account_size)
+            .sum();
+        ProcessorGcReport {
+            removed_program_entries: removed.len(),
+            reclaimed_bytes,
+            removed_sysvar_cache_snapshots,
         }
     }
 
     /// Main entrypoint to the SVM.
+    ///
+    /// A `dry_run`, e.g. the simulation of a transaction, executes the
+    /// transactions and records their details as usual, but leaves the
+    /// processor as it was: the usage counters and statistics of the program
+    /// cache are not updated, programs missing from it are loaded for the
+    /// batch only and nothing is evicted, sysvar cache snapshots are not
+    /// taken, and neither the load signal nor the program load metrics see
+    /// the batch.
     #[allow(clippy::too_many_arguments)]
     pub fn load_and_execute_sanitized_transactions<'a, CB: TransactionProcessingCallback>(
         &self,
@@ -175,32 +601,26 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         builtin_programs: impl Iterator<Item = &'a Pubkey>,
         log_messages_bytes_limit: Option<usize>,
         limit_to_load_programs: bool,
+        dry_run: bool,
     ) -> LoadAndExecuteSanitizedTransactionsOutput {
-        let mut program_cache_time = Measure::start("program_cache");
-        let mut program_accounts_map = Self::filter_executable_program_accounts(
+        let Some(mut batch) = self.prepare_batch(
             callbacks,
             sanitized_txs,
             check_results,
-            PROGRAM_OWNERS,
-        );
-        let native_loader = native_loader::id();
-        for builtin_program in builtin_programs {
-            program_accounts_map.insert(*builtin_program, (&native_loader, 0));
-        }
-
-        let programs_loaded_for_tx_batch = Rc::new(RefCell::new(self.replenish_program_cache(
-            callbacks,
-            &program_accounts_map,
+            recording_config,
+            builtin_programs,
             limit_to_load_programs,
-        )));
-
-        if programs_loaded_for_tx_batch.borrow().hit_max_limit {
+            dry_run,
+        ) else {
             return LoadAndExecuteSanitizedTransactionsOutput {
                 loaded_transactions: vec![],
                 execution_results: vec![],
+                fee_distribution: FeeDistribution::default(),
+                program_usage_report: ProgramUsageReport::default(),
+                account_contention_report: AccountContentionReport::default(),
+                execution_details_digest: None,
             };
-        }
-        program_cache_time.stop();
+        };
 
         let mut load_time = Measure::start("accounts_load");
         let mut loaded_transactions = load_accounts(
@@ -210,75 +630,355 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             error_counters,
             &self.fee_structure,
             account_overrides,
-            &program_accounts_map,
-            &programs_loaded_for_tx_batch.borrow(),
+            &batch.program_accounts_map,
+            &batch.programs_loaded_for_tx_batch.borrow(),
+            &self.precompiles,
         );
         load_time.stop();
 
         let mut execution_time = Measure::start("execution_time");
-
         let execution_results: Vec<TransactionExecutionResult> = loaded_transactions
             .iter_mut()
             .zip(sanitized_txs.iter())
-            .map(|(accs, tx)| match accs {
-                (Err(e), _nonce) => TransactionExecutionResult::NotExecuted(e.clone()),
-                (Ok(loaded_transaction), nonce) => {
-                    let compute_budget =
-                        if let Some(compute_budget) = self.runtime_config.compute_budget {
-                            compute_budget
-                        } else {
-                            let mut compute_budget_process_transaction_time =
-                                Measure::start("compute_budget_process_transaction_time");
-                            let maybe_compute_budget = ComputeBudget::try_from_instructions(
-                                tx.message().program_instructions_iter(),
-                            );
-                            compute_budget_process_transaction_time.stop();
-                            saturating_add_assign!(
-                                timings
-                                    .execute_accessories
-                                    .compute_budget_process_transaction_us,
-                                compute_budget_process_transaction_time.as_us()
-                            );
-                            if let Err(err) = maybe_compute_budget {
-                                return TransactionExecutionResult::NotExecuted(err);
-                            }
-                            maybe_compute_budget.unwrap()
-                        };
-
-                    let result = self.execute_loaded_transaction(
-                        callbacks,
-                        tx,
-                        loaded_transaction,
-                        compute_budget,
-                        nonce.as_ref().map(DurableNonceFee::from),
-                        recording_config,
-                        timings,
-                        error_counters,
-                        log_messages_bytes_limit,
-                        &programs_loaded_for_tx_batch.borrow(),
-                    );
-
-                    if let TransactionExecutionResult::Executed {
-                        details,
-                        programs_modified_by_tx,
-                    } = &result
-                    {
-                        // Update batch specific cache of the loaded programs with the modifications
-                        // made by the transaction, if it executed successfully.
-                        if details.status.is_ok() {
-                            programs_loaded_for_tx_batch
-                                .borrow_mut()
-                                .merge(programs_modified_by_tx);
-                        }
-                    }
+            .enumerate()
+            .map(|(index, (load_result, tx))| {
+                self.execute_prepared_transaction(
+                    callbacks,
+                    &mut batch,
+                    index,
+                    load_result,
+                    tx,
+                    recording_config,
+                    error_counters,
+                    log_messages_bytes_limit,
+                )
+            })
+            .collect();
+        execution_time.stop();
 
-                    result
+        let executed_units = execution_results
+            .iter()
+            .filter_map(|result| match result {
+                TransactionExecutionResult::Executed { details, .. } => {
+                    Some(details.executed_units)
+                }
+                TransactionExecutionResult::NotExecuted(_) => None,
+            })
+            .sum();
+        let account_contention_report =
+            AccountContentionReport::new(sanitized_txs, &execution_results);
+        let transaction_fees = loaded_transactions
+            .iter()
+            .zip(execution_results.iter())
+            .map(|((load_result, _nonce), execution_result)| {
+                match (load_result, execution_result.was_executed()) {
+                    (Ok(loaded_transaction), true) => Some(loaded_transaction.fee),
+                    _ => None,
                 }
             })
             .collect();
+        let FinishedBatch {
+            fee_distribution,
+            program_usage_report,
+            execution_details_digest,
+        } = self.finish_batch(
+            batch,
+            sanitized_txs.len(),
+            timings,
+            load_time.as_us(),
+            execution_time.as_us(),
+            executed_units,
+            transaction_fees,
+        );
 
-        execution_time.stop();
+        LoadAndExecuteSanitizedTransactionsOutput {
+            loaded_transactions,
+            execution_results,
+            fee_distribution,
+            program_usage_report,
+            account_contention_report,
+            execution_details_digest,
+        }
+    }
+
+    /// Like `load_and_execute_sanitized_transactions()`, but hands the
+    /// results of each transaction to `on_result` as soon as it executed,
+    /// with its index in `sanitized_txs`, instead of collecting the results
+    /// of the whole batch.
+    ///
+    /// Transactions are loaded one at a time, right before they execute, so
+    /// handling their results, e.g. hashing receipts or writing them to a
+    /// ledger, can be pipelined with the execution of the rest of the batch.
+    /// `on_result` is called in the order of `sanitized_txs`, once for every
+    /// transaction, unless the program cache hits its limit of loaded
+    /// programs, in which case no transaction is processed, like
+    /// `load_and_execute_sanitized_transactions()` returns empty results.
+    /// A `dry_run` leaves the processor as it was, like it does there.
+    ///
+    /// Loading each transaction with the programs of the batch as they were
+    /// before any of them executed takes a copy of the batch's programs, so
+    /// embedders which handle the results of the batch as a whole should
+    /// keep calling `load_and_execute_sanitized_transactions()`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn load_and_execute_sanitized_transactions_streamed<
+        'a,
+        CB: TransactionProcessingCallback,
+    >(
+        &self,
+        callbacks: &CB,
+        sanitized_txs: &[SanitizedTransaction],
+        check_results: &mut [TransactionCheckResult],
+        error_counters: &mut TransactionErrorMetrics,
+        recording_config: ExecutionRecordingConfig,
+        timings: &mut ExecuteTimings,
+        account_overrides: Option<&AccountOverrides>,
+        builtin_programs: impl Iterator<Item = &'a Pubkey>,
+        log_messages_bytes_limit: Option<usize>,
+        limit_to_load_programs: bool,
+        dry_run: bool,
+        mut on_result: impl FnMut(usize, TransactionLoadResult, TransactionExecutionResult),
+    ) -> StreamedBatchOutput {
+        let Some(mut batch) = self.prepare_batch(
+            callbacks,
+            sanitized_txs,
+            check_results,
+            recording_config,
+            builtin_programs,
+            limit_to_load_programs,
+            dry_run,
+        ) else {
+            return StreamedBatchOutput::default();
+        };
+
+        let mut load_us: u64 = 0;
+        let mut execution_us: u64 = 0;
+        let mut executed_units: u64 = 0;
+        let mut transaction_fees = Vec::with_capacity(sanitized_txs.len());
+        let mut account_contention_report = AccountContentionReport::default();
+        // Transactions are loaded with the programs of the batch as they were
+        // before any of them executed, like when the whole batch is loaded up
+        // front, while the executions see the programs modified by the
+        // previous transactions
+        let programs_for_loading = batch.programs_loaded_for_tx_batch.borrow().clone();
+
+        for (index, (tx, check_result)) in
+            sanitized_txs.iter().zip(check_results.iter()).enumerate()
+        {
+            let mut load_time = Measure::start("accounts_load");
+            let mut load_result = load_transaction(
+                callbacks,
+                tx,
+                check_result,
+                error_counters,
+                &self.fee_structure,
+                account_overrides,
+                &batch.program_accounts_map,
+                &programs_for_loading,
+                &self.precompiles,
+            );
+            load_time.stop();
+            saturating_add_assign!(load_us, load_time.as_us());
+
+            let mut execution_time = Measure::start("execution_time");
+            let execution_result = self.execute_prepared_transaction(
+                callbacks,
+                &mut batch,
+                index,
+                &mut load_result,
+                tx,
+                recording_config,
+                error_counters,
+                log_messages_bytes_limit,
+            );
+            execution_time.stop();
+            saturating_add_assign!(execution_us, execution_time.as_us());
+
+            if let TransactionExecutionResult::Executed { details, .. } = &execution_result {
+                saturating_add_assign!(executed_units, details.executed_units);
+            }
+            transaction_fees.push(match (&load_result, execution_result.was_executed()) {
+                ((Ok(loaded_transaction), _nonce), true) => Some(loaded_transaction.fee),
+                _ => None,
+            });
+            account_contention_report.record(tx, &execution_result);
+
+            on_result(index, load_result, execution_result);
+        }
+
+        let FinishedBatch {
+            fee_distribution,
+            program_usage_report,
+            execution_details_digest,
+        } = self.finish_batch(
+            batch,
+            sanitized_txs.len(),
+            timings,
+            load_us,
+            execution_us,
+            executed_units,
+            transaction_fees,
+        );
+
+        StreamedBatchOutput {
+            fee_distribution,
+            program_usage_report,
+            account_contention_report: account_contention_report.retain_contended(),
+            execution_details_digest,
+        }
+    }
+
+    /// Set up a batch before its transactions are loaded: expire them and
+    /// evaluate the signature policy, then load the programs they invoke.
+    /// Returns `None` if the program cache hit its limit of loaded programs,
+    /// in which case no transaction of the batch is processed.
+    #[allow(clippy::too_many_arguments)]
+    fn prepare_batch<'a, CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        sanitized_txs: &[SanitizedTransaction],
+        check_results: &mut [TransactionCheckResult],
+        recording_config: ExecutionRecordingConfig,
+        builtin_programs: impl Iterator<Item = &'a Pubkey>,
+        limit_to_load_programs: bool,
+        dry_run: bool,
+    ) -> Option<PreparedBatch<'_>> {
+        let in_flight_batch = (!dry_run).then(|| self.load_signal.begin_batch(sanitized_txs.len()));
+        if let Some(transaction_expiration) = &self.transaction_expiration {
+            Self::expire_transactions(
+                transaction_expiration.as_ref(),
+                self.slot,
+                sanitized_txs,
+                check_results,
+            );
+        }
+        let signature_policy_decisions = self.signature_policy.as_ref().map(|signature_policy| {
+            Self::evaluate_signature_policy(signature_policy.as_ref(), sanitized_txs, check_results)
+        });
+        let mut program_cache_time = Measure::start("program_cache");
+        let mut program_accounts_map = Self::filter_executable_program_accounts(
+            callbacks,
+            sanitized_txs,
+            check_results,
+            PROGRAM_OWNERS,
+        );
+        for builtin_program in builtin_programs {
+            program_accounts_map.insert(*builtin_program, (&native_loader::ID, 0));
+        }
+
+        let programs_loaded_for_tx_batch = Rc::new(RefCell::new(if dry_run {
+            self.load_programs_for_dry_run(callbacks, &program_accounts_map)
+        } else {
+            self.replenish_program_cache(callbacks, &program_accounts_map, limit_to_load_programs)
+        }));
+
+        if programs_loaded_for_tx_batch.borrow().hit_max_limit {
+            return None;
+        }
+        program_cache_time.stop();
+        let sysvar_cache_snapshot = self.sysvar_cache_snapshot_for(callbacks, !dry_run);
+
+        Some(PreparedBatch {
+            _in_flight_batch: in_flight_batch,
+            dry_run,
+            program_accounts_map,
+            programs_loaded_for_tx_batch,
+            program_cache_us: program_cache_time.as_us(),
+            signature_policy_decisions,
+            sysvar_cache_snapshot,
+            // Collected separately so the program usage of this batch can be reported
+            batch_timings: ExecuteTimings::default(),
+            details_hasher: self
+                .result_retention
+                .may_prune()
+                .then(ExecutionDetailsHasher::default),
+            // Log buffers of transactions whose logs are pruned are reused by
+            // the next transactions of the batch
+            log_buffer_pool: recording_config
+                .enable_log_recording
+                .then(|| LogBufferPool::new_ref(LOG_BUFFER_POOL_MAX_BYTES)),
+        })
+    }
+
+    /// Execute the transaction `index` of a prepared batch, if it loaded
+    #[allow(clippy::too_many_arguments)]
+    fn execute_prepared_transaction<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        batch: &mut PreparedBatch,
+        index: usize,
+        load_result: &mut TransactionLoadResult,
+        tx: &SanitizedTransaction,
+        recording_config: ExecutionRecordingConfig,
+        error_counters: &mut TransactionErrorMetrics,
+        log_messages_bytes_limit: Option<usize>,
+    ) -> TransactionExecutionResult {
+        match load_result {
+            (Err(e), _nonce) => TransactionExecutionResult::NotExecuted(e.clone()),
+            (Ok(loaded_transaction), nonce) => self.execute_batch_transaction(
+                callbacks,
+                index,
+                tx,
+                loaded_transaction,
+                nonce.as_ref().map(DurableNonceFee::from),
+                recording_config,
+                &mut batch.batch_timings,
+                error_counters,
+                log_messages_bytes_limit,
+                batch.log_buffer_pool.as_ref(),
+                &batch.programs_loaded_for_tx_batch,
+                batch.sysvar_cache_snapshot.as_ref(),
+                batch.details_hasher.as_mut(),
+                batch
+                    .signature_policy_decisions
+                    .as_mut()
+                    .and_then(|decisions| decisions[index].take()),
+            ),
+        }
+    }
+
+    /// Wrap up a batch once its transactions executed: report the load and
+    /// the timings, shrink the program cache and distribute the fees
+    #[allow(clippy::too_many_arguments)]
+    fn finish_batch(
+        &self,
+        batch: PreparedBatch,
+        num_transactions: usize,
+        timings: &mut ExecuteTimings,
+        load_us: u64,
+        execution_us: u64,
+        executed_units: u64,
+        transaction_fees: Vec<Option<u64>>,
+    ) -> FinishedBatch {
+        if !batch.dry_run {
+            self.load_signal.record_executed_units(executed_units);
+        }
+
+        let program_usage_report = ProgramUsageReport::new(&batch.batch_timings.details);
+        timings.accumulate(&batch.batch_timings);
+
+        if !batch.dry_run {
+            self.evict_programs();
+        }
+
+        debug!(
+            "load: {}us execute: {}us txs_len={}",
+            load_us, execution_us, num_transactions,
+        );
+
+        timings.saturating_add_in_place(ExecuteTimingType::ProgramCacheUs, batch.program_cache_us);
+        timings.saturating_add_in_place(ExecuteTimingType::LoadUs, load_us);
+        timings.saturating_add_in_place(ExecuteTimingType::ExecuteUs, execution_us);
+
+        let collected_fees = CollectedFees::new(transaction_fees);
+        FinishedBatch {
+            fee_distribution: self.fee_distributor.distribute(&collected_fees),
+            program_usage_report,
+            execution_details_digest: batch.details_hasher.map(ExecutionDetailsHasher::result),
+        }
+    }
 
+    /// Shrink the program cache after a batch
+    fn evict_programs(&self) {
         const SHRINK_LOADED_PROGRAMS_TO_PERCENTAGE: u8 = 90;
         self.program_cache
             .write()
@@ -287,24 +987,330 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                 Percentage::from(SHRINK_LOADED_PROGRAMS_TO_PERCENTAGE),
                 self.slot,
             );
+    }
 
-        debug!(
-            "load: {}us execute: {}us txs_len={}",
-            load_time.as_us(),
-            execution_time.as_us(),
-            sanitized_txs.len(),
+    /// Execute a loaded transaction of a batch and apply the result retention
+    /// policy to its details
+    #[allow(clippy::too_many_arguments)]
+    fn execute_batch_transaction<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        index: usize,
+        tx: &SanitizedTransaction,
+        loaded_transaction: &mut LoadedTransaction,
+        durable_nonce_fee: Option<DurableNonceFee>,
+        recording_config: ExecutionRecordingConfig,
+        batch_timings: &mut ExecuteTimings,
+        error_counters: &mut TransactionErrorMetrics,
+        log_messages_bytes_limit: Option<usize>,
+        log_buffer_pool: Option<&Rc<RefCell<LogBufferPool>>>,
+        programs_loaded_for_tx_batch: &RefCell<LoadedProgramsForTxBatch>,
+        sysvar_cache_snapshot: Option<&SysvarCache>,
+        details_hasher: Option<&mut ExecutionDetailsHasher>,
+        signature_policy_decision: Option<SignaturePolicyDecision>,
+    ) -> TransactionExecutionResult {
+        let compute_budget = if let Some(compute_budget) = self.runtime_config.compute_budget {
+            compute_budget
+        } else {
+            let mut compute_budget_process_transaction_time =
+                Measure::start("compute_budget_process_transaction_time");
+            let maybe_compute_budget =
+                ComputeBudget::try_from_instructions(tx.message().program_instructions_iter());
+            compute_budget_process_transaction_time.stop();
+            saturating_add_assign!(
+                batch_timings
+                    .execute_accessories
+                    .compute_budget_process_transaction_us,
+                compute_budget_process_transaction_time.as_us()
+            );
+            match maybe_compute_budget {
+                Ok(compute_budget) => compute_budget,
+                Err(err) => return TransactionExecutionResult::NotExecuted(err),
+            }
+        };
+
+        let mut result = self.execute_loaded_transaction(
+            callbacks,
+            tx,
+            loaded_transaction,
+            compute_budget,
+            durable_nonce_fee,
+            recording_config,
+            batch_timings,
+            error_counters,
+            log_messages_bytes_limit,
+            log_buffer_pool,
+            &programs_loaded_for_tx_batch.borrow(),
+            sysvar_cache_snapshot,
         );
 
-        timings.saturating_add_in_place(
-            ExecuteTimingType::ProgramCacheUs,
-            program_cache_time.as_us(),
+        if let TransactionExecutionResult::Executed {
+            details,
+            programs_modified_by_tx,
+        } = &result
+        {
+            // Update batch specific cache of the loaded programs with the modifications
+            // made by the transaction, if it executed successfully.
+            if details.status.is_ok() {
+                programs_loaded_for_tx_batch
+                    .borrow_mut()
+                    .merge(programs_modified_by_tx);
+            }
+        }
+
+        if let TransactionExecutionResult::Executed { details, .. } = &mut result {
+            details.signature_policy_decision = signature_policy_decision;
+            if let Some(details_hasher) = details_hasher {
+                details_hasher.hash_details(index, details);
+            }
+            let pruned_log_messages = self.result_retention.apply(index, tx, details);
+            if let (Some(log_buffer_pool), Some(log_messages)) =
+                (log_buffer_pool, pruned_log_messages)
+            {
+                log_buffer_pool.borrow_mut().recycle(log_messages);
+            }
+        }
+
+        result
+    }
+
+    /// A copy of this processor running under `runtime_config`, for one-off
+    /// executions which share its caches and hooks
+    fn clone_with_config(&self, runtime_config: Arc<RuntimeConfig>) -> Self {
+        Self {
+            slot: self.slot,
+            epoch: self.epoch,
+            epoch_schedule: self.epoch_schedule.clone(),
+            fee_structure: self.fee_structure.clone(),
+            runtime_config,
+            sysvar_cache: RwLock::new(self.sysvar_cache.read().unwrap().clone()),
+            sysvar_cache_snapshots: self.sysvar_cache_snapshots.clone(),
+            program_cache: self.program_cache.clone(),
+            fee_distributor: self.fee_distributor.clone(),
+            result_retention: self.result_retention.clone(),
+            write_policy: self.write_policy.clone(),
+            owner_transition_validator: self.owner_transition_validator.clone(),
+            transaction_expiration: self.transaction_expiration.clone(),
+            feature_overrides: self.feature_overrides.clone(),
+            // Not part of the load of the processor
+            load_signal: Arc::default(),
+            instruction_decoders: self.instruction_decoders.clone(),
+            precompiles: self.precompiles.clone(),
+            signature_policy: self.signature_policy.clone(),
+        }
+    }
+
+    /// Re-execute the transactions at `indices` of a batch previously
+    /// processed into `output`, under `modified_config`, e.g. a higher
+    /// compute unit limit, to debug failures without re-running the whole
+    /// batch.
+    ///
+    /// The read-only accounts and the builtin programs loaded by the
+    /// original run are reused from `output`, and other programs are served
+    /// from the shared program cache. Writable accounts are loaded again
+    /// through `callbacks`, because `output` holds their post-execution
+    /// state. Transactions which failed to load fail again with the same
+    /// error. The returned output is ordered like `indices`, so each entry
+    /// can be diffed against the original result at the same index.
+    pub fn re_execute_subset<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        sanitized_txs: &[SanitizedTransaction],
+        output: &LoadAndExecuteSanitizedTransactionsOutput,
+        indices: &[usize],
+        modified_config: Arc<RuntimeConfig>,
+    ) -> Result<LoadAndExecuteSanitizedTransactionsOutput, ReExecutionError> {
+        let len = sanitized_txs.len().min(output.loaded_transactions.len());
+        if let Some(index) = indices.iter().find(|index| **index >= len) {
+            return Err(ReExecutionError::IndexOutOfBounds { index: *index, len });
+        }
+
+        let (_, lamports_per_signature) = callbacks.get_last_blockhash_and_lamports_per_signature();
+        let mut account_overrides = AccountOverrides::default();
+        // Accounts written by one of the transactions, or loaded in a
+        // different state by two of them, are loaded again through the
+        // callbacks
+        let mut conflicting_accounts: HashSet<Pubkey> = indices
+            .iter()
+            .flat_map(|index| {
+                let message = sanitized_txs[*index].message();
+                message
+                    .account_keys()
+                    .iter()
+                    .enumerate()
+                    .filter(|(account_index, _)| message.is_writable(*account_index))
+                    .map(|(_, key)| *key)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let mut builtin_programs = HashSet::new();
+        let mut subset_txs = Vec::with_capacity(indices.len());
+        let mut subset_check_results = Vec::with_capacity(indices.len());
+        for index in indices {
+            let tx = &sanitized_txs[*index];
+            subset_txs.push(tx.clone());
+            let (loaded_transaction, nonce) = match &output.loaded_transactions[*index] {
+                (Ok(loaded_transaction), nonce) => (loaded_transaction, nonce),
+                (Err(err), _nonce) => {
+                    subset_check_results.push((Err(err.clone()), None, None));
+                    continue;
+                }
+            };
+            // Durable nonce transactions pay the fee recorded in their nonce
+            let nonce_lamports_per_signature =
+                nonce.as_ref().and_then(NonceInfo::lamports_per_signature);
+            subset_check_results.push((
+                Ok(()),
+                nonce
+                    .as_ref()
+                    .map(|nonce| NoncePartial::new(*nonce.address(), nonce.account().clone())),
+                Some(nonce_lamports_per_signature.unwrap_or(lamports_per_signature)),
+            ));
+
+            let message = tx.message();
+            for (account_index, (key, account)) in loaded_transaction.accounts.iter().enumerate() {
+                if account.executable() && native_loader::check_id(account.owner()) {
+                    builtin_programs.insert(*key);
+                }
+                if account_index >= message.account_keys().len()
+                    || account.executable()
+                    || solana_sdk::sysvar::instructions::check_id(key)
+                    || conflicting_accounts.contains(key)
+                {
+                    continue;
+                }
+                match account_overrides.get(key) {
+                    Some(overridden) if overridden != account => {
+                        conflicting_accounts.insert(*key);
+                    }
+                    Some(_) => {}
+                    None => account_overrides.set_account(key, Some(account.clone())),
+                }
+            }
+        }
+        for key in &conflicting_accounts {
+            account_overrides.set_account(key, None);
+        }
+
+        let processor = self.clone_with_config(modified_config);
+        Ok(processor.load_and_execute_sanitized_transactions(
+            callbacks,
+            &subset_txs,
+            &mut subset_check_results,
+            &mut TransactionErrorMetrics::default(),
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+            Some(&account_overrides),
+            builtin_programs.iter(),
+            processor.runtime_config.log_messages_bytes_limit,
+            false,
+            false,
+        ))
+    }
+
+    /// Execute a single transaction as a read-only view call, analogous to
+    /// `eth_call`.
+    ///
+    /// No fee is charged and signatures are not verified, so an unsigned
+    /// message can be passed with default signatures. If the fee payer
+    /// account does not exist, a placeholder system account stands in for
+    /// it. The compute unit limit is capped at
+    /// `MAX_VIEW_CALL_COMPUTE_UNIT_LIMIT`. Account changes are discarded;
+    /// only the status, logs and return data are returned.
+    pub fn process_view_call<'a, CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        transaction: &SanitizedTransaction,
+        builtin_programs: impl Iterator<Item = &'a Pubkey>,
+    ) -> ViewCallResult {
+        let mut compute_budget = match self.runtime_config.compute_budget {
+            Some(compute_budget) => compute_budget,
+            None => match ComputeBudget::try_from_instructions(
+                transaction.message().program_instructions_iter(),
+            ) {
+                Ok(compute_budget) => compute_budget,
+                Err(err) => {
+                    return ViewCallResult {
+                        status: Err(err),
+                        log_messages: None,
+                        return_data: None,
+                        executed_units: 0,
+                    }
+                }
+            },
+        };
+        compute_budget.compute_unit_limit = compute_budget
+            .compute_unit_limit
+            .min(MAX_VIEW_CALL_COMPUTE_UNIT_LIMIT);
+
+        let mut account_overrides = AccountOverrides::default();
+        let fee_payer = transaction.message().fee_payer();
+        if callbacks
+            .get_account_shared_data(fee_payer)
+            .filter(|account| account.lamports() != 0)
+            .is_none()
+        {
+            let rent = &callbacks.get_rent_collector().rent;
+            account_overrides.set_account(
+                fee_payer,
+                Some(AccountSharedData::new(
+                    rent.minimum_balance(0),
+                    0,
+                    &system_program::id(),
+                )),
+            );
+        }
+
+        let mut processor = self.clone_with_config(Arc::new(RuntimeConfig {
+            compute_budget: Some(compute_budget),
+            ..RuntimeConfig::clone(&self.runtime_config)
+        }));
+        // The view call result is made of the details
+        processor.result_retention = ResultRetention::KeepAll;
+        // The signatures of view calls are not verified
+        processor.signature_policy = None;
+        // Zero lamports per signature makes the fee zero
+        let mut check_results = vec![(Ok(()), None, Some(0))];
+        let LoadAndExecuteSanitizedTransactionsOutput {
+            execution_results, ..
+        } = processor.load_and_execute_sanitized_transactions(
+            callbacks,
+            std::slice::from_ref(transaction),
+            &mut check_results,
+            &mut TransactionErrorMetrics::default(),
+            ExecutionRecordingConfig {
+                enable_cpi_recording: false,
+                enable_log_recording: true,
+                enable_return_data_recording: true,
+                enable_heap_stats_recording: false,
+                enable_error_location_recording: false,
+                enable_capability_recording: false,
+                enable_instruction_decoding: false,
+                enable_compute_budget_report: false,
+            },
+            &mut ExecuteTimings::default(),
+            Some(&account_overrides),
+            builtin_programs,
+            processor.runtime_config.log_messages_bytes_limit,
+            false,
+            // View calls must not affect the caches of the processor
+            true,
         );
-        timings.saturating_add_in_place(ExecuteTimingType::LoadUs, load_time.as_us());
-        timings.saturating_add_in_place(ExecuteTimingType::ExecuteUs, execution_time.as_us());
 
-        LoadAndExecuteSanitizedTransactionsOutput {
-            loaded_transactions,
-            execution_results,
+        // Program loading is not limited, so the transaction is always processed
+        match execution_results.into_iter().next().unwrap() {
+            TransactionExecutionResult::Executed { details, .. } => ViewCallResult {
+                status: details.status,
+                log_messages: details.log_messages,
+                return_data: details.return_data,
+                executed_units: details.executed_units,
+            },
+            TransactionExecutionResult::NotExecuted(err) => ViewCallResult {
+                status: Err(err),
+                log_messages: None,
+                return_data: None,
+                executed_units: 0,
+            },
         }
     }
 
@@ -359,12 +1365,33 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         reload: bool,
         effective_epoch: Epoch,
     ) -> Arc<LoadedProgram> {
-        let program_cache = self.program_cache.read().unwrap();
-        let environments = program_cache.get_environments_for_epoch(effective_epoch);
         let mut load_program_metrics = LoadProgramMetrics {
             program_id: pubkey.to_string(),
             ..LoadProgramMetrics::default()
         };
+        let loaded_program = self.load_program(
+            callbacks,
+            pubkey,
+            reload,
+            effective_epoch,
+            &mut load_program_metrics,
+        );
+        let mut timings = ExecuteDetailsTimings::default();
+        load_program_metrics.submit_datapoint(&mut timings);
+        loaded_program
+    }
+
+    /// `load_program_with_pubkey()` without submitting the metrics
+    fn load_program<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        pubkey: &Pubkey,
+        reload: bool,
+        effective_epoch: Epoch,
+        load_program_metrics: &mut LoadProgramMetrics,
+    ) -> Arc<LoadedProgram> {
+        let program_cache = self.program_cache.read().unwrap();
+        let environments = program_cache.get_environments_for_epoch(effective_epoch);
 
         let mut loaded_program = match load_program_accounts(callbacks, pubkey, environments) {
             ProgramAccountLoadResult::AccountNotFound => Ok(LoadedProgram::new_tombstone(
@@ -376,7 +1403,7 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
 
             ProgramAccountLoadResult::ProgramOfLoaderV1orV2(program_account) => {
                 load_program_from_bytes(
-                    &mut load_program_metrics,
+                    load_program_metrics,
                     program_account.data(),
                     program_account.owner(),
                     program_account.data().len(),
@@ -397,7 +1424,7 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                 .ok_or(Box::new(InstructionError::InvalidAccountData).into())
                 .and_then(|programdata| {
                     load_program_from_bytes(
-                        &mut load_program_metrics,
+                        load_program_metrics,
                         programdata,
                         program_account.owner(),
                         program_account
@@ -417,7 +1444,7 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                 .ok_or(Box::new(InstructionError::InvalidAccountData).into())
                 .and_then(|elf_bytes| {
                     load_program_from_bytes(
-                        &mut load_program_metrics,
+                        load_program_metrics,
                         elf_bytes,
                         &loader_v4::id(),
                         program_account.data().len(),
@@ -432,8 +1459,6 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             LoadedProgram::new_tombstone(slot, LoadedProgramType::FailedVerification(env))
         });
 
-        let mut timings = ExecuteDetailsTimings::default();
-        load_program_metrics.submit_datapoint(&mut timings);
         if !Arc::ptr_eq(
             &environments.program_runtime_v1,
             &program_cache.environments.program_runtime_v1,
@@ -508,6 +1533,10 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                     loaded_programs_for_txs.as_mut().unwrap(),
                     is_first_round,
                 );
+                if is_first_round {
+                    self.load_signal
+                        .record_program_lookups(program_accounts_map.len(), missing_programs.len());
+                }
                 let task_waiter = Arc::clone(&program_cache.loading_task_waiter);
                 (program_to_load, task_waiter.cookie(), task_waiter)
                 // Unlock the global cache again.
@@ -531,8 +1560,45 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                 // trying to lock it.
             }
         }
-
-        loaded_programs_for_txs.unwrap()
+
+        loaded_programs_for_txs.unwrap()
+    }
+
+    /// Look up the programs of a dry run in the program cache, without
+    /// updating it, and load the missing ones for the batch only
+    fn load_programs_for_dry_run<CB: TransactionProcessingCallback>(
+        &self,
+        callback: &CB,
+        program_accounts_map: &HashMap<Pubkey, (&Pubkey, u64)>,
+    ) -> LoadedProgramsForTxBatch {
+        let mut missing_programs: Vec<(Pubkey, (LoadedProgramMatchCriteria, u64))> =
+            program_accounts_map
+                .iter()
+                .map(|(pubkey, (_, count))| {
+                    (
+                        *pubkey,
+                        (callback.get_program_match_criteria(pubkey), *count),
+                    )
+                })
+                .collect();
+        let mut loaded_programs_for_txs = {
+            let program_cache = self.program_cache.read().unwrap();
+            let mut loaded_programs_for_txs =
+                LoadedProgramsForTxBatch::new_from_cache(self.slot, self.epoch, &program_cache);
+            program_cache.extract_for_dry_run(&mut missing_programs, &mut loaded_programs_for_txs);
+            loaded_programs_for_txs
+        };
+        for (key, _) in missing_programs {
+            let program = self.load_program(
+                callback,
+                &key,
+                false,
+                self.epoch,
+                &mut LoadProgramMetrics::default(),
+            );
+            loaded_programs_for_txs.replenish(key, program);
+        }
+        loaded_programs_for_txs
     }
 
     /// Execute a transaction using the provided loaded accounts and update
@@ -549,9 +1615,27 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         timings: &mut ExecuteTimings,
         error_counters: &mut TransactionErrorMetrics,
         log_messages_bytes_limit: Option<usize>,
+        log_buffer_pool: Option<&Rc<RefCell<LogBufferPool>>>,
         programs_loaded_for_tx_batch: &LoadedProgramsForTxBatch,
+        sysvar_cache_snapshot: Option<&SysvarCache>,
     ) -> TransactionExecutionResult {
         let transaction_accounts = std::mem::take(&mut loaded_transaction.accounts);
+        // Only the write policy needs the accounts as they were before execution
+        let pre_transaction_accounts = self
+            .write_policy
+            .as_ref()
+            .map(|_| transaction_accounts.clone());
+        // Only the owner transition validator needs the owners before execution
+        let pre_owners = self.owner_transition_validator.as_ref().map(|_| {
+            transaction_accounts
+                .iter()
+                .map(|(_, account)| *account.owner())
+                .collect::<Vec<_>>()
+        });
+        let pre_lamports = transaction_accounts
+            .iter()
+            .map(|(_, account)| account.lamports())
+            .collect::<Vec<_>>();
 
         fn transaction_accounts_lamports_sum(
             accounts: &[(Pubkey, AccountSharedData)],
@@ -576,6 +1660,9 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         );
         #[cfg(debug_assertions)]
         transaction_context.set_signature(tx.signature());
+        transaction_context.set_max_accounts_data_growth(
+            self.runtime_config.max_accounts_data_growth_per_transaction,
+        );
 
         let pre_account_state_info = TransactionAccountStateInfo::new(
             &callback.get_rent_collector().rent,
@@ -584,11 +1671,13 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         );
 
         let log_collector = if recording_config.enable_log_recording {
-            match log_messages_bytes_limit {
-                None => Some(LogCollector::new_ref()),
-                Some(log_messages_bytes_limit) => Some(LogCollector::new_ref_with_limit(Some(
-                    log_messages_bytes_limit,
-                ))),
+            let bytes_limit = log_messages_bytes_limit.unwrap_or(LOG_MESSAGES_BYTES_LIMIT);
+            match log_buffer_pool {
+                None => Some(LogCollector::new_ref_with_limit(Some(bytes_limit))),
+                Some(log_buffer_pool) => Some(LogCollector::new_ref_with_pool(
+                    Some(bytes_limit),
+                    log_buffer_pool.clone(),
+                )),
             }
         } else {
             None
@@ -604,7 +1693,13 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             programs_loaded_for_tx_batch.upcoming_environments.clone(),
             programs_loaded_for_tx_batch.latest_root_epoch,
         );
-        let sysvar_cache = &self.sysvar_cache.read().unwrap();
+        let active_sysvar_cache = self.sysvar_cache.read().unwrap();
+        let sysvar_cache = sysvar_cache_snapshot.unwrap_or(&active_sysvar_cache);
+        let feature_set = callback.get_feature_set();
+        let feature_set = self
+            .feature_overrides
+            .feature_set_for(tx.signature(), &feature_set, self.slot)
+            .unwrap_or(feature_set);
 
         let mut invoke_context = InvokeContext::new(
             &mut transaction_context,
@@ -613,10 +1708,20 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             compute_budget,
             programs_loaded_for_tx_batch,
             &mut programs_modified_by_tx,
-            callback.get_feature_set(),
+            feature_set,
             blockhash,
             lamports_per_signature,
         );
+        invoke_context.set_strict_determinism(self.runtime_config.strict_determinism);
+        if recording_config.enable_heap_stats_recording {
+            invoke_context.enable_heap_allocation_stats();
+        }
+        if recording_config.enable_error_location_recording {
+            invoke_context.enable_error_location_recording();
+        }
+        if recording_config.enable_capability_recording {
+            invoke_context.enable_capability_recording();
+        }
 
         let mut process_message_time = Measure::start("process_message_time");
         let process_result = MessageProcessor::process_message(
@@ -625,11 +1730,23 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             &mut invoke_context,
             timings,
             &mut executed_units,
+            &self.precompiles,
         );
         process_message_time.stop();
 
+        let heap_allocation_stats = invoke_context.get_heap_allocation_stats();
+        let error_location = invoke_context.get_error_location().cloned();
+        let capability_manifest = invoke_context.get_capability_manifest();
         drop(invoke_context);
 
+        let decoded_instructions = recording_config
+            .enable_instruction_decoding
+            .then(|| self.instruction_decoders.decode_message(tx.message()));
+
+        let compute_budget_report = recording_config.enable_compute_budget_report.then(|| {
+            canonicalize_compute_budget_instructions(tx.message().program_instructions_iter())
+        });
+
         saturating_add_assign!(
             timings.execute_accessories.process_message_us,
             process_message_time.as_us()
@@ -685,6 +1802,7 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
             return_data,
             touched_account_count,
             accounts_resize_delta: accounts_data_len_delta,
+            accounts_data_growth,
         } = transaction_context.into();
 
         if status.is_ok()
@@ -694,7 +1812,36 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         {
             status = Err(TransactionError::UnbalancedTransaction);
         }
+        if let (Ok(_), Some(write_policy), Some(pre_transaction_accounts)) =
+            (&status, &self.write_policy, &pre_transaction_accounts)
+        {
+            if let Some(account_index) = Self::find_vetoed_write(
+                write_policy.as_ref(),
+                tx,
+                pre_transaction_accounts,
+                &accounts,
+            ) {
+                status = Err(TransactionError::AccountWriteVetoed { account_index });
+            }
+        }
+        if let (Ok(_), Some(validator), Some(pre_owners)) =
+            (&status, &self.owner_transition_validator, &pre_owners)
+        {
+            if let Err((account_index, error)) =
+                Self::validate_owner_transitions(validator.as_ref(), pre_owners, &accounts)
+            {
+                status = Err(TransactionError::OwnerTransitionRejected {
+                    account_index,
+                    error,
+                });
+            }
+        }
         let status = status.map(|_| ());
+        let account_lifecycle = if status.is_ok() {
+            AccountLifecycle::new(tx.message(), &pre_lamports, &accounts)
+        } else {
+            AccountLifecycle::default()
+        };
 
         loaded_transaction.accounts = accounts;
         saturating_add_assign!(
@@ -719,6 +1866,14 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
                 return_data,
                 executed_units,
                 accounts_data_len_delta,
+                accounts_data_growth,
+                heap_allocation_stats,
+                error_location,
+                capability_manifest,
+                decoded_instructions,
+                compute_budget_report,
+                account_lifecycle,
+                signature_policy_decision: None,
             },
             programs_modified_by_tx: Box::new(programs_modified_by_tx),
         }
@@ -762,6 +1917,84 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         }
     }
 
+    /// Index of the first account modified by `tx` whose write the policy
+    /// vetoes, if any
+    fn find_vetoed_write(
+        write_policy: &dyn WritePolicy,
+        tx: &SanitizedTransaction,
+        pre_transaction_accounts: &[TransactionAccount],
+        post_transaction_accounts: &[TransactionAccount],
+    ) -> Option<u8> {
+        let message = tx.message();
+        (0..message.account_keys().len()).find_map(|index| {
+            if !message.is_writable(index) {
+                return None;
+            }
+            let (pubkey, pre) = pre_transaction_accounts.get(index)?;
+            let (_, post) = post_transaction_accounts.get(index)?;
+            (pre != post && !write_policy.allow_write(tx, pubkey, pre, post)).then_some(index as u8)
+        })
+    }
+
+    /// Fail the transactions which may no longer be processed in `slot`
+    fn expire_transactions(
+        transaction_expiration: &dyn TransactionExpiration,
+        slot: Slot,
+        sanitized_txs: &[SanitizedTransaction],
+        check_results: &mut [TransactionCheckResult],
+    ) {
+        for (tx, check_result) in sanitized_txs.iter().zip(check_results.iter_mut()) {
+            if check_result.0.is_ok() && transaction_expiration.is_expired(tx, slot) {
+                check_result.0 = Err(TransactionError::TransactionExpired);
+            }
+        }
+    }
+
+    /// Fail the transactions the signature policy rejects, and return the
+    /// decisions of the policy on the others
+    fn evaluate_signature_policy(
+        signature_policy: &dyn SignaturePolicy,
+        sanitized_txs: &[SanitizedTransaction],
+        check_results: &mut [TransactionCheckResult],
+    ) -> Vec<Option<SignaturePolicyDecision>> {
+        sanitized_txs
+            .iter()
+            .zip(check_results.iter_mut())
+            .map(|(tx, check_result)| {
+                if check_result.0.is_err() {
+                    return None;
+                }
+                match signature_policy.evaluate(tx) {
+                    Ok(decision) => Some(decision),
+                    Err(err) => {
+                        check_result.0 = Err(err);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Validate the owner changes of the accounts whose owner differs from
+    /// `pre_owners`. Returns the index of the first rejected account, with the
+    /// error of the validator.
+    fn validate_owner_transitions(
+        validator: &dyn OwnerTransitionValidator,
+        pre_owners: &[Pubkey],
+        post_transaction_accounts: &[TransactionAccount],
+    ) -> Result<(), (u8, OwnerTransitionError)> {
+        for (index, ((pubkey, account), old_owner)) in
+            post_transaction_accounts.iter().zip(pre_owners).enumerate()
+        {
+            if account.owner() != old_owner {
+                validator
+                    .validate_owner_transition(pubkey, old_owner, account.owner(), account)
+                    .map_err(|error| (index as u8, error))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Extract the InnerInstructionsList from a TransactionContext
     fn inner_instructions_list_from_instruction_trace(
         transaction_context: &TransactionContext,
@@ -831,6 +2064,57 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
         sysvar_cache.reset();
     }
 
+    /// Store a snapshot of the sysvar cache for the fork at `slot` with
+    /// `bank_hash`, which batches select with `get_sysvar_cache_snapshot_id`
+    pub fn snapshot_sysvar_cache(&self, slot: Slot, bank_hash: Hash) {
+        let sysvar_cache = self.sysvar_cache.read().unwrap();
+        self.sysvar_cache_snapshots
+            .write()
+            .unwrap()
+            .insert(slot, bank_hash, &sysvar_cache);
+    }
+
+    /// Drop the sysvar cache snapshots of the slots before `root`
+    pub fn purge_sysvar_cache_snapshots(&self, root: Slot) {
+        self.sysvar_cache_snapshots
+            .write()
+            .unwrap()
+            .purge_older_than(root);
+    }
+
+    /// The sysvar cache snapshot selected by `callbacks`, if any. A snapshot
+    /// which was not taken yet is filled from the accounts of `callbacks`,
+    /// so that the fork never falls back to the sysvars of another fork, and
+    /// is kept for later batches if `keep_snapshot`.
+    fn sysvar_cache_snapshot_for<CB: TransactionProcessingCallback>(
+        &self,
+        callbacks: &CB,
+        keep_snapshot: bool,
+    ) -> Option<SysvarCache> {
+        let (slot, bank_hash) = callbacks.get_sysvar_cache_snapshot_id()?;
+        if let Some(snapshot) = self
+            .sysvar_cache_snapshots
+            .read()
+            .unwrap()
+            .get(slot, &bank_hash)
+        {
+            return Some(snapshot.clone());
+        }
+        let mut sysvar_cache = SysvarCache::default();
+        sysvar_cache.fill_missing_entries(|pubkey, set_sysvar| {
+            if let Some(account) = callbacks.get_account_shared_data(pubkey) {
+                set_sysvar(account.data());
+            }
+        });
+        if keep_snapshot {
+            self.sysvar_cache_snapshots
+                .write()
+                .unwrap()
+                .insert(slot, bank_hash, &sysvar_cache);
+        }
+        Some(sysvar_cache)
+    }
+
     pub fn get_sysvar_cache_for_tests(&self) -> SysvarCache {
         self.sysvar_cache.read().unwrap().clone()
     }
@@ -840,7 +2124,16 @@ impl<FG: ForkGraph> TransactionBatchProcessor<FG> {
 mod tests {
     use {
         super::*,
-        solana_program_runtime::loaded_programs::{BlockRelation, ProgramRuntimeEnvironments},
+        crate::{
+            owner_transition::KnownOwnersValidator, signature_policy::SessionKeysPolicy,
+            transaction_expiration::ValidUntilSlots, write_policy::FrozenAccountsWritePolicy,
+        },
+        solana_program_runtime::{
+            compute_budget_processor::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+            loaded_programs::{BlockRelation, ProgramRuntimeEnvironments},
+            solana_rbpf::program::BuiltinProgram,
+            sysvar_cache::MAX_SYSVAR_CACHE_SNAPSHOTS,
+        },
         solana_sdk::{
             account::{create_account_shared_data_for_test, WritableAccount},
             bpf_loader,
@@ -851,7 +2144,8 @@ mod tests {
             message::{LegacyMessage, Message, MessageHeader},
             rent_collector::RentCollector,
             rent_debits::RentDebits,
-            signature::{Keypair, Signature},
+            signature::{Keypair, Signature, Signer},
+            system_transaction,
             sysvar::{self, rent::Rent},
             transaction::{SanitizedTransaction, Transaction, TransactionError},
             transaction_context::TransactionContext,
@@ -876,6 +2170,7 @@ mod tests {
         rent_collector: RentCollector,
         feature_set: Arc<FeatureSet>,
         pub account_shared_data: HashMap<Pubkey, AccountSharedData>,
+        sysvar_cache_snapshot_id: Option<(Slot, Hash)>,
     }
 
     impl TransactionProcessingCallback for MockBankCallback {
@@ -906,6 +2201,10 @@ mod tests {
         fn get_feature_set(&self) -> Arc<FeatureSet> {
             self.feature_set.clone()
         }
+
+        fn get_sysvar_cache_snapshot_id(&self) -> Option<(Slot, Hash)> {
+            self.sysvar_cache_snapshot_id
+        }
     }
 
     #[test]
@@ -1359,12 +2658,18 @@ mod tests {
             program_indices: vec![vec![0]],
             rent: 0,
             rent_debits: RentDebits::default(),
+            fee: 0,
         };
 
         let mut record_config = ExecutionRecordingConfig {
             enable_cpi_recording: false,
             enable_log_recording: true,
             enable_return_data_recording: false,
+            enable_heap_stats_recording: false,
+            enable_error_location_recording: false,
+            enable_capability_recording: false,
+            enable_instruction_decoding: false,
+            enable_compute_budget_report: false,
         };
 
         let result = batch_processor.execute_loaded_transaction(
@@ -1377,7 +2682,9 @@ mod tests {
             &mut ExecuteTimings::default(),
             &mut TransactionErrorMetrics::default(),
             None,
+            None,
             &loaded_programs,
+            None,
         );
 
         let TransactionExecutionResult::Executed {
@@ -1399,7 +2706,9 @@ mod tests {
             &mut ExecuteTimings::default(),
             &mut TransactionErrorMetrics::default(),
             Some(2),
+            None,
             &loaded_programs,
+            None,
         );
 
         let TransactionExecutionResult::Executed {
@@ -1430,7 +2739,9 @@ mod tests {
             &mut ExecuteTimings::default(),
             &mut TransactionErrorMetrics::default(),
             None,
+            None,
             &loaded_programs,
+            None,
         );
 
         let TransactionExecutionResult::Executed {
@@ -1447,6 +2758,42 @@ mod tests {
         };
         assert!(log_messages.is_none());
         assert!(inner_instructions.is_some());
+
+        record_config.enable_compute_budget_report = true;
+
+        let result = batch_processor.execute_loaded_transaction(
+            &mock_bank,
+            &sanitized_transaction,
+            &mut loaded_transaction,
+            ComputeBudget::default(),
+            None,
+            record_config,
+            &mut ExecuteTimings::default(),
+            &mut TransactionErrorMetrics::default(),
+            None,
+            None,
+            &loaded_programs,
+            None,
+        );
+
+        let TransactionExecutionResult::Executed {
+            details:
+                TransactionExecutionDetails {
+                    compute_budget_report,
+                    ..
+                },
+            ..
+        } = result
+        else {
+            panic!("Unexpected result")
+        };
+        // The default limit of the single instruction
+        let compute_budget_report = compute_budget_report.unwrap();
+        assert_eq!(compute_budget_report.compute_unit_limit.requested, None);
+        assert_eq!(
+            compute_budget_report.compute_unit_limit.effective,
+            DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT
+        );
     }
 
     #[test]
@@ -1489,6 +2836,7 @@ mod tests {
             program_indices: vec![vec![0]],
             rent: 0,
             rent_debits: RentDebits::default(),
+            fee: 0,
         };
 
         let record_config = ExecutionRecordingConfig::new_single_setting(false);
@@ -1504,12 +2852,230 @@ mod tests {
             &mut ExecuteTimings::default(),
             &mut error_metrics,
             None,
+            None,
             &loaded_programs,
+            None,
         );
 
         assert_eq!(error_metrics.instruction_error, 1);
     }
 
+    #[test]
+    fn test_find_vetoed_write() {
+        let payer = Keypair::new();
+        let frozen = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let tx = SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &payer,
+            &recipient,
+            1,
+            Hash::default(),
+        ));
+        let pre_accounts = vec![
+            (
+                payer.pubkey(),
+                AccountSharedData::new(10, 0, &system_program::id()),
+            ),
+            (recipient, AccountSharedData::default()),
+            (system_program::id(), AccountSharedData::default()),
+        ];
+        let mut post_accounts = pre_accounts.clone();
+        post_accounts[0].1.set_lamports(9);
+        post_accounts[1].1.set_lamports(1);
+
+        // Unmodified and read-only accounts are not subject to the policy
+        let policy = FrozenAccountsWritePolicy::new([frozen, system_program::id()]);
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::find_vetoed_write(
+                &policy,
+                &tx,
+                &pre_accounts,
+                &post_accounts,
+            ),
+            None
+        );
+
+        let policy = FrozenAccountsWritePolicy::new([recipient]);
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::find_vetoed_write(
+                &policy,
+                &tx,
+                &pre_accounts,
+                &post_accounts,
+            ),
+            Some(1)
+        );
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::find_vetoed_write(
+                &policy,
+                &tx,
+                &pre_accounts,
+                &pre_accounts,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_owner_transitions() {
+        let known_program = Pubkey::new_unique();
+        let unknown_program = Pubkey::new_unique();
+        let pre_owners = vec![system_program::id(), system_program::id()];
+        let mut post_accounts = vec![
+            (
+                Pubkey::new_unique(),
+                AccountSharedData::new(10, 0, &system_program::id()),
+            ),
+            (
+                Pubkey::new_unique(),
+                AccountSharedData::new(10, 0, &system_program::id()),
+            ),
+        ];
+        let validator = KnownOwnersValidator::new([known_program]);
+
+        // Accounts whose owner is unchanged are not subject to the validator
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::validate_owner_transitions(
+                &validator,
+                &pre_owners,
+                &post_accounts,
+            ),
+            Ok(())
+        );
+
+        post_accounts[0].1.set_owner(known_program);
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::validate_owner_transitions(
+                &validator,
+                &pre_owners,
+                &post_accounts,
+            ),
+            Ok(())
+        );
+
+        post_accounts[1].1.set_owner(unknown_program);
+        assert_eq!(
+            TransactionBatchProcessor::<TestForkGraph>::validate_owner_transitions(
+                &validator,
+                &pre_owners,
+                &post_accounts,
+            ),
+            Err((1, OwnerTransitionError::UnknownOwner))
+        );
+    }
+
+    #[test]
+    fn test_expire_transactions() {
+        let new_tx = || {
+            SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+                &Keypair::new(),
+                &Pubkey::new_unique(),
+                1,
+                Hash::default(),
+            ))
+        };
+        let sanitized_txs = [new_tx(), new_tx(), new_tx(), new_tx()];
+        let expiration = ValidUntilSlots::new([
+            (*sanitized_txs[0].signature(), 10),
+            (*sanitized_txs[1].signature(), 9),
+            (*sanitized_txs[2].signature(), 9),
+        ]);
+        let mut check_results = vec![
+            (Ok(()), None, Some(0)),
+            (Ok(()), None, Some(0)),
+            (Err(TransactionError::BlockhashNotFound), None, None),
+            (Ok(()), None, Some(0)),
+        ];
+
+        TransactionBatchProcessor::<TestForkGraph>::expire_transactions(
+            &expiration,
+            10,
+            &sanitized_txs,
+            &mut check_results,
+        );
+        assert_eq!(
+            check_results
+                .into_iter()
+                .map(|(result, _, _)| result)
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(()),
+                Err(TransactionError::TransactionExpired),
+                // The error of the check is kept
+                Err(TransactionError::BlockhashNotFound),
+                // Not bounded by slot
+                Ok(()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_evaluate_signature_policy() {
+        let fee_payer = Keypair::new();
+        let session_key = Keypair::new();
+        let mut signature_policy = SessionKeysPolicy::default();
+        signature_policy.register(fee_payer.pubkey(), vec![session_key.pubkey()], 1);
+
+        let new_tx = |signing: &[&Keypair]| {
+            let instruction = solana_sdk::instruction::Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![solana_sdk::instruction::AccountMeta::new_readonly(
+                    session_key.pubkey(),
+                    true,
+                )],
+            );
+            let mut transaction =
+                Transaction::new_unsigned(Message::new(&[instruction], Some(&fee_payer.pubkey())));
+            transaction.partial_sign(signing, Hash::default());
+            SanitizedTransaction::from_transaction_for_tests(transaction)
+        };
+        let sanitized_txs = [
+            new_tx(&[&fee_payer, &session_key]),
+            new_tx(&[&session_key]),
+            new_tx(&[&session_key]),
+            new_tx(&[&fee_payer]),
+        ];
+        let mut check_results = vec![
+            (Ok(()), None, Some(0)),
+            (Ok(()), None, Some(0)),
+            (Err(TransactionError::BlockhashNotFound), None, None),
+            (Ok(()), None, Some(0)),
+        ];
+
+        let decisions = TransactionBatchProcessor::<TestForkGraph>::evaluate_signature_policy(
+            &signature_policy,
+            &sanitized_txs,
+            &mut check_results,
+        );
+        assert_eq!(
+            decisions,
+            vec![
+                Some(SignaturePolicyDecision::AllSigners),
+                Some(SignaturePolicyDecision::SessionKeys {
+                    fee_payer: fee_payer.pubkey(),
+                    signers: vec![session_key.pubkey()],
+                }),
+                None,
+                None,
+            ]
+        );
+        assert_eq!(
+            check_results
+                .into_iter()
+                .map(|(result, _, _)| result)
+                .collect::<Vec<_>>(),
+            vec![
+                Ok(()),
+                Ok(()),
+                // The error of the check is kept
+                Err(TransactionError::BlockhashNotFound),
+                // Only the fee payer may leave its signature out
+                Err(TransactionError::SignatureFailure),
+            ]
+        );
+    }
+
     #[test]
     fn test_replenish_program_cache() {
         // Case 1
@@ -1553,6 +3119,159 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_gc() {
+        let program_id = Pubkey::new_unique();
+        let mut mock_bank = MockBankCallback::default();
+        let mut account_data = AccountSharedData::default();
+        account_data.set_owner(bpf_loader::id());
+        account_data.set_data(load_test_program());
+        mock_bank
+            .account_shared_data
+            .insert(program_id, account_data);
+
+        let batch_processor = TransactionBatchProcessor::<TestForkGraph>::default();
+        let program = batch_processor.load_program_with_pubkey(&mock_bank, &program_id, false, 0);
+        let account_size = program.account_size;
+        let mut program_cache = batch_processor.program_cache.write().unwrap();
+        program_cache.assign_program(program_id, program);
+        // The next epoch has different environments
+        let mut upcoming_environments = program_cache.environments.clone();
+        upcoming_environments.program_runtime_v1 = Arc::new(BuiltinProgram::new_mock());
+        upcoming_environments.program_runtime_v2 = Arc::new(BuiltinProgram::new_mock());
+        program_cache.upcoming_environments = Some(upcoming_environments);
+        program_cache.latest_root_slot = 2;
+        drop(program_cache);
+        // Slot 1 is before the root, slot 2 is the root
+        let slot1_hash = Hash::new_unique();
+        let slot2_hash = Hash::new_unique();
+        batch_processor.snapshot_sysvar_cache(1, slot1_hash);
+        batch_processor.snapshot_sysvar_cache(2, slot2_hash);
+
+        // The program is verified for the environments of the current epoch
+        assert_eq!(
+            batch_processor.gc(0),
+            ProcessorGcReport {
+                removed_sysvar_cache_snapshots: 1,
+                ..ProcessorGcReport::default()
+            }
+        );
+        {
+            let snapshots = batch_processor.sysvar_cache_snapshots.read().unwrap();
+            assert!(snapshots.get(1, &slot1_hash).is_none());
+            assert!(snapshots.get(2, &slot2_hash).is_some());
+        }
+        assert_eq!(
+            batch_processor
+                .program_cache
+                .read()
+                .unwrap()
+                .get_flattened_entries(true, true)
+                .len(),
+            1
+        );
+
+        // But not for the ones of the next epoch
+        assert_eq!(
+            batch_processor.gc(1),
+            ProcessorGcReport {
+                removed_program_entries: 1,
+                reclaimed_bytes: account_size,
+                removed_sysvar_cache_snapshots: 0,
+            }
+        );
+        assert!(batch_processor
+            .program_cache
+            .read()
+            .unwrap()
+            .get_flattened_entries(true, true)
+            .is_empty());
+        assert_eq!(batch_processor.gc(1), ProcessorGcReport::default());
+    }
+
+    #[test]
+    fn test_export_and_import_cache_manifest() {
+        let program_id = Pubkey::new_unique();
+        let mut mock_bank = MockBankCallback::default();
+        let mut account_data = AccountSharedData::default();
+        account_data.set_owner(bpf_loader::id());
+        account_data.set_data(load_test_program());
+        mock_bank
+            .account_shared_data
+            .insert(program_id, account_data);
+
+        let batch_processor = TransactionBatchProcessor::<TestForkGraph>::default();
+        let program = batch_processor.load_program_with_pubkey(&mock_bank, &program_id, false, 0);
+        program.tx_usage_counter.store(7, Ordering::Relaxed);
+        batch_processor
+            .program_cache
+            .write()
+            .unwrap()
+            .assign_program(program_id, program);
+
+        let manifest = batch_processor.export_cache_manifest();
+        assert_eq!(
+            manifest.programs,
+            vec![ProgramCacheManifestEntry {
+                program_id,
+                deployment_slot: 0,
+                usage_count: 7,
+            }]
+        );
+
+        // A restarted processor loads the program again
+        let restarted = TransactionBatchProcessor::<TestForkGraph>::default();
+        let expected = ProgramCacheImport {
+            imported: vec![program_id],
+            skipped: vec![],
+        };
+        assert_eq!(
+            restarted.import_cache_manifest(&mock_bank, &manifest),
+            Ok(expected.clone())
+        );
+        let cached = restarted
+            .program_cache
+            .read()
+            .unwrap()
+            .get_flattened_entries(true, true);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].0, program_id);
+        assert_eq!(cached[0].1.tx_usage_counter.load(Ordering::Relaxed), 7);
+
+        // Cached programs are left untouched
+        assert_eq!(
+            restarted.import_cache_manifest(&mock_bank, &manifest),
+            Ok(expected)
+        );
+
+        // Programs which are gone from the restored accounts are skipped
+        let restarted = TransactionBatchProcessor::<TestForkGraph>::default();
+        mock_bank.account_shared_data.remove(&program_id);
+        assert_eq!(
+            restarted.import_cache_manifest(&mock_bank, &manifest),
+            Ok(ProgramCacheImport {
+                imported: vec![],
+                skipped: vec![program_id],
+            })
+        );
+        assert!(restarted
+            .program_cache
+            .read()
+            .unwrap()
+            .get_flattened_entries(true, true)
+            .is_empty());
+
+        // Manifests of other environments are rejected
+        let manifest = ProgramCacheManifest {
+            environments_hash: Hash::new_unique(),
+            ..manifest
+        };
+        assert!(matches!(
+            restarted.import_cache_manifest(&mock_bank, &manifest),
+            Err(ProgramCacheManifestError::EnvironmentsMismatch { .. })
+        ));
+    }
+
     #[test]
     fn test_filter_executable_program_accounts() {
         let mut mock_bank = MockBankCallback::default();
@@ -1893,6 +3612,93 @@ mod tests {
         assert!(sysvar_cache.get_epoch_rewards().is_err());
     }
 
+    #[test]
+    fn test_sysvar_cache_snapshots() {
+        let new_fork = |slot| {
+            let mut mock_bank = MockBankCallback::default();
+            let clock = sysvar::clock::Clock {
+                slot,
+                ..sysvar::clock::Clock::default()
+            };
+            mock_bank.account_shared_data.insert(
+                sysvar::clock::id(),
+                create_account_shared_data_for_test(&clock),
+            );
+            mock_bank
+        };
+        let transaction_processor = TransactionBatchProcessor::<TestForkGraph>::default();
+
+        // Snapshot the sysvar cache of the fork at slot 1
+        let mut fork1 = new_fork(1);
+        let fork1_hash = Hash::new_unique();
+        transaction_processor.fill_missing_sysvar_cache_entries(&fork1);
+        transaction_processor.snapshot_sysvar_cache(1, fork1_hash);
+
+        // Then move the sysvar cache of the processor on to another fork
+        let fork2 = new_fork(2);
+        transaction_processor.reset_sysvar_cache();
+        transaction_processor.fill_missing_sysvar_cache_entries(&fork2);
+        assert!(transaction_processor
+            .sysvar_cache_snapshot_for(&fork2, true)
+            .is_none());
+
+        // The fork at slot 1 still executes with its own sysvars
+        fork1.sysvar_cache_snapshot_id = Some((1, fork1_hash));
+        let snapshot = transaction_processor
+            .sysvar_cache_snapshot_for(&fork1, true)
+            .unwrap();
+        assert_eq!(snapshot.get_clock().unwrap().slot, 1);
+
+        // Snapshots which were not taken are filled from the accounts of the
+        // fork
+        let mut fork3 = new_fork(3);
+        let fork3_hash = Hash::new_unique();
+        fork3.sysvar_cache_snapshot_id = Some((3, fork3_hash));
+        let snapshot = transaction_processor
+            .sysvar_cache_snapshot_for(&fork3, false)
+            .unwrap();
+        assert_eq!(snapshot.get_clock().unwrap().slot, 3);
+        assert!(transaction_processor
+            .sysvar_cache_snapshots
+            .read()
+            .unwrap()
+            .get(3, &fork3_hash)
+            .is_none());
+        let snapshot = transaction_processor
+            .sysvar_cache_snapshot_for(&fork3, true)
+            .unwrap();
+        assert_eq!(snapshot.get_clock().unwrap().slot, 3);
+        assert_eq!(
+            transaction_processor
+                .sysvar_cache_snapshots
+                .read()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        transaction_processor.purge_sysvar_cache_snapshots(2);
+        {
+            let snapshots = transaction_processor.sysvar_cache_snapshots.read().unwrap();
+            assert!(snapshots.get(1, &fork1_hash).is_none());
+            assert!(snapshots.get(3, &fork3_hash).is_some());
+        }
+
+        // The number of snapshots is bounded, the oldest slot is dropped first
+        for slot in 4..4 + MAX_SYSVAR_CACHE_SNAPSHOTS as Slot {
+            transaction_processor.snapshot_sysvar_cache(slot, Hash::new_unique());
+        }
+        {
+            let snapshots = transaction_processor.sysvar_cache_snapshots.read().unwrap();
+            assert_eq!(snapshots.len(), MAX_SYSVAR_CACHE_SNAPSHOTS);
+            assert!(snapshots.get(3, &fork3_hash).is_none());
+        }
+
+        // None of this affects the sysvar cache of the processor
+        let sysvar_cache = transaction_processor.sysvar_cache.read().unwrap();
+        assert_eq!(sysvar_cache.get_clock().unwrap().slot, 2);
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_reset_and_fill_sysvar_cache() {
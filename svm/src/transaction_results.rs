@@ -5,12 +5,20 @@
 )]
 pub use solana_sdk::inner_instruction::{InnerInstruction, InnerInstructionsList};
 use {
-    solana_program_runtime::loaded_programs::LoadedProgramsForTxBatch,
+    crate::{instruction_decoder::DecodedInstruction, signature_policy::SignaturePolicyDecision},
+    solana_program_runtime::{
+        compute_budget_processor::ComputeBudgetReport,
+        invoke_context::{CapabilityManifest, HeapAllocationStats, ProgramErrorLocation},
+        loaded_programs::LoadedProgramsForTxBatch,
+    },
     solana_sdk::{
+        account::ReadableAccount,
+        message::SanitizedMessage,
         nonce_info::{NonceFull, NonceInfo},
+        pubkey::Pubkey,
         rent_debits::RentDebits,
         transaction::{self, TransactionError},
-        transaction_context::TransactionReturnData,
+        transaction_context::{TransactionAccount, TransactionReturnData},
     },
 };
 
@@ -80,6 +88,69 @@ pub struct TransactionExecutionDetails {
     /// The change in accounts data len for this transaction.
     /// NOTE: This value is valid IFF `status` is `Ok`.
     pub accounts_data_len_delta: i64,
+    /// The sum of the increases of accounts data len for this transaction,
+    /// which shrinking accounts does not reduce.
+    pub accounts_data_growth: u64,
+    /// Heap usage of the invoked programs, if recording was enabled
+    pub heap_allocation_stats: Option<HeapAllocationStats>,
+    /// Location at which the failing program aborted, if recording was
+    /// enabled
+    pub error_location: Option<ProgramErrorLocation>,
+    /// Syscalls invoked and capabilities used by the programs, if recording
+    /// was enabled
+    pub capability_manifest: Option<CapabilityManifest>,
+    /// Top-level instructions, decoded if their program is known, if
+    /// decoding was enabled
+    pub decoded_instructions: Option<Vec<Option<DecodedInstruction>>>,
+    /// How the compute budget instructions of the transaction determined its
+    /// limits, if reporting was enabled
+    pub compute_budget_report: Option<ComputeBudgetReport>,
+    /// Accounts created and closed by this transaction.
+    /// NOTE: This value is empty unless `status` is `Ok`.
+    pub account_lifecycle: AccountLifecycle,
+    /// How the transaction satisfied the signature policy of the processor,
+    /// if one is installed
+    pub signature_policy_decision: Option<SignaturePolicyDecision>,
+}
+
+/// Accounts which a transaction brought into or out of existence, so that
+/// indexers can maintain the set of live accounts without diffing states.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountLifecycle {
+    /// Accounts which had no lamports before the transaction and are funded
+    /// after it
+    pub created: Vec<Pubkey>,
+    /// Accounts which had lamports before the transaction and have none
+    /// after it. They are removed, along with their data, once committed.
+    pub closed: Vec<Pubkey>,
+}
+
+impl AccountLifecycle {
+    /// Compare the lamports of the writable accounts of `message` before
+    /// and after execution
+    pub fn new(
+        message: &SanitizedMessage,
+        pre_lamports: &[u64],
+        post_transaction_accounts: &[TransactionAccount],
+    ) -> Self {
+        let mut lifecycle = Self::default();
+        for (index, ((pubkey, post), pre_lamports)) in post_transaction_accounts
+            .iter()
+            .zip(pre_lamports)
+            .take(message.account_keys().len())
+            .enumerate()
+        {
+            if !message.is_writable(index) {
+                continue;
+            }
+            match (*pre_lamports == 0, post.lamports() == 0) {
+                (true, false) => lifecycle.created.push(*pubkey),
+                (false, true) => lifecycle.closed.push(*pubkey),
+                _ => {}
+            }
+        }
+        lifecycle
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -105,3 +176,61 @@ impl DurableNonceFee {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            account::AccountSharedData,
+            hash::Hash,
+            signature::{Keypair, Signer},
+            system_program, system_transaction,
+            transaction::SanitizedTransaction,
+        },
+    };
+
+    #[test]
+    fn test_account_lifecycle() {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let tx = SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &payer,
+            &recipient,
+            10,
+            Hash::default(),
+        ));
+        let post_transaction_accounts = vec![
+            (payer.pubkey(), AccountSharedData::default()),
+            (
+                recipient,
+                AccountSharedData::new(10, 0, &system_program::id()),
+            ),
+            // Read-only accounts are never created or closed
+            (
+                system_program::id(),
+                AccountSharedData::new(1, 0, &Pubkey::default()),
+            ),
+        ];
+
+        let lifecycle =
+            AccountLifecycle::new(tx.message(), &[10, 0, 0], &post_transaction_accounts);
+        assert_eq!(
+            lifecycle,
+            AccountLifecycle {
+                created: vec![recipient],
+                closed: vec![payer.pubkey()],
+            }
+        );
+
+        let lifecycle =
+            AccountLifecycle::new(tx.message(), &[20, 5, 1], &post_transaction_accounts);
+        assert_eq!(
+            lifecycle,
+            AccountLifecycle {
+                created: vec![],
+                closed: vec![payer.pubkey()],
+            }
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Embedder defined rules over the accounts written by transactions.
+//!
+//! Permissioned deployments may need to enforce controls which are not
+//! expressed by programs, e.g. that a list of frozen accounts must not change.
+//! A `WritePolicy` is consulted for every account a successfully executed
+//! transaction modified, before its results are finalized. If the policy
+//! vetoes any of the writes, the transaction fails with
+//! `TransactionError::AccountWriteVetoed` and none of its writes are committed,
+//! like for any other failed transaction.
+
+use {
+    solana_sdk::{account::AccountSharedData, pubkey::Pubkey, transaction::SanitizedTransaction},
+    std::collections::HashSet,
+};
+
+pub trait WritePolicy: Send + Sync {
+    /// Whether `transaction` may write `post` to the account at `pubkey`,
+    /// which held `pre` before the transaction executed
+    fn allow_write(
+        &self,
+        transaction: &SanitizedTransaction,
+        pubkey: &Pubkey,
+        pre: &AccountSharedData,
+        post: &AccountSharedData,
+    ) -> bool;
+}
+
+/// Denies any modification of the listed accounts
+#[derive(Debug, Default)]
+pub struct FrozenAccountsWritePolicy {
+    pub frozen_accounts: HashSet<Pubkey>,
+}
+
+impl FrozenAccountsWritePolicy {
+    pub fn new(frozen_accounts: impl IntoIterator<Item = Pubkey>) -> Self {
+        Self {
+            frozen_accounts: frozen_accounts.into_iter().collect(),
+        }
+    }
+}
+
+impl WritePolicy for FrozenAccountsWritePolicy {
+    fn allow_write(
+        &self,
+        _transaction: &SanitizedTransaction,
+        pubkey: &Pubkey,
+        _pre: &AccountSharedData,
+        _post: &AccountSharedData,
+    ) -> bool {
+        !self.frozen_accounts.contains(pubkey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{hash::Hash, signature::Keypair, system_transaction},
+    };
+
+    #[test]
+    fn test_frozen_accounts_write_policy() {
+        let frozen = Pubkey::new_unique();
+        let policy = FrozenAccountsWritePolicy::new([frozen]);
+        let tx = SanitizedTransaction::from_transaction_for_tests(system_transaction::transfer(
+            &Keypair::new(),
+            &frozen,
+            1,
+            Hash::default(),
+        ));
+        let pre = AccountSharedData::default();
+        let post = AccountSharedData::new(1, 0, &Pubkey::default());
+
+        assert!(!policy.allow_write(&tx, &frozen, &pre, &post));
+        assert!(policy.allow_write(&tx, &Pubkey::new_unique(), &pre, &post));
+        assert!(FrozenAccountsWritePolicy::default().allow_write(&tx, &frozen, &pre, &post));
+    }
+}
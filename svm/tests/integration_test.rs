@@ -37,7 +37,9 @@ use {
         account_loader::TransactionCheckResult,
         transaction_error_metrics::TransactionErrorMetrics,
         transaction_processing_callback::TransactionProcessingCallback,
-        transaction_processor::{ExecutionRecordingConfig, TransactionBatchProcessor},
+        transaction_processor::{
+            ExecutionRecordingConfig, ReExecutionError, TransactionBatchProcessor,
+        },
         transaction_results::TransactionExecutionResult,
     },
     std::{
@@ -46,7 +48,7 @@ use {
         env,
         fs::{self, File},
         io::Read,
-        sync::{Arc, RwLock},
+        sync::{atomic::Ordering::Relaxed, Arc, RwLock},
         time::{SystemTime, UNIX_EPOCH},
     },
 };
@@ -448,6 +450,11 @@ fn svm_integration() {
         enable_log_recording: true,
         enable_return_data_recording: true,
         enable_cpi_recording: false,
+        enable_heap_stats_recording: false,
+        enable_error_location_recording: false,
+        enable_capability_recording: false,
+        enable_instruction_decoding: false,
+        enable_compute_budget_report: false,
     };
     let mut timings = ExecuteTimings::default();
 
@@ -462,6 +469,7 @@ fn svm_integration() {
         builtins.iter(),
         None,
         false,
+        false,
     );
 
     assert_eq!(result.execution_results.len(), 5);
@@ -524,4 +532,350 @@ fn svm_integration() {
         result.execution_results[4],
         TransactionExecutionResult::NotExecuted(TransactionError::BlockhashNotFound)
     ));
+
+    let (hello_program, _) = transactions[0]
+        .message()
+        .program_instructions_iter()
+        .next()
+        .unwrap();
+    let usage = result.program_usage_report.get(hello_program).unwrap();
+    assert_eq!(usage.invocation_count, 1);
+    let executed_units = result.execution_results[0]
+        .details()
+        .unwrap()
+        .executed_units;
+    assert!(usage.compute_units > 0 && usage.compute_units <= executed_units);
+}
+
+#[test]
+fn svm_re_execute_subset() {
+    let mut mock_bank = MockBankCallback::default();
+    let (transactions, check_results) = prepare_transactions(&mut mock_bank);
+    let (program_cache, builtins) = create_executable_environment(&mut mock_bank);
+    let program_cache = Arc::new(RwLock::new(program_cache));
+    let batch_processor = TransactionBatchProcessor::<MockForkGraph>::new(
+        EXECUTION_SLOT,
+        EXECUTION_EPOCH,
+        EpochSchedule::default(),
+        FeeStructure::default(),
+        Arc::new(RuntimeConfig {
+            compute_budget: Some(ComputeBudget {
+                compute_unit_limit: 1,
+                ..ComputeBudget::default()
+            }),
+            ..RuntimeConfig::default()
+        }),
+        program_cache.clone(),
+    );
+    batch_processor.fill_missing_sysvar_cache_entries(&mock_bank);
+
+    let recording_config = ExecutionRecordingConfig::new_single_setting(true);
+    let result = batch_processor.load_and_execute_sanitized_transactions(
+        &mock_bank,
+        &transactions,
+        check_results.clone().as_mut_slice(),
+        &mut TransactionErrorMetrics::default(),
+        recording_config,
+        &mut ExecuteTimings::default(),
+        None,
+        builtins.iter(),
+        None,
+        false,
+        false,
+    );
+    assert!(result.execution_results[0]
+        .details()
+        .unwrap()
+        .status
+        .is_err());
+    assert!(result.execution_results[2]
+        .details()
+        .unwrap()
+        .status
+        .is_err());
+
+    // Re-running the failed transactions with the default compute budget succeeds
+    let indices = [2, 0];
+    let re_executed = batch_processor
+        .re_execute_subset(
+            &mock_bank,
+            &transactions,
+            &result,
+            &indices,
+            Arc::new(RuntimeConfig::default()),
+        )
+        .unwrap();
+    assert_eq!(re_executed.execution_results.len(), indices.len());
+    assert!(re_executed.execution_results[0]
+        .details()
+        .unwrap()
+        .return_data
+        .is_some());
+    let logs = re_executed.execution_results[1]
+        .details()
+        .unwrap()
+        .log_messages
+        .as_ref()
+        .unwrap();
+    assert!(logs.contains(&"Program log: Hello, Solana!".to_string()));
+    assert!(re_executed
+        .execution_results
+        .iter()
+        .all(TransactionExecutionResult::was_executed_successfully));
+
+    // Transactions which failed their checks still fail
+    let re_executed = batch_processor
+        .re_execute_subset(
+            &mock_bank,
+            &transactions,
+            &result,
+            &[4],
+            Arc::new(RuntimeConfig::default()),
+        )
+        .unwrap();
+    assert!(matches!(
+        re_executed.execution_results[0],
+        TransactionExecutionResult::NotExecuted(TransactionError::BlockhashNotFound)
+    ));
+
+    let len = transactions.len();
+    assert_eq!(
+        batch_processor
+            .re_execute_subset(
+                &mock_bank,
+                &transactions,
+                &result,
+                &[0, len],
+                Arc::new(RuntimeConfig::default()),
+            )
+            .err(),
+        Some(ReExecutionError::IndexOutOfBounds { index: len, len })
+    );
+}
+
+#[test]
+fn svm_streamed_output() {
+    let mut mock_bank = MockBankCallback::default();
+    let (transactions, check_results) = prepare_transactions(&mut mock_bank);
+    let (program_cache, builtins) = create_executable_environment(&mut mock_bank);
+    let program_cache = Arc::new(RwLock::new(program_cache));
+    let batch_processor = TransactionBatchProcessor::<MockForkGraph>::new(
+        EXECUTION_SLOT,
+        EXECUTION_EPOCH,
+        EpochSchedule::default(),
+        FeeStructure::default(),
+        Arc::new(RuntimeConfig::default()),
+        program_cache.clone(),
+    );
+    batch_processor.fill_missing_sysvar_cache_entries(&mock_bank);
+
+    let recording_config = ExecutionRecordingConfig::new_single_setting(true);
+    let result = batch_processor.load_and_execute_sanitized_transactions(
+        &mock_bank,
+        &transactions,
+        check_results.clone().as_mut_slice(),
+        &mut TransactionErrorMetrics::default(),
+        recording_config,
+        &mut ExecuteTimings::default(),
+        None,
+        builtins.iter(),
+        None,
+        false,
+        false,
+    );
+
+    let mut streamed = vec![];
+    let output = batch_processor.load_and_execute_sanitized_transactions_streamed(
+        &mock_bank,
+        &transactions,
+        check_results.clone().as_mut_slice(),
+        &mut TransactionErrorMetrics::default(),
+        recording_config,
+        &mut ExecuteTimings::default(),
+        None,
+        builtins.iter(),
+        None,
+        false,
+        false,
+        |index, load_result, execution_result| {
+            streamed.push((index, load_result.0.is_ok(), execution_result));
+        },
+    );
+
+    assert_eq!(streamed.len(), transactions.len());
+    for (index, ((streamed_index, loaded, execution_result), (load_result, _nonce))) in streamed
+        .iter()
+        .zip(result.loaded_transactions.iter())
+        .enumerate()
+    {
+        assert_eq!(*streamed_index, index);
+        assert_eq!(*loaded, load_result.is_ok());
+        assert_eq!(
+            execution_result.flattened_result(),
+            result.execution_results[index].flattened_result()
+        );
+        assert_eq!(
+            execution_result
+                .details()
+                .map(|details| details.executed_units),
+            result.execution_results[index]
+                .details()
+                .map(|details| details.executed_units)
+        );
+    }
+    assert_eq!(output.fee_distribution, result.fee_distribution);
+    assert_eq!(
+        output.account_contention_report,
+        result.account_contention_report
+    );
+}
+
+#[test]
+fn svm_view_call() {
+    let mut mock_bank = MockBankCallback::default();
+    let (program_cache, builtins) = create_executable_environment(&mut mock_bank);
+    let program_cache = Arc::new(RwLock::new(program_cache));
+    let batch_processor = TransactionBatchProcessor::<MockForkGraph>::new(
+        EXECUTION_SLOT,
+        EXECUTION_EPOCH,
+        EpochSchedule::default(),
+        FeeStructure::default(),
+        Arc::new(RuntimeConfig::default()),
+        program_cache.clone(),
+    );
+    batch_processor.fill_missing_sysvar_cache_entries(&mock_bank);
+
+    // The fee payer doesn't exist, so the transaction can't be processed normally
+    let clock_program = deploy_program("clock-sysvar".to_string(), &mut mock_bank);
+    let mut transaction_builder = SanitizedTransactionBuilder::default();
+    transaction_builder.create_instruction(clock_program, Vec::new(), HashMap::new(), Vec::new());
+    let transaction = transaction_builder.build(
+        Hash::default(),
+        (Pubkey::new_unique(), Signature::default()),
+    );
+    let result = batch_processor.load_and_execute_sanitized_transactions(
+        &mock_bank,
+        std::slice::from_ref(&transaction),
+        &mut [(Ok(()), None, Some(20))],
+        &mut TransactionErrorMetrics::default(),
+        ExecutionRecordingConfig::new_single_setting(true),
+        &mut ExecuteTimings::default(),
+        None,
+        builtins.iter(),
+        None,
+        false,
+        false,
+    );
+    assert!(matches!(
+        result.execution_results[0],
+        TransactionExecutionResult::NotExecuted(TransactionError::AccountNotFound)
+    ));
+
+    let result = batch_processor.process_view_call(&mock_bank, &transaction, builtins.iter());
+    assert!(result.status.is_ok());
+    assert!(result.executed_units > 0);
+    assert!(result.log_messages.unwrap().len() > 1);
+    let return_data = result.return_data.unwrap();
+    assert_eq!(return_data.program_id, clock_program);
+    let time = i64::from_be_bytes(return_data.data[0..8].try_into().unwrap());
+    let clock_data = mock_bank.get_account_shared_data(&Clock::id()).unwrap();
+    let clock_info: Clock = bincode::deserialize(clock_data.data()).unwrap();
+    assert_eq!(clock_info.unix_timestamp, time);
+}
+
+#[test]
+fn svm_dry_run() {
+    let mut mock_bank = MockBankCallback::default();
+    let (transactions, check_results) = prepare_transactions(&mut mock_bank);
+    let (program_cache, builtins) = create_executable_environment(&mut mock_bank);
+    let program_cache = Arc::new(RwLock::new(program_cache));
+    let batch_processor = TransactionBatchProcessor::<MockForkGraph>::new(
+        EXECUTION_SLOT,
+        EXECUTION_EPOCH,
+        EpochSchedule::default(),
+        FeeStructure::default(),
+        Arc::new(RuntimeConfig::default()),
+        program_cache.clone(),
+    );
+    batch_processor.fill_missing_sysvar_cache_entries(&mock_bank);
+
+    let hello_program = *transactions[0]
+        .message()
+        .program_instructions_iter()
+        .next()
+        .unwrap()
+        .0;
+    let process = |dry_run| {
+        batch_processor.load_and_execute_sanitized_transactions(
+            &mock_bank,
+            &transactions,
+            check_results.clone().as_mut_slice(),
+            &mut TransactionErrorMetrics::default(),
+            ExecutionRecordingConfig::new_single_setting(true),
+            &mut ExecuteTimings::default(),
+            None,
+            builtins.iter(),
+            None,
+            false,
+            dry_run,
+        )
+    };
+    let system_program_instructions = || {
+        program_cache
+            .read()
+            .unwrap()
+            .get_slot_versions_for_tests(&solana_system_program::id())[0]
+            .ix_usage_counter
+            .load(Relaxed)
+    };
+
+    // A dry run executes the transactions as usual...
+    let result = process(true);
+    let logs = result.execution_results[0]
+        .details()
+        .unwrap()
+        .log_messages
+        .as_ref()
+        .unwrap();
+    assert!(logs.contains(&"Program log: Hello, Solana!".to_string()));
+    assert!(result.execution_results[1]
+        .details()
+        .unwrap()
+        .status
+        .is_ok());
+
+    // ...but leaves the program cache as it was
+    {
+        let program_cache = program_cache.read().unwrap();
+        assert!(program_cache
+            .get_slot_versions_for_tests(&hello_program)
+            .is_empty());
+        assert_eq!(program_cache.stats.hits.load(Relaxed), 0);
+        assert_eq!(program_cache.stats.misses.load(Relaxed), 0);
+    }
+    assert_eq!(system_program_instructions(), 0);
+
+    let dry_run_statuses = result
+        .execution_results
+        .iter()
+        .map(|result| result.details().map(|details| details.status.clone()))
+        .collect::<Vec<_>>();
+    let result = process(false);
+    assert_eq!(
+        result
+            .execution_results
+            .iter()
+            .map(|result| result.details().map(|details| details.status.clone()))
+            .collect::<Vec<_>>(),
+        dry_run_statuses
+    );
+    assert_eq!(
+        program_cache
+            .read()
+            .unwrap()
+            .get_slot_versions_for_tests(&hello_program)
+            .len(),
+        1
+    );
+    assert!(system_program_instructions() > 0);
 }